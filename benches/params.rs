@@ -0,0 +1,119 @@
+//!
+//! Rust Firebird Client
+//!
+//! Benchmark of the executions of a prepared statement with parameters,
+//! which reuse the parameter buffers of the statement between them
+//!
+//! Needs the database of the examples: `cargo bench --bench params`
+//!
+
+#![allow(unused_variables, unused_mut)]
+
+use chrono::NaiveDate;
+use rsfbclient::{prelude::*, Connection, FbError};
+use rsfbclient_core::FirebirdClient;
+use std::time::{Duration, Instant};
+
+/// Executions measured in each case
+const EXECUTIONS: usize = 20_000;
+
+fn main() -> Result<(), FbError> {
+    #[cfg(feature = "linking")]
+    let mut conn = rsfbclient::builder_native()
+        .with_dyn_link()
+        .with_remote()
+        .host("localhost")
+        .db_name("examples.fdb")
+        .user("SYSDBA")
+        .pass("masterkey")
+        .connect()?;
+
+    #[cfg(feature = "dynamic_loading")]
+    let mut conn = rsfbclient::builder_native()
+        .with_dyn_load("./fbclient.lib")
+        .with_remote()
+        .host("localhost")
+        .db_name("examples.fdb")
+        .user("SYSDBA")
+        .pass("masterkey")
+        .connect()?;
+
+    #[cfg(feature = "pure_rust")]
+    let mut conn = rsfbclient::builder_pure_rust()
+        .host("localhost")
+        .db_name("examples.fdb")
+        .user("SYSDBA")
+        .pass("masterkey")
+        .connect()?;
+
+    conn.execute(
+        "recreate table bench_params (id int, name varchar(100), price numeric(10, 2), ts timestamp)",
+        (),
+    )?;
+
+    // Same insert with the values in the sql, to compare the cost of the parameters
+    let literals = bench(
+        &mut conn,
+        "insert into bench_params (id, name, price, ts) values (1, 'name', 1.5, '2021-03-04 10:20:30')",
+        |stmt, _| stmt.execute(()),
+    )?;
+    report("no parameters", literals);
+
+    let ts = NaiveDate::from_ymd_opt(2021, 3, 4)
+        .unwrap()
+        .and_hms_opt(10, 20, 30)
+        .unwrap();
+    let names = ["a", "a longer name, needing a larger buffer", "", "name"];
+
+    // The values change in length and in nullability between the executions
+    let params = bench(
+        &mut conn,
+        "insert into bench_params (id, name, price, ts) values (?, ?, ?, ?)",
+        |stmt, i| {
+            let name = names[i % names.len()];
+            let price = if i % 5 == 0 {
+                None
+            } else {
+                Some(i as f64 / 100.0)
+            };
+
+            stmt.execute((i as i32, name, price, ts))
+        },
+    )?;
+    report("4 parameters", params);
+
+    conn.execute("drop table bench_params", ())?;
+
+    Ok(())
+}
+
+/// Prepare the sql and run the executions in a transaction, returning the time elapsed
+fn bench<C, F>(conn: &mut Connection<C>, sql: &str, mut execute: F) -> Result<Duration, FbError>
+where
+    C: FirebirdClient,
+    F: FnMut(&mut rsfbclient::Statement<C>, usize) -> Result<usize, FbError>,
+{
+    conn.with_transaction(|tr| {
+        let mut stmt = tr.prepare(sql, false)?;
+
+        // Warm up, also binding the parameters for the first time
+        for i in 0..100 {
+            execute(&mut stmt, i)?;
+        }
+
+        let start = Instant::now();
+        for i in 0..EXECUTIONS {
+            execute(&mut stmt, i)?;
+        }
+
+        Ok(start.elapsed())
+    })
+}
+
+fn report(case: &str, elapsed: Duration) {
+    println!(
+        "{:<15} {:>8.2} us/execution",
+        case,
+        elapsed.as_secs_f64() * 1e6 / EXECUTIONS as f64
+    );
+}
@@ -0,0 +1,117 @@
+//!
+//! Rust Firebird Client
+//!
+//! Benchmark of the statement cache hit rate with each `SqlNormalization`,
+//! running the same queries written with different whitespace, comments and case
+//!
+//! Needs the database of the examples: `cargo bench --bench stmt_cache`
+//!
+
+#![allow(unused_variables, unused_mut)]
+
+use rsfbclient::{prelude::*, Connection, FbError, SqlNormalization};
+use rsfbclient_core::FirebirdClient;
+use std::time::Instant;
+
+/// Queries run in each case
+const QUERIES: usize = 5_000;
+
+/// Queries of the workload, each run with the variants of `variants`
+const SQLS: &[&str] = &[
+    "select rdb$relation_name from rdb$relations where rdb$relation_id = ?",
+    "select count(*) from rdb$fields where rdb$field_length > ?",
+    "select rdb$character_set_name from rdb$character_sets where rdb$character_set_id = ?",
+];
+
+fn main() -> Result<(), FbError> {
+    for normalization in [
+        SqlNormalization::Exact,
+        SqlNormalization::Whitespace,
+        SqlNormalization::Uppercase,
+    ] {
+        #[cfg(feature = "linking")]
+        let mut conn = rsfbclient::builder_native()
+            .with_dyn_link()
+            .with_remote()
+            .host("localhost")
+            .db_name("examples.fdb")
+            .user("SYSDBA")
+            .pass("masterkey")
+            .stmt_cache_size(100)
+            .stmt_cache_normalization(normalization)
+            .connect()?;
+
+        #[cfg(feature = "dynamic_loading")]
+        let mut conn = rsfbclient::builder_native()
+            .with_dyn_load("./fbclient.lib")
+            .with_remote()
+            .host("localhost")
+            .db_name("examples.fdb")
+            .user("SYSDBA")
+            .pass("masterkey")
+            .stmt_cache_size(100)
+            .stmt_cache_normalization(normalization)
+            .connect()?;
+
+        #[cfg(feature = "pure_rust")]
+        let mut conn = rsfbclient::builder_pure_rust()
+            .host("localhost")
+            .db_name("examples.fdb")
+            .user("SYSDBA")
+            .pass("masterkey")
+            .stmt_cache_size(100)
+            .stmt_cache_normalization(normalization)
+            .connect()?;
+
+        bench(&mut conn, normalization)?;
+    }
+
+    Ok(())
+}
+
+/// Run the workload, reporting the time and the hit rate of the cache
+fn bench<C>(conn: &mut Connection<C>, normalization: SqlNormalization) -> Result<(), FbError>
+where
+    C: FirebirdClient,
+{
+    let sqls: Vec<String> = SQLS.iter().flat_map(|sql| variants(sql)).collect();
+
+    let start = Instant::now();
+    for i in 0..QUERIES {
+        let _: Vec<(String,)> = conn.query(&sqls[i % sqls.len()], (i as i32 % 10,))?;
+    }
+    let elapsed = start.elapsed();
+
+    // The cached statements are kept allocated in the server, one for each cache miss.
+    // Excludes the statement of this query
+    let (allocated,): (i64,) = conn
+        .query_first(
+            "select count(*) from mon$statements where mon$attachment_id = current_connection",
+            (),
+        )?
+        .unwrap();
+    let misses = (allocated - 1).max(0) as f64;
+
+    println!(
+        "{:<10} {:>8.2} us/query, {:>3} statements prepared, {:>6.2}% hit rate",
+        format!("{:?}", normalization),
+        elapsed.as_secs_f64() * 1e6 / QUERIES as f64,
+        misses,
+        100.0 * (1.0 - misses / QUERIES as f64)
+    );
+
+    Ok(())
+}
+
+/// The same sql with different whitespace, comments and case, like the sqls
+/// built by different parts of an application
+fn variants(sql: &str) -> Vec<String> {
+    vec![
+        sql.to_string(),
+        sql.replace(" from ", "\n  from ")
+            .replace(" where ", "\n where "),
+        format!("{} -- by id", sql),
+        format!("/* report */ {}", sql),
+        sql.to_uppercase(),
+    ]
+}
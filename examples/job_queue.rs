@@ -0,0 +1,88 @@
+//!
+//! Rust Firebird Client
+//!
+//! Example of a job queue consumer, with many consumers taking the
+//! pending jobs without waiting for each other
+//!
+//! You need create a database with this table:
+//! create table job (id int generated by default as identity primary key, payload varchar(100), done boolean default false not null);
+//!
+//! Run more than one consumer at the same time to see the locked jobs being skipped.
+//! On Firebird 5+, `SELECT ... WITH LOCK SKIP LOCKED` does the same in a single query
+//!
+
+#![allow(unused_variables, unused_mut)]
+
+use rsfbclient::{prelude::*, FbError, TrLockResolution};
+use std::time::Duration;
+
+const SQL_PENDING: &str = "select first 10 id from job where done = false order by id";
+const SQL_LOCK: &str = "select id, payload from job where id = ? and done = false with lock";
+const SQL_DONE: &str = "update job set done = true where id = ?";
+
+fn main() -> Result<(), FbError> {
+    let mut builder = {
+        #[cfg(feature = "linking")]
+        let mut builder = rsfbclient::builder_native().with_dyn_link().with_remote();
+
+        #[cfg(feature = "dynamic_loading")]
+        let mut builder = rsfbclient::builder_native()
+            .with_dyn_load("./fbclient.lib")
+            .with_remote();
+
+        #[cfg(feature = "pure_rust")]
+        let mut builder = rsfbclient::builder_pure_rust();
+
+        builder
+            .host("localhost")
+            .db_name("examples.fdb")
+            .user("SYSDBA")
+            .pass("masterkey");
+
+        builder
+    };
+
+    // Fail right away on the jobs locked by the other consumers, instead of waiting for them
+    let mut conn = builder
+        .with_lock_resolution(TrLockResolution::NoWait)
+        .connect()?;
+
+    loop {
+        let pending: Vec<(i32,)> = match conn.query(SQL_PENDING, ()) {
+            Ok(pending) => pending,
+            // Older servers may refuse to read a job being changed by another consumer
+            Err(e) if e.is_lock_conflict() => vec![],
+            Err(e) => return Err(e),
+        };
+
+        let mut processed = 0;
+
+        for (id,) in pending {
+            // Each job is locked, processed and marked as done in its own transaction
+            let res = conn.with_transaction(|tr| {
+                let job: Option<(i32, String)> = tr.query_first(SQL_LOCK, (id,))?;
+
+                if let Some((id, payload)) = &job {
+                    println!("Processing the job {}: {}", id, payload);
+                    tr.execute(SQL_DONE, (id,))?;
+                }
+
+                Ok(job.is_some())
+            });
+
+            match res {
+                Ok(true) => processed += 1,
+                // Already done by another consumer
+                Ok(false) => {}
+                Err(e) if e.is_lock_conflict() => {
+                    println!("The job {} is taken by another consumer", id)
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        if processed == 0 {
+            std::thread::sleep(Duration::from_secs(1));
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//!
+//! Rust Firebird Client
+//!
+//! Example of update with a named cursor (`WHERE CURRENT OF`)
+//!
+//! You need create a database with this table:
+//! create table test (col_a int generated by default as identity, col_b float, col_c varchar(10));
+//!
+
+#![allow(unused_variables, unused_mut)]
+
+use rsfbclient::{FbError, Statement};
+
+const SQL_SELECT: &str = "select col_a, col_b from test for update";
+const SQL_UPDATE: &str = "update test set col_b = ? where current of test_cursor";
+
+fn main() -> Result<(), FbError> {
+    #[cfg(feature = "linking")]
+    let mut conn = rsfbclient::builder_native()
+        .with_dyn_link()
+        .with_remote()
+        .host("localhost")
+        .db_name("examples.fdb")
+        .user("SYSDBA")
+        .pass("masterkey")
+        .connect()?;
+
+    #[cfg(feature = "dynamic_loading")]
+    let mut conn = rsfbclient::builder_native()
+        .with_dyn_load("./fbclient.lib")
+        .with_remote()
+        .host("localhost")
+        .db_name("examples.fdb")
+        .user("SYSDBA")
+        .pass("masterkey")
+        .connect()?;
+
+    #[cfg(feature = "pure_rust")]
+    let mut conn = rsfbclient::builder_pure_rust()
+        .host("localhost")
+        .db_name("examples.fdb")
+        .user("SYSDBA")
+        .pass("masterkey")
+        .connect()?;
+
+    // The cursor and the updates must share the same transaction
+    conn.with_transaction(|tr| {
+        let mut stmt = tr.prepare(SQL_SELECT, false)?;
+        // Named before opening the cursor
+        stmt.set_cursor_name("TEST_CURSOR")?;
+
+        let mut rows = stmt.query::<(i32, Option<f32>), _>(())?;
+
+        while let Some((col_a, col_b)) = rows.fetch()? {
+            // Changes the last row fetched. Prepared on each row, as the
+            // positioned statements are bound to the open cursor
+            let mut update = Statement::prepare(rows.transaction(), SQL_UPDATE, false)?;
+            update.execute((col_b.unwrap_or_default() * 2.0,))?;
+
+            println!("Updated the row {}", col_a);
+        }
+
+        Ok(())
+    })?;
+
+    // Explicit close is optional
+    conn.close()?;
+
+    Ok(())
+}
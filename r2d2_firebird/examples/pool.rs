@@ -8,7 +8,7 @@
 
 #![allow(unused_variables, unused_mut)]
 
-use r2d2_firebird::FirebirdConnectionManager;
+use r2d2_firebird::PoolBuilder;
 use rsfbclient::prelude::*;
 use std::{sync::Arc, thread, time::Duration};
 
@@ -25,11 +25,17 @@ fn main() {
         builder
     };
 
-    //FirebirdConnectionManager makes use of FirebirdClientFactory, which is implemented
+    //PoolBuilder makes use of FirebirdClientFactory, which is implemented
     //by builders
 
-    let manager = FirebirdConnectionManager::new(builder);
-    let pool = Arc::new(r2d2::Pool::builder().max_size(4).build(manager).unwrap());
+    let pool = Arc::new(
+        PoolBuilder::new()
+            .max_size(4)
+            .min_idle(Some(1))
+            .idle_timeout(Some(Duration::from_secs(60)))
+            .build(builder)
+            .unwrap(),
+    );
 
     let mut tasks = vec![];
 
@@ -4,8 +4,12 @@
 //! R2D2 Connection Pool
 //!
 
-use rsfbclient::{Connection, FbError, FirebirdClientFactory, Transaction};
+use rsfbclient::{Connection, FbError, FirebirdClientFactory, Queryable, Row};
 use rsfbclient_core::FirebirdClientDbOps;
+use std::time::Duration;
+
+/// Default query used to check if a connection is still alive
+pub const DEFAULT_TEST_QUERY: &str = "SELECT 1 FROM RDB$DATABASE";
 
 /// A manager for connection pools. Requires the `pool` feature.
 pub struct FirebirdConnectionManager<F>
@@ -13,6 +17,7 @@ where
     F: FirebirdClientFactory,
 {
     client_factory: F,
+    test_query: String,
 }
 
 impl<F> FirebirdConnectionManager<F>
@@ -20,7 +25,16 @@ where
     F: FirebirdClientFactory,
 {
     pub fn new(client_factory: F) -> Self {
-        Self { client_factory }
+        Self {
+            client_factory,
+            test_query: DEFAULT_TEST_QUERY.to_string(),
+        }
+    }
+
+    /// Query executed to check if a connection is still alive. Default: `SELECT 1 FROM RDB$DATABASE`
+    pub fn test_query<S: Into<String>>(&mut self, sql: S) -> &mut Self {
+        self.test_query = sql.into();
+        self
     }
 }
 
@@ -38,12 +52,149 @@ where
     }
 
     fn is_valid(&self, conn: &mut Self::Connection) -> Result<(), Self::Error> {
-        // If it can start a transaction, we are ok
-        Transaction::new(conn)?;
+        // If the test query runs, we are ok
+        let _: Option<Row> = conn.query_first(&self.test_query, ())?;
         Ok(())
     }
 
-    fn has_broken(&self, _conn: &mut Self::Connection) -> bool {
-        false
+    fn has_broken(&self, conn: &mut Self::Connection) -> bool {
+        // Set when an operation failed due to a connection loss
+        conn.is_broken()
+    }
+}
+
+/// Records the time waited for a connection on each checkout, in the
+/// `fb_pool_checkout_duration_seconds` histogram, and the checkouts that
+/// timed out, in the `fb_pool_checkout_timeouts_total` counter.
+/// Requires the `metrics` feature.
+///
+/// Used by the pools made with [`PoolBuilder`], and can be set as the
+/// `event_handler` of the pools built with `r2d2::Pool::builder()`
+#[cfg(feature = "metrics")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MetricsEventHandler;
+
+#[cfg(feature = "metrics")]
+impl r2d2::HandleEvent for MetricsEventHandler {
+    fn handle_checkout(&self, event: r2d2::event::CheckoutEvent) {
+        metrics::histogram!("fb_pool_checkout_duration_seconds")
+            .record(event.duration().as_secs_f64());
+    }
+
+    fn handle_timeout(&self, _event: r2d2::event::TimeoutEvent) {
+        metrics::counter!("fb_pool_checkout_timeouts_total").increment(1);
+    }
+}
+
+/// Builder for a `r2d2::Pool` of firebird connections.
+///
+/// The options are passed to the `r2d2::Builder` of the same name. Other
+/// r2d2 options, like the error handler, can be used by building the pool
+/// with `r2d2::Pool::builder()` and a [`FirebirdConnectionManager`].
+///
+/// With `test_on_check_out` enabled, r2d2 calls
+/// [`is_valid`](r2d2::ManageConnection::is_valid) on each checkout, running the
+/// test query and discarding the connection if it fails. The connections that
+/// lost the link with the server in an operation are discarded when returned
+/// to the pool, without waiting for the next checkout.
+#[derive(Clone)]
+pub struct PoolBuilder {
+    max_size: u32,
+    min_idle: Option<u32>,
+    connection_timeout: Duration,
+    idle_timeout: Option<Duration>,
+    max_lifetime: Option<Duration>,
+    test_on_check_out: bool,
+    test_query: String,
+}
+
+impl Default for PoolBuilder {
+    fn default() -> Self {
+        Self {
+            max_size: 10,
+            min_idle: None,
+            connection_timeout: Duration::from_secs(30),
+            idle_timeout: Some(Duration::from_secs(10 * 60)),
+            max_lifetime: Some(Duration::from_secs(30 * 60)),
+            test_on_check_out: true,
+            test_query: DEFAULT_TEST_QUERY.to_string(),
+        }
+    }
+}
+
+impl PoolBuilder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Maximum number of connections managed by the pool. Default: 10
+    pub fn max_size(&mut self, max_size: u32) -> &mut Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Minimum number of idle connections kept by the pool. Default: same as `max_size`
+    pub fn min_idle(&mut self, min_idle: Option<u32>) -> &mut Self {
+        self.min_idle = min_idle;
+        self
+    }
+
+    /// Maximum time to wait for a connection on checkout. Default: 30 seconds
+    pub fn connection_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.connection_timeout = timeout;
+        self
+    }
+
+    /// Idle connections are closed after this time, only when the pool has more
+    /// than `min_idle` connections. Default: 10 minutes
+    pub fn idle_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// Connections older than this are closed instead of returned to the pool,
+    /// recycling them before being dropped by the server or a firewall.
+    /// Default: 30 minutes
+    pub fn max_lifetime(&mut self, lifetime: Option<Duration>) -> &mut Self {
+        self.max_lifetime = lifetime;
+        self
+    }
+
+    /// Check the connections with the test query on checkout. Default: true
+    pub fn test_on_check_out(&mut self, test: bool) -> &mut Self {
+        self.test_on_check_out = test;
+        self
+    }
+
+    /// Query executed to check if a connection is still alive. Default: `SELECT 1 FROM RDB$DATABASE`
+    pub fn test_query<S: Into<String>>(&mut self, sql: S) -> &mut Self {
+        self.test_query = sql.into();
+        self
+    }
+
+    /// Build the pool, opening the `min_idle` connections
+    pub fn build<F>(
+        &self,
+        client_factory: F,
+    ) -> Result<r2d2::Pool<FirebirdConnectionManager<F>>, r2d2::Error>
+    where
+        F: FirebirdClientFactory + Send + Sync + 'static,
+        <F::C as FirebirdClientDbOps>::AttachmentConfig: Send + Sync + Clone,
+    {
+        let mut manager = FirebirdConnectionManager::new(client_factory);
+        manager.test_query(self.test_query.clone());
+
+        let builder = r2d2::Pool::builder()
+            .max_size(self.max_size)
+            .min_idle(self.min_idle)
+            .connection_timeout(self.connection_timeout)
+            .idle_timeout(self.idle_timeout)
+            .max_lifetime(self.max_lifetime)
+            .test_on_check_out(self.test_on_check_out);
+
+        #[cfg(feature = "metrics")]
+        let builder = builder.event_handler(Box::new(MetricsEventHandler));
+
+        builder.build(manager)
     }
 }
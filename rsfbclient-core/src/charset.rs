@@ -5,7 +5,7 @@
 use encoding::{all, types::EncodingRef, DecoderTrap, EncoderTrap};
 use std::{borrow::Cow, fmt, str, str::FromStr};
 
-use crate::FbError;
+use crate::{FbError, SqlType};
 
 /// Charset definition. Used to encode/decode the
 /// strings.
@@ -63,6 +63,50 @@ impl Charset {
             })
         }
     }
+
+    /// The connection charset is `NONE`, so the server doesn't transliterate
+    /// the text and it's returned as bytes
+    pub fn is_none(&self) -> bool {
+        self.on_firebird.eq_ignore_ascii_case("NONE")
+    }
+
+    /// Decode the bytes of a text column. With the [`NONE`] charset the bytes
+    /// are returned as `Text` if they are valid utf-8, or as they are,
+    /// in a `Binary` value
+    pub fn decode_text<'a, B>(&self, bytes: B) -> Result<SqlType, FbError>
+    where
+        B: Into<Cow<'a, [u8]>>,
+    {
+        if self.is_none() {
+            Ok(match String::from_utf8(bytes.into().into_owned()) {
+                Ok(s) => SqlType::Text(s),
+                Err(e) => SqlType::Binary(e.into_bytes()),
+            })
+        } else {
+            self.decode(bytes).map(SqlType::Text)
+        }
+    }
+}
+
+impl Charset {
+    /// Check if the charset name is one of the charsets known by firebird
+    /// ([`FIREBIRD_CHARSETS`]), to catch typos like `UTF-8` instead of `UTF8`
+    /// before connecting
+    pub fn validate(&self) -> Result<(), FbError> {
+        if FIREBIRD_CHARSETS
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(self.on_firebird))
+        {
+            Ok(())
+        } else {
+            Err(format!(
+                "Unknown firebird charset '{}', the valid names are: {}",
+                self.on_firebird,
+                FIREBIRD_CHARSETS.join(", ")
+            )
+            .into())
+        }
+    }
 }
 
 impl Clone for Charset {
@@ -101,9 +145,11 @@ impl FromStr for Charset {
             "koi8u" => Ok(KOI8_U),
             "eucjp" => Ok(EUC_JP),
             "big52003" => Ok(BIG5_2003),
+            "none" => Ok(NONE),
             _ => Err(FbError::from(format!(
-                "'{}' doesn't represent any charset",
-                s
+                "'{}' doesn't represent any charset, the supported are: {}",
+                s,
+                SUPPORTED_CHARSETS.join(", ")
             ))),
         }
     }
@@ -123,12 +169,106 @@ impl PartialEq for Charset {
     }
 }
 
+/// Names of the charsets known by firebird, from `RDB$CHARACTER_SETS`
+pub const FIREBIRD_CHARSETS: &[&str] = &[
+    "NONE",
+    "OCTETS",
+    "ASCII",
+    "UNICODE_FSS",
+    "UTF8",
+    "SJIS_0208",
+    "EUCJ_0208",
+    "DOS437",
+    "DOS737",
+    "DOS775",
+    "DOS850",
+    "DOS852",
+    "DOS857",
+    "DOS858",
+    "DOS860",
+    "DOS861",
+    "DOS862",
+    "DOS863",
+    "DOS864",
+    "DOS865",
+    "DOS866",
+    "DOS869",
+    "ISO8859_1",
+    "ISO8859_2",
+    "ISO8859_3",
+    "ISO8859_4",
+    "ISO8859_5",
+    "ISO8859_6",
+    "ISO8859_7",
+    "ISO8859_8",
+    "ISO8859_9",
+    "ISO8859_13",
+    "WIN1250",
+    "WIN1251",
+    "WIN1252",
+    "WIN1253",
+    "WIN1254",
+    "WIN1255",
+    "WIN1256",
+    "WIN1257",
+    "WIN1258",
+    "CYRL",
+    "NEXT",
+    "KSC_5601",
+    "BIG_5",
+    "GB_2312",
+    "GBK",
+    "GB18030",
+    "CP943C",
+    "KOI8R",
+    "KOI8U",
+    "TIS620",
+];
+
+/// Names accepted by the `FromStr` implementation
+const SUPPORTED_CHARSETS: &[&str] = &[
+    "UTF8",
+    "ISO8859_1",
+    "ISO8859_2",
+    "ISO8859_3",
+    "ISO8859_4",
+    "ISO8859_5",
+    "ISO8859_6",
+    "ISO8859_7",
+    "ISO8859_13",
+    "WIN1250",
+    "WIN1251",
+    "WIN1252",
+    "WIN1253",
+    "WIN1254",
+    "WIN1256",
+    "WIN1257",
+    "WIN1258",
+    "ASCII",
+    "KOI8R",
+    "KOI8U",
+    "EUC_JP",
+    "BIG5_2003",
+    "NONE",
+];
+
 /// The default charset. Works in most cases
 pub const UTF_8: Charset = Charset {
     on_firebird: "UTF8",
     on_rust: None, // Will use the std from_utf8
 };
 
+/// No charset, so the server sends the text as it's stored, without transliterating
+/// it. The text columns can be read as `Vec<u8>`, like the `OCTETS` columns, or as
+/// `String` when the bytes are valid utf-8. Useful to read the legacy data stored with an unknown or mixed
+/// charset, or to keep the exact bytes. The charset of the bytes is not known, so
+/// decoding them is up to the application. The sql and the string parameters are sent
+/// in UTF-8 and stored as they are, even in the columns with another charset
+pub const NONE: Charset = Charset {
+    on_firebird: "NONE",
+    on_rust: None,
+};
+
 /// Western Europe. Latin 1
 pub const ISO_8859_1: Charset = Charset {
     on_firebird: "ISO8859_1",
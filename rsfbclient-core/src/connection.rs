@@ -2,6 +2,7 @@
 
 use num_enum::TryFromPrimitive;
 use std::str::FromStr;
+use std::time::Duration;
 
 use crate::*;
 
@@ -31,6 +32,9 @@ pub trait FirebirdClientDbOps: Send {
     /// via this type when a new attachment is requested
     type AttachmentConfig: Send + Clone;
 
+    /// A token to cancel the operations running in a database attachment
+    type CancelHandle: FirebirdClientCancelOps;
+
     /// Create a new attachment to a database with the provided configuration
     /// Returns a database handle on success
     fn attach_database(
@@ -49,8 +53,164 @@ pub trait FirebirdClientDbOps: Send {
     fn create_database(
         &mut self,
         config: &Self::AttachmentConfig,
-        page_size: Option<u32>,
+        create_config: &CreateDatabaseConfig,
+        dialect: Dialect,
     ) -> Result<Self::DbHandle, FbError>;
+
+    /// Create a token able to cancel the operations
+    /// running in the database attachment from another thread
+    fn cancel_handle(
+        &mut self,
+        db_handle: &mut Self::DbHandle,
+    ) -> Result<Self::CancelHandle, FbError>;
+
+    /// Check if the database attachment is still alive,
+    /// with a cheap database info request
+    fn ping(&mut self, db_handle: &mut Self::DbHandle) -> Result<(), FbError>;
+
+    /// Query the database for the requested info items,
+    /// returning the raw response buffer
+    fn database_info(
+        &mut self,
+        db_handle: &mut Self::DbHandle,
+        request_items: &[u8],
+        buffer_len: usize,
+    ) -> Result<Vec<u8>, FbError>;
+}
+
+/// Configuration used only on the database creation
+#[derive(Clone, Default)]
+pub struct CreateDatabaseConfig {
+    /// Database page size. Default: depends on firebird version
+    pub page_size: Option<u32>,
+
+    /// Default charset of the database. Default: NONE
+    pub db_charset: Option<Charset>,
+
+    /// Replace the database if it already exists, instead of returning an error
+    pub overwrite: bool,
+}
+
+/// Connect to each host in order, returning the first connection successful.
+///
+/// When all fail, the error lists the failure of each host, except with a
+/// single host, that returns its error unchanged.
+pub fn connect_failover<T, F>(hosts: &[(&str, u16)], mut connect: F) -> Result<T, FbError>
+where
+    F: FnMut(&str, u16) -> Result<T, FbError>,
+{
+    if let [(host, port)] = hosts {
+        return connect(host, *port);
+    }
+
+    let mut errors = Vec::with_capacity(hosts.len());
+    for (host, port) in hosts {
+        match connect(host, *port) {
+            Ok(conn) => return Ok(conn),
+            Err(e) => errors.push(format!("{}:{}: {}", host, port, e)),
+        }
+    }
+
+    Err(FbError::from(format!(
+        "Failed to connect to all the hosts: {}",
+        errors.join("; ")
+    )))
+}
+
+///Responsible for the operations with the services manager of the server
+pub trait FirebirdClientServiceOps: FirebirdClientDbOps {
+    /// A services manager handle
+    type SvcHandle: Send;
+
+    /// Attach to the services manager of the server configured
+    /// in the attachment configuration. The database name is not used
+    fn attach_service(
+        &mut self,
+        config: &Self::AttachmentConfig,
+    ) -> Result<Self::SvcHandle, FbError>;
+
+    /// Disconnect from the services manager
+    fn detach_service(&mut self, svc_handle: &mut Self::SvcHandle) -> Result<(), FbError>;
+
+    /// Start a service action, described by the service parameter buffer
+    fn start_service(
+        &mut self,
+        svc_handle: &mut Self::SvcHandle,
+        spb: &[u8],
+    ) -> Result<(), FbError>;
+
+    /// Query the services manager for the requested items,
+    /// returning the raw response buffer
+    fn query_service(
+        &mut self,
+        svc_handle: &mut Self::SvcHandle,
+        request_items: &[u8],
+        buffer_len: usize,
+    ) -> Result<Vec<u8>, FbError>;
+}
+
+///Responsible for the scrollable cursors, that can move to any row of the result set
+pub trait FirebirdClientScrollOps: FirebirdClientSqlOps {
+    /// Execute the prepared statement with parameters,
+    /// opening a scrollable cursor for the returned rows
+    fn execute_scrollable(
+        &mut self,
+        db_handle: &mut Self::DbHandle,
+        tr_handle: &mut Self::TrHandle,
+        stmt_handle: &mut Self::StmtHandle,
+        params: &[SqlType],
+    ) -> Result<(), FbError>;
+
+    /// Move the scrollable cursor, fetching the row in the new position
+    fn fetch_scroll(
+        &mut self,
+        db_handle: &mut Self::DbHandle,
+        tr_handle: &mut Self::TrHandle,
+        stmt_handle: &mut Self::StmtHandle,
+        op: FetchScroll,
+    ) -> Result<Option<Vec<Column>>, FbError>;
+}
+
+///Responsible for the transactions spanning more than one database,
+///committed with the two-phase commit
+pub trait FirebirdClientMultiDbOps: FirebirdClientSqlOps {
+    /// Start a transaction spanning all the database handles
+    fn begin_multi_transaction(
+        &mut self,
+        db_handles: &mut [Self::DbHandle],
+        isolation_level: TrIsolationLevel,
+    ) -> Result<Self::TrHandle, FbError>;
+
+    /// First phase of the two-phase commit, preparing the transaction to be
+    /// committed in all the databases. The description is stored in the
+    /// databases to help the recovery of the transaction if it is left in limbo
+    fn prepare_transaction(
+        &mut self,
+        tr_handle: &mut Self::TrHandle,
+        description: &[u8],
+    ) -> Result<(), FbError>;
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+/// Movements of a scrollable cursor
+pub enum FetchScroll {
+    Next,
+    Prior,
+    First,
+    Last,
+    /// Move to the position, starting from 1
+    Absolute(i32),
+    /// Move by the offset from the current position
+    Relative(i32),
+}
+
+///Responsible for cancelling operations running in a database attachment.
+///Implementations must be safe to use while another thread is executing
+///statements on the same attachment
+pub trait FirebirdClientCancelOps: Send {
+    /// Cancel the operation currently running in the attachment, making it
+    /// return a `FbError::Cancelled` error. Does nothing if no operation is running
+    fn cancel_operation(&self) -> Result<(), FbError>;
 }
 
 ///Responsible for actual transaction and statement execution
@@ -69,6 +229,20 @@ pub trait FirebirdClientSqlOps {
         isolation_level: TrIsolationLevel,
     ) -> Result<Self::TrHandle, FbError>;
 
+    /// Start a new transaction with the isolation level and the data access mode.
+    /// The default implementation only starts the read-write transactions
+    fn begin_transaction_with_config(
+        &mut self,
+        db_handle: &mut Self::DbHandle,
+        conf: TransactionConfiguration,
+    ) -> Result<Self::TrHandle, FbError> {
+        if conf.data_access != TrDataAccessMode::ReadWrite {
+            return Err("The read-only transactions are not supported by this client".into());
+        }
+
+        self.begin_transaction(db_handle, conf.isolation)
+    }
+
     /// Commit / Rollback a transaction
     fn transaction_operation(
         &mut self,
@@ -76,6 +250,15 @@ pub trait FirebirdClientSqlOps {
         op: TrOp,
     ) -> Result<(), FbError>;
 
+    /// Query the transaction for the requested info items,
+    /// returning the raw response buffer
+    fn transaction_info(
+        &mut self,
+        tr_handle: &mut Self::TrHandle,
+        request_items: &[u8],
+        buffer_len: usize,
+    ) -> Result<Vec<u8>, FbError>;
+
     /// Execute a sql immediately, without returning rows
     fn exec_immediate(
         &mut self,
@@ -95,6 +278,40 @@ pub trait FirebirdClientSqlOps {
         sql: &str,
     ) -> Result<(StmtType, Self::StmtHandle), FbError>;
 
+    /// Describe the output columns of a prepared statement
+    fn columns(&mut self, stmt_handle: &mut Self::StmtHandle) -> Result<Vec<ColumnMeta>, FbError>;
+
+    /// Describe the input parameters of a prepared statement
+    fn params(&mut self, stmt_handle: &mut Self::StmtHandle) -> Result<Vec<ParamMeta>, FbError>;
+
+    /// Name the cursor of a prepared statement, to be referenced
+    /// by the positioned updates and deletes (`WHERE CURRENT OF`)
+    fn set_cursor_name(
+        &mut self,
+        stmt_handle: &mut Self::StmtHandle,
+        name: &str,
+    ) -> Result<(), FbError>;
+
+    /// Set the execution timeout of a prepared statement, in milliseconds, sent to
+    /// the server with its next executions. Zero disables it. Requires Firebird 4+
+    fn set_statement_timeout(
+        &mut self,
+        stmt_handle: &mut Self::StmtHandle,
+        timeout_ms: u32,
+    ) -> Result<(), FbError> {
+        let _ = (stmt_handle, timeout_ms);
+        Err("The statement timeout is not supported by this client".into())
+    }
+
+    /// Query the prepared statement for the requested info items,
+    /// returning the raw response buffer
+    fn statement_info(
+        &mut self,
+        stmt_handle: &mut Self::StmtHandle,
+        request_items: &[u8],
+        buffer_len: usize,
+    ) -> Result<Vec<u8>, FbError>;
+
     /// Closes or drops a statement
     fn free_statement(
         &mut self,
@@ -109,7 +326,7 @@ pub trait FirebirdClientSqlOps {
         db_handle: &mut Self::DbHandle,
         tr_handle: &mut Self::TrHandle,
         stmt_handle: &mut Self::StmtHandle,
-        params: Vec<SqlType>,
+        params: &[SqlType],
     ) -> Result<usize, FbError>;
 
     /// Execute the prepared statement
@@ -122,7 +339,7 @@ pub trait FirebirdClientSqlOps {
         db_handle: &mut Self::DbHandle,
         tr_handle: &mut Self::TrHandle,
         stmt_handle: &mut Self::StmtHandle,
-        params: Vec<SqlType>,
+        params: &[SqlType],
     ) -> Result<Vec<Column>, FbError>;
 
     /// Fetch rows from the executed statement, coercing the types
@@ -133,6 +350,23 @@ pub trait FirebirdClientSqlOps {
         tr_handle: &mut Self::TrHandle,
         stmt_handle: &mut Self::StmtHandle,
     ) -> Result<Option<Vec<Column>>, FbError>;
+
+    /// Fetch the values of the next row, like `fetch`, without the column names,
+    /// for the callers that already have the statement description
+    fn fetch_values(
+        &mut self,
+        db_handle: &mut Self::DbHandle,
+        tr_handle: &mut Self::TrHandle,
+        stmt_handle: &mut Self::StmtHandle,
+    ) -> Result<Option<Vec<SqlType>>, FbError> {
+        Ok(self
+            .fetch(db_handle, tr_handle, stmt_handle)?
+            .map(|cols| cols.into_iter().map(|col| col.value).collect()))
+    }
+
+    /// Take the warnings returned by the server alongside the successful
+    /// results, like a string truncation, since the last call
+    fn take_warnings(&mut self) -> Vec<String>;
 }
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
@@ -161,6 +395,7 @@ impl FromStr for Dialect {
 }
 
 #[repr(u8)]
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
 /// Transaction isolation level
 pub enum TrIsolationLevel {
     /// Transactions can't see alterations commited after they started
@@ -177,6 +412,81 @@ impl Default for TrIsolationLevel {
     }
 }
 
+#[repr(u8)]
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Default)]
+/// Transaction data access mode
+pub enum TrDataAccessMode {
+    /// The transaction can read and change the data
+    #[default]
+    ReadWrite = ibase::isc_tpb_write as u8,
+    /// The transaction can only read the data, failing on any change
+    ReadOnly = ibase::isc_tpb_read as u8,
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Default)]
+/// Parameters of a new transaction
+pub struct TransactionConfiguration {
+    pub isolation: TrIsolationLevel,
+    pub data_access: TrDataAccessMode,
+}
+
+impl TransactionConfiguration {
+    /// Items of the transaction parameter buffer (tpb) for the isolation
+    /// level and data access mode, after the tpb version
+    pub fn tpb_items(self) -> Vec<u8> {
+        vec![
+            ibase::isc_tpb_version3 as u8,
+            self.isolation as u8,
+            self.data_access as u8,
+        ]
+    }
+}
+
+impl From<TrIsolationLevel> for TransactionConfiguration {
+    fn from(isolation: TrIsolationLevel) -> Self {
+        Self {
+            isolation,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Default)]
+/// What a transaction does when it needs a record locked by another transaction,
+/// like an update of a record changed and not yet committed by another one
+pub enum TrLockResolution {
+    /// Wait until the other transaction ends
+    #[default]
+    Wait,
+    /// Fail immediately with a lock conflict
+    NoWait,
+    /// Wait up to the timeout, failing with a lock conflict when it expires.
+    /// Firebird uses whole seconds, from 1 to 32767
+    WaitTimeout(Duration),
+}
+
+impl TrLockResolution {
+    /// Items of the transaction parameter buffer (tpb) for the lock resolution
+    pub fn tpb_items(self) -> Vec<u8> {
+        match self {
+            Self::Wait => vec![ibase::isc_tpb_wait as u8],
+            Self::NoWait => vec![ibase::isc_tpb_nowait as u8],
+            Self::WaitTimeout(timeout) => {
+                let secs = timeout.as_secs().clamp(1, i16::MAX as u64) as u32;
+
+                // The timeout value is prefixed by its length
+                let mut items = vec![
+                    ibase::isc_tpb_wait as u8,
+                    ibase::isc_tpb_lock_timeout as u8,
+                    4,
+                ];
+                items.extend_from_slice(&secs.to_le_bytes());
+                items
+            }
+        }
+    }
+}
+
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
 /// Commit / Rollback operations
 pub enum TrOp {
@@ -213,3 +523,49 @@ pub enum StmtType {
     SetGenerator = ibase::isc_info_sql_stmt_set_generator as u8,
     Savepoint = ibase::isc_info_sql_stmt_savepoint as u8,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn failover() {
+        let hosts = [("db1", 3050), ("db2", 3051), ("db3", 3050)];
+
+        // Stops on the first success
+        let mut tried = vec![];
+        let res = connect_failover(&hosts, |host, port| {
+            tried.push((host.to_string(), port));
+            if host == "db2" {
+                Ok(port)
+            } else {
+                Err(FbError::from("refused"))
+            }
+        });
+        assert_eq!(3051, res.unwrap());
+        assert_eq!(
+            vec![("db1".to_string(), 3050), ("db2".to_string(), 3051)],
+            tried
+        );
+
+        // Lists the failure of each host, in order
+        let res: Result<(), _> = connect_failover(&hosts, |host, _| {
+            Err(FbError::from(format!("{} down", host)))
+        });
+        assert_eq!(
+            "error: Failed to connect to all the hosts: db1:3050: error: db1 down; \
+             db2:3051: error: db2 down; db3:3050: error: db3 down",
+            res.unwrap_err().to_string()
+        );
+
+        // A single host returns its error unchanged
+        let res: Result<(), _> = connect_failover(&hosts[..1], |_, _| {
+            Err(FbError::Sql {
+                msg: "refused".to_string(),
+                code: -902,
+                status: vec![],
+            })
+        });
+        assert!(matches!(res, Err(FbError::Sql { code: -902, .. })));
+    }
+}
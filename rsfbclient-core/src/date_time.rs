@@ -1,4 +1,7 @@
-use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use chrono::{
+    DateTime, Datelike, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone,
+    Timelike, Utc,
+};
 
 use crate::{
     error::{err_column_null, err_type_conv},
@@ -7,6 +10,10 @@ use crate::{
 
 const FRACTION_TO_NANOS: u32 = 1e9 as u32 / ibase::ISC_TIME_SECONDS_PRECISION;
 
+/// Time zone id of the `+00:00` offset. The ids of the other offsets
+/// are this plus the offset in minutes, while the region ids starts from `u16::MAX` downwards
+const TZ_OFFSET_ZERO: i32 = 23 * 60 + 59;
+
 /// Convert a numeric day to [day, month, year]. (Ported from the firebird source)
 ///
 /// Calenders are divided into 4 year cycles: 3 non-leap years, and 1 leap year.
@@ -70,7 +77,9 @@ pub fn encode_date(date: NaiveDate) -> ibase::ISC_DATE {
         as ibase::ISC_DATE
 }
 
-/// Convert a numeric time to [hours, minutes, seconds] (Ported from the firebird source)
+/// Convert a numeric time to [hours, minutes, seconds] (Ported from the firebird source).
+///
+/// Keeps the fraction of the second, in the 1/10000 second precision of firebird
 pub fn decode_time(time: ibase::ISC_TIME) -> NaiveTime {
     let mut ntime = time;
 
@@ -87,12 +96,16 @@ pub fn decode_time(time: ibase::ISC_TIME) -> NaiveTime {
     chrono::NaiveTime::from_hms_nano(hours, minutes, seconds, fraction * FRACTION_TO_NANOS)
 }
 
-/// Convert a [hours, minutes, seconds] to a numeric time (Ported from the firebird source)
+/// Convert a [hours, minutes, seconds] to a numeric time (Ported from the firebird source).
+///
+/// The fraction of the second is truncated to the 1/10000 second precision of firebird
 pub fn encode_time(time: chrono::NaiveTime) -> ibase::ISC_TIME {
     let hours = time.hour();
     let minutes = time.minute();
     let seconds = time.second();
-    let fraction = time.nanosecond() / FRACTION_TO_NANOS;
+    // Chrono represents the leap seconds with more than 1s in the nanoseconds,
+    // kept in the last fraction of the second instead of overflowing to the next one
+    let fraction = time.nanosecond().min(999_999_999) / FRACTION_TO_NANOS;
 
     ((hours * 60 + minutes) * 60 + seconds) * ibase::ISC_TIME_SECONDS_PRECISION + fraction
 }
@@ -110,6 +123,38 @@ pub fn encode_timestamp(dt: NaiveDateTime) -> ibase::ISC_TIMESTAMP {
     }
 }
 
+/// Convert a numeric utc timestamp with the offset of the time zone to a DateTime.
+///
+/// Region time zones are resolved to the offset by the server, in the `ext_offset`
+pub fn decode_timestamp_tz(
+    ts: ibase::ISC_TIMESTAMP_TZ_EX,
+) -> Result<DateTime<FixedOffset>, FbError> {
+    let offset = FixedOffset::east_opt(ts.ext_offset as i32 * 60)
+        .ok_or_else(|| FbError::from(format!("Invalid time zone offset: {}", ts.ext_offset)))?;
+
+    Ok(offset.from_utc_datetime(&decode_timestamp(ts.utc_timestamp)))
+}
+
+/// Convert a DateTime to a numeric utc timestamp, with the time zone id of the offset
+pub fn encode_timestamp_tz(dt: DateTime<FixedOffset>) -> ibase::ISC_TIMESTAMP_TZ {
+    ibase::ISC_TIMESTAMP_TZ {
+        utc_timestamp: encode_timestamp(dt.naive_utc()),
+        time_zone: (TZ_OFFSET_ZERO + dt.offset().local_minus_utc() / 60) as u16,
+    }
+}
+
+impl IntoParam for DateTime<FixedOffset> {
+    fn into_param(self) -> SqlType {
+        SqlType::TimestampTz(self)
+    }
+}
+
+impl IntoParam for DateTime<Utc> {
+    fn into_param(self) -> SqlType {
+        self.with_timezone(&self.offset().fix()).into_param()
+    }
+}
+
 impl IntoParam for NaiveDateTime {
     fn into_param(self) -> SqlType {
         SqlType::Timestamp(self)
@@ -165,3 +210,27 @@ impl ColumnToVal<chrono::NaiveDateTime> for Column {
         }
     }
 }
+
+impl ColumnToVal<DateTime<FixedOffset>> for Column {
+    fn to_val(self) -> Result<DateTime<FixedOffset>, FbError> {
+        match self.value {
+            SqlType::TimestampTz(ts) => Ok(ts),
+
+            SqlType::Null => Err(err_column_null("DateTime<FixedOffset>")),
+
+            col => err_type_conv(col, "DateTime<FixedOffset>"),
+        }
+    }
+}
+
+impl ColumnToVal<DateTime<Utc>> for Column {
+    fn to_val(self) -> Result<DateTime<Utc>, FbError> {
+        match self.value {
+            SqlType::TimestampTz(ts) => Ok(ts.with_timezone(&Utc)),
+
+            SqlType::Null => Err(err_column_null("DateTime<Utc>")),
+
+            col => err_type_conv(col, "DateTime<Utc>"),
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! Conversions of the exact numerics to `rust_decimal::Decimal`
+
+use rust_decimal::Decimal;
+use std::convert::TryFrom;
+
+use crate::{
+    error::{err_column_null, err_type_conv},
+    Column, ColumnToVal, FbError, IntoParam, SqlType,
+};
+
+/// Convert a firebird scaled integer (`value * 10^scale`) to a decimal
+pub fn decode_decimal(value: i128, scale: i16) -> Result<Decimal, FbError> {
+    let dec = Decimal::try_from_i128_with_scale(value, (-scale).max(0) as u32)
+        .map_err(|e| FbError::from(e.to_string()))?;
+
+    if scale > 0 {
+        dec.checked_mul(Decimal::from(10_i64.pow(scale as u32)))
+            .ok_or_else(|| "Decimal value out of range".into())
+    } else {
+        Ok(dec)
+    }
+}
+
+/// Convert a decimal to a firebird scaled integer (`value * 10^scale`),
+/// returns `None` if the digits doesn't fit in a `i64`
+pub fn encode_decimal(dec: Decimal) -> Option<(i64, i16)> {
+    let dec = dec.normalize();
+
+    let value = i64::try_from(dec.mantissa()).ok()?;
+
+    Some((value, -(dec.scale() as i16)))
+}
+
+impl IntoParam for Decimal {
+    fn into_param(self) -> SqlType {
+        match encode_decimal(self) {
+            Some((value, scale)) => SqlType::Numeric {
+                value: value as i128,
+                scale,
+            },
+            // Too many digits for a scaled integer, let firebird convert the text
+            None => SqlType::Text(self.to_string()),
+        }
+    }
+}
+
+impl ColumnToVal<Decimal> for Column {
+    fn to_val(self) -> Result<Decimal, FbError> {
+        match self.value {
+            SqlType::Numeric { value, scale } => decode_decimal(value, scale),
+
+            SqlType::Integer(i) => Ok(Decimal::from(i)),
+
+            SqlType::Int128(i) => decode_decimal(i, 0),
+
+            SqlType::Text(ref t) => t
+                .trim()
+                .parse()
+                .map_err(|e| format!("Can't convert '{}' to Decimal: {}", t, e).into()),
+
+            SqlType::Null => Err(err_column_null("Decimal")),
+
+            col => err_type_conv(col, "Decimal"),
+        }
+    }
+}
@@ -0,0 +1,122 @@
+//! Conversions of `chrono::Duration` to and from the numeric columns
+//!
+//! Firebird has no interval type, so this is only a convenience mapping of a common
+//! pattern, not a native type: the durations are stored as a count of a unit, chosen
+//! with the wrapper, [`DurationSecs`] or [`DurationMillis`], in a column like:
+//!
+//! ```sql
+//! create table job (elapsed_ms bigint, timeout numeric(18, 3))
+//! ```
+//!
+//! The fractions of the unit are sent as an exact numeric, rounded by firebird to the
+//! scale of the column, and read back from the `NUMERIC` columns, like `1.5` seconds
+//! in the `timeout` column above. The conversion must be requested explicitly,
+//! like `row.get::<DurationMillis>(0)`.
+
+use chrono::Duration;
+
+use crate::{
+    error::{err_column_null, err_type_conv},
+    Column, ColumnToVal, FbError, IntoParam, SqlType,
+};
+
+/// A `chrono::Duration` stored as a count of seconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DurationSecs(pub Duration);
+
+/// A `chrono::Duration` stored as a count of milliseconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DurationMillis(pub Duration);
+
+const NANOS_PER_SEC: i128 = 1_000_000_000;
+
+/// Scale of the nanoseconds in the seconds and in the milliseconds
+const SECS_NANOS_SCALE: i16 = -9;
+const MILLIS_NANOS_SCALE: i16 = -6;
+
+impl IntoParam for DurationSecs {
+    fn into_param(self) -> SqlType {
+        encode_duration(self.0, SECS_NANOS_SCALE)
+    }
+}
+
+impl IntoParam for DurationMillis {
+    fn into_param(self) -> SqlType {
+        encode_duration(self.0, MILLIS_NANOS_SCALE)
+    }
+}
+
+impl ColumnToVal<DurationSecs> for Column {
+    fn to_val(self) -> Result<DurationSecs, FbError> {
+        decode_duration(self, SECS_NANOS_SCALE, "DurationSecs").map(DurationSecs)
+    }
+}
+
+impl ColumnToVal<DurationMillis> for Column {
+    fn to_val(self) -> Result<DurationMillis, FbError> {
+        decode_duration(self, MILLIS_NANOS_SCALE, "DurationMillis").map(DurationMillis)
+    }
+}
+
+/// Convert the duration to a count of the unit, with the nanoseconds scale of the unit.
+///
+/// Whole counts are sent as integers, and the fractions as exact numerics
+fn encode_duration(duration: Duration, nanos_scale: i16) -> SqlType {
+    let secs = duration.num_seconds();
+    // The remainder is less than a second, so it always fits
+    let subsec_nanos = (duration - Duration::seconds(secs))
+        .num_nanoseconds()
+        .unwrap_or_default();
+
+    let nanos = secs as i128 * NANOS_PER_SEC + subsec_nanos as i128;
+    let unit_nanos = 10_i128.pow(-nanos_scale as u32);
+
+    if nanos % unit_nanos == 0 {
+        SqlType::Integer((nanos / unit_nanos) as i64)
+    } else {
+        SqlType::Numeric {
+            value: nanos,
+            scale: nanos_scale,
+        }
+    }
+}
+
+/// Convert a count of the unit, with the nanoseconds scale of the unit, to a duration
+fn decode_duration(col: Column, nanos_scale: i16, type_name: &str) -> Result<Duration, FbError> {
+    let (value, scale) = match col.value {
+        SqlType::Integer(i) => (i as i128, 0),
+
+        SqlType::Int128(i) => (i, 0),
+
+        SqlType::Numeric { value, scale } => (value, scale),
+
+        SqlType::Null => return Err(err_column_null(type_name)),
+
+        col => return err_type_conv(col, type_name),
+    };
+
+    let err_range = || FbError::from(format!("The duration is out of the {} range", type_name));
+
+    // Scale the value to nanoseconds, truncating the extra digits
+    let shift = scale - nanos_scale;
+    let nanos = if shift >= 0 {
+        10_i128
+            .checked_pow(shift as u32)
+            .and_then(|mul| value.checked_mul(mul))
+            .ok_or_else(err_range)?
+    } else {
+        10_i128
+            .checked_pow(-shift as u32)
+            .map(|div| value / div)
+            .unwrap_or_default()
+    };
+
+    let secs = nanos / NANOS_PER_SEC;
+    if secs.abs() > (i64::MAX / 1000) as i128 {
+        return Err(err_range());
+    }
+
+    Duration::seconds(secs as i64)
+        .checked_add(&Duration::nanoseconds((nanos % NANOS_PER_SEC) as i64))
+        .ok_or_else(err_range)
+}
@@ -4,12 +4,48 @@ use std::str::Utf8Error;
 use std::string::FromUtf8Error;
 use thiserror::Error;
 
-use crate::SqlType;
+use crate::{ibase, SqlType};
 
 #[derive(Debug, Error)]
 pub enum FbError {
     #[error("sql error {code}: {msg}")]
-    Sql { msg: String, code: i32 },
+    Sql {
+        msg: String,
+        code: i32,
+        /// Errors in the status vector, empty if not available
+        status: Vec<StatusItem>,
+    },
+
+    #[error("statement timeout: {msg}")]
+    Timeout {
+        msg: String,
+        /// Errors in the status vector, empty if not available
+        status: Vec<StatusItem>,
+    },
+
+    #[error("operation cancelled: {msg}")]
+    Cancelled {
+        msg: String,
+        /// Errors in the status vector, empty if not available
+        status: Vec<StatusItem>,
+    },
+
+    #[error("connection lost: {msg}")]
+    ConnectionLost {
+        msg: String,
+        /// Errors in the status vector, empty if not available
+        status: Vec<StatusItem>,
+    },
+
+    #[error("transaction lost: {msg}")]
+    TransactionLost { msg: String },
+
+    #[error("metadata changed: {msg}")]
+    MetadataChanged {
+        msg: String,
+        /// Errors in the status vector, empty if not available
+        status: Vec<StatusItem>,
+    },
 
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
@@ -18,6 +54,117 @@ pub enum FbError {
     Other(String),
 }
 
+/// Error from the status vector, with its gds code and the arguments of the message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusItem {
+    /// Gds code of the error, like `ibase::isc_unique_key_violation`
+    pub code: u32,
+    /// Arguments of the error message, like the name of the violated constraint
+    pub args: Vec<StatusArg>,
+}
+
+/// Argument of an error message in the status vector
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StatusArg {
+    Number(i32),
+    String(String),
+}
+
+impl FbError {
+    /// Build the error from the contents of a status vector,
+    /// using the gds codes to identify cancellations, timeouts,
+    /// lost connections and metadata changes
+    pub fn from_status(code: i32, msg: String, status: Vec<StatusItem>) -> Self {
+        let gds_codes: Vec<u32> = status.iter().map(|item| item.code).collect();
+
+        match &gds_codes[..] {
+            // Firebird reports an expired timeout as a cancellation
+            // followed by the timeout level
+            [ibase::isc_cancelled, ibase::isc_cfg_stmt_timeout
+            | ibase::isc_att_stmt_timeout
+            | ibase::isc_req_stmt_timeout, ..] => Self::Timeout { msg, status },
+            [ibase::isc_cancelled, ..] => Self::Cancelled { msg, status },
+            [ibase::isc_network_error
+            | ibase::isc_net_read_err
+            | ibase::isc_net_write_err
+            | ibase::isc_lost_db_connection
+            | ibase::isc_att_shutdown, ..] => Self::ConnectionLost { msg, status },
+            // A prepared statement using an altered object, or a ddl
+            // blocked by the prepared statements using the object
+            codes
+                if codes.iter().any(|code| {
+                    matches!(*code, ibase::isc_obsolete_metadata | ibase::isc_obj_in_use)
+                }) =>
+            {
+                Self::MetadataChanged { msg, status }
+            }
+            _ => Self::Sql { msg, code, status },
+        }
+    }
+
+    /// Chain of errors from the status vector, with the gds codes and the message
+    /// arguments, to identify a specific error, like a violated constraint.
+    ///
+    /// Only available in the errors returned by the server
+    pub fn status_vector(&self) -> Option<&[StatusItem]> {
+        match self {
+            Self::Sql { status, .. }
+            | Self::Timeout { status, .. }
+            | Self::Cancelled { status, .. }
+            | Self::ConnectionLost { status, .. }
+            | Self::MetadataChanged { status, .. }
+                if !status.is_empty() =>
+            {
+                Some(status)
+            }
+            _ => None,
+        }
+    }
+
+    /// True if the error was caused by the loss of the connection
+    /// with the server. The connection can't be used anymore
+    pub fn is_connection_lost(&self) -> bool {
+        match self {
+            Self::ConnectionLost { .. } => true,
+            Self::Io(e) => matches!(
+                e.kind(),
+                std::io::ErrorKind::BrokenPipe
+                    | std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::NotConnected
+                    | std::io::ErrorKind::UnexpectedEof
+                    | std::io::ErrorKind::TimedOut
+            ),
+            _ => false,
+        }
+    }
+
+    /// True if the error was caused by a record locked by another transaction, like
+    /// an update or a `SELECT ... WITH LOCK` of a record changed and not yet committed.
+    /// Only returned right away by the transactions started with `TrLockResolution::NoWait`,
+    /// or after the timeout with `TrLockResolution::WaitTimeout`, the others wait for the
+    /// lock. Also true for the update conflicts and the deadlocks.
+    ///
+    /// The transaction stays usable, so the operation can be skipped or retried later.
+    /// With `SELECT ... WITH LOCK SKIP LOCKED` (Firebird 5+), the locked records
+    /// are skipped instead
+    pub fn is_lock_conflict(&self) -> bool {
+        self.status_vector()
+            .map(|status| {
+                status.iter().any(|item| {
+                    matches!(
+                        item.code,
+                        ibase::isc_lock_conflict
+                            | ibase::isc_lock_timeout
+                            | ibase::isc_update_conflict
+                            | ibase::isc_deadlock
+                    )
+                })
+            })
+            .unwrap_or(false)
+    }
+}
+
 impl From<String> for FbError {
     fn from(msg: String) -> Self {
         Self::Other(msg)
@@ -49,6 +196,19 @@ pub fn err_column_null(type_name: &str) -> FbError {
     ))
 }
 
+pub fn err_out_of_range(
+    column: &str,
+    value: impl std::fmt::Display,
+    type_name: &str,
+    min: impl std::fmt::Display,
+    max: impl std::fmt::Display,
+) -> FbError {
+    FbError::Other(format!(
+        "The value {} of the column {} is out of the {} range, from {} to {}",
+        value, column, type_name, min, max
+    ))
+}
+
 pub fn err_type_conv<T>(from: SqlType, to: &str) -> Result<T, FbError> {
     Err(FbError::Other(format!(
         "Can't convert {:?} column to {}",
@@ -305,7 +305,27 @@ pub const SQL_TYPE_TIME: u32 = 560;
 pub const SQL_TYPE_DATE: u32 = 570;
 pub const SQL_INT64: u32 = 580;
 pub const SQL_BOOLEAN: u32 = 32764;
+pub const SQL_INT128: u32 = 32752;
+pub const SQL_TIMESTAMP_TZ: u32 = 32754;
+pub const SQL_TIME_TZ: u32 = 32756;
+pub const SQL_TIMESTAMP_TZ_EX: u32 = 32748;
+pub const SQL_TIME_TZ_EX: u32 = 32750;
 pub const SQL_NULL: u32 = 32766;
+pub const CS_BINARY: u32 = 1;
+pub const blr_text: u8 = 14;
+pub const blr_short: u8 = 7;
+pub const blr_long: u8 = 8;
+pub const blr_quad: u8 = 9;
+pub const blr_float: u8 = 10;
+pub const blr_double: u8 = 27;
+pub const blr_d_float: u8 = 11;
+pub const blr_timestamp: u8 = 35;
+pub const blr_varying: u8 = 37;
+pub const blr_cstring: u8 = 40;
+pub const blr_sql_date: u8 = 12;
+pub const blr_sql_time: u8 = 13;
+pub const blr_int64: u8 = 16;
+pub const blr_bool: u8 = 23;
 pub const SQL_DATE: u32 = 510;
 pub const SQL_DIALECT_V5: u32 = 1;
 pub const SQL_DIALECT_V6_TRANSITION: u32 = 2;
@@ -425,6 +445,12 @@ pub const isc_dpb_config: u32 = 87;
 pub const isc_dpb_nolinger: u32 = 88;
 pub const isc_dpb_reset_icu: u32 = 89;
 pub const isc_dpb_map_attach: u32 = 90;
+pub const isc_dpb_session_time_zone: u32 = 91;
+pub const isc_dpb_set_db_replica: u32 = 92;
+pub const isc_dpb_set_bind: u32 = 93;
+pub const isc_dpb_decfloat_round: u32 = 94;
+pub const isc_dpb_decfloat_traps: u32 = 95;
+pub const isc_dpb_clear_map: u32 = 96;
 pub const isc_dpb_address: u32 = 1;
 pub const isc_dpb_addr_protocol: u32 = 1;
 pub const isc_dpb_addr_endpoint: u32 = 2;
@@ -1676,6 +1702,9 @@ pub const isc_login_error: u32 = 335545106;
 pub const isc_already_opened: u32 = 335545107;
 pub const isc_bad_crypt_key: u32 = 335545108;
 pub const isc_encrypt_error: u32 = 335545109;
+pub const isc_cfg_stmt_timeout: u32 = 335545127;
+pub const isc_att_stmt_timeout: u32 = 335545128;
+pub const isc_req_stmt_timeout: u32 = 335545129;
 pub const isc_gfix_db_name: u32 = 335740929;
 pub const isc_gfix_invalid_sw: u32 = 335740930;
 pub const isc_gfix_incmp_sw: u32 = 335740932;
@@ -2318,6 +2347,19 @@ pub struct ISC_TIMESTAMP {
 }
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
+pub struct ISC_TIMESTAMP_TZ {
+    pub utc_timestamp: ISC_TIMESTAMP,
+    pub time_zone: ISC_USHORT,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct ISC_TIMESTAMP_TZ_EX {
+    pub utc_timestamp: ISC_TIMESTAMP,
+    pub time_zone: ISC_USHORT,
+    pub ext_offset: ISC_SHORT,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
 pub struct GDS_QUAD_t {
     pub gds_quad_high: ISC_LONG,
     pub gds_quad_low: ISC_ULONG,
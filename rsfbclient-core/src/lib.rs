@@ -4,33 +4,63 @@
 pub mod charset;
 mod connection;
 pub mod date_time;
+#[cfg(feature = "decimal")]
+pub mod decimal;
+pub mod duration;
 pub(crate) mod error;
 pub mod ibase;
 mod params;
 mod row;
+#[cfg(feature = "serde")]
+mod serialize;
+#[cfg(feature = "uuid")]
+pub mod uuid;
 
 pub use charset::Charset;
 pub use connection::*;
-pub use error::FbError;
+pub use error::{FbError, StatusArg, StatusItem};
 pub use params::*;
 pub use row::*;
 
-#[derive(Debug, Clone)]
-/// Sql parameter / column data
+#[derive(Clone)]
+/// Sql parameter / column data.
+///
+/// `Display` renders the value as text, with the timestamps in the ISO 8601 format,
+/// `NULL` for the nulls and only the first bytes of the large binaries, and `Debug`
+/// the same inside the name of the variant, like `Integer(10)`
 pub enum SqlType {
     Text(String),
 
     Integer(i64),
 
+    /// Only works in fb >= 4.0
+    Int128(i128),
+
+    /// Exact numeric (`NUMERIC` / `DECIMAL`), representing `value * 10^scale`.
+    ///
+    /// Values outside of the `i64` range only works in fb >= 4.0
+    Numeric {
+        value: i128,
+        scale: i16,
+    },
+
     Floating(f64),
 
     Timestamp(chrono::NaiveDateTime),
 
+    /// Only works in fb >= 4.0
+    TimestampTz(chrono::DateTime<chrono::FixedOffset>),
+
     Binary(Vec<u8>),
 
     /// Only works in fb >= 3.0
     Boolean(bool),
 
+    /// Elements of an `ARRAY` column, nested for each extra dimension.
+    ///
+    /// Only read by the native client
+    Array(Vec<SqlType>),
+
     Null,
 }
 
@@ -39,4 +69,13 @@ impl SqlType {
     pub fn is_null(&self) -> bool {
         matches!(self, Null)
     }
+
+    /// Returns `true` if the value needs the 128 bits integer representation,
+    /// as it doesn't fit in an `i64`
+    pub fn is_int128(&self) -> bool {
+        match self {
+            Int128(i) | Numeric { value: i, .. } => i64::try_from(*i).is_err(),
+            _ => false,
+        }
+    }
 }
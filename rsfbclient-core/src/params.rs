@@ -2,7 +2,7 @@
 
 use crate::{error::FbError, ibase, SqlType};
 use regex::{Captures, Regex};
-use std::collections::HashMap;
+use std::{borrow::Cow, collections::HashMap};
 
 pub use SqlType::*;
 
@@ -21,16 +21,49 @@ impl SqlType {
                 }
             }
             Integer(_) => (ibase::SQL_INT64 + 1, 0),
+            Int128(_) | Numeric { .. } => {
+                if self.is_int128() {
+                    (ibase::SQL_INT128 + 1, 0)
+                } else {
+                    (ibase::SQL_INT64 + 1, 0)
+                }
+            }
             Floating(_) => (ibase::SQL_DOUBLE + 1, 0),
             Timestamp(_) => (ibase::SQL_TIMESTAMP + 1, 0),
+            TimestampTz(_) => (ibase::SQL_TIMESTAMP_TZ + 1, 0),
             Null => (ibase::SQL_TEXT + 1, 0),
             Binary(_) => (ibase::SQL_BLOB + 1, 0),
             Boolean(_) => (ibase::SQL_BOOLEAN + 1, 0),
+            Array(_) => (ibase::SQL_ARRAY + 1, 0),
         }
     }
 }
 
-/// Implemented for types that can be sent as parameters
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Description of a statement parameter, with the type expected by the server
+pub struct ParamMeta {
+    /// Sql type code (`ibase::SQL_*`), without the nullable flag
+    pub sql_type: u32,
+
+    /// Blob subtype, or the charset id for the text types
+    pub sub_type: i16,
+
+    /// Length of the parameter data in bytes
+    pub length: u16,
+
+    /// Scale of the exact numerics: the real value is `data * 10.pow(scale)`
+    pub scale: i16,
+
+    /// The parameter accepts `NULL` values
+    pub nullable: bool,
+}
+
+/// Implemented for types that can be sent as parameters.
+///
+/// Implement it to send your own types, like an enum, converting it to one of the
+/// existing types. The `Option`, borrowed and `Into<SqlType>` variants come with it,
+/// and the type can be used in the parameters tuples, in the fields of the structs
+/// deriving `IntoParams` and, with `into()`, in the `Vec<SqlType>` parameters
 pub trait IntoParam {
     fn into_param(self) -> SqlType;
 }
@@ -53,6 +86,12 @@ impl IntoParam for i64 {
     }
 }
 
+impl IntoParam for i128 {
+    fn into_param(self) -> SqlType {
+        Int128(self)
+    }
+}
+
 impl IntoParam for bool {
     fn into_param(self) -> SqlType {
         Boolean(self)
@@ -100,7 +139,31 @@ where
     }
 }
 
-/// Implements `IntoParam` for all borrowed variants (&str, Cow and etc)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// A `NULL` parameter, without a rust type. Obtained with [`null`]
+pub struct NullParam;
+
+impl IntoParam for NullParam {
+    fn into_param(self) -> SqlType {
+        Null
+    }
+}
+
+/// A `NULL` parameter, for when there is no concrete type to use `Option::<T>::None`,
+/// like `(name, null())`.
+///
+/// Sent with the type the server expects for the parameter, as described on the prepare
+pub fn null() -> NullParam {
+    NullParam
+}
+
+/// Implements `IntoParam` for all borrowed variants (`&str`, `&String`, `&[u8]`,
+/// `&Vec<u8>`, Cow and etc), so the values can be bound without giving up the ownership.
+///
+/// This is not a zero-allocation path: the value is copied once into the owned `SqlType`
+/// on each execution, when the parameters are converted, so the borrow ends before the
+/// statement is executed, and the clients copy it again to their buffers. Binding without
+/// the copy would need a borrowed `SqlType`, with a lifetime in the public api
 impl<T, B> IntoParam for &B
 where
     B: ToOwned<Owned = T> + ?Sized,
@@ -121,6 +184,7 @@ where
     }
 }
 
+#[derive(Debug, Clone)]
 /// Parameters type
 pub enum ParamsType {
     /// Positional parameters, using '?'. This is the default option.
@@ -183,6 +247,13 @@ impl IntoParams for Vec<SqlType> {
     }
 }
 
+/// Same as the `Vec<SqlType>` implementation, cloning the values
+impl IntoParams for &[SqlType] {
+    fn to_params(self) -> ParamsType {
+        ParamsType::Positional(self.to_vec())
+    }
+}
+
 /// Represents 0 parameters
 impl IntoParams for () {
     fn to_params(self) -> ParamsType {
@@ -239,6 +310,25 @@ impls_into_params!(
     [O, o]
 );
 
+/// String literal of the sql, kept as is by the parsers below
+const STRING_LITERAL: &str = "'[^']*'";
+
+/// Words of the sql (keywords and identifiers), uppercased,
+/// skipping the string literals, the quoted identifiers and the comments
+pub fn sql_words(sql: &str) -> Vec<String> {
+    let rwords = Regex::new(&format!(
+        r#"(?s)({})|"[^"]*"|--[^\n]*|/\*.*?\*/|([\w$]+)"#,
+        STRING_LITERAL
+    ))
+    .unwrap();
+
+    rwords
+        .captures_iter(sql)
+        .filter_map(|caps| caps.get(2))
+        .map(|word| word.as_str().to_ascii_uppercase())
+        .collect()
+}
+
 /// Named params implementation.
 ///
 /// Works on top of firebird positional parameters (`?`)
@@ -251,7 +341,7 @@ impl NamedParams {
     /// Parse the sql statement and return a
     /// structure representing the named parameters found
     pub fn parse(raw_sql: &str) -> Result<Self, FbError> {
-        let rparams = Regex::new(r#"('[^']*')|:\w+"#)
+        let rparams = Regex::new(&format!(r#"({})|:\w+"#, STRING_LITERAL))
             .map_err(|e| FbError::from(format!("Error on start the regex for named params: {}", e)))
             .unwrap();
 
@@ -291,6 +381,15 @@ impl NamedParams {
         P: IntoParams,
     {
         match params.to_params() {
+            ParamsType::Positional(p) => Ok(p),
+            named => self.convert_ref(&named).map(Cow::into_owned),
+        }
+    }
+
+    /// Same as `convert`, but borrowing the parameters, so they
+    /// can be used again. Only the named parameters are copied
+    pub fn convert_ref<'p>(&self, params: &'p ParamsType) -> Result<Cow<'p, [SqlType]>, FbError> {
+        match params {
             ParamsType::Named(names) => {
                 let mut new_params = vec![];
 
@@ -305,9 +404,26 @@ impl NamedParams {
                     }
                 }
 
-                Ok(new_params)
+                Ok(Cow::Owned(new_params))
             }
-            ParamsType::Positional(p) => Ok(p),
+            ParamsType::Positional(p) => Ok(Cow::Borrowed(p)),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn words() {
+        assert_eq!(
+            vec!["SELECT", "A", "FROM", "RDB$DATABASE", "FOR", "UPDATE"],
+            sql_words("select a, 'for update' from rdb$database\nfor update")
+        );
+        assert_eq!(
+            vec!["SELECT", "FROM", "T"],
+            sql_words("select \"with lock\" from t -- with lock\n/* for\nupdate */")
+        );
+    }
+}
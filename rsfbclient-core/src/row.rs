@@ -1,20 +1,33 @@
 //! Sql column types and traits
 
 use crate::{
-    error::{err_column_null, err_type_conv},
+    error::{err_column_null, err_out_of_range, err_type_conv},
     FbError, SqlType,
 };
+use std::{collections::HashMap, fmt};
 
 pub use SqlType::*;
 
 /// A database row
+#[derive(Debug, Clone)]
 pub struct Row {
     pub cols: Vec<Column>,
 }
 
 impl Row {
+    /// Get the column value by the index or by the name.
+    ///
+    /// The names are compared ignoring the case, as firebird
+    /// uppercases the unquoted column names and aliases
+    pub fn get<T>(&self, idx: impl ColumnIndex) -> Result<T, FbError>
+    where
+        Column: ColumnToVal<T>,
+    {
+        self.get_by_index(idx.position(&self.cols)?)
+    }
+
     /// Get the column value by the index
-    pub fn get<T>(&self, idx: usize) -> Result<T, FbError>
+    pub fn get_by_index<T>(&self, idx: usize) -> Result<T, FbError>
     where
         Column: ColumnToVal<T>,
     {
@@ -25,6 +38,36 @@ impl Row {
         }
     }
 
+    /// Check if the column value is `NULL`, by the index or by the name.
+    ///
+    /// Unlike converting the value, distinguishes a `NULL` from a zero or empty value
+    pub fn is_null(&self, idx: impl ColumnIndex) -> Result<bool, FbError> {
+        self.cols
+            .get(idx.position(&self.cols)?)
+            .map(Column::is_null)
+            .ok_or_else(|| "This index doesn't exists".into())
+    }
+
+    /// Check if the column value is `NULL`, by the name
+    pub fn is_null_named(&self, name: &str) -> Result<bool, FbError> {
+        self.is_null(name)
+    }
+
+    /// Names of the columns, in the select order
+    pub fn column_names(&self) -> Vec<&str> {
+        self.cols.iter().map(|col| col.name.as_str()).collect()
+    }
+
+    /// Map the column names to the values, keeping the `NULL`s as `SqlType::Null`.
+    ///
+    /// If more than one column has the same name, only the last is kept
+    pub fn into_map(self) -> HashMap<String, SqlType> {
+        self.cols
+            .into_iter()
+            .map(|col| (col.name, col.value))
+            .collect()
+    }
+
     /// Get the values for all columns
     pub fn get_all<T>(self) -> Result<T, FbError>
     where
@@ -34,6 +77,98 @@ impl Row {
     }
 }
 
+/// Identifies a column of a row, by the index (`usize`) or by the name (`&str`)
+pub trait ColumnIndex {
+    /// Find the position of the column
+    fn position(&self, cols: &[Column]) -> Result<usize, FbError>;
+}
+
+impl ColumnIndex for usize {
+    fn position(&self, _cols: &[Column]) -> Result<usize, FbError> {
+        Ok(*self)
+    }
+}
+
+impl ColumnIndex for &str {
+    fn position(&self, cols: &[Column]) -> Result<usize, FbError> {
+        cols.iter()
+            .position(|col| col.name.eq_ignore_ascii_case(self))
+            .ok_or_else(|| format!("This column doesn't exists: {}", self).into())
+    }
+}
+
+/// Binaries longer than this are truncated when formatted
+const BINARY_PREVIEW_LEN: usize = 32;
+
+impl fmt::Display for SqlType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Text(s) => f.write_str(s),
+            Integer(i) => write!(f, "{}", i),
+            Int128(i) => write!(f, "{}", i),
+            Numeric { value, scale } => f.write_str(&numeric_to_string(*value, *scale)),
+            Floating(n) => write!(f, "{}", n),
+            Timestamp(dt) => write!(f, "{}", dt.format("%Y-%m-%dT%H:%M:%S%.f")),
+            TimestampTz(dt) => write!(f, "{}", dt.format("%Y-%m-%dT%H:%M:%S%.f%:z")),
+            Binary(bytes) => fmt_binary(f, bytes),
+            Boolean(b) => write!(f, "{}", b),
+            Array(items) => {
+                f.write_str("[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                f.write_str("]")
+            }
+            Null => f.write_str("NULL"),
+        }
+    }
+}
+
+impl fmt::Debug for SqlType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Text(s) => return write!(f, "Text({:?})", s),
+            Array(items) => return f.debug_tuple("Array").field(items).finish(),
+            Null => return f.write_str("Null"),
+            // The raw value, so the scale is not lost in the formatting
+            Numeric { value, scale } => {
+                return f
+                    .debug_struct("Numeric")
+                    .field("value", value)
+                    .field("scale", scale)
+                    .finish()
+            }
+            Integer(_) => "Integer",
+            Int128(_) => "Int128",
+            Floating(_) => "Floating",
+            Timestamp(_) => "Timestamp",
+            TimestampTz(_) => "TimestampTz",
+            Binary(_) => "Binary",
+            Boolean(_) => "Boolean",
+        };
+
+        write!(f, "{}({})", name, self)
+    }
+}
+
+/// Formats the bytes in hex as a binary literal, like `x'0a1b'`,
+/// followed by the full length when truncated
+fn fmt_binary(f: &mut fmt::Formatter<'_>, bytes: &[u8]) -> fmt::Result {
+    f.write_str("x'")?;
+    for b in bytes.iter().take(BINARY_PREVIEW_LEN) {
+        write!(f, "{:02x}", b)?;
+    }
+
+    if bytes.len() > BINARY_PREVIEW_LEN {
+        write!(f, "...' ({} bytes)", bytes.len())
+    } else {
+        f.write_str("'")
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Column {
     pub value: SqlType,
@@ -44,10 +179,54 @@ impl Column {
     pub fn new(name: String, value: SqlType) -> Self {
         Column { name, value }
     }
+
+    /// The value is `NULL`
+    pub fn is_null(&self) -> bool {
+        self.value.is_null()
+    }
+}
+
+/// Formats as `name: value`, with the value formatted like the `SqlType`
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Description of a result set column, as reported by the server
+pub struct ColumnMeta {
+    /// Field name in the relation, empty for the expressions
+    pub name: String,
+
+    /// Column alias, used as the name of the fetched columns
+    pub alias: String,
+
+    /// Relation (table or view) of the field, empty for the expressions
+    pub relation: String,
+
+    /// Sql type code (`ibase::SQL_*`), without the nullable flag
+    pub sql_type: u32,
+
+    /// Blob subtype, or the charset id for the text types
+    pub sub_type: i16,
+
+    /// Length of the column data in bytes
+    pub length: u16,
+
+    /// Scale of the exact numerics: the real value is `data * 10.pow(scale)`
+    pub scale: i16,
+
+    /// The column can contain `NULL` values
+    pub nullable: bool,
 }
 
 #[allow(clippy::wrong_self_convention)]
-/// Define the conversion from the buffer to a value
+/// Define the conversion from the buffer to a value.
+///
+/// Implement `ColumnToVal<YourType>` for `Column` to read your own types, like an enum,
+/// converting from one of the existing types. The `Option` variant and the use in the
+/// tuples of `FromRow` come with it
 pub trait ColumnToVal<T> {
     fn to_val(self) -> Result<T, FbError>
     where
@@ -61,40 +240,66 @@ impl ColumnToVal<String> for Column {
 
             Integer(i) => Ok(i.to_string()),
 
+            Int128(i) => Ok(i.to_string()),
+
+            Numeric { value, scale } => Ok(numeric_to_string(value, scale)),
+
             Floating(f) => Ok(f.to_string()),
 
             Timestamp(ts) => Ok(ts.to_string()),
 
+            TimestampTz(ts) => Ok(ts.to_string()),
+
             Binary(_) => Err("This is a binary column. You cannot use string to access".into()),
 
             Boolean(bo) => Ok(bo.to_string()),
 
+            Array(_) => Err("This is an array column. You cannot use string to access".into()),
+
             Null => Err(err_column_null("String")),
         }
     }
 }
 
-impl ColumnToVal<i64> for Column {
-    fn to_val(self) -> Result<i64, FbError> {
-        match self.value {
-            Integer(i) => Ok(i),
+/// Implements the conversion of the integer columns, failing if the value
+/// is out of the type range
+macro_rules! impl_int_to_val {
+    ( $( $t: ty ),+ ) => {
+        $(
+            impl ColumnToVal<$t> for Column {
+                fn to_val(self) -> Result<$t, FbError> {
+                    let value = match self.value {
+                        Integer(i) => i as i128,
 
-            Null => Err(err_column_null("i64")),
+                        Int128(i) => i,
 
-            col => err_type_conv(col, "i64"),
-        }
-    }
-}
+                        Null => return Err(err_column_null(stringify!($t))),
 
-impl ColumnToVal<i32> for Column {
-    fn to_val(self) -> Result<i32, FbError> {
-        ColumnToVal::<i64>::to_val(self).map(|i| i as i32)
-    }
+                        col => return err_type_conv(col, stringify!($t)),
+                    };
+
+                    <$t>::try_from(value).map_err(|_| {
+                        err_out_of_range(&self.name, value, stringify!($t), <$t>::MIN, <$t>::MAX)
+                    })
+                }
+            }
+        )+
+    };
 }
 
-impl ColumnToVal<i16> for Column {
-    fn to_val(self) -> Result<i16, FbError> {
-        ColumnToVal::<i64>::to_val(self).map(|i| i as i16)
+impl_int_to_val!(i64, i32, i16);
+
+impl ColumnToVal<i128> for Column {
+    fn to_val(self) -> Result<i128, FbError> {
+        match self.value {
+            Int128(i) => Ok(i),
+
+            Integer(i) => Ok(i as i128),
+
+            Null => Err(err_column_null("i128")),
+
+            col => err_type_conv(col, "i128"),
+        }
     }
 }
 
@@ -103,6 +308,10 @@ impl ColumnToVal<f64> for Column {
         match self.value {
             Floating(f) => Ok(f),
 
+            Numeric { value, scale } => Ok(numeric_to_f64(value, scale)),
+
+            Int128(i) => Ok(i as f64),
+
             Null => Err(err_column_null("f64")),
 
             col => err_type_conv(col, "f64"),
@@ -112,7 +321,27 @@ impl ColumnToVal<f64> for Column {
 
 impl ColumnToVal<f32> for Column {
     fn to_val(self) -> Result<f32, FbError> {
-        ColumnToVal::<f64>::to_val(self).map(|i| i as f32)
+        match self.value {
+            // The `FLOAT` columns are widened to f64, so they are converted back exactly
+            Floating(f) => {
+                let narrowed = f as f32;
+
+                if f.is_nan() || narrowed as f64 == f {
+                    Ok(narrowed)
+                } else {
+                    Err(FbError::Other(format!(
+                        "Can't convert the {} value to f32 without losing precision, use f64 instead",
+                        f
+                    )))
+                }
+            }
+
+            Numeric { value, scale } => numeric_to_f32(value, scale),
+
+            Int128(i) => numeric_to_f32(i, 0),
+
+            _ => ColumnToVal::<f64>::to_val(self).map(|i| i as f32),
+        }
     }
 }
 
@@ -121,6 +350,9 @@ impl ColumnToVal<Vec<u8>> for Column {
         match self.value {
             Binary(b) => Ok(b),
 
+            // Like the valid utf-8 text columns of the connections with the `NONE` charset
+            Text(s) => Ok(s.into_bytes()),
+
             Null => Err(err_column_null("Vec<u8>")),
 
             col => err_type_conv(col, "Vec<u8>"),
@@ -140,6 +372,111 @@ impl ColumnToVal<bool> for Column {
     }
 }
 
+/// Implements the conversion of the array columns, with one or two dimensions
+macro_rules! impl_array_to_val {
+    ( $( $t: ty ),+ ) => {
+        $(
+            impl ColumnToVal<Vec<$t>> for Column {
+                fn to_val(self) -> Result<Vec<$t>, FbError> {
+                    match self.value {
+                        Array(items) => items
+                            .into_iter()
+                            .map(|value| Column::new(self.name.clone(), value).to_val())
+                            .collect(),
+
+                        Null => Err(err_column_null(concat!("Vec<", stringify!($t), ">"))),
+
+                        col => err_type_conv(col, concat!("Vec<", stringify!($t), ">")),
+                    }
+                }
+            }
+
+            impl ColumnToVal<Vec<Vec<$t>>> for Column {
+                fn to_val(self) -> Result<Vec<Vec<$t>>, FbError> {
+                    match self.value {
+                        Array(items) => items
+                            .into_iter()
+                            .map(|value| Column::new(self.name.clone(), value).to_val())
+                            .collect(),
+
+                        Null => Err(err_column_null(concat!("Vec<Vec<", stringify!($t), ">>"))),
+
+                        col => err_type_conv(col, concat!("Vec<Vec<", stringify!($t), ">>")),
+                    }
+                }
+            }
+        )+
+    };
+}
+
+impl_array_to_val!(String, i64, i32, i16, f64, f32, bool);
+
+/// Formats the exact numeric `value * 10^scale`
+pub(crate) fn numeric_to_string(value: i128, scale: i16) -> String {
+    if scale >= 0 {
+        return format!("{}{}", value, "0".repeat(scale as usize));
+    }
+
+    let scale = scale.unsigned_abs() as usize;
+    let digits = format!("{:0>width$}", value.unsigned_abs(), width = scale + 1);
+    let (int, frac) = digits.split_at(digits.len() - scale);
+
+    format!("{}{}.{}", if value < 0 { "-" } else { "" }, int, frac)
+}
+
+/// Converts the exact numeric `value * 10^scale` to the nearest float
+fn numeric_to_f64(value: i128, scale: i16) -> f64 {
+    if scale >= 0 {
+        value as f64 * 10_f64.powi(scale as i32)
+    } else {
+        // Dividing by the exact power of ten avoids an extra rounding
+        value as f64 / 10_f64.powi(-scale as i32)
+    }
+}
+
+/// Converts an exact numeric to f32, failing if the f32 doesn't convert back
+/// to the same value with the scale, like `16777217` or `2358.35321`
+fn numeric_to_f32(value: i128, scale: i16) -> Result<f32, FbError> {
+    let narrowed = numeric_to_f64(value, scale) as f32;
+
+    let back = if scale >= 0 {
+        (narrowed as f64 / 10_f64.powi(scale as i32)).round()
+    } else {
+        (narrowed as f64 * 10_f64.powi(-scale as i32)).round()
+    };
+
+    if back as i128 == value {
+        Ok(narrowed)
+    } else {
+        Err(FbError::Other(format!(
+            "Can't convert the {} value to f32 without losing precision, use f64 instead",
+            numeric_to_string(value, scale)
+        )))
+    }
+}
+
+/// Converts a double precision column with a scale to an exact numeric.
+///
+/// In dialect 1 the `NUMERIC` and `DECIMAL` with more than 9 digits are stored
+/// as double precision, keeping the declared scale
+pub fn scaled_float_to_sql(value: f64, scale: i16) -> SqlType {
+    let scaled = if scale >= 0 {
+        value / 10_f64.powi(scale as i32)
+    } else {
+        value * 10_f64.powi(-scale as i32)
+    }
+    .round();
+
+    if scale == 0 || !scaled.is_finite() || scaled.abs() >= i64::MAX as f64 {
+        Floating(value)
+    } else {
+        Numeric {
+            value: scaled as i128,
+            scale,
+        }
+    }
+}
+
 /// Implements for all nullable variants
 impl<T> ColumnToVal<Option<T>> for Column
 where
@@ -156,6 +493,10 @@ where
 
 /// Implemented for types that represents a list of values of columns
 pub trait FromRow {
+    /// Number of columns the query must return, checked after the prepare, so a
+    /// wrong query fails even without rows. Default: not checked
+    const COLUMNS: Option<usize> = None;
+
     fn try_from(row: Vec<Column>) -> Result<Self, FbError>
     where
         Self: std::marker::Sized;
@@ -223,3 +564,65 @@ macro_rules! impls_from_row {
 }
 
 impls_from_row!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let big = SqlType::Binary(vec![0xab; 1000]);
+        assert_eq!(
+            format!("x'{}...' (1000 bytes)", "ab".repeat(32)),
+            big.to_string()
+        );
+        assert_eq!("x'0a1b'", SqlType::Binary(vec![0x0a, 0x1b]).to_string());
+
+        let array = SqlType::Array(vec![
+            SqlType::Array(vec![SqlType::Integer(1), SqlType::Null]),
+            SqlType::Array(vec![SqlType::Boolean(true), SqlType::Floating(1.5)]),
+        ]);
+        assert_eq!("[[1, NULL], [true, 1.5]]", array.to_string());
+
+        let num = SqlType::Numeric {
+            value: -1234,
+            scale: -2,
+        };
+        assert_eq!("-12.34", num.to_string());
+
+        let tiny = SqlType::Numeric {
+            value: -5,
+            scale: i16::MIN,
+        };
+        assert!(tiny.to_string().starts_with("-0.000"));
+
+        let col = Column::new("NAME".to_string(), SqlType::Null);
+        assert_eq!("NAME: NULL", col.to_string());
+    }
+
+    #[test]
+    fn debug() {
+        let array = SqlType::Array(vec![
+            SqlType::Array(vec![SqlType::Integer(1), SqlType::Null]),
+            SqlType::Array(vec![SqlType::Boolean(true), SqlType::Floating(1.5)]),
+        ]);
+        assert_eq!(
+            "Array([Array([Integer(1), Null]), Array([Boolean(true), Floating(1.5)])])",
+            format!("{:?}", array)
+        );
+
+        let num = SqlType::Numeric {
+            value: 1200,
+            scale: -2,
+        };
+        assert_eq!("Numeric { value: 1200, scale: -2 }", format!("{:?}", num));
+        assert_eq!(
+            "Text(\"abc\")",
+            format!("{:?}", SqlType::Text("abc".into()))
+        );
+        assert_eq!(
+            "Binary(x'0a')",
+            format!("{:?}", SqlType::Binary(vec![0x0a]))
+        );
+    }
+}
@@ -0,0 +1,59 @@
+//! Serde serialization of the rows and columns
+
+use serde::ser::{Serialize, SerializeMap, SerializeStruct, Serializer};
+
+use crate::{row::numeric_to_string, Column, Row, SqlType};
+
+/// Serialized as the value, `NULL` as none, the timestamps as ISO-8601 strings,
+/// the exact numerics as decimal strings, to keep the precision, and the arrays as sequences
+impl Serialize for SqlType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            SqlType::Text(t) => serializer.serialize_str(t),
+            SqlType::Integer(i) => serializer.serialize_i64(*i),
+            SqlType::Int128(i) => serializer.serialize_i128(*i),
+            SqlType::Numeric { value, scale } => {
+                serializer.serialize_str(&numeric_to_string(*value, *scale))
+            }
+            SqlType::Floating(f) => serializer.serialize_f64(*f),
+            SqlType::Timestamp(ts) => serializer.collect_str(&ts.format("%Y-%m-%dT%H:%M:%S%.f")),
+            SqlType::TimestampTz(ts) => {
+                serializer.collect_str(&ts.format("%Y-%m-%dT%H:%M:%S%.f%:z"))
+            }
+            SqlType::Binary(b) => serializer.serialize_bytes(b),
+            SqlType::Boolean(b) => serializer.serialize_bool(*b),
+            SqlType::Array(a) => serializer.collect_seq(a),
+            SqlType::Null => serializer.serialize_none(),
+        }
+    }
+}
+
+/// Serialized as a struct with the `name` and `value` fields
+impl Serialize for Column {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut st = serializer.serialize_struct("Column", 2)?;
+        st.serialize_field("name", &self.name)?;
+        st.serialize_field("value", &self.value)?;
+        st.end()
+    }
+}
+
+/// Serialized as a map of the column names to the values
+impl Serialize for Row {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.cols.len()))?;
+        for col in &self.cols {
+            map.serialize_entry(&col.name, &col.value)?;
+        }
+        map.end()
+    }
+}
@@ -0,0 +1,48 @@
+//! Conversions of `uuid::Uuid` to and from the `OCTETS` columns
+//!
+//! The uuids are stored in the same byte order of `Uuid::as_bytes`, in a column like:
+//!
+//! ```sql
+//! create table example (id char(16) character set octets not null primary key)
+//! ```
+//!
+//! The values are read as binary data, so the conversion must be requested explicitly,
+//! like `row.get::<Uuid>(0)`. The same applies to the values generated by the server
+//! with `GEN_UUID()`, like `insert into example (id) values (gen_uuid()) returning id`,
+//! that follow the RFC 4122 byte order from Firebird 4.
+
+use uuid::Uuid;
+
+use crate::{
+    error::{err_column_null, err_type_conv},
+    Column, ColumnToVal, FbError, IntoParam, SqlType,
+};
+
+impl IntoParam for Uuid {
+    fn into_param(self) -> SqlType {
+        SqlType::Binary(self.as_bytes().to_vec())
+    }
+}
+
+impl ColumnToVal<Uuid> for Column {
+    fn to_val(self) -> Result<Uuid, FbError> {
+        match self.value {
+            SqlType::Binary(ref b) => Uuid::from_slice(b).map_err(|_| {
+                format!(
+                    "Can't convert {} bytes to Uuid, expected a CHAR(16) CHARACTER SET OCTETS column",
+                    b.len()
+                )
+                .into()
+            }),
+
+            SqlType::Text(ref t) => t
+                .trim()
+                .parse()
+                .map_err(|e| format!("Can't convert '{}' to Uuid: {}", t, e).into()),
+
+            SqlType::Null => Err(err_column_null("Uuid")),
+
+            col => err_type_conv(col, "Uuid"),
+        }
+    }
+}
@@ -4,7 +4,7 @@ extern crate proc_macro;
 
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{Data, DataStruct, DeriveInput, Fields};
+use syn::{ext::IdentExt, Data, DataStruct, DeriveInput, Field, Fields, Lit, Meta, NestedMeta};
 
 /// Derive an [IntoParams<T>](../trait.IntoParams.html) implementation for structs.
 ///
@@ -33,7 +33,8 @@ pub fn into_params_derive(input: TokenStream) -> TokenStream {
         .iter()
         .map(|field| field.ident.as_ref().expect("Field name required"))
         .map(|field| {
-            let field_str = field.to_string();
+            // Without the `r#` of the raw identifiers, like `r#type`
+            let field_str = field.unraw().to_string();
             quote! { #field_str.to_string(), self.#field.into_param() }
         });
 
@@ -54,3 +55,116 @@ pub fn into_params_derive(input: TokenStream) -> TokenStream {
 
     TokenStream::from(st_impl)
 }
+
+/// Derive a [FromRow](../trait.FromRow.html) implementation for structs.
+///
+/// This enables using the struct as the result of a query. In a struct with named
+/// fields, each field is mapped to the column with the same name, ignoring the case.
+/// Use `#[fb(rename = "COLUMN")]` on a field to map it to a column with another name.
+/// In a tuple struct, the fields are mapped to the columns by position.
+///
+/// The fields' types must be convertible from a column, like the ones used in tuples.
+///
+/// Note that `Option<T>` may be used as a field type to accept `null` values in the column.
+///
+/// ```ignore
+/// #[derive(FromRow)]
+/// struct User {
+///     id: i32,
+///     #[fb(rename = "NAME")]
+///     user_name: String,
+///     email: Option<String>,
+/// }
+///
+/// let users: Vec<User> = conn.query("SELECT id, name, email FROM t", ())?;
+/// ```
+#[proc_macro_derive(FromRow, attributes(fb))]
+pub fn from_row_derive(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+
+    let st_name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let st_build = match &input.data {
+        Data::Struct(DataStruct {
+            fields: Fields::Named(fields),
+            ..
+        }) => {
+            let st_fields = fields.named.iter().map(|field| {
+                let field_name = field.ident.as_ref().expect("Field name required");
+                let col_name = column_name(field).unwrap_or_else(|| field_name.unraw().to_string());
+                let err_msg = format!(
+                    "Column {} not found, required by the field {}::{}",
+                    col_name, st_name, field_name
+                );
+
+                quote! {
+                    #field_name: {
+                        let idx = ColumnIndex::position(&#col_name, &row.cols)
+                            .map_err(|_| FbError::from(#err_msg))?;
+                        row.get_by_index(idx)?
+                    }
+                }
+            });
+
+            quote! { Self { #(#st_fields),* } }
+        }
+        Data::Struct(DataStruct {
+            fields: Fields::Unnamed(fields),
+            ..
+        }) => {
+            let st_fields = (0..fields.unnamed.len()).map(|idx| {
+                let err_msg = format!(
+                    "Column {} not found, required by the field {}::{}",
+                    idx, st_name, idx
+                );
+
+                quote! {
+                    {
+                        if #idx >= row.cols.len() {
+                            return Err(FbError::from(#err_msg));
+                        }
+                        row.get_by_index(#idx)?
+                    }
+                }
+            });
+
+            quote! { Self ( #(#st_fields),* ) }
+        }
+        _ => panic!("expected a struct with fields"),
+    };
+
+    let st_impl = quote! {
+        impl #impl_generics rsfbclient::FromRow for #st_name #ty_generics #where_clause {
+            fn try_from(row: Vec<rsfbclient::Column>) -> Result<Self, rsfbclient::FbError> {
+                use rsfbclient::{ColumnIndex, FbError, Row};
+
+                let row = Row { cols: row };
+
+                Ok(#st_build)
+            }
+        }
+    };
+
+    TokenStream::from(st_impl)
+}
+
+/// Column name from the `#[fb(rename = "COLUMN")]` attribute
+fn column_name(field: &Field) -> Option<String> {
+    const EXPECTED: &str = "expected #[fb(rename = \"COLUMN\")]";
+
+    let attr = field.attrs.iter().find(|attr| attr.path.is_ident("fb"))?;
+
+    match attr.parse_meta() {
+        Ok(Meta::List(list)) if list.nested.len() == 1 => match list.nested.first() {
+            Some(NestedMeta::Meta(Meta::NameValue(nv))) if nv.path.is_ident("rename") => {
+                match &nv.lit {
+                    Lit::Str(name) => Some(name.value()),
+                    _ => panic!("{}", EXPECTED),
+                }
+            }
+            _ => panic!("{}", EXPECTED),
+        },
+        _ => panic!("{}", EXPECTED),
+    }
+}
@@ -3,7 +3,7 @@
 use crate::{
     ibase::{self, IBase},
     params::Params,
-    row::ColumnBuffer,
+    row::{self, ColumnBuffer},
     status::Status,
     xsqlda::XSqlDa,
 };
@@ -14,20 +14,55 @@ use std::{convert::TryFrom, io::Cursor, ptr};
 type NativeDbHandle = ibase::isc_db_handle;
 type NativeTrHandle = ibase::isc_tr_handle;
 type NativeStmtHandle = ibase::isc_stmt_handle;
+type NativeSvcHandle = ibase::isc_svc_handle;
 
 /// Client that wraps the native fbclient library
 pub struct NativeFbClient<T: LinkageMarker> {
     ibase: T::L,
     status: Status,
     charset: Charset,
+    /// Warnings returned by the server, until taken
+    warnings: Vec<String>,
+    /// Number of columns allocated in the xsqlda before describing the prepared statements
+    describe_hint: i16,
+    /// Lock resolution of the transactions started
+    lock_resolution: TrLockResolution,
+}
+
+/// Maximum number of warnings kept until taken, the newer are discarded
+const MAX_WARNINGS: usize = 100;
+
+/// Token to cancel the operations running in an attachment of the native client
+pub struct NativeCancelHandle<T: LinkageMarker> {
+    ibase: T::L,
+    db_handle: NativeDbHandle,
 }
 
 /// The remote part of native client configuration
 #[derive(Clone, Default)]
 pub struct RemoteConfig {
+    /// Empty to attach with the local protocol (XNET on windows), without the host and port
     pub host: String,
     pub port: u16,
     pub pass: String,
+    /// Other servers, as `(host, port)`, tried in order when
+    /// the attachment with the `host` and `port` fails
+    pub failover_hosts: Vec<(String, u16)>,
+}
+
+impl RemoteConfig {
+    /// Prefix of the database or services manager name, with the host and port.
+    /// IPv6 addresses are enclosed in brackets, so their `:` are not taken as
+    /// the separator of the database name
+    fn prefix(&self) -> String {
+        if self.host.is_empty() {
+            String::new()
+        } else if self.host.contains(':') {
+            format!("[{}]/{}:", self.host, self.port)
+        } else {
+            format!("{}/{}:", self.host, self.port)
+        }
+    }
 }
 
 /// Data associated with a prepared statement
@@ -38,6 +73,10 @@ pub struct StmtHandleData {
     xsqlda: XSqlDa,
     /// Buffers for the output xsqlda
     col_buffers: Vec<ColumnBuffer>,
+    /// Description of the columns, before the coercions
+    columns: Vec<ColumnMeta>,
+    /// Input xsqlda and buffers, reused by the executions
+    params: Params,
 }
 
 ///The common part of native client configuration (for both embedded/remote)
@@ -46,12 +85,14 @@ pub struct NativeFbAttachmentConfig {
     pub db_name: String,
     pub user: String,
     pub remote: Option<RemoteConfig>,
+    /// Extra items appended to the database parameter buffer, as `(tag, value)`
+    pub dpb_items: Vec<(u8, Vec<u8>)>,
 }
 
 /// A marker trait which can be used to
 /// obtain the associated client instance
 pub trait LinkageMarker: Send + Sync {
-    type L: IBase + Send;
+    type L: IBase + Send + Clone;
 }
 
 /// Configuration details for dynamic linking
@@ -70,6 +111,9 @@ impl DynLink {
             ibase: ibase::IBaseLinking,
             status: Default::default(),
             charset: self.0.clone(),
+            warnings: vec![],
+            describe_hint: 1,
+            lock_resolution: TrLockResolution::Wait,
         };
         result
     }
@@ -97,6 +141,9 @@ impl DynLoad {
             ibase: load_result,
             status: Default::default(),
             charset: self.charset.clone(),
+            warnings: vec![],
+            describe_hint: 1,
+            lock_resolution: TrLockResolution::Wait,
         };
 
         Ok(result)
@@ -106,32 +153,33 @@ impl DynLoad {
 impl<T: LinkageMarker> FirebirdClientDbOps for NativeFbClient<T> {
     type DbHandle = NativeDbHandle;
     type AttachmentConfig = NativeFbAttachmentConfig;
+    type CancelHandle = NativeCancelHandle<T>;
 
     fn attach_database(
         &mut self,
         config: &Self::AttachmentConfig,
     ) -> Result<NativeDbHandle, FbError> {
         let (dpb, conn_string) = self.build_dpb(config);
-        let mut handle = 0;
 
-        unsafe {
-            if self.ibase.isc_attach_database()(
-                &mut self.status[0],
-                conn_string.len() as i16,
-                conn_string.as_ptr() as *const _,
-                &mut handle,
-                dpb.len() as i16,
-                dpb.as_ptr() as *const _,
-            ) != 0
-            {
-                return Err(self.status.as_error(&self.ibase));
+        match &config.remote {
+            Some(remote) if !remote.failover_hosts.is_empty() => {
+                let hosts: Vec<(&str, u16)> = std::iter::once((remote.host.as_str(), remote.port))
+                    .chain(
+                        remote
+                            .failover_hosts
+                            .iter()
+                            .map(|(host, port)| (host.as_str(), *port)),
+                    )
+                    .collect();
+
+                connect_failover(&hosts, |host, port| {
+                    let conn_string = format!("{}/{}:{}", host, port, config.db_name);
+
+                    self.attach_conn_string(&dpb, &conn_string)
+                })
             }
+            _ => self.attach_conn_string(&dpb, &conn_string),
         }
-
-        // Assert that the handle is valid
-        debug_assert_ne!(handle, 0);
-
-        Ok(handle)
     }
 
     fn detach_database(&mut self, db_handle: &mut NativeDbHandle) -> Result<(), FbError> {
@@ -158,16 +206,31 @@ impl<T: LinkageMarker> FirebirdClientDbOps for NativeFbClient<T> {
     fn create_database(
         &mut self,
         config: &Self::AttachmentConfig,
-        page_size: Option<u32>,
+        create_config: &CreateDatabaseConfig,
+        dialect: Dialect,
     ) -> Result<NativeDbHandle, FbError> {
         let (mut dpb, conn_string) = self.build_dpb(config);
         let mut handle = 0;
 
-        if let Some(ps) = page_size {
+        if let Some(ps) = create_config.page_size {
             dpb.extend(&[ibase::isc_dpb_page_size as u8, 4]);
             dpb.write_u32::<LittleEndian>(ps)?;
         }
 
+        if let Some(charset) = &create_config.db_charset {
+            let charset = charset.on_firebird.bytes();
+
+            dpb.extend(&[ibase::isc_dpb_set_db_charset as u8, charset.len() as u8]);
+            dpb.extend(charset);
+        }
+
+        dpb.extend(&[ibase::isc_dpb_sql_dialect as u8, 4]);
+        dpb.write_u32::<LittleEndian>(dialect as u32)?;
+
+        if create_config.overwrite {
+            dpb.extend(&[ibase::isc_dpb_overwrite as u8, 1, 1]);
+        }
+
         unsafe {
             if self.ibase.isc_create_database()(
                 &mut self.status[0],
@@ -188,40 +251,104 @@ impl<T: LinkageMarker> FirebirdClientDbOps for NativeFbClient<T> {
 
         Ok(handle)
     }
-}
 
-impl<T: LinkageMarker> FirebirdClientSqlOps for NativeFbClient<T> {
-    type DbHandle = NativeDbHandle;
-    type TrHandle = NativeTrHandle;
-    type StmtHandle = StmtHandleData;
+    fn cancel_handle(
+        &mut self,
+        db_handle: &mut NativeDbHandle,
+    ) -> Result<NativeCancelHandle<T>, FbError> {
+        Ok(NativeCancelHandle {
+            ibase: self.ibase.clone(),
+            db_handle: *db_handle,
+        })
+    }
 
-    fn begin_transaction(
+    fn ping(&mut self, db_handle: &mut NativeDbHandle) -> Result<(), FbError> {
+        let items = [ibase::db_info_types_isc_info_ods_version as u8];
+        let mut buffer = [0u8; 16];
+
+        unsafe {
+            if self.ibase.isc_database_info()(
+                &mut self.status[0],
+                db_handle,
+                items.len() as i16,
+                items.as_ptr() as *const _,
+                buffer.len() as i16,
+                buffer.as_mut_ptr() as *mut _,
+            ) != 0
+            {
+                return Err(self.status.as_error(&self.ibase));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn database_info(
         &mut self,
-        db_handle: &mut Self::DbHandle,
-        isolation_level: TrIsolationLevel,
-    ) -> Result<Self::TrHandle, FbError> {
-        let mut handle = 0;
+        db_handle: &mut NativeDbHandle,
+        request_items: &[u8],
+        buffer_len: usize,
+    ) -> Result<Vec<u8>, FbError> {
+        let mut buffer = vec![0u8; buffer_len.min(i16::MAX as usize)];
 
-        // Transaction parameter buffer
-        let tpb = [ibase::isc_tpb_version3 as u8, isolation_level as u8];
+        unsafe {
+            if self.ibase.isc_database_info()(
+                &mut self.status[0],
+                db_handle,
+                request_items.len() as i16,
+                request_items.as_ptr() as *const _,
+                buffer.len() as i16,
+                buffer.as_mut_ptr() as *mut _,
+            ) != 0
+            {
+                return Err(self.status.as_error(&self.ibase));
+            }
+        }
 
-        #[repr(C)]
-        struct IscTeb {
-            db_handle: *mut ibase::isc_db_handle,
-            tpb_len: usize,
-            tpb_ptr: *const u8,
+        Ok(buffer)
+    }
+}
+
+impl<T: LinkageMarker> FirebirdClientCancelOps for NativeCancelHandle<T> {
+    fn cancel_operation(&self) -> Result<(), FbError> {
+        // The fbclient synchronizes the cancellation with the operations
+        // running in the attachment, so a local status and handle copy are enough
+        let mut status = Status::default();
+        let mut db_handle = self.db_handle;
+
+        unsafe {
+            if self.ibase.fb_cancel_operation()(
+                &mut status[0],
+                &mut db_handle,
+                ibase::fb_cancel_raise as u16,
+            ) != 0
+            {
+                return Err(status.as_error(&self.ibase));
+            }
         }
 
+        Ok(())
+    }
+}
+
+impl<T: LinkageMarker> FirebirdClientServiceOps for NativeFbClient<T> {
+    type SvcHandle = NativeSvcHandle;
+
+    fn attach_service(
+        &mut self,
+        config: &Self::AttachmentConfig,
+    ) -> Result<NativeSvcHandle, FbError> {
+        let (spb, service_name) = self.build_spb(config);
+        let mut handle = 0;
+
         unsafe {
-            if self.ibase.isc_start_multiple()(
+            if self.ibase.isc_service_attach()(
                 &mut self.status[0],
+                service_name.len() as u16,
+                service_name.as_ptr() as *const _,
                 &mut handle,
-                1,
-                &mut IscTeb {
-                    db_handle,
-                    tpb_len: tpb.len(),
-                    tpb_ptr: &tpb[0],
-                } as *mut _ as _,
+                spb.len() as u16,
+                spb.as_ptr() as *const _,
             ) != 0
             {
                 return Err(self.status.as_error(&self.ibase));
@@ -234,6 +361,88 @@ impl<T: LinkageMarker> FirebirdClientSqlOps for NativeFbClient<T> {
         Ok(handle)
     }
 
+    fn detach_service(&mut self, svc_handle: &mut NativeSvcHandle) -> Result<(), FbError> {
+        unsafe {
+            // Close the connection, if the handle is valid
+            if *svc_handle != 0
+                && self.ibase.isc_service_detach()(&mut self.status[0], svc_handle) != 0
+            {
+                return Err(self.status.as_error(&self.ibase));
+            }
+        }
+        Ok(())
+    }
+
+    fn start_service(
+        &mut self,
+        svc_handle: &mut NativeSvcHandle,
+        spb: &[u8],
+    ) -> Result<(), FbError> {
+        unsafe {
+            if self.ibase.isc_service_start()(
+                &mut self.status[0],
+                svc_handle,
+                ptr::null_mut(),
+                spb.len() as u16,
+                spb.as_ptr() as *const _,
+            ) != 0
+            {
+                return Err(self.status.as_error(&self.ibase));
+            }
+        }
+        Ok(())
+    }
+
+    fn query_service(
+        &mut self,
+        svc_handle: &mut NativeSvcHandle,
+        request_items: &[u8],
+        buffer_len: usize,
+    ) -> Result<Vec<u8>, FbError> {
+        let mut buffer = vec![0u8; buffer_len.min(u16::MAX as usize)];
+
+        unsafe {
+            if self.ibase.isc_service_query()(
+                &mut self.status[0],
+                svc_handle,
+                ptr::null_mut(),
+                0,
+                ptr::null(),
+                request_items.len() as u16,
+                request_items.as_ptr() as *const _,
+                buffer.len() as u16,
+                buffer.as_mut_ptr() as *mut _,
+            ) != 0
+            {
+                return Err(self.status.as_error(&self.ibase));
+            }
+        }
+
+        Ok(buffer)
+    }
+}
+
+impl<T: LinkageMarker> FirebirdClientSqlOps for NativeFbClient<T> {
+    type DbHandle = NativeDbHandle;
+    type TrHandle = NativeTrHandle;
+    type StmtHandle = StmtHandleData;
+
+    fn begin_transaction(
+        &mut self,
+        db_handle: &mut Self::DbHandle,
+        isolation_level: TrIsolationLevel,
+    ) -> Result<Self::TrHandle, FbError> {
+        self.begin_transaction_with_config(db_handle, isolation_level.into())
+    }
+
+    fn begin_transaction_with_config(
+        &mut self,
+        db_handle: &mut Self::DbHandle,
+        conf: TransactionConfiguration,
+    ) -> Result<Self::TrHandle, FbError> {
+        self.start_multiple(std::slice::from_mut(db_handle), conf)
+    }
+
     fn transaction_operation(
         &mut self,
         tr_handle: &mut Self::TrHandle,
@@ -260,6 +469,31 @@ impl<T: LinkageMarker> FirebirdClientSqlOps for NativeFbClient<T> {
         Ok(())
     }
 
+    fn transaction_info(
+        &mut self,
+        tr_handle: &mut Self::TrHandle,
+        request_items: &[u8],
+        buffer_len: usize,
+    ) -> Result<Vec<u8>, FbError> {
+        let mut buffer = vec![0u8; buffer_len.min(i16::MAX as usize)];
+
+        unsafe {
+            if self.ibase.isc_transaction_info()(
+                &mut self.status[0],
+                tr_handle,
+                request_items.len() as i16,
+                request_items.as_ptr() as *const _,
+                buffer.len() as i16,
+                buffer.as_mut_ptr() as *mut _,
+            ) != 0
+            {
+                return Err(self.status.as_error(&self.ibase));
+            }
+        }
+
+        Ok(buffer)
+    }
+
     fn exec_immediate(
         &mut self,
         db_handle: &mut Self::DbHandle,
@@ -283,6 +517,8 @@ impl<T: LinkageMarker> FirebirdClientSqlOps for NativeFbClient<T> {
                 return Err(self.status.as_error(&self.ibase));
             }
         }
+        self.collect_warnings();
+
         Ok(())
     }
 
@@ -297,7 +533,7 @@ impl<T: LinkageMarker> FirebirdClientSqlOps for NativeFbClient<T> {
 
         let mut handle = 0;
 
-        let mut xsqlda = XSqlDa::new(1);
+        let mut xsqlda = XSqlDa::new(self.describe_hint);
 
         let mut stmt_type = 0;
 
@@ -320,6 +556,7 @@ impl<T: LinkageMarker> FirebirdClientSqlOps for NativeFbClient<T> {
             {
                 return Err(self.status.as_error(&self.ibase));
             }
+            self.collect_warnings();
 
             let row_count = xsqlda.sqld;
 
@@ -362,6 +599,8 @@ impl<T: LinkageMarker> FirebirdClientSqlOps for NativeFbClient<T> {
         let stmt_type = StmtType::try_from(stmt_type as u8)
             .map_err(|_| FbError::from(format!("Invalid statement type: {}", stmt_type)))?;
 
+        let mut columns = Vec::with_capacity(xsqlda.sqld as usize);
+
         // Create the column buffers and set the xsqlda conercions
         let col_buffers = (0..xsqlda.sqld)
             .map(|col| {
@@ -369,6 +608,8 @@ impl<T: LinkageMarker> FirebirdClientSqlOps for NativeFbClient<T> {
                     .get_xsqlvar_mut(col as usize)
                     .ok_or_else(|| FbError::from("Error getting the xsqlvar"))?;
 
+                columns.push(row::column_meta(xcol)?);
+
                 ColumnBuffer::from_xsqlvar(xcol)
             })
             .collect::<Result<_, _>>()?;
@@ -379,10 +620,87 @@ impl<T: LinkageMarker> FirebirdClientSqlOps for NativeFbClient<T> {
                 handle,
                 xsqlda,
                 col_buffers,
+                columns,
+                params: Default::default(),
             },
         ))
     }
 
+    fn columns(&mut self, stmt_handle: &mut Self::StmtHandle) -> Result<Vec<ColumnMeta>, FbError> {
+        Ok(stmt_handle.columns.clone())
+    }
+
+    fn params(&mut self, stmt_handle: &mut Self::StmtHandle) -> Result<Vec<ParamMeta>, FbError> {
+        Params::describe(&self.ibase, &mut self.status, &mut stmt_handle.handle)
+    }
+
+    fn set_cursor_name(
+        &mut self,
+        stmt_handle: &mut Self::StmtHandle,
+        name: &str,
+    ) -> Result<(), FbError> {
+        let mut name = self.charset.encode(name)?.into_owned();
+        name.push(0);
+
+        unsafe {
+            if self.ibase.isc_dsql_set_cursor_name()(
+                &mut self.status[0],
+                &mut stmt_handle.handle,
+                name.as_ptr() as *const _,
+                0,
+            ) != 0
+            {
+                return Err(self.status.as_error(&self.ibase));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set_statement_timeout(
+        &mut self,
+        stmt_handle: &mut Self::StmtHandle,
+        timeout_ms: u32,
+    ) -> Result<(), FbError> {
+        unsafe {
+            if self.ibase.fb_dsql_set_timeout()(
+                &mut self.status[0],
+                &mut stmt_handle.handle,
+                timeout_ms,
+            ) != 0
+            {
+                return Err(self.status.as_error(&self.ibase));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn statement_info(
+        &mut self,
+        stmt_handle: &mut Self::StmtHandle,
+        request_items: &[u8],
+        buffer_len: usize,
+    ) -> Result<Vec<u8>, FbError> {
+        let mut buffer = vec![0u8; buffer_len.min(i16::MAX as usize)];
+
+        unsafe {
+            if self.ibase.isc_dsql_sql_info()(
+                &mut self.status[0],
+                &mut stmt_handle.handle,
+                request_items.len() as i16,
+                request_items.as_ptr() as *const _,
+                buffer.len() as i16,
+                buffer.as_mut_ptr() as *mut _,
+            ) != 0
+            {
+                return Err(self.status.as_error(&self.ibase));
+            }
+        }
+
+        Ok(buffer)
+    }
+
     fn free_statement(
         &mut self,
         stmt_handle: &mut Self::StmtHandle,
@@ -407,9 +725,9 @@ impl<T: LinkageMarker> FirebirdClientSqlOps for NativeFbClient<T> {
         db_handle: &mut Self::DbHandle,
         tr_handle: &mut Self::TrHandle,
         stmt_handle: &mut Self::StmtHandle,
-        params: Vec<SqlType>,
+        params: &[SqlType],
     ) -> Result<usize, FbError> {
-        let params = Params::new(
+        stmt_handle.params.bind(
             db_handle,
             tr_handle,
             &self.ibase,
@@ -425,19 +743,13 @@ impl<T: LinkageMarker> FirebirdClientSqlOps for NativeFbClient<T> {
                 tr_handle,
                 &mut stmt_handle.handle,
                 1,
-                if let Some(xsqlda) = &params.xsqlda {
-                    &**xsqlda
-                } else {
-                    ptr::null()
-                },
+                stmt_handle.params.xsqlda_ptr(),
             ) != 0
             {
                 return Err(self.status.as_error(&self.ibase));
             }
         }
-
-        // Just to make sure the params are not dropped too soon
-        drop(params);
+        self.collect_warnings();
 
         // Get the affected rows count
         let info_req = [ibase::isc_info_sql_records as std::os::raw::c_char];
@@ -498,22 +810,8 @@ impl<T: LinkageMarker> FirebirdClientSqlOps for NativeFbClient<T> {
         tr_handle: &mut Self::TrHandle,
         stmt_handle: &mut Self::StmtHandle,
     ) -> Result<Option<Vec<Column>>, FbError> {
-        unsafe {
-            let fetch_status = self.ibase.isc_dsql_fetch()(
-                &mut self.status[0],
-                &mut stmt_handle.handle,
-                1,
-                &*stmt_handle.xsqlda,
-            );
-
-            // 100 indicates that no more rows: http://docwiki.embarcadero.com/InterBase/2020/en/Isc_dsql_fetch()
-            if fetch_status == 100 {
-                return Ok(None);
-            }
-
-            if fetch_status != 0 {
-                return Err(self.status.as_error(&self.ibase));
-            };
+        if !self.fetch_row(stmt_handle)? {
+            return Ok(None);
         }
 
         let cols = stmt_handle
@@ -525,14 +823,33 @@ impl<T: LinkageMarker> FirebirdClientSqlOps for NativeFbClient<T> {
         Ok(Some(cols))
     }
 
+    fn fetch_values(
+        &mut self,
+        db_handle: &mut Self::DbHandle,
+        tr_handle: &mut Self::TrHandle,
+        stmt_handle: &mut Self::StmtHandle,
+    ) -> Result<Option<Vec<SqlType>>, FbError> {
+        if !self.fetch_row(stmt_handle)? {
+            return Ok(None);
+        }
+
+        let values = stmt_handle
+            .col_buffers
+            .iter()
+            .map(|cb| cb.to_value(db_handle, tr_handle, &self.ibase, &self.charset))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Some(values))
+    }
+
     fn execute2(
         &mut self,
         db_handle: &mut Self::DbHandle,
         tr_handle: &mut Self::TrHandle,
         stmt_handle: &mut Self::StmtHandle,
-        params: Vec<SqlType>,
+        params: &[SqlType],
     ) -> Result<Vec<Column>, FbError> {
-        let params = Params::new(
+        stmt_handle.params.bind(
             db_handle,
             tr_handle,
             &self.ibase,
@@ -548,20 +865,14 @@ impl<T: LinkageMarker> FirebirdClientSqlOps for NativeFbClient<T> {
                 tr_handle,
                 &mut stmt_handle.handle,
                 1,
-                if let Some(xsqlda) = &params.xsqlda {
-                    &**xsqlda
-                } else {
-                    ptr::null()
-                },
+                stmt_handle.params.xsqlda_ptr(),
                 &*stmt_handle.xsqlda,
             ) != 0
             {
                 return Err(self.status.as_error(&self.ibase));
             }
         }
-
-        // Just to make sure the params are not dropped too soon
-        drop(params);
+        self.collect_warnings();
 
         let rcol = stmt_handle
             .col_buffers
@@ -571,9 +882,201 @@ impl<T: LinkageMarker> FirebirdClientSqlOps for NativeFbClient<T> {
 
         Ok(rcol)
     }
+    fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+}
+
+/// The scrollable cursors are only available in the object oriented api of the
+/// fbclient (`IResultSet`), not in the isc api used by this client
+impl<T: LinkageMarker> FirebirdClientScrollOps for NativeFbClient<T> {
+    fn execute_scrollable(
+        &mut self,
+        _db_handle: &mut Self::DbHandle,
+        _tr_handle: &mut Self::TrHandle,
+        _stmt_handle: &mut Self::StmtHandle,
+        _params: &[SqlType],
+    ) -> Result<(), FbError> {
+        err_scroll_not_supported()
+    }
+
+    fn fetch_scroll(
+        &mut self,
+        _db_handle: &mut Self::DbHandle,
+        _tr_handle: &mut Self::TrHandle,
+        _stmt_handle: &mut Self::StmtHandle,
+        _op: FetchScroll,
+    ) -> Result<Option<Vec<Column>>, FbError> {
+        err_scroll_not_supported()
+    }
+}
+
+fn err_scroll_not_supported<T>() -> Result<T, FbError> {
+    Err(
+        "The scrollable cursors are not supported by the native client, use the pure rust client"
+            .into(),
+    )
+}
+
+impl<T: LinkageMarker> FirebirdClientMultiDbOps for NativeFbClient<T> {
+    fn begin_multi_transaction(
+        &mut self,
+        db_handles: &mut [Self::DbHandle],
+        isolation_level: TrIsolationLevel,
+    ) -> Result<Self::TrHandle, FbError> {
+        self.start_multiple(db_handles, isolation_level.into())
+    }
+
+    fn prepare_transaction(
+        &mut self,
+        tr_handle: &mut Self::TrHandle,
+        description: &[u8],
+    ) -> Result<(), FbError> {
+        if description.len() > u16::MAX as usize {
+            return Err("The transaction description is too long".into());
+        }
+
+        unsafe {
+            if self.ibase.isc_prepare_transaction2()(
+                &mut self.status[0],
+                tr_handle,
+                description.len() as u16,
+                description.as_ptr(),
+            ) != 0
+            {
+                return Err(self.status.as_error(&self.ibase));
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl<T: LinkageMarker> NativeFbClient<T> {
+    /// Number of columns allocated before describing the prepared statements. If the
+    /// statement has more columns, the description is done again with the needed size
+    pub fn set_describe_hint(&mut self, columns: u16) {
+        self.describe_hint = columns.clamp(1, i16::MAX as u16) as i16;
+    }
+
+    /// Set what the transactions started do when they find a record
+    /// locked by another transaction
+    pub fn set_lock_resolution(&mut self, lock_resolution: TrLockResolution) {
+        self.lock_resolution = lock_resolution;
+    }
+
+    /// Keep the warnings of the last successful call, up to `MAX_WARNINGS`
+    fn collect_warnings(&mut self) {
+        let warnings = self.status.warnings(&self.ibase);
+        let free = MAX_WARNINGS.saturating_sub(self.warnings.len());
+
+        self.warnings.extend(warnings.into_iter().take(free));
+    }
+
+    /// Fetch the next row to the output buffers, returning false after the last row
+    fn fetch_row(&mut self, stmt_handle: &mut StmtHandleData) -> Result<bool, FbError> {
+        unsafe {
+            let fetch_status = self.ibase.isc_dsql_fetch()(
+                &mut self.status[0],
+                &mut stmt_handle.handle,
+                1,
+                &*stmt_handle.xsqlda,
+            );
+
+            // 100 indicates that no more rows: http://docwiki.embarcadero.com/InterBase/2020/en/Isc_dsql_fetch()
+            if fetch_status == 100 {
+                return Ok(false);
+            }
+
+            // The row is still valid if the status only has warnings
+            if fetch_status != 0 && self.status.has_error() {
+                return Err(self.status.as_error(&self.ibase));
+            };
+        }
+        self.collect_warnings();
+
+        Ok(true)
+    }
+
+    /// Start a transaction in all the databases
+    fn start_multiple(
+        &mut self,
+        db_handles: &mut [NativeDbHandle],
+        conf: TransactionConfiguration,
+    ) -> Result<NativeTrHandle, FbError> {
+        let mut handle = 0;
+
+        // Transaction parameter buffer
+        let mut tpb = conf.tpb_items();
+        tpb.extend(self.lock_resolution.tpb_items());
+
+        #[repr(C)]
+        struct IscTeb {
+            db_handle: *mut ibase::isc_db_handle,
+            tpb_len: usize,
+            tpb_ptr: *const u8,
+        }
+
+        // One transaction existence block for each database
+        let mut tebs: Vec<IscTeb> = db_handles
+            .iter_mut()
+            .map(|db_handle| IscTeb {
+                db_handle,
+                tpb_len: tpb.len(),
+                tpb_ptr: &tpb[0],
+            })
+            .collect();
+
+        if tebs.is_empty() || tebs.len() > i16::MAX as usize {
+            return Err("Invalid number of databases for the transaction".into());
+        }
+
+        unsafe {
+            if self.ibase.isc_start_multiple()(
+                &mut self.status[0],
+                &mut handle,
+                tebs.len() as i16,
+                tebs.as_mut_ptr() as _,
+            ) != 0
+            {
+                return Err(self.status.as_error(&self.ibase));
+            }
+        }
+
+        // Assert that the handle is valid
+        debug_assert_ne!(handle, 0);
+
+        Ok(handle)
+    }
+
+    /// Attach to the database of the connection string
+    fn attach_conn_string(
+        &mut self,
+        dpb: &[u8],
+        conn_string: &str,
+    ) -> Result<NativeDbHandle, FbError> {
+        let mut handle = 0;
+
+        unsafe {
+            if self.ibase.isc_attach_database()(
+                &mut self.status[0],
+                conn_string.len() as i16,
+                conn_string.as_ptr() as *const _,
+                &mut handle,
+                dpb.len() as i16,
+                dpb.as_ptr() as *const _,
+            ) != 0
+            {
+                return Err(self.status.as_error(&self.ibase));
+            }
+        }
+
+        // Assert that the handle is valid
+        debug_assert_ne!(handle, 0);
+
+        Ok(handle)
+    }
+
     /// Build the dpb and the connection string
     ///
     /// Used by attach database operations
@@ -586,12 +1089,7 @@ impl<T: LinkageMarker> NativeFbClient<T> {
             None => db_name.clone(),
             Some(remote_conf) => {
                 password = Some(remote_conf.pass.as_str());
-                format!(
-                    "{}/{}:{}",
-                    remote_conf.host.as_str(),
-                    remote_conf.port,
-                    db_name.as_str()
-                )
+                format!("{}{}", remote_conf.prefix(), db_name.as_str())
             }
         };
 
@@ -613,9 +1111,53 @@ impl<T: LinkageMarker> NativeFbClient<T> {
             dpb.extend(&[ibase::isc_dpb_lc_ctype as u8, charset.len() as u8]);
             dpb.extend(charset);
 
+            // The path is sent in utf-8, so it must not be read in the system codepage.
+            // The ascii paths are the same in both, so the flag is only needed for the others
+            if !db_name.is_ascii() {
+                dpb.extend(&[ibase::isc_dpb_utf8_filename as u8, 0]);
+            }
+
+            for (tag, value) in &config.dpb_items {
+                dpb.extend(&[*tag, value.len() as u8]);
+                dpb.extend(value);
+            }
+
             dpb
         };
 
         (dpb, conn_string)
     }
+
+    /// Build the spb and the services manager name
+    ///
+    /// Used by attach service operations
+    fn build_spb(&mut self, config: &NativeFbAttachmentConfig) -> (Vec<u8>, String) {
+        let user = &config.user;
+        let mut password = None;
+
+        let service_name = match &config.remote {
+            None => "service_mgr".to_string(),
+            Some(remote_conf) => {
+                password = Some(remote_conf.pass.as_str());
+                format!("{}service_mgr", remote_conf.prefix())
+            }
+        };
+
+        let mut spb: Vec<u8> = Vec::with_capacity(64);
+
+        spb.extend(&[
+            ibase::isc_spb_version as u8,
+            ibase::isc_spb_current_version as u8,
+        ]);
+
+        spb.extend(&[ibase::isc_spb_user_name as u8, user.len() as u8]);
+        spb.extend(user.bytes());
+
+        if let Some(pass_str) = password {
+            spb.extend(&[ibase::isc_spb_password as u8, pass_str.len() as u8]);
+            spb.extend(pass_str.bytes());
+        };
+
+        (spb, service_name)
+    }
 }
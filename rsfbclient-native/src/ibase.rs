@@ -17,6 +17,7 @@ macro_rules! parse_functions {
       }
 
       #[cfg(feature = "linking")]
+      #[derive(Clone)]
       pub struct IBaseLinking;
       #[cfg(feature = "linking")]
       impl IBase for IBaseLinking {
@@ -24,6 +25,7 @@ macro_rules! parse_functions {
       }
 
       #[cfg(feature = "dynamic_loading")]
+      #[derive(Clone)]
       pub struct IBaseDynLoading(std::sync::Arc<libloading::Library>);
       #[cfg(feature = "dynamic_loading")]
       impl IBase for IBaseDynLoading {
@@ -124,27 +126,27 @@ parse_functions! {
     //         arg5: *mut ISC_SHORT,
     //     ) -> ISC_STATUS;
     // }
-    // extern "C" {
-    //     pub fn isc_array_get_slice(
-    //         arg1: *mut ISC_STATUS,
-    //         arg2: *mut isc_db_handle,
-    //         arg3: *mut isc_tr_handle,
-    //         arg4: *mut ISC_QUAD,
-    //         arg5: *const ISC_ARRAY_DESC,
-    //         arg6: *mut ::std::os::raw::c_void,
-    //         arg7: *mut ISC_LONG,
-    //     ) -> ISC_STATUS;
-    // }
-    // extern "C" {
-    //     pub fn isc_array_lookup_bounds(
-    //         arg1: *mut ISC_STATUS,
-    //         arg2: *mut isc_db_handle,
-    //         arg3: *mut isc_tr_handle,
-    //         arg4: *const ISC_SCHAR,
-    //         arg5: *const ISC_SCHAR,
-    //         arg6: *mut ISC_ARRAY_DESC,
-    //     ) -> ISC_STATUS;
-    // }
+    extern "C" {
+        pub fn isc_array_get_slice(
+            arg1: *mut ISC_STATUS,
+            arg2: *mut isc_db_handle,
+            arg3: *mut isc_tr_handle,
+            arg4: *mut ISC_QUAD,
+            arg5: *const ISC_ARRAY_DESC,
+            arg6: *mut ::std::os::raw::c_void,
+            arg7: *mut ISC_LONG,
+        ) -> ISC_STATUS;
+    }
+    extern "C" {
+        pub fn isc_array_lookup_bounds(
+            arg1: *mut ISC_STATUS,
+            arg2: *mut isc_db_handle,
+            arg3: *mut isc_tr_handle,
+            arg4: *const ISC_SCHAR,
+            arg5: *const ISC_SCHAR,
+            arg6: *mut ISC_ARRAY_DESC,
+        ) -> ISC_STATUS;
+    }
     // extern "C" {
     //     pub fn isc_array_lookup_desc(
     //         arg1: *mut ISC_STATUS,
@@ -276,16 +278,16 @@ parse_functions! {
            arg7: ::std::os::raw::c_short,
         ) -> ISC_STATUS;
     }
-    // extern "C" {
-    //     pub fn isc_database_info(
-    //         arg1: *mut ISC_STATUS,
-    //         arg2: *mut isc_db_handle,
-    //         arg3: ::std::os::raw::c_short,
-    //         arg4: *const ISC_SCHAR,
-    //         arg5: ::std::os::raw::c_short,
-    //         arg6: *mut ISC_SCHAR,
-    //     ) -> ISC_STATUS;
-    // }
+    extern "C" {
+        pub fn isc_database_info(
+            arg1: *mut ISC_STATUS,
+            arg2: *mut isc_db_handle,
+            arg3: ::std::os::raw::c_short,
+            arg4: *const ISC_SCHAR,
+            arg5: ::std::os::raw::c_short,
+            arg6: *mut ISC_SCHAR,
+        ) -> ISC_STATUS;
+    }
     // extern "C" {
     //     pub fn isc_decode_date(arg1: *const ISC_QUAD, arg2: *mut ::std::os::raw::c_void);
     // }
@@ -413,14 +415,21 @@ parse_functions! {
             arg7: *mut XSQLDA,
         ) -> ISC_STATUS;
     }
-    // extern "C" {
-    //     pub fn isc_dsql_set_cursor_name(
-    //         arg1: *mut ISC_STATUS,
-    //         arg2: *mut isc_stmt_handle,
-    //         arg3: *const ISC_SCHAR,
-    //         arg4: ::std::os::raw::c_ushort,
-    //     ) -> ISC_STATUS;
-    // }
+    extern "C" {
+        pub fn isc_dsql_set_cursor_name(
+            arg1: *mut ISC_STATUS,
+            arg2: *mut isc_stmt_handle,
+            arg3: *const ISC_SCHAR,
+            arg4: ::std::os::raw::c_ushort,
+        ) -> ISC_STATUS;
+    }
+    extern "C" {
+        pub fn fb_dsql_set_timeout(
+            arg1: *mut ISC_STATUS,
+            arg2: *mut isc_stmt_handle,
+            arg3: ISC_ULONG,
+        ) -> ISC_STATUS;
+    }
     extern "C" {
         pub fn isc_dsql_sql_info(
             arg1: *mut ISC_STATUS,
@@ -545,14 +554,14 @@ parse_functions! {
     //         arg7: *const ISC_UCHAR,
     //     ) -> ISC_STATUS;
     // }
-    // extern "C" {
-    //     pub fn isc_prepare_transaction2(
-    //         arg1: *mut ISC_STATUS_ARRAY,
-    //         arg2: *mut isc_tr_handle,
-    //         arg3: ISC_USHORT,
-    //         arg4: *const ISC_UCHAR,
-    //     ) -> ISC_STATUS;
-    // }
+    extern "C" {
+        pub fn isc_prepare_transaction2(
+            arg1: *mut ISC_STATUS,
+            arg2: *mut isc_tr_handle,
+            arg3: ISC_USHORT,
+            arg4: *const ISC_UCHAR,
+        ) -> ISC_STATUS;
+    }
     // extern "C" {
     //     pub fn isc_print_sqlerror(arg1: ISC_SHORT, arg2: *const ISC_STATUS);
     // }
@@ -634,16 +643,16 @@ parse_functions! {
     //         arg3: ::std::os::raw::c_short,
     //     );
     // }
-    // extern "C" {
-    //     pub fn isc_transaction_info(
-    //         arg1: *mut ISC_STATUS,
-    //         arg2: *mut isc_tr_handle,
-    //         arg3: ::std::os::raw::c_short,
-    //         arg4: *const ISC_SCHAR,
-    //         arg5: ::std::os::raw::c_short,
-    //         arg6: *mut ISC_SCHAR,
-    //     ) -> ISC_STATUS;
-    // }
+    extern "C" {
+        pub fn isc_transaction_info(
+            arg1: *mut ISC_STATUS,
+            arg2: *mut isc_tr_handle,
+            arg3: ::std::os::raw::c_short,
+            arg4: *const ISC_SCHAR,
+            arg5: ::std::os::raw::c_short,
+            arg6: *mut ISC_SCHAR,
+        ) -> ISC_STATUS;
+    }
     // extern "C" {
     //     pub fn isc_transact_request(
     //         arg1: *mut ISC_STATUS,
@@ -1203,41 +1212,41 @@ parse_functions! {
     // extern "C" {
     //     pub fn isc_baddress_s(arg1: *const ISC_SCHAR, arg2: *mut usize);
     // }
-    // extern "C" {
-    //     pub fn isc_service_attach(
-    //         arg1: *mut ISC_STATUS,
-    //         arg2: ::std::os::raw::c_ushort,
-    //         arg3: *const ISC_SCHAR,
-    //         arg4: *mut isc_svc_handle,
-    //         arg5: ::std::os::raw::c_ushort,
-    //         arg6: *const ISC_SCHAR,
-    //     ) -> ISC_STATUS;
-    // }
-    // extern "C" {
-    //     pub fn isc_service_detach(arg1: *mut ISC_STATUS, arg2: *mut isc_svc_handle) -> ISC_STATUS;
-    // }
-    // extern "C" {
-    //     pub fn isc_service_query(
-    //         arg1: *mut ISC_STATUS,
-    //         arg2: *mut isc_svc_handle,
-    //         arg3: *mut isc_resv_handle,
-    //         arg4: ::std::os::raw::c_ushort,
-    //         arg5: *const ISC_SCHAR,
-    //         arg6: ::std::os::raw::c_ushort,
-    //         arg7: *const ISC_SCHAR,
-    //         arg8: ::std::os::raw::c_ushort,
-    //         arg9: *mut ISC_SCHAR,
-    //     ) -> ISC_STATUS;
-    // }
-    // extern "C" {
-    //     pub fn isc_service_start(
-    //         arg1: *mut ISC_STATUS,
-    //         arg2: *mut isc_svc_handle,
-    //         arg3: *mut isc_resv_handle,
-    //         arg4: ::std::os::raw::c_ushort,
-    //         arg5: *const ISC_SCHAR,
-    //     ) -> ISC_STATUS;
-    // }
+    extern "C" {
+        pub fn isc_service_attach(
+            arg1: *mut ISC_STATUS,
+            arg2: ::std::os::raw::c_ushort,
+            arg3: *const ISC_SCHAR,
+            arg4: *mut isc_svc_handle,
+            arg5: ::std::os::raw::c_ushort,
+            arg6: *const ISC_SCHAR,
+        ) -> ISC_STATUS;
+    }
+    extern "C" {
+        pub fn isc_service_detach(arg1: *mut ISC_STATUS, arg2: *mut isc_svc_handle) -> ISC_STATUS;
+    }
+    extern "C" {
+        pub fn isc_service_query(
+            arg1: *mut ISC_STATUS,
+            arg2: *mut isc_svc_handle,
+            arg3: *mut isc_resv_handle,
+            arg4: ::std::os::raw::c_ushort,
+            arg5: *const ISC_SCHAR,
+            arg6: ::std::os::raw::c_ushort,
+            arg7: *const ISC_SCHAR,
+            arg8: ::std::os::raw::c_ushort,
+            arg9: *mut ISC_SCHAR,
+        ) -> ISC_STATUS;
+    }
+    extern "C" {
+        pub fn isc_service_start(
+            arg1: *mut ISC_STATUS,
+            arg2: *mut isc_svc_handle,
+            arg3: *mut isc_resv_handle,
+            arg4: ::std::os::raw::c_ushort,
+            arg5: *const ISC_SCHAR,
+        ) -> ISC_STATUS;
+    }
     // extern "C" {
     //     pub fn fb_shutdown(
     //         arg1: ::std::os::raw::c_uint,
@@ -1252,13 +1261,13 @@ parse_functions! {
     //         arg4: *mut ::std::os::raw::c_void,
     //     ) -> ISC_STATUS;
     // }
-    // extern "C" {
-    //     pub fn fb_cancel_operation(
-    //         arg1: *mut ISC_STATUS,
-    //         arg2: *mut isc_db_handle,
-    //         arg3: ISC_USHORT,
-    //     ) -> ISC_STATUS;
-    // }
+    extern "C" {
+        pub fn fb_cancel_operation(
+            arg1: *mut ISC_STATUS,
+            arg2: *mut isc_db_handle,
+            arg3: ISC_USHORT,
+        ) -> ISC_STATUS;
+    }
     // extern "C" {
     //     pub fn fb_ping(arg1: *mut ISC_STATUS, arg2: *mut isc_db_handle) -> ISC_STATUS;
     // }
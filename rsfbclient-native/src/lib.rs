@@ -8,6 +8,6 @@ pub(crate) mod status;
 pub(crate) mod varchar;
 pub(crate) mod xsqlda;
 
-pub use connection::{NativeFbAttachmentConfig, NativeFbClient, RemoteConfig};
+pub use connection::{NativeCancelHandle, NativeFbAttachmentConfig, NativeFbClient, RemoteConfig};
 
 pub use connection::{DynLink, DynLoad, LinkageMarker};
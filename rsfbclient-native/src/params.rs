@@ -5,75 +5,185 @@ use crate::{
     status::Status,
     xsqlda::XSqlDa,
 };
-use rsfbclient_core::{Charset, FbError, SqlType, MAX_TEXT_LENGTH};
+use rsfbclient_core::{Charset, FbError, ParamMeta, SqlType, MAX_TEXT_LENGTH};
 
 use ParamBufferData::*;
 
-/// Stores the data needed to send the parameters
+/// Maximum length of a blob segment
+const MAX_SEGMENT_LENGTH: usize = u16::MAX as usize;
+
+/// Stores the data needed to send the parameters, kept with the statement
+/// to reuse the xsqlda and the buffers on the next executions
+#[derive(Default)]
 pub struct Params {
-    /// Input xsqlda
-    pub(crate) xsqlda: Option<XSqlDa>,
+    /// Input xsqlda, described on the first execution
+    xsqlda: Option<XSqlDa>,
+
+    /// Types described by the server, restored before binding the values,
+    /// as the xsqlvars are changed to the types of the values
+    described: Vec<DescribedVar>,
 
     /// Data used by the xsqlda above
-    _buffers: Vec<ParamBuffer>,
+    buffers: Vec<ParamBuffer>,
+
+    /// The last execution has parameters
+    bound: bool,
+}
+
+/// Type of a parameter, as described by the server
+#[derive(Clone, Copy)]
+struct DescribedVar {
+    sqltype: i16,
+    sqlsubtype: i16,
+    sqlscale: i16,
+    sqllen: i16,
+}
+
+impl DescribedVar {
+    fn new(var: &ibase::XSQLVAR) -> Self {
+        Self {
+            sqltype: var.sqltype,
+            sqlsubtype: var.sqlsubtype,
+            sqlscale: var.sqlscale,
+            sqllen: var.sqllen,
+        }
+    }
+
+    fn restore(&self, var: &mut ibase::XSQLVAR) {
+        var.sqltype = self.sqltype;
+        var.sqlsubtype = self.sqlsubtype;
+        var.sqlscale = self.sqlscale;
+        var.sqllen = self.sqllen;
+    }
 }
 
 impl Params {
-    /// Validate and set the parameters of a statement
-    pub(crate) fn new<T: IBase>(
+    /// Validate and set the parameters of a statement, reusing
+    /// the xsqlda and the buffers of the previous execution
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn bind<T: IBase>(
+        &mut self,
         db_handle: &mut ibase::isc_db_handle,
         tr_handle: &mut ibase::isc_tr_handle,
         ibase: &T,
         status: &mut Status,
         stmt_handle: &mut ibase::isc_stmt_handle,
-        infos: Vec<SqlType>,
+        infos: &[SqlType],
         charset: &Charset,
-    ) -> Result<Self, FbError> {
-        let params = if !infos.is_empty() {
-            let mut xsqlda = XSqlDa::new(infos.len() as i16);
-
-            let ok = unsafe {
-                ibase.isc_dsql_describe_bind()(&mut status[0], stmt_handle, 1, &mut *xsqlda)
-            };
-            if ok != 0 {
-                return Err(status.as_error(ibase));
-            }
+    ) -> Result<(), FbError> {
+        self.bound = false;
 
-            if xsqlda.sqld != xsqlda.sqln {
-                return Err(format!(
-                    "Wrong parameter count, you passed {}, but the sql contains needs {} params",
-                    xsqlda.sqln, xsqlda.sqld
-                )
-                .into());
-            }
+        if infos.is_empty() {
+            return Ok(());
+        }
+
+        let xsqlda = match &mut self.xsqlda {
+            Some(xsqlda) => xsqlda,
+            None => {
+                let mut xsqlda = XSqlDa::new(infos.len() as i16);
 
-            let mut buffers = vec![];
-
-            for (col, info) in infos.into_iter().enumerate() {
-                buffers.push(ParamBuffer::from_parameter(
-                    info,
-                    xsqlda
-                        .get_xsqlvar_mut(col)
-                        .ok_or_else(|| FbError::from("Error getting the xsqlvar"))?,
-                    db_handle,
-                    tr_handle,
-                    ibase,
-                    charset,
-                )?);
+                let ok = unsafe {
+                    ibase.isc_dsql_describe_bind()(&mut status[0], stmt_handle, 1, &mut *xsqlda)
+                };
+                if ok != 0 {
+                    return Err(status.as_error(ibase));
+                }
+
+                if xsqlda.sqld != xsqlda.sqln {
+                    return Err(format!(
+                        "Tried to execute a statement that has {} parameters while providing {}",
+                        xsqlda.sqld, xsqlda.sqln
+                    )
+                    .into());
+                }
+
+                self.described = (0..xsqlda.sqld as usize)
+                    .map(|col| {
+                        xsqlda
+                            .get_xsqlvar(col)
+                            .map(DescribedVar::new)
+                            .ok_or_else(|| FbError::from("Error getting the xsqlvar"))
+                    })
+                    .collect::<Result<_, _>>()?;
+
+                self.xsqlda.insert(xsqlda)
             }
+        };
+
+        if xsqlda.sqld as usize != infos.len() {
+            return Err(format!(
+                "Tried to execute a statement that has {} parameters while providing {}",
+                xsqlda.sqld,
+                infos.len()
+            )
+            .into());
+        }
+
+        self.buffers.resize_with(infos.len(), Default::default);
 
-            Self {
-                _buffers: buffers,
-                xsqlda: Some(xsqlda),
+        for ((col, info), buffer) in infos.iter().enumerate().zip(&mut self.buffers) {
+            let var = xsqlda
+                .get_xsqlvar_mut(col)
+                .ok_or_else(|| FbError::from("Error getting the xsqlvar"))?;
+
+            self.described[col].restore(var);
+            buffer.bind(info, var, db_handle, tr_handle, ibase, charset)?;
+        }
+
+        self.bound = true;
+
+        Ok(())
+    }
+
+    /// Input xsqlda of the last `bind`, or null if it has no parameters.
+    ///
+    /// Points to the buffers of this struct, so it is only valid until the next `bind`
+    pub(crate) fn xsqlda_ptr(&self) -> *const ibase::XSQLDA {
+        match &self.xsqlda {
+            Some(xsqlda) if self.bound => &**xsqlda,
+            _ => ptr::null(),
+        }
+    }
+
+    /// Describe the parameters of a statement
+    pub(crate) fn describe<T: IBase>(
+        ibase: &T,
+        status: &mut Status,
+        stmt_handle: &mut ibase::isc_stmt_handle,
+    ) -> Result<Vec<ParamMeta>, FbError> {
+        let mut xsqlda = XSqlDa::new(1);
+
+        unsafe {
+            if ibase.isc_dsql_describe_bind()(&mut status[0], stmt_handle, 1, &mut *xsqlda) != 0 {
+                return Err(status.as_error(ibase));
             }
-        } else {
-            Self {
-                _buffers: vec![],
-                xsqlda: None,
+
+            if xsqlda.sqld > xsqlda.sqln {
+                // Need more XSQLVARs
+                xsqlda = XSqlDa::new(xsqlda.sqld);
+
+                if ibase.isc_dsql_describe_bind()(&mut status[0], stmt_handle, 1, &mut *xsqlda) != 0
+                {
+                    return Err(status.as_error(ibase));
+                }
             }
-        };
+        }
 
-        Ok(params)
+        (0..xsqlda.sqld as usize)
+            .map(|col| {
+                let var = xsqlda
+                    .get_xsqlvar(col)
+                    .ok_or_else(|| FbError::from("Error getting the xsqlvar"))?;
+
+                Ok(ParamMeta {
+                    sql_type: (var.sqltype & !1) as u32,
+                    sub_type: var.sqlsubtype,
+                    length: var.sqllen as u16,
+                    scale: var.sqlscale,
+                    nullable: var.sqltype & 1 == 1,
+                })
+            })
+            .collect()
     }
 
     // /// For use when there is no statement, cant verify the number of parameters ahead of time
@@ -105,111 +215,193 @@ impl Params {
 }
 
 /// Data for the input XSQLVAR
+#[derive(Default)]
 pub struct ParamBuffer {
     /// Buffer for the parameter data
-    _buffer: ParamBufferData,
+    buffer: ParamBufferData,
 
     /// Null indicator
-    _nullind: Box<i16>,
+    nullind: i16,
 }
 
 /// Data for the input XSQLVAR.
 /// Holds the data in the format expected by the `fbclient`
 enum ParamBufferData {
-    Text(Box<[u8]>),
+    /// Text, blob ids and the zeroed buffers of the nulls, keeping
+    /// the allocation to be reused by the next values
+    Bytes(Vec<u8>),
 
-    Integer(Box<i64>),
+    Integer(i64),
 
-    Floating(Box<f64>),
+    /// Only works in fb >= 4.0
+    Int128(i128),
 
-    Timestamp(Box<ibase::ISC_TIMESTAMP>),
+    Floating(f64),
 
-    Null,
+    Timestamp(ibase::ISC_TIMESTAMP),
 
-    Binary(Box<[u8]>),
+    /// Only works in fb >= 4.0
+    TimestampTz(ibase::ISC_TIMESTAMP_TZ),
 
     /// Only works in fb >= 3.0
-    Boolean(Box<i8>),
+    Boolean(i8),
+}
+
+impl Default for ParamBufferData {
+    fn default() -> Self {
+        Bytes(vec![])
+    }
 }
 
 impl ParamBufferData {
     /// Get a pointer to the underlying data
     fn as_mut_ptr(&mut self) -> *mut ibase::ISC_SCHAR {
         match self {
-            Text(s) => s.as_ptr() as _,
-            Integer(i) => &**i as *const _ as _,
-            Floating(f) => &**f as *const _ as _,
-            Timestamp(ts) => &**ts as *const _ as _,
-            Null => ptr::null_mut(),
-            Binary(b) => b.as_ptr() as _,
-            Boolean(b) => &**b as *const _ as _,
+            Bytes(b) => b.as_mut_ptr() as _,
+            Integer(i) => i as *mut _ as _,
+            Int128(i) => i as *mut _ as _,
+            Floating(f) => f as *mut _ as _,
+            Timestamp(ts) => ts as *mut _ as _,
+            TimestampTz(ts) => ts as *mut _ as _,
+            Boolean(b) => b as *mut _ as _,
+        }
+    }
+
+    /// Empty byte buffer, reusing the previous one if possible
+    fn bytes(&mut self) -> &mut Vec<u8> {
+        if !matches!(self, Bytes(_)) {
+            *self = Bytes(vec![]);
+        }
+
+        match self {
+            Bytes(b) => {
+                b.clear();
+                b
+            }
+            _ => unreachable!(),
         }
     }
 }
 
 impl ParamBuffer {
-    /// Allocate a buffer from a value to use in an input (parameter) XSQLVAR
-    pub fn from_parameter<T: IBase>(
-        info: SqlType,
+    /// Store the value in the buffer and point the input (parameter) XSQLVAR to it.
+    /// The XSQLVAR must have the type described by the server
+    pub fn bind<T: IBase>(
+        &mut self,
+        info: &SqlType,
         var: &mut ibase::XSQLVAR,
         db: &mut ibase::isc_db_handle,
         tr: &mut ibase::isc_tr_handle,
         ibase: &T,
         charset: &Charset,
-    ) -> Result<Self, FbError> {
-        let mut null = 0;
-
-        let (sqltype, sqlsubtype) = info.sql_type_and_subtype();
-        var.sqltype = sqltype as i16;
-        var.sqlsubtype = sqlsubtype as i16;
-        var.sqlscale = 0;
+    ) -> Result<(), FbError> {
+        self.nullind = 0;
+
+        // The nulls keep the type described by the server
+        if !info.is_null() {
+            let (sqltype, sqlsubtype) = info.sql_type_and_subtype();
+            var.sqltype = sqltype as i16;
+            var.sqlsubtype = sqlsubtype as i16;
+            var.sqlscale = 0;
+        }
 
-        let (size, mut buffer) = match info {
+        let size = match info {
             SqlType::Text(s) => {
-                let bytes = charset.encode(s)?;
+                let bytes = charset.encode(s.as_str())?;
+                let buffer = self.buffer.bytes();
 
-                let bytes = if bytes.len() > MAX_TEXT_LENGTH {
-                    binary_to_blob(&bytes, db, tr, ibase)?
+                if bytes.len() > MAX_TEXT_LENGTH {
+                    buffer.extend_from_slice(&binary_to_blob(&bytes, db, tr, ibase)?);
                 } else {
-                    bytes.into_owned()
-                };
+                    buffer.extend_from_slice(&bytes);
+                }
+
+                buffer.len()
+            }
 
-                (bytes.len(), Text(bytes.into_boxed_slice()))
+            SqlType::Integer(i) => {
+                self.buffer = Integer(*i);
+                mem::size_of_val(i)
             }
 
-            SqlType::Integer(i) => (mem::size_of_val(&i), Integer(Box::new(i))),
+            SqlType::Int128(i) => self.set_int(*i),
+
+            SqlType::Numeric { value, scale } => {
+                var.sqlscale = *scale;
+
+                self.set_int(*value)
+            }
 
-            SqlType::Floating(f) => (mem::size_of_val(&f), Floating(Box::new(f))),
+            SqlType::Floating(f) => {
+                self.buffer = Floating(*f);
+                mem::size_of_val(f)
+            }
 
             SqlType::Timestamp(dt) => {
-                let ts = rsfbclient_core::date_time::encode_timestamp(dt);
+                let ts = rsfbclient_core::date_time::encode_timestamp(*dt);
+                self.buffer = Timestamp(ts);
+                mem::size_of_val(&ts)
+            }
 
-                (mem::size_of_val(&ts), Timestamp(Box::new(ts)))
+            SqlType::TimestampTz(dt) => {
+                let ts = rsfbclient_core::date_time::encode_timestamp_tz(*dt);
+                self.buffer = TimestampTz(ts);
+                mem::size_of_val(&ts)
             }
 
             SqlType::Null => {
-                null = -1;
-                (0, Null)
+                self.nullind = -1;
+                var.sqltype |= 1;
+
+                // Zeroed value, with an empty length for the varying
+                let size = var.sqllen as usize;
+                let buffer_len = if (var.sqltype & !1) as u32 == ibase::SQL_VARYING {
+                    size + 2
+                } else {
+                    size
+                };
+
+                self.buffer.bytes().resize(buffer_len, 0);
+
+                size
             }
 
             SqlType::Binary(bin) => {
-                let bytes = binary_to_blob(&bin, db, tr, ibase)?;
-                (bytes.len(), Binary(bytes.into_boxed_slice()))
+                let blob_id = binary_to_blob(bin, db, tr, ibase)?;
+                self.buffer.bytes().extend_from_slice(&blob_id);
+
+                blob_id.len()
+            }
+
+            SqlType::Boolean(bo) => {
+                self.buffer = Boolean(*bo as i8);
+                mem::size_of::<i8>()
             }
 
-            SqlType::Boolean(bo) => (mem::size_of::<i8>(), Boolean(Box::new(bo as i8))),
+            SqlType::Array(_) => return Err("Array parameters are not supported".into()),
         };
 
-        let mut nullind = Box::new(null);
-        var.sqlind = &mut *nullind;
+        var.sqlind = &mut self.nullind;
 
-        var.sqldata = buffer.as_mut_ptr();
+        var.sqldata = self.buffer.as_mut_ptr();
         var.sqllen = size as i16;
 
-        Ok(ParamBuffer {
-            _buffer: buffer,
-            _nullind: nullind,
-        })
+        Ok(())
+    }
+
+    /// Store the integer, using the 128 bits representation only
+    /// when needed, so the older servers can still receive the value
+    fn set_int(&mut self, i: i128) -> usize {
+        match i64::try_from(i) {
+            Ok(i) => {
+                self.buffer = Integer(i);
+                mem::size_of_val(&i)
+            }
+            Err(_) => {
+                self.buffer = Int128(i);
+                mem::size_of_val(&i)
+            }
+        }
     }
 }
 
@@ -219,7 +411,7 @@ fn binary_to_blob<T: IBase>(
     db_handle: &mut ibase::isc_db_handle,
     tr_handle: &mut ibase::isc_tr_handle,
     ibase: &T,
-) -> Result<Vec<u8>, FbError> {
+) -> Result<[u8; 8], FbError> {
     let mut status = Status::default();
     let mut handle = 0;
 
@@ -244,15 +436,18 @@ fn binary_to_blob<T: IBase>(
     // Assert that the handle is valid
     debug_assert_ne!(handle, 0);
 
-    unsafe {
-        if ibase.isc_put_segment()(
-            &mut status[0],
-            &mut handle,
-            bytes.len() as u16,
-            bytes.as_ptr() as *mut std::os::raw::c_char,
-        ) != 0
-        {
-            return Err(status.as_error(ibase));
+    // The segment length is limited to an u16
+    for segment in bytes.chunks(MAX_SEGMENT_LENGTH) {
+        unsafe {
+            if ibase.isc_put_segment()(
+                &mut status[0],
+                &mut handle,
+                segment.len() as u16,
+                segment.as_ptr() as *mut std::os::raw::c_char,
+            ) != 0
+            {
+                return Err(status.as_error(ibase));
+            }
         }
     }
 
@@ -262,9 +457,9 @@ fn binary_to_blob<T: IBase>(
         }
     }
 
-    Ok([
-        blob_id.gds_quad_high.to_ne_bytes(),
-        blob_id.gds_quad_low.to_ne_bytes(),
-    ]
-    .concat())
+    let mut id = [0; 8];
+    id[..4].copy_from_slice(&blob_id.gds_quad_high.to_ne_bytes());
+    id[4..].copy_from_slice(&blob_id.gds_quad_low.to_ne_bytes());
+
+    Ok(id)
 }
@@ -4,8 +4,8 @@
 //! Representation of a fetched row
 //!
 
-use rsfbclient_core::{Charset, Column, FbError, SqlType};
-use std::{mem, result::Result};
+use rsfbclient_core::{Charset, Column, ColumnMeta, FbError, SqlType};
+use std::{convert::TryInto, ffi::CString, mem, os::raw::c_char, result::Result};
 
 use crate::{ibase, ibase::IBase, status::Status, varchar::Varchar};
 
@@ -16,30 +16,52 @@ use ColumnBufferData::*;
 pub enum ColumnBufferData {
     /// Coerces to Varchar
     Text(Varchar),
+    /// Coerces to Varchar, with the OCTETS charset
+    Octets(Varchar),
     /// Coerces to Int64
     Integer(Box<i64>),
-    /// Coerces to Double
-    Float(Box<f64>),
+    /// Coerces to Int64, keeping the scale
+    Numeric(Box<i64>, i16),
+    /// Coerces to Int128, keeping the scale. Fb >= 4
+    Int128(Box<i128>, i16),
+    /// Coerces to Double, keeping the scale of the dialect 1 numerics
+    Float(Box<f64>, i16),
     /// Coerces to Timestamp
     Timestamp(Box<ibase::ISC_TIMESTAMP>),
+    /// Coerces to Timestamp with time zone, extended with the offset. Fb >= 4
+    TimestampTz(Box<ibase::ISC_TIMESTAMP_TZ_EX>),
     /// Coerces to Blob sub_type 1
     BlobText(Box<ibase::GDS_QUAD_t>),
     /// Coerces to Blob sub_type 0
     BlobBinary(Box<ibase::GDS_QUAD_t>),
     /// Coerces to boolean. Fb >= 3
     Boolean(Box<i8>),
+    /// Array id, the elements are read with the bounds of the field
+    Array(Box<ibase::GDS_QUAD_t>, Box<ArrayField>),
+}
+
+#[derive(Debug)]
+/// Relation and field names of an array column
+pub struct ArrayField {
+    relation: CString,
+    field: CString,
 }
 
 impl ColumnBufferData {
     fn as_mut_ptr(&mut self) -> *mut ibase::ISC_SCHAR {
         match self {
             Text(v) => v.as_ptr() as _,
+            Octets(v) => v.as_ptr() as _,
             Integer(i) => &**i as *const _ as _,
-            Float(f) => &**f as *const _ as _,
+            Numeric(i, _) => &**i as *const _ as _,
+            Int128(i, _) => &**i as *const _ as _,
+            Float(f, _) => &**f as *const _ as _,
             Timestamp(ts) => &**ts as *const _ as _,
+            TimestampTz(ts) => &**ts as *const _ as _,
             BlobText(bid) => &**bid as *const _ as _,
             BlobBinary(bid) => &**bid as *const _ as _,
             Boolean(b) => &**b as *const _ as _,
+            Array(aid, _) => &**aid as *const _ as _,
         }
     }
 }
@@ -95,30 +117,39 @@ impl ColumnBuffer {
             ibase::SQL_TEXT | ibase::SQL_VARYING => {
                 var.sqltype = ibase::SQL_VARYING as i16 + 1;
 
-                Text(Varchar::new(var.sqllen as u16))
+                // The lower byte of the subtype is the charset id
+                if sqlsubtype & 0xff == ibase::CS_BINARY as i16 {
+                    Octets(Varchar::new(var.sqllen as u16))
+                } else {
+                    Text(Varchar::new(var.sqllen as u16))
+                }
             }
 
             ibase::SQL_SHORT | ibase::SQL_LONG | ibase::SQL_INT64 => {
                 var.sqllen = mem::size_of::<i64>() as i16;
+                var.sqltype = ibase::SQL_INT64 as i16 + 1;
 
                 if var.sqlscale == 0 {
-                    var.sqltype = ibase::SQL_INT64 as i16 + 1;
-
                     Integer(Box::new(0))
                 } else {
-                    var.sqlscale = 0;
-                    var.sqltype = ibase::SQL_DOUBLE as i16 + 1;
-
-                    Float(Box::new(0.0))
+                    // Is actually a decimal or numeric value, the scale is kept to not lose precision
+                    Numeric(Box::new(0), var.sqlscale)
                 }
             }
 
+            ibase::SQL_INT128 => {
+                var.sqllen = mem::size_of::<i128>() as i16;
+                var.sqltype = ibase::SQL_INT128 as i16 + 1;
+
+                Int128(Box::new(0), var.sqlscale)
+            }
+
             ibase::SQL_FLOAT | ibase::SQL_DOUBLE => {
                 var.sqllen = mem::size_of::<i64>() as i16;
 
                 var.sqltype = ibase::SQL_DOUBLE as i16 + 1;
 
-                Float(Box::new(0.0))
+                Float(Box::new(0.0), var.sqlscale)
             }
 
             ibase::SQL_TIMESTAMP | ibase::SQL_TYPE_DATE | ibase::SQL_TYPE_TIME => {
@@ -132,6 +163,43 @@ impl ColumnBuffer {
                 }))
             }
 
+            ibase::SQL_TIMESTAMP_TZ
+            | ibase::SQL_TIME_TZ
+            | ibase::SQL_TIMESTAMP_TZ_EX
+            | ibase::SQL_TIME_TZ_EX => {
+                var.sqllen = mem::size_of::<ibase::ISC_TIMESTAMP_TZ_EX>() as i16;
+
+                // The extended type has the offset of region time zones resolved by the server
+                var.sqltype = ibase::SQL_TIMESTAMP_TZ_EX as i16 + 1;
+
+                TimestampTz(Box::new(ibase::ISC_TIMESTAMP_TZ_EX {
+                    utc_timestamp: ibase::ISC_TIMESTAMP {
+                        timestamp_date: 0,
+                        timestamp_time: 0,
+                    },
+                    time_zone: 0,
+                    ext_offset: 0,
+                }))
+            }
+
+            ibase::SQL_ARRAY => {
+                let array_id = Box::new(ibase::GDS_QUAD_t {
+                    gds_quad_high: 0,
+                    gds_quad_low: 0,
+                });
+
+                var.sqltype = ibase::SQL_ARRAY as i16 + 1;
+
+                let field = ArrayField {
+                    relation: CString::new(name_bytes(&var.relname, var.relname_length))
+                        .map_err(|e| FbError::from(e.to_string()))?,
+                    field: CString::new(name_bytes(&var.sqlname, var.sqlname_length))
+                        .map_err(|e| FbError::from(e.to_string()))?,
+                };
+
+                Array(array_id, Box::new(field))
+            }
+
             sqltype => {
                 return Err(format!("Unsupported column type ({} {})", sqltype, sqlsubtype).into())
             }
@@ -139,15 +207,7 @@ impl ColumnBuffer {
 
         var.sqldata = buffer.as_mut_ptr();
 
-        let col_name = {
-            let len = usize::min(var.aliasname_length as usize, var.aliasname.len());
-            let bname = var.aliasname[..len]
-                .iter()
-                .map(|b| *b as u8)
-                .collect::<Vec<u8>>();
-
-            String::from_utf8(bname)
-        }?;
+        let col_name = String::from_utf8(name_bytes(&var.aliasname, var.aliasname_length))?;
 
         Ok(ColumnBuffer {
             buffer,
@@ -164,51 +224,251 @@ impl ColumnBuffer {
         ibase: &T,
         charset: &Charset,
     ) -> Result<Column, FbError> {
+        let value = self.to_value(db, tr, ibase, charset)?;
+
+        Ok(Column::new(self.col_name.clone(), value))
+    }
+
+    /// Converts the buffer to a value
+    pub fn to_value<T: IBase>(
+        &self,
+        db: &mut ibase::isc_db_handle,
+        tr: &mut ibase::isc_tr_handle,
+        ibase: &T,
+        charset: &Charset,
+    ) -> Result<SqlType, FbError> {
         if *self.nullind != 0 {
-            return Ok(Column::new(self.col_name.clone(), SqlType::Null));
+            return Ok(SqlType::Null);
         }
 
-        let col_type = match &self.buffer {
-            Text(varchar) => SqlType::Text(charset.decode(varchar.as_bytes())?),
+        let value = match &self.buffer {
+            Text(varchar) => charset.decode_text(varchar.as_bytes())?,
+
+            Octets(varchar) => SqlType::Binary(varchar.as_bytes().to_vec()),
 
             Integer(i) => SqlType::Integer(**i),
 
-            Float(f) => SqlType::Floating(**f),
+            Numeric(i, scale) => SqlType::Numeric {
+                value: **i as i128,
+                scale: *scale,
+            },
+
+            Int128(i, 0) => SqlType::Int128(**i),
+
+            Int128(i, scale) => SqlType::Numeric {
+                value: **i,
+                scale: *scale,
+            },
+
+            Float(f, scale) => rsfbclient_core::scaled_float_to_sql(**f, *scale),
 
             Timestamp(ts) => SqlType::Timestamp(rsfbclient_core::date_time::decode_timestamp(**ts)),
 
-            BlobText(b) => SqlType::Text(blobtext_to_string(**b, db, tr, ibase, charset)?),
+            TimestampTz(ts) => {
+                SqlType::TimestampTz(rsfbclient_core::date_time::decode_timestamp_tz(**ts)?)
+            }
+
+            BlobText(b) => charset.decode_text(read_blob(**b, db, tr, ibase)?)?,
 
             BlobBinary(b) => SqlType::Binary(blobbinary_to_vec(**b, db, tr, ibase)?),
 
             Boolean(b) => SqlType::Boolean(**b != 0),
+
+            Array(aid, field) => read_array(**aid, field, db, tr, ibase, charset)?,
         };
 
-        Ok(Column::new(self.col_name.clone(), col_type))
+        Ok(value)
     }
 }
 
-/// Converts a binary blob to a vec<u8>
-fn blobbinary_to_vec<T: IBase>(
-    blob_id: ibase::GDS_QUAD_t,
+/// Describe the column, must be called before the coercion of the XSQLVAR
+pub fn column_meta(var: &ibase::XSQLVAR) -> Result<ColumnMeta, FbError> {
+    Ok(ColumnMeta {
+        name: String::from_utf8(name_bytes(&var.sqlname, var.sqlname_length))?,
+        alias: String::from_utf8(name_bytes(&var.aliasname, var.aliasname_length))?,
+        relation: String::from_utf8(name_bytes(&var.relname, var.relname_length))?,
+        sql_type: (var.sqltype & !1) as u32,
+        sub_type: var.sqlsubtype,
+        length: var.sqllen as u16,
+        scale: var.sqlscale,
+        nullable: var.sqltype & 1 == 1,
+    })
+}
+
+/// Bytes of a name from the XSQLVAR
+fn name_bytes(name: &[c_char], len: i16) -> Vec<u8> {
+    let len = usize::min(len as usize, name.len());
+
+    name[..len].iter().map(|b| *b as u8).collect()
+}
+
+/// Read all elements of an array, nested for each dimension
+fn read_array<T: IBase>(
+    mut array_id: ibase::GDS_QUAD_t,
+    field: &ArrayField,
     db: &mut ibase::isc_db_handle,
     tr: &mut ibase::isc_tr_handle,
     ibase: &T,
-) -> Result<Vec<u8>, FbError> {
-    read_blob(blob_id, db, tr, ibase)
+    charset: &Charset,
+) -> Result<SqlType, FbError> {
+    let mut status = Status::default();
+    let mut desc: ibase::ISC_ARRAY_DESC = unsafe { mem::zeroed() };
+
+    unsafe {
+        if ibase.isc_array_lookup_bounds()(
+            &mut status[0],
+            db,
+            tr,
+            field.relation.as_ptr(),
+            field.field.as_ptr(),
+            &mut desc,
+        ) != 0
+        {
+            return Err(status.as_error(ibase));
+        }
+    }
+
+    let dims = desc.array_desc_bounds[..desc.array_desc_dimensions as usize]
+        .iter()
+        .map(|b| (b.array_bound_upper - b.array_bound_lower + 1).max(0) as usize)
+        .collect::<Vec<_>>();
+
+    let elem_len = match desc.array_desc_dtype {
+        // Length prefixed
+        ibase::blr_varying => desc.array_desc_length as usize + 2,
+        _ => desc.array_desc_length as usize,
+    };
+
+    let mut buffer = vec![0_u8; dims.iter().product::<usize>() * elem_len];
+    let mut slice_len = buffer.len() as ibase::ISC_LONG;
+
+    unsafe {
+        if ibase.isc_array_get_slice()(
+            &mut status[0],
+            db,
+            tr,
+            &mut array_id,
+            &desc,
+            buffer.as_mut_ptr() as _,
+            &mut slice_len,
+        ) != 0
+        {
+            return Err(status.as_error(ibase));
+        }
+    }
+
+    let items = buffer
+        .chunks_exact(elem_len.max(1))
+        .map(|bytes| array_element(&desc, bytes, charset))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(nest_array(items, &dims))
 }
 
-/// Converts a text blob to a string
-fn blobtext_to_string<T: IBase>(
+/// Converts an element of the array slice
+fn array_element(
+    desc: &ibase::ISC_ARRAY_DESC,
+    bytes: &[u8],
+    charset: &Charset,
+) -> Result<SqlType, FbError> {
+    let scale = desc.array_desc_scale as i16;
+    let int = |i: i64| {
+        if scale == 0 {
+            SqlType::Integer(i)
+        } else {
+            SqlType::Numeric {
+                value: i as i128,
+                scale,
+            }
+        }
+    };
+
+    let col = match desc.array_desc_dtype {
+        ibase::blr_short => int(i16::from_ne_bytes(fixed_bytes(bytes)?) as i64),
+
+        ibase::blr_long => int(i32::from_ne_bytes(fixed_bytes(bytes)?) as i64),
+
+        ibase::blr_int64 => int(i64::from_ne_bytes(fixed_bytes(bytes)?)),
+
+        ibase::blr_float => SqlType::Floating(f32::from_ne_bytes(fixed_bytes(bytes)?) as f64),
+
+        ibase::blr_double | ibase::blr_d_float => {
+            rsfbclient_core::scaled_float_to_sql(f64::from_ne_bytes(fixed_bytes(bytes)?), scale)
+        }
+
+        ibase::blr_text => charset.decode_text(bytes)?,
+
+        ibase::blr_varying => {
+            let len = u16::from_ne_bytes(fixed_bytes(&bytes[..2])?) as usize;
+
+            charset.decode_text(&bytes[2..usize::min(2 + len, bytes.len())])?
+        }
+
+        ibase::blr_cstring => {
+            let len = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+
+            charset.decode_text(&bytes[..len])?
+        }
+
+        ibase::blr_sql_date | ibase::blr_sql_time | ibase::blr_timestamp => {
+            let ts = match desc.array_desc_dtype {
+                ibase::blr_sql_date => ibase::ISC_TIMESTAMP {
+                    timestamp_date: i32::from_ne_bytes(fixed_bytes(bytes)?),
+                    timestamp_time: 0,
+                },
+                ibase::blr_sql_time => ibase::ISC_TIMESTAMP {
+                    timestamp_date: 0,
+                    timestamp_time: u32::from_ne_bytes(fixed_bytes(bytes)?),
+                },
+                _ => ibase::ISC_TIMESTAMP {
+                    timestamp_date: i32::from_ne_bytes(fixed_bytes(&bytes[..4])?),
+                    timestamp_time: u32::from_ne_bytes(fixed_bytes(&bytes[4..])?),
+                },
+            };
+
+            SqlType::Timestamp(rsfbclient_core::date_time::decode_timestamp(ts))
+        }
+
+        ibase::blr_bool => SqlType::Boolean(bytes[0] != 0),
+
+        dtype => return Err(format!("Unsupported array element type ({})", dtype).into()),
+    };
+
+    Ok(col)
+}
+
+/// Bytes of a fixed size element
+fn fixed_bytes<const N: usize>(bytes: &[u8]) -> Result<[u8; N], FbError> {
+    bytes
+        .try_into()
+        .map_err(|_| format!("Invalid array element length ({})", bytes.len()).into())
+}
+
+/// Splits the elements in nested arrays, one for each extra dimension
+fn nest_array(items: Vec<SqlType>, dims: &[usize]) -> SqlType {
+    match dims {
+        [len, inner @ ..] if !inner.is_empty() => {
+            let chunk = inner.iter().product::<usize>();
+            let mut items = items.into_iter();
+
+            SqlType::Array(
+                (0..*len)
+                    .map(|_| nest_array(items.by_ref().take(chunk).collect(), inner))
+                    .collect(),
+            )
+        }
+        _ => SqlType::Array(items),
+    }
+}
+
+/// Converts a binary blob to a vec<u8>
+fn blobbinary_to_vec<T: IBase>(
     blob_id: ibase::GDS_QUAD_t,
     db: &mut ibase::isc_db_handle,
     tr: &mut ibase::isc_tr_handle,
     ibase: &T,
-    charset: &Charset,
-) -> Result<String, FbError> {
-    let blob_bytes = read_blob(blob_id, db, tr, ibase)?;
-
-    charset.decode(blob_bytes)
+) -> Result<Vec<u8>, FbError> {
+    read_blob(blob_id, db, tr, ibase)
 }
 
 /// Read the blob type
@@ -233,10 +493,10 @@ fn read_blob<T: IBase>(
     debug_assert_ne!(handle, 0);
 
     let mut blob_stat = 0;
+    let mut blob_seg_slice = vec![0_u8; u16::MAX as usize];
 
     while blob_stat == 0 || status[1] == (ibase::isc_segment as isize) {
         let mut blob_seg_loaded = 0;
-        let mut blob_seg_slice = [0_u8; 255];
 
         blob_stat = unsafe {
             ibase.isc_get_segment()(
@@ -251,6 +511,17 @@ fn read_blob<T: IBase>(
         blob_bytes.extend_from_slice(&blob_seg_slice[..blob_seg_loaded as usize]);
     }
 
+    // Anything other than the end of the blob is an error
+    if status[1] != (ibase::isc_segstr_eof as isize) {
+        let err = status.as_error(ibase);
+
+        unsafe {
+            ibase.isc_close_blob()(&mut status[0], &mut handle);
+        }
+
+        return Err(err);
+    }
+
     unsafe {
         if ibase.isc_close_blob()(&mut status[0], &mut handle) != 0 {
             return Err(status.as_error(ibase));
@@ -1,9 +1,12 @@
 //! Status of connetions, transactions...
 
 pub use rsfbclient_core::FbError;
+use rsfbclient_core::{StatusArg, StatusItem};
 use std::{
-    fmt::Write,
+    ffi::CStr,
     ops::{Deref, DerefMut},
+    os::raw::c_char,
+    slice,
 };
 
 use crate::ibase::{self, IBase};
@@ -36,13 +39,36 @@ impl Status {
         unsafe { ibase.isc_sqlcode()(self.0.as_ptr()) }
     }
 
+    /// Message of the errors in the status vector, without the warnings
     pub fn message<T: IBase>(&self, ibase: &T) -> String {
+        let end = self.warnings_pos().unwrap_or(self.0.len());
+
+        self.interpret(ibase, 0, end).join("\n")
+    }
+
+    /// Messages of the warnings in the status vector, returned
+    /// alongside a successful result
+    pub fn warnings<T: IBase>(&self, ibase: &T) -> Vec<String> {
+        match self.warnings_pos() {
+            Some(pos) => self.interpret(ibase, pos, self.0.len()),
+            None => vec![],
+        }
+    }
+
+    /// True if the status vector contains an error, and not only warnings
+    pub fn has_error(&self) -> bool {
+        self.0[0] as u32 == ibase::isc_arg_gds && self.0[1] != 0
+    }
+
+    /// Interpret the messages of the status vector items between the positions
+    fn interpret<T: IBase>(&self, ibase: &T, start: usize, end: usize) -> Vec<String> {
         let mut buffer: Vec<u8> = Vec::with_capacity(256);
-        let mut msg = String::new();
+        let mut msgs = vec![];
 
-        let mut ptr = self.0.as_ptr();
+        let mut ptr = self.0[start..].as_ptr();
+        let end = unsafe { self.0.as_ptr().add(end) };
 
-        loop {
+        while ptr < end {
             unsafe {
                 let len = ibase.fb_interpret()(
                     buffer.as_mut_ptr() as *mut _,
@@ -56,23 +82,79 @@ impl Status {
                 break;
             }
 
-            writeln!(
-                &mut msg,
-                "{}",
-                std::str::from_utf8(&buffer).unwrap_or("Invalid error message")
-            )
-            .unwrap();
+            msgs.push(
+                std::str::from_utf8(&buffer)
+                    .unwrap_or("Invalid error message")
+                    .to_string(),
+            );
         }
-        // Remove the last \n
-        msg.pop();
 
-        msg
+        msgs
     }
 
-    pub fn as_error<T: IBase>(&self, ibase: &T) -> FbError {
-        FbError::Sql {
-            code: self.sql_code(ibase),
-            msg: self.message(ibase),
+    /// Position of the first warning in the status vector
+    fn warnings_pos(&self) -> Option<usize> {
+        let mut pos = 0;
+
+        while pos + 1 < self.0.len() {
+            match self.0[pos] as u32 {
+                ibase::isc_arg_end => break,
+                ibase::isc_arg_warning => return Some(pos),
+                // Length and pointer to the string
+                ibase::isc_arg_cstring => pos += 3,
+                _ => pos += 2,
+            }
+        }
+
+        None
+    }
+
+    /// Errors present in the status vector, with their arguments, without the warnings
+    pub fn items(&self) -> Vec<StatusItem> {
+        let mut items: Vec<StatusItem> = vec![];
+        let end = self.warnings_pos().unwrap_or(self.0.len());
+        let mut pos = 0;
+
+        while pos + 1 < end {
+            let value = self.0[pos + 1];
+
+            let arg = match self.0[pos] as u32 {
+                ibase::isc_arg_end => break,
+                ibase::isc_arg_gds if value != 0 => {
+                    items.push(StatusItem {
+                        code: value as u32,
+                        args: vec![],
+                    });
+                    None
+                }
+                ibase::isc_arg_number => Some(StatusArg::Number(value as i32)),
+                // Pointer to a null terminated string
+                ibase::isc_arg_string | ibase::isc_arg_interpreted if value != 0 => {
+                    let s = unsafe { CStr::from_ptr(value as *const c_char) };
+                    Some(StatusArg::String(s.to_string_lossy().into_owned()))
+                }
+                // Length and pointer to the string
+                ibase::isc_arg_cstring if pos + 2 < end => {
+                    pos += 1;
+                    let s = unsafe {
+                        slice::from_raw_parts(self.0[pos + 1] as *const u8, value as usize)
+                    };
+                    Some(StatusArg::String(String::from_utf8_lossy(s).into_owned()))
+                }
+                _ => None,
+            };
+
+            if let (Some(arg), Some(item)) = (arg, items.last_mut()) {
+                item.args.push(arg);
+            }
+
+            pos += 2;
         }
+
+        items
+    }
+
+    pub fn as_error<T: IBase>(&self, ibase: &T) -> FbError {
+        FbError::from_status(self.sql_code(ibase), self.message(ibase), self.items())
     }
 }
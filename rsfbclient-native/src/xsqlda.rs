@@ -33,6 +33,17 @@ impl XSqlDa {
         xsqlda
     }
 
+    /// Returns a reference to a XSQLVAR
+    pub fn get_xsqlvar(&self, col: usize) -> Option<&ibase::XSQLVAR> {
+        if col < self.len as usize {
+            let xsqlvar = unsafe { self.ptr.as_ref().sqlvar.get_unchecked(col) };
+
+            Some(xsqlvar)
+        } else {
+            None
+        }
+    }
+
     /// Returns a mutable reference to a XSQLVAR
     pub fn get_xsqlvar_mut(&mut self, col: usize) -> Option<&mut ibase::XSQLVAR> {
         if col < self.len as usize {
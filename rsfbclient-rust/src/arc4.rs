@@ -1,6 +1,9 @@
 //! Arc4 stream cipher implementation for the firebird wire encryption (Wire Protocol 13)
 
-use std::io::{Read, Write};
+use std::{
+    io::{Read, Write},
+    sync::{Arc, Mutex},
+};
 
 #[derive(Clone)]
 pub struct Arc4 {
@@ -49,7 +52,8 @@ impl Arc4 {
 /// Wraps a stream, encoding / decoding the data
 pub struct Arc4Stream<S> {
     read_rc4: Box<Arc4>,
-    write_rc4: Box<Arc4>,
+    /// Shared with the clones of the stream, as they write to the same socket
+    write_rc4: Arc<Mutex<Arc4>>,
     enc_buf: Box<[u8]>,
     stream: S,
 }
@@ -58,13 +62,29 @@ impl<S> Arc4Stream<S> {
     pub fn new(stream: S, key: &[u8], buf_len: usize) -> Self {
         Self {
             read_rc4: Box::new(Arc4::new(key)),
-            write_rc4: Box::new(Arc4::new(key)),
+            write_rc4: Arc::new(Mutex::new(Arc4::new(key))),
             enc_buf: vec![0; buf_len].into_boxed_slice(),
             stream,
         }
     }
 }
 
+impl<S> Arc4Stream<S> {
+    /// Creates a new stream to write in the same socket, sharing the write cipher state,
+    /// with the inner stream cloned by `clone_stream`. The clone must not be used for reads
+    pub fn try_clone_with<F>(&self, clone_stream: F) -> std::io::Result<Self>
+    where
+        F: FnOnce(&S) -> std::io::Result<S>,
+    {
+        Ok(Self {
+            read_rc4: self.read_rc4.clone(),
+            write_rc4: self.write_rc4.clone(),
+            enc_buf: vec![0; self.enc_buf.len()].into_boxed_slice(),
+            stream: clone_stream(&self.stream)?,
+        })
+    }
+}
+
 impl<S: Read> Read for Arc4Stream<S> {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
         let Self {
@@ -93,6 +113,12 @@ impl<S: Write> Write for Arc4Stream<S> {
             ..
         } = self;
 
+        // Hold the lock until the data is written, so the cipher
+        // state matches the order of the data in the socket
+        let mut write_rc4 = write_rc4
+            .lock()
+            .map_err(|_| std::io::Error::other("Poisoned cipher lock"))?;
+
         let max_len = buf.len().min(enc_buf.len());
         // Encrypt
         write_rc4.process(&buf[..max_len], &mut enc_buf[..max_len]);
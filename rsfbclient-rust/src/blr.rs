@@ -1,6 +1,6 @@
 use crate::{client::FirebirdWireConnection, consts};
 use bytes::{BufMut, Bytes, BytesMut};
-use rsfbclient_core::{FbError, SqlType};
+use rsfbclient_core::{ibase, FbError, ParamMeta, SqlType};
 
 /// Maximum parameter data length
 pub const MAX_DATA_LENGTH: usize = 32767;
@@ -14,11 +14,15 @@ pub struct ParamsBlr {
     pub(crate) values: Bytes,
 }
 
-/// Convert the parameters to a blr (binary representation)
+/// Convert the parameters to a blr (binary representation).
+///
+/// The `NULL`s are sent with the type in `params_meta`, as described by the server,
+/// or as an empty text when not described
 pub fn params_to_blr(
     conn: &mut FirebirdWireConnection,
     tr_handle: &mut crate::TrHandle,
     params: &[SqlType],
+    params_meta: &[ParamMeta],
 ) -> Result<ParamsBlr, FbError> {
     let mut blr = BytesMut::with_capacity(256);
     let mut values = BytesMut::with_capacity(256);
@@ -56,7 +60,7 @@ pub fn params_to_blr(
         Ok::<_, FbError>(())
     };
 
-    for p in params {
+    for (i, p) in params.iter().enumerate() {
         match p {
             SqlType::Text(s) => {
                 let bytes = conn.charset.encode(s)?;
@@ -86,6 +90,10 @@ pub fn params_to_blr(
                 values.put_i64(*i);
             }
 
+            SqlType::Int128(i) => put_int(&mut blr, &mut values, *i, 0),
+
+            SqlType::Numeric { value, scale } => put_int(&mut blr, &mut values, *value, *scale),
+
             SqlType::Floating(f) => {
                 blr.put_u8(consts::blr::DOUBLE);
 
@@ -100,16 +108,38 @@ pub fn params_to_blr(
                 values.put_u32(ts.timestamp_time);
             }
 
+            SqlType::TimestampTz(dt) => {
+                blr.put_u8(consts::blr::TIMESTAMP_TZ);
+
+                let ts = rsfbclient_core::date_time::encode_timestamp_tz(*dt);
+                values.put_i32(ts.utc_timestamp.timestamp_date);
+                values.put_u32(ts.utc_timestamp.timestamp_time);
+                values.put_u32(ts.time_zone as u32);
+            }
+
             SqlType::Boolean(b) => {
                 blr.put_u8(consts::blr::BOOL);
 
                 values.put_slice(if *b { &[1, 0, 0, 0] } else { &[0, 0, 0, 0] });
             }
 
+            SqlType::Array(_) => return Err("Array parameters are not supported".into()),
+
             SqlType::Null => {
-                // Represent as empty text
-                blr.put_u8(consts::blr::TEXT);
-                blr.put_u16_le(0);
+                let len = match params_meta.get(i) {
+                    Some(meta) => put_null_type(&mut blr, meta),
+                    None => {
+                        // Represent as empty text
+                        blr.put_u8(consts::blr::TEXT);
+                        blr.put_u16_le(0);
+                        0
+                    }
+                };
+
+                if conn.version < consts::ProtocolVersion::V13 {
+                    // Without the null bitmap, the value is still sent
+                    values.put_bytes(0, len);
+                }
             }
         }
 
@@ -130,6 +160,87 @@ pub fn params_to_blr(
     })
 }
 
+/// Insert the blr of the type of a `NULL` parameter, as described by the server,
+/// returning the length of its value in the message.
+///
+/// The types are the same used by the non null values, with the texts
+/// represented as empty, so the value is kept small
+fn put_null_type(blr: &mut BytesMut, meta: &ParamMeta) -> usize {
+    match meta.sql_type {
+        ibase::SQL_SHORT | ibase::SQL_LONG | ibase::SQL_INT64 => {
+            blr.put_slice(&[consts::blr::INT64, meta.scale as u8]);
+            8
+        }
+
+        ibase::SQL_INT128 => {
+            blr.put_slice(&[consts::blr::INT128, meta.scale as u8]);
+            16
+        }
+
+        ibase::SQL_FLOAT | ibase::SQL_DOUBLE | ibase::SQL_D_FLOAT => {
+            blr.put_u8(consts::blr::DOUBLE);
+            8
+        }
+
+        ibase::SQL_TIMESTAMP => {
+            blr.put_u8(consts::blr::TIMESTAMP);
+            8
+        }
+
+        ibase::SQL_TYPE_DATE => {
+            blr.put_u8(consts::blr::SQL_DATE);
+            4
+        }
+
+        ibase::SQL_TYPE_TIME => {
+            blr.put_u8(consts::blr::SQL_TIME);
+            4
+        }
+
+        ibase::SQL_TIMESTAMP_TZ | ibase::SQL_TIMESTAMP_TZ_EX => {
+            blr.put_u8(consts::blr::TIMESTAMP_TZ);
+            12
+        }
+
+        ibase::SQL_TIME_TZ | ibase::SQL_TIME_TZ_EX => {
+            blr.put_u8(consts::blr::TIME_TZ);
+            8
+        }
+
+        ibase::SQL_BLOB => {
+            blr.put_slice(&[consts::blr::QUAD, meta.sub_type as u8]);
+            8
+        }
+
+        ibase::SQL_BOOLEAN => {
+            blr.put_u8(consts::blr::BOOL);
+            4
+        }
+
+        // Texts and the types without a parameter representation
+        _ => {
+            blr.put_u8(consts::blr::TEXT);
+            blr.put_u16_le(0);
+            0
+        }
+    }
+}
+
+/// Insert an integer, using the 128 bits representation only
+/// when needed, so the older servers can still receive the value
+fn put_int(blr: &mut BytesMut, values: &mut BytesMut, i: i128, scale: i16) {
+    match i64::try_from(i) {
+        Ok(i) => {
+            blr.put_slice(&[consts::blr::INT64, scale as u8]);
+            values.put_i64(i);
+        }
+        Err(_) => {
+            blr.put_slice(&[consts::blr::INT128, scale as u8]);
+            values.put_i128(i);
+        }
+    }
+}
+
 /// Create a null indicator bitmap and insert into the `values`
 ///
 /// The bitmap is a list of bytes,
@@ -150,3 +261,33 @@ fn null_bitmap(values: &mut BytesMut, params: &[SqlType]) {
         values.put_u32_le(bitmap);
     }
 }
+
+#[test]
+fn null_types() {
+    let meta = |sql_type, scale, sub_type| ParamMeta {
+        sql_type,
+        sub_type,
+        length: 0,
+        scale,
+        nullable: true,
+    };
+
+    let mut blr = BytesMut::new();
+    assert_eq!(8, put_null_type(&mut blr, &meta(ibase::SQL_LONG, -2, 0)));
+    assert_eq!(&[consts::blr::INT64, -2_i8 as u8][..], &blr[..]);
+
+    let mut blr = BytesMut::new();
+    assert_eq!(
+        4,
+        put_null_type(&mut blr, &meta(ibase::SQL_TYPE_DATE, 0, 0))
+    );
+    assert_eq!(&[consts::blr::SQL_DATE][..], &blr[..]);
+
+    let mut blr = BytesMut::new();
+    assert_eq!(8, put_null_type(&mut blr, &meta(ibase::SQL_BLOB, 0, 1)));
+    assert_eq!(&[consts::blr::QUAD, 1][..], &blr[..]);
+
+    let mut blr = BytesMut::new();
+    assert_eq!(0, put_null_type(&mut blr, &meta(ibase::SQL_VARYING, 0, 0)));
+    assert_eq!(&[consts::blr::TEXT, 0, 0][..], &blr[..]);
+}
@@ -2,9 +2,13 @@
 
 use bytes::{BufMut, Bytes, BytesMut};
 use std::{
+    collections::VecDeque,
     env,
     io::{Read, Write},
     net::TcpStream,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
 };
 
 use crate::{
@@ -14,21 +18,59 @@ use crate::{
     srp::*,
     util::*,
     wire::*,
-    xsqlda::{parse_xsqlda, xsqlda_to_blr, PrepareInfo, XSqlVar, XSQLDA_DESCRIBE_VARS},
+    xsqlda::{
+        parse_bind_xsqlda, parse_xsqlda, xsqlda_to_blr, PrepareInfo, XSqlVar, XSQLDA_DESCRIBE_BIND,
+        XSQLDA_DESCRIBE_VARS,
+    },
 };
 use rsfbclient_core::{
-    ibase, Charset, Column, Dialect, FbError, FirebirdClientDbOps, FirebirdClientSqlOps,
-    FreeStmtOp, SqlType, StmtType, TrIsolationLevel, TrOp,
+    connect_failover, ibase, Charset, Column, ColumnMeta, CreateDatabaseConfig, Dialect, FbError,
+    FetchScroll, FirebirdClientCancelOps, FirebirdClientDbOps, FirebirdClientScrollOps,
+    FirebirdClientSqlOps, FreeStmtOp, ParamMeta, SqlType, StmtType, TrIsolationLevel,
+    TrLockResolution, TrOp, TransactionConfiguration,
 };
 
 type RustDbHandle = DbHandle;
 type RustTrHandle = TrHandle;
 type RustStmtHandle = StmtHandle;
 
+/// Cursor flag of the scrollable cursors
+const CURSOR_TYPE_SCROLLABLE: u32 = 1;
+
+/// Interval between the reconnection attempts
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Maximum number of warnings kept until taken, the newer are discarded
+const MAX_WARNINGS: usize = 100;
+
+/// Stream already connected with the server, like a tunnel, used in place of the tcp socket
+pub trait WireStream: Read + Write + Send {}
+
+impl<S: Read + Write + Send> WireStream for S {}
+
 /// Firebird client implemented in pure rust
 pub struct RustFbClient {
     conn: Option<FirebirdWireConnection>,
     charset: Charset,
+    fetch_size: u32,
+    /// Maximum number of reconnection attempts after the connection is lost. Zero disables
+    max_reconnects: u32,
+    /// Highest wire protocol version offered on the connection
+    max_protocol: ProtocolVersion,
+    /// Stream to be used by the connection instead of connecting to the host
+    stream: Option<Box<dyn WireStream>>,
+    /// The connection uses a provided stream, so it can't be restored when lost
+    provided_stream: bool,
+    /// Lock resolution of the transactions started
+    lock_resolution: TrLockResolution,
+    /// Maximum time waiting for the server data on the socket
+    read_timeout: Option<Duration>,
+    /// Configuration of the current attachment, used to reconnect
+    attachment: Option<Box<RustFbClientAttachmentConfig>>,
+    /// Handle of the current attachment, replaces the handles of a lost connection
+    db_handle: Option<DbHandle>,
+    /// Incremented on each reconnection
+    generation: u32,
 }
 
 /// Required configuration for an attachment with the pure rust client
@@ -39,6 +81,20 @@ pub struct RustFbClientAttachmentConfig {
     pub db_name: String,
     pub user: String,
     pub pass: String,
+    /// Extra items appended to the database parameter buffer, as `(tag, value)`
+    pub dpb_items: Vec<(u8, Vec<u8>)>,
+    /// Keys of an encrypted database, as `(key holder plugin, key)`, sent when
+    /// requested by the server
+    pub db_crypt_keys: Vec<(String, Vec<u8>)>,
+    /// Other servers, as `(host, port)`, tried in order when
+    /// the connection with the `host` and `port` fails
+    pub failover_hosts: Vec<(String, u16)>,
+}
+
+/// Token to cancel the operations running in an attachment of the pure rust client
+pub struct RustCancelHandle {
+    /// Clone of the connection socket, only used for writing
+    socket: Mutex<FbStream>,
 }
 
 /// A Connection to a firebird server
@@ -56,6 +112,12 @@ pub struct FirebirdWireConnection {
     lazy_count: u32,
 
     pub(crate) charset: Charset,
+
+    /// Incremented on each reconnection, to identify the handles of a lost connection
+    generation: u32,
+
+    /// Warnings returned by the server, until taken
+    warnings: Vec<String>,
 }
 
 /// Data to keep track about a prepared statement
@@ -64,10 +126,26 @@ pub struct StmtHandleData {
     handle: RustStmtHandle,
     /// Output xsqlda
     xsqlda: Vec<XSqlVar>,
+    /// Description of the columns, before the coercions
+    columns: Vec<ColumnMeta>,
     /// Blr representation of the above
     blr: Bytes,
     /// Number of parameters
     param_count: usize,
+    /// Rows already received from the server, but not yet returned
+    rows: VecDeque<Vec<ParsedColumn>>,
+    /// All the rows of the result set were received
+    end_of_stream: bool,
+    /// Sql and dialect used to prepare the statement again after a reconnection
+    sql: String,
+    dialect: Dialect,
+    /// Connection generation that prepared the statement
+    generation: u32,
+    /// Execution timeout sent with the executions, zero for none
+    timeout_ms: u32,
+    /// Description of the parameters, made on the first execution with a `NULL`,
+    /// so they are sent with the parameter type
+    params_meta: Option<Vec<ParamMeta>>,
 }
 
 impl RustFbClient {
@@ -76,20 +154,224 @@ impl RustFbClient {
         Self {
             conn: None,
             charset,
+            fetch_size: 1,
+            max_reconnects: 0,
+            max_protocol: ProtocolVersion::V13,
+            stream: None,
+            provided_stream: false,
+            lock_resolution: TrLockResolution::Wait,
+            read_timeout: None,
+            attachment: None,
+            db_handle: None,
+            generation: 0,
+        }
+    }
+
+    /// Set the maximum number of rows received in each fetch round trip
+    pub fn set_fetch_size(&mut self, fetch_size: u32) {
+        self.fetch_size = fetch_size.max(1);
+    }
+
+    /// Set the highest wire protocol version offered to the server on the connection,
+    /// to enable the newer features or to limit the ones used with an old server.
+    /// The connection is rejected if the server doesn't support any of the versions offered.
+    /// Default: `ProtocolVersion::V13`
+    pub fn set_max_protocol_version(&mut self, version: ProtocolVersion) {
+        self.max_protocol = version;
+    }
+
+    /// Set what the transactions started do when they find a record
+    /// locked by another transaction
+    pub fn set_lock_resolution(&mut self, lock_resolution: TrLockResolution) {
+        self.lock_resolution = lock_resolution;
+    }
+
+    /// Set the maximum time waiting for the server data on the socket, so a stalled
+    /// server fails the operation instead of blocking it forever. None or zero disables.
+    ///
+    /// The response may still arrive later, so the connection is closed on a timeout,
+    /// failing with a `FbError::Io` of the `TimedOut` kind, identified as a connection
+    /// loss. Not used with the provided streams
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+        // A zero timeout is rejected by the socket
+        self.read_timeout = timeout.filter(|t| !t.is_zero());
+    }
+
+    /// Use a stream already connected with the server, like a socket handed over by a
+    /// proxy or a ssh tunnel, instead of connecting to the host and port configured.
+    /// The authentication and the wire encryption still run over the stream.
+    ///
+    /// The stream is only used by the next attach or database creation, and can't be
+    /// restored if lost, so the reconnection is disabled. The cancel handles are
+    /// not available, as they need a second handle of the socket
+    pub fn set_stream<S>(&mut self, stream: S)
+    where
+        S: WireStream + 'static,
+    {
+        self.stream = Some(Box::new(stream));
+        self.provided_stream = true;
+    }
+
+    /// Set the maximum number of attempts to attach again when the connection
+    /// with the server is lost. Zero disables the reconnection
+    ///
+    /// The transactions active in the lost connection are rolled back by the server,
+    /// so the operations using them return a `FbError::TransactionLost` error.
+    /// Rolling back a lost transaction with `RollbackRetaining` starts a new one in its place,
+    /// and the statements prepared in the lost connection are prepared again on the next execution.
+    /// The session state, like the statement timeout, is not restored
+    pub fn set_auto_reconnect(&mut self, max_reconnects: u32) {
+        self.max_reconnects = max_reconnects;
+    }
+
+    /// True if the error was caused by a lost connection that can be restored
+    fn can_reconnect(&self, err: &FbError) -> bool {
+        self.max_reconnects > 0
+            && !self.provided_stream
+            && self.attachment.is_some()
+            && err.is_connection_lost()
+    }
+
+    /// Drop the lost connection and attach again with the current configuration
+    fn reconnect(&mut self) -> Result<(), FbError> {
+        let config = match &self.attachment {
+            Some(config) if self.max_reconnects > 0 && !self.provided_stream => config.clone(),
+            _ => return err_client_not_connected(),
+        };
+
+        self.conn = None;
+        self.generation += 1;
+
+        let mut res = Ok(());
+        for attempt in 0..self.max_reconnects {
+            if attempt > 0 {
+                thread::sleep(RECONNECT_INTERVAL);
+            }
+
+            res = self.attach_database(&config).map(|_| ());
+            if res.is_ok() {
+                break;
+            }
+        }
+
+        res
+    }
+
+    /// Connect to the server, over the provided stream if any
+    fn connect(
+        &mut self,
+        config: &RustFbClientAttachmentConfig,
+    ) -> Result<FirebirdWireConnection, FbError> {
+        if !self.provided_stream {
+            let hosts: Vec<(&str, u16)> = std::iter::once((config.host.as_str(), config.port))
+                .chain(
+                    config
+                        .failover_hosts
+                        .iter()
+                        .map(|(host, port)| (host.as_str(), *port)),
+                )
+                .collect();
+
+            return connect_failover(&hosts, |host, port| {
+                FirebirdWireConnection::connect(
+                    host,
+                    port,
+                    &config.db_name,
+                    &config.user,
+                    &config.pass,
+                    self.charset.clone(),
+                    self.max_protocol,
+                    self.read_timeout,
+                )
+            });
+        }
+
+        let stream = self.stream.take().ok_or_else(|| {
+            FbError::from("The provided stream was already used, each connection needs a new one")
+        })?;
+
+        FirebirdWireConnection::connect_stream(
+            Transport::Stream(stream),
+            "",
+            &config.db_name,
+            &config.user,
+            &config.pass,
+            self.charset.clone(),
+            self.max_protocol,
+        )
+    }
+
+    /// Connection with the server, attaching again if the previous reconnection failed
+    fn conn(&mut self) -> Result<&mut FirebirdWireConnection, FbError> {
+        if self.conn.is_none() {
+            self.reconnect()?;
+        }
+
+        self.conn
+            .as_mut()
+            .map(Ok)
+            .unwrap_or_else(err_client_not_connected)
+    }
+
+    /// Replace a database handle of a lost connection by the current one
+    fn sync_db_handle(&self, db_handle: &mut DbHandle) {
+        if let Some(current) = self.db_handle {
+            if current.generation != db_handle.generation {
+                *db_handle = current;
+            }
         }
     }
+
+    /// Run an operation that uses a transaction. If the transaction belongs to a lost
+    /// connection, or the connection is lost while running, returns a `FbError::TransactionLost`
+    fn in_transaction<T, F>(&mut self, tr_generation: u32, op: F) -> Result<T, FbError>
+    where
+        F: FnOnce(&mut FirebirdWireConnection) -> Result<T, FbError>,
+    {
+        if tr_generation != self.generation {
+            return err_transaction_lost();
+        }
+
+        match op(self.conn()?) {
+            Err(e) if self.can_reconnect(&e) => {
+                self.reconnect()?;
+
+                err_transaction_lost()
+            }
+            res => res,
+        }
+    }
+
+    /// Prepare again a statement of a lost connection
+    fn sync_stmt_handle(
+        &mut self,
+        db_handle: &mut DbHandle,
+        tr_handle: &mut TrHandle,
+        stmt_handle: &mut StmtHandleData,
+    ) -> Result<(), FbError> {
+        if stmt_handle.generation != self.generation && tr_handle.generation == self.generation {
+            let sql = stmt_handle.sql.clone();
+            let dialect = stmt_handle.dialect;
+
+            let (_, stmt) = self.prepare_statement(db_handle, tr_handle, dialect, &sql)?;
+            let timeout_ms = stmt_handle.timeout_ms;
+            *stmt_handle = stmt;
+            stmt_handle.timeout_ms = timeout_ms;
+        }
+
+        Ok(())
+    }
 }
 
 impl FirebirdClientDbOps for RustFbClient {
     type DbHandle = RustDbHandle;
     type AttachmentConfig = RustFbClientAttachmentConfig;
+    type CancelHandle = RustCancelHandle;
 
     fn attach_database(
         &mut self,
         config: &Self::AttachmentConfig,
     ) -> Result<RustDbHandle, FbError> {
-        let host = config.host.as_str();
-        let port = config.port;
         let db_name = config.db_name.as_str();
         let user = config.user.as_str();
         let pass = config.pass.as_str();
@@ -97,25 +379,35 @@ impl FirebirdClientDbOps for RustFbClient {
         // Take the existing connection, or connects
         let mut conn = match self.conn.take() {
             Some(conn) => conn,
-            None => FirebirdWireConnection::connect(
-                host,
-                port,
-                db_name,
-                user,
-                pass,
-                self.charset.clone(),
-            )?,
+            None => self.connect(config)?,
         };
 
-        let attach_result = conn.attach_database(db_name, user, pass);
+        conn.generation = self.generation;
+
+        let attach_result = conn.attach_database(
+            db_name,
+            user,
+            pass,
+            &config.dpb_items,
+            &config.db_crypt_keys,
+        );
 
         // Put the connection back
         self.conn.replace(conn);
 
+        if let Ok(db_handle) = attach_result {
+            self.attachment = Some(Box::new(config.clone()));
+            self.db_handle = Some(db_handle);
+        }
+
         attach_result
     }
 
     fn detach_database(&mut self, db_handle: &mut RustDbHandle) -> Result<(), FbError> {
+        // Don't reconnect after the detach
+        self.attachment = None;
+        self.sync_db_handle(db_handle);
+
         self.conn
             .as_mut()
             .map(|conn| conn.detach_database(db_handle))
@@ -129,13 +421,41 @@ impl FirebirdClientDbOps for RustFbClient {
             .unwrap_or_else(err_client_not_connected)
     }
 
+    fn cancel_handle(
+        &mut self,
+        _db_handle: &mut RustDbHandle,
+    ) -> Result<RustCancelHandle, FbError> {
+        self.conn
+            .as_mut()
+            .map(|conn| conn.cancel_handle())
+            .unwrap_or_else(err_client_not_connected)
+    }
+
+    fn ping(&mut self, db_handle: &mut RustDbHandle) -> Result<(), FbError> {
+        self.conn
+            .as_mut()
+            .map(|conn| conn.ping(db_handle))
+            .unwrap_or_else(err_client_not_connected)
+    }
+
+    fn database_info(
+        &mut self,
+        db_handle: &mut RustDbHandle,
+        request_items: &[u8],
+        buffer_len: usize,
+    ) -> Result<Vec<u8>, FbError> {
+        self.conn
+            .as_mut()
+            .map(|conn| conn.database_info(db_handle, request_items, buffer_len))
+            .unwrap_or_else(err_client_not_connected)
+    }
+
     fn create_database(
         &mut self,
         config: &Self::AttachmentConfig,
-        page_size: Option<u32>,
+        create_config: &CreateDatabaseConfig,
+        dialect: Dialect,
     ) -> Result<RustDbHandle, FbError> {
-        let host = config.host.as_str();
-        let port = config.port;
         let db_name = config.db_name.as_str();
         let user = config.user.as_str();
         let pass = config.pass.as_str();
@@ -143,21 +463,29 @@ impl FirebirdClientDbOps for RustFbClient {
         // Take the existing connection, or connects
         let mut conn = match self.conn.take() {
             Some(conn) => conn,
-            None => FirebirdWireConnection::connect(
-                host,
-                port,
-                db_name,
-                user,
-                pass,
-                self.charset.clone(),
-            )?,
+            None => self.connect(config)?,
         };
 
-        let attach_result = conn.create_database(db_name, user, pass, page_size);
+        conn.generation = self.generation;
+
+        let attach_result = conn.create_database(
+            db_name,
+            user,
+            pass,
+            &config.dpb_items,
+            &config.db_crypt_keys,
+            create_config,
+            dialect,
+        );
 
         // Put the connection back
         self.conn.replace(conn);
 
+        if let Ok(db_handle) = attach_result {
+            self.attachment = Some(Box::new(config.clone()));
+            self.db_handle = Some(db_handle);
+        }
+
         attach_result
     }
 }
@@ -172,10 +500,31 @@ impl FirebirdClientSqlOps for RustFbClient {
         db_handle: &mut Self::DbHandle,
         isolation_level: TrIsolationLevel,
     ) -> Result<Self::TrHandle, FbError> {
-        self.conn
-            .as_mut()
-            .map(|conn| conn.begin_transaction(db_handle, isolation_level))
-            .unwrap_or_else(err_client_not_connected)
+        self.begin_transaction_with_config(db_handle, isolation_level.into())
+    }
+
+    fn begin_transaction_with_config(
+        &mut self,
+        db_handle: &mut Self::DbHandle,
+        conf: TransactionConfiguration,
+    ) -> Result<Self::TrHandle, FbError> {
+        self.sync_db_handle(db_handle);
+        let lock_resolution = self.lock_resolution;
+
+        match self
+            .conn()?
+            .begin_transaction(db_handle, conf, lock_resolution)
+        {
+            // No transaction was lost yet, so just start it in a new connection
+            Err(e) if self.can_reconnect(&e) => {
+                self.reconnect()?;
+                self.sync_db_handle(db_handle);
+
+                self.conn()?
+                    .begin_transaction(db_handle, conf, lock_resolution)
+            }
+            res => res,
+        }
     }
 
     fn transaction_operation(
@@ -183,10 +532,36 @@ impl FirebirdClientSqlOps for RustFbClient {
         tr_handle: &mut Self::TrHandle,
         op: TrOp,
     ) -> Result<(), FbError> {
-        self.conn
-            .as_mut()
-            .map(|conn| conn.transaction_operation(tr_handle, op))
-            .unwrap_or_else(err_client_not_connected)
+        let res = self.in_transaction(tr_handle.generation, |conn| {
+            conn.transaction_operation(tr_handle, op)
+        });
+
+        match (res, op) {
+            // The server already rolled back the lost transaction
+            (Err(FbError::TransactionLost { .. }), TrOp::Rollback) => Ok(()),
+            (Err(FbError::TransactionLost { .. }), TrOp::RollbackRetaining) => {
+                let mut db_handle = self
+                    .db_handle
+                    .map(Ok)
+                    .unwrap_or_else(err_client_not_connected)?;
+
+                *tr_handle = self.begin_transaction_with_config(&mut db_handle, tr_handle.conf)?;
+
+                Ok(())
+            }
+            (res, _) => res,
+        }
+    }
+
+    fn transaction_info(
+        &mut self,
+        tr_handle: &mut Self::TrHandle,
+        request_items: &[u8],
+        buffer_len: usize,
+    ) -> Result<Vec<u8>, FbError> {
+        self.in_transaction(tr_handle.generation, |conn| {
+            conn.transaction_info(tr_handle, request_items, buffer_len)
+        })
     }
 
     fn exec_immediate(
@@ -196,10 +571,9 @@ impl FirebirdClientSqlOps for RustFbClient {
         dialect: Dialect,
         sql: &str,
     ) -> Result<(), FbError> {
-        self.conn
-            .as_mut()
-            .map(|conn| conn.exec_immediate(tr_handle, dialect, sql))
-            .unwrap_or_else(err_client_not_connected)
+        self.in_transaction(tr_handle.generation, |conn| {
+            conn.exec_immediate(tr_handle, dialect, sql)
+        })
     }
 
     fn prepare_statement(
@@ -209,9 +583,86 @@ impl FirebirdClientSqlOps for RustFbClient {
         dialect: Dialect,
         sql: &str,
     ) -> Result<(StmtType, Self::StmtHandle), FbError> {
+        self.sync_db_handle(db_handle);
+
+        self.in_transaction(tr_handle.generation, |conn| {
+            conn.prepare_statement(db_handle, tr_handle, dialect, sql)
+        })
+    }
+
+    fn columns(&mut self, stmt_handle: &mut Self::StmtHandle) -> Result<Vec<ColumnMeta>, FbError> {
+        Ok(stmt_handle.columns.clone())
+    }
+
+    fn params(&mut self, stmt_handle: &mut Self::StmtHandle) -> Result<Vec<ParamMeta>, FbError> {
+        if stmt_handle.generation != self.generation {
+            return Err(FbError::ConnectionLost {
+                msg: "The statement was prepared in a lost connection, execute it to prepare again"
+                    .into(),
+                status: vec![],
+            });
+        }
+
+        self.conn
+            .as_mut()
+            .map(|conn| conn.describe_params(stmt_handle))
+            .unwrap_or_else(err_client_not_connected)
+    }
+
+    fn set_cursor_name(
+        &mut self,
+        stmt_handle: &mut Self::StmtHandle,
+        name: &str,
+    ) -> Result<(), FbError> {
+        if stmt_handle.generation != self.generation {
+            return Err(FbError::ConnectionLost {
+                msg: "The statement was prepared in a lost connection, prepare it again".into(),
+                status: vec![],
+            });
+        }
+
         self.conn
             .as_mut()
-            .map(|conn| conn.prepare_statement(db_handle, tr_handle, dialect, sql))
+            .map(|conn| conn.set_cursor_name(stmt_handle, name))
+            .unwrap_or_else(err_client_not_connected)
+    }
+
+    fn set_statement_timeout(
+        &mut self,
+        stmt_handle: &mut Self::StmtHandle,
+        timeout_ms: u32,
+    ) -> Result<(), FbError> {
+        let version = match &self.conn {
+            Some(conn) => conn.version,
+            None => return err_client_not_connected(),
+        };
+
+        // Sent in the execution requests, only from the protocol 16
+        if timeout_ms != 0 && version < ProtocolVersion::V16 {
+            return Err("The statement timeout requires the wire protocol 16 (Firebird 4+)".into());
+        }
+
+        stmt_handle.timeout_ms = timeout_ms;
+
+        Ok(())
+    }
+
+    fn statement_info(
+        &mut self,
+        stmt_handle: &mut Self::StmtHandle,
+        request_items: &[u8],
+        buffer_len: usize,
+    ) -> Result<Vec<u8>, FbError> {
+        if stmt_handle.generation != self.generation {
+            return Err(FbError::ConnectionLost {
+                msg: "The statement was prepared in a lost connection, prepare it again".into(),
+                status: vec![],
+            });
+        }
+
+        self.conn
+            .as_mut()
+            .map(|conn| conn.statement_info(stmt_handle, request_items, buffer_len))
             .unwrap_or_else(err_client_not_connected)
     }
 
@@ -220,6 +671,14 @@ impl FirebirdClientSqlOps for RustFbClient {
         stmt_handle: &mut Self::StmtHandle,
         op: FreeStmtOp,
     ) -> Result<(), FbError> {
+        if stmt_handle.generation != self.generation {
+            // Already released by the server with the lost connection
+            stmt_handle.rows.clear();
+            stmt_handle.end_of_stream = false;
+
+            return Ok(());
+        }
+
         self.conn
             .as_mut()
             .map(|conn| conn.free_statement(stmt_handle, op))
@@ -228,28 +687,32 @@ impl FirebirdClientSqlOps for RustFbClient {
 
     fn execute(
         &mut self,
-        _db_handle: &mut Self::DbHandle,
+        db_handle: &mut Self::DbHandle,
         tr_handle: &mut Self::TrHandle,
         stmt_handle: &mut Self::StmtHandle,
-        params: Vec<SqlType>,
+        params: &[SqlType],
     ) -> Result<usize, FbError> {
-        self.conn
-            .as_mut()
-            .map(|conn| conn.execute(tr_handle, stmt_handle, &params))
-            .unwrap_or_else(err_client_not_connected)
+        self.sync_db_handle(db_handle);
+        self.sync_stmt_handle(db_handle, tr_handle, stmt_handle)?;
+
+        self.in_transaction(tr_handle.generation, |conn| {
+            conn.execute(tr_handle, stmt_handle, params)
+        })
     }
 
     fn execute2(
         &mut self,
-        _db_handle: &mut Self::DbHandle,
+        db_handle: &mut Self::DbHandle,
         tr_handle: &mut Self::TrHandle,
         stmt_handle: &mut Self::StmtHandle,
-        params: Vec<SqlType>,
+        params: &[SqlType],
     ) -> Result<Vec<Column>, FbError> {
-        self.conn
-            .as_mut()
-            .map(|conn| conn.execute2(tr_handle, stmt_handle, &params))
-            .unwrap_or_else(err_client_not_connected)
+        self.sync_db_handle(db_handle);
+        self.sync_stmt_handle(db_handle, tr_handle, stmt_handle)?;
+
+        self.in_transaction(tr_handle.generation, |conn| {
+            conn.execute2(tr_handle, stmt_handle, params)
+        })
     }
 
     fn fetch(
@@ -258,19 +721,104 @@ impl FirebirdClientSqlOps for RustFbClient {
         tr_handle: &mut Self::TrHandle,
         stmt_handle: &mut Self::StmtHandle,
     ) -> Result<Option<Vec<Column>>, FbError> {
+        let fetch_size = self.fetch_size;
+
+        if stmt_handle.generation != self.generation {
+            // The cursor was closed with the lost connection
+            return err_transaction_lost();
+        }
+
+        self.in_transaction(tr_handle.generation, |conn| {
+            conn.fetch(tr_handle, stmt_handle, fetch_size)
+        })
+    }
+
+    fn fetch_values(
+        &mut self,
+        _db_handle: &mut Self::DbHandle,
+        tr_handle: &mut Self::TrHandle,
+        stmt_handle: &mut Self::StmtHandle,
+    ) -> Result<Option<Vec<SqlType>>, FbError> {
+        let fetch_size = self.fetch_size;
+
+        if stmt_handle.generation != self.generation {
+            // The cursor was closed with the lost connection
+            return err_transaction_lost();
+        }
+
+        self.in_transaction(tr_handle.generation, |conn| {
+            conn.fetch_values(tr_handle, stmt_handle, fetch_size)
+        })
+    }
+
+    fn take_warnings(&mut self) -> Vec<String> {
         self.conn
             .as_mut()
-            .map(|conn| conn.fetch(tr_handle, stmt_handle))
-            .unwrap_or_else(err_client_not_connected)
+            .map(|conn| conn.take_warnings())
+            .unwrap_or_default()
+    }
+}
+
+impl FirebirdClientScrollOps for RustFbClient {
+    fn execute_scrollable(
+        &mut self,
+        db_handle: &mut Self::DbHandle,
+        tr_handle: &mut Self::TrHandle,
+        stmt_handle: &mut Self::StmtHandle,
+        params: &[SqlType],
+    ) -> Result<(), FbError> {
+        self.sync_db_handle(db_handle);
+        self.sync_stmt_handle(db_handle, tr_handle, stmt_handle)?;
+
+        self.in_transaction(tr_handle.generation, |conn| {
+            conn.execute_scrollable(tr_handle, stmt_handle, params)
+        })
+    }
+
+    fn fetch_scroll(
+        &mut self,
+        _db_handle: &mut Self::DbHandle,
+        tr_handle: &mut Self::TrHandle,
+        stmt_handle: &mut Self::StmtHandle,
+        op: FetchScroll,
+    ) -> Result<Option<Vec<Column>>, FbError> {
+        if stmt_handle.generation != self.generation {
+            // The cursor was closed with the lost connection
+            return err_transaction_lost();
+        }
+
+        self.in_transaction(tr_handle.generation, |conn| {
+            conn.fetch_scroll(tr_handle, stmt_handle, op)
+        })
     }
 }
 
+/// Columns of the values, named by the aliases in the statement description
+fn with_names(values: Vec<SqlType>, stmt_handle: &StmtHandleData) -> Vec<Column> {
+    values
+        .into_iter()
+        .zip(&stmt_handle.xsqlda)
+        .map(|(value, var)| Column::new(var.alias_name.clone(), value))
+        .collect()
+}
+
+fn err_scroll_protocol<T>() -> Result<T, FbError> {
+    Err("The scrollable cursors require the wire protocol 18 (Firebird 5+)".into())
+}
+
 fn err_client_not_connected<T>() -> Result<T, FbError> {
     Err("Client not connected to the server, call `attach_database` to connect".into())
 }
 
+fn err_transaction_lost<T>() -> Result<T, FbError> {
+    Err(FbError::TransactionLost {
+        msg: "The connection with the server was lost and restored, the changes not committed were discarded. Rollback the transaction to start a new one".into(),
+    })
+}
+
 impl FirebirdWireConnection {
     /// Start a connection to the firebird server
+    #[allow(clippy::too_many_arguments)]
     pub fn connect(
         host: &str,
         port: u16,
@@ -278,23 +826,48 @@ impl FirebirdWireConnection {
         user: &str,
         pass: &str,
         charset: Charset,
+        max_protocol: ProtocolVersion,
+        read_timeout: Option<Duration>,
     ) -> Result<Self, FbError> {
         let socket = TcpStream::connect((host, port))?;
+        socket.set_read_timeout(read_timeout)?;
 
-        // System username
-        let username =
-            env::var("USER").unwrap_or_else(|_| env::var("USERNAME").unwrap_or_default());
         let hostname = socket
             .local_addr()
             .map(|addr| addr.to_string())
             .unwrap_or_default();
 
-        let mut socket = FbStream::Plain(socket);
+        Self::connect_stream(
+            Transport::Tcp(socket),
+            &hostname,
+            db_name,
+            user,
+            pass,
+            charset,
+            max_protocol,
+        )
+    }
+
+    /// Start the connection over the transport, authenticating the user
+    fn connect_stream(
+        socket: Transport,
+        hostname: &str,
+        db_name: &str,
+        user: &str,
+        pass: &str,
+        charset: Charset,
+        max_protocol: ProtocolVersion,
+    ) -> Result<Self, FbError> {
+        // System username
+        let username =
+            env::var("USER").unwrap_or_else(|_| env::var("USERNAME").unwrap_or_default());
+
+        let mut socket = FbStream::new(socket);
 
         // Random key for the srp
         let srp_key: [u8; 32] = rand::random();
 
-        let req = connect(db_name, user, &username, &hostname, &srp_key);
+        let req = connect(db_name, user, &username, hostname, &srp_key, max_protocol);
         socket.write_all(&req)?;
         socket.flush()?;
 
@@ -374,30 +947,41 @@ impl FirebirdWireConnection {
             buff,
             lazy_count: 0,
             charset,
+            generation: 0,
+            warnings: vec![],
         })
     }
 
     /// Create the database and attach, returning a database handle
+    #[allow(clippy::too_many_arguments)]
     pub fn create_database(
         &mut self,
         db_name: &str,
         user: &str,
         pass: &str,
-        page_size: Option<u32>,
+        dpb_items: &[(u8, Vec<u8>)],
+        db_crypt_keys: &[(String, Vec<u8>)],
+        create_config: &CreateDatabaseConfig,
+        dialect: Dialect,
     ) -> Result<DbHandle, FbError> {
         self.socket.write_all(&create(
             db_name,
             user,
             pass,
+            dpb_items,
             self.version,
             self.charset.clone(),
-            page_size,
+            create_config,
+            dialect,
         ))?;
         self.socket.flush()?;
 
-        let resp = self.read_response()?;
+        let resp = self.read_attach_response(db_crypt_keys)?;
 
-        Ok(DbHandle(resp.handle))
+        Ok(DbHandle {
+            handle: resp.handle,
+            generation: self.generation,
+        })
     }
 
     /// Connect to a database, returning a database handle
@@ -406,24 +990,30 @@ impl FirebirdWireConnection {
         db_name: &str,
         user: &str,
         pass: &str,
+        dpb_items: &[(u8, Vec<u8>)],
+        db_crypt_keys: &[(String, Vec<u8>)],
     ) -> Result<DbHandle, FbError> {
         self.socket.write_all(&attach(
             db_name,
             user,
             pass,
+            dpb_items,
             self.version,
             self.charset.clone(),
         ))?;
         self.socket.flush()?;
 
-        let resp = self.read_response()?;
+        let resp = self.read_attach_response(db_crypt_keys)?;
 
-        Ok(DbHandle(resp.handle))
+        Ok(DbHandle {
+            handle: resp.handle,
+            generation: self.generation,
+        })
     }
 
     /// Disconnect from the database
     pub fn detach_database(&mut self, db_handle: &mut DbHandle) -> Result<(), FbError> {
-        self.socket.write_all(&detach(db_handle.0))?;
+        self.socket.write_all(&detach(db_handle.handle))?;
         self.socket.flush()?;
 
         self.read_response()?;
@@ -431,9 +1021,50 @@ impl FirebirdWireConnection {
         Ok(())
     }
 
+    /// Create a token to cancel the operations from another thread,
+    /// using a clone of the connection socket
+    pub fn cancel_handle(&self) -> Result<RustCancelHandle, FbError> {
+        Ok(RustCancelHandle {
+            socket: Mutex::new(self.socket.try_clone()?),
+        })
+    }
+
+    /// Request a trivial database information, to check if the attachment is alive
+    pub fn ping(&mut self, db_handle: &mut DbHandle) -> Result<(), FbError> {
+        self.socket.write_all(&info_database(
+            db_handle.handle,
+            &[ibase::db_info_types_isc_info_ods_version as u8],
+            BUFFER_LENGTH,
+        ))?;
+        self.socket.flush()?;
+
+        self.read_response()?;
+
+        Ok(())
+    }
+
+    /// Query the database for the requested info items, returning the raw response buffer
+    pub fn database_info(
+        &mut self,
+        db_handle: &mut DbHandle,
+        request_items: &[u8],
+        buffer_len: usize,
+    ) -> Result<Vec<u8>, FbError> {
+        self.socket.write_all(&info_database(
+            db_handle.handle,
+            request_items,
+            buffer_len.min(u16::MAX as usize) as u32,
+        ))?;
+        self.socket.flush()?;
+
+        let resp = self.read_response()?;
+
+        Ok(resp.data.to_vec())
+    }
+
     /// Drop the database
     pub fn drop_database(&mut self, db_handle: &mut DbHandle) -> Result<(), FbError> {
-        self.socket.write_all(&drop_database(db_handle.0))?;
+        self.socket.write_all(&drop_database(db_handle.handle))?;
         self.socket.flush()?;
 
         self.read_response()?;
@@ -445,16 +1076,23 @@ impl FirebirdWireConnection {
     pub fn begin_transaction(
         &mut self,
         db_handle: &mut DbHandle,
-        isolation_level: TrIsolationLevel,
+        conf: TransactionConfiguration,
+        lock_resolution: TrLockResolution,
     ) -> Result<TrHandle, FbError> {
-        let tpb = [ibase::isc_tpb_version3 as u8, isolation_level as u8];
+        let mut tpb = conf.tpb_items();
+        tpb.extend(lock_resolution.tpb_items());
 
-        self.socket.write_all(&transaction(db_handle.0, &tpb))?;
+        self.socket
+            .write_all(&transaction(db_handle.handle, &tpb))?;
         self.socket.flush()?;
 
         let resp = self.read_response()?;
 
-        Ok(TrHandle(resp.handle))
+        Ok(TrHandle {
+            handle: resp.handle,
+            generation: self.generation,
+            conf,
+        })
     }
 
     /// Commit / Rollback a transaction
@@ -464,7 +1102,7 @@ impl FirebirdWireConnection {
         op: TrOp,
     ) -> Result<(), FbError> {
         self.socket
-            .write_all(&transaction_operation(tr_handle.0, op))?;
+            .write_all(&transaction_operation(tr_handle.handle, op))?;
         self.socket.flush()?;
 
         self.read_response()?;
@@ -472,6 +1110,25 @@ impl FirebirdWireConnection {
         Ok(())
     }
 
+    /// Query the transaction for the requested info items, returning the raw response buffer
+    pub fn transaction_info(
+        &mut self,
+        tr_handle: &mut TrHandle,
+        request_items: &[u8],
+        buffer_len: usize,
+    ) -> Result<Vec<u8>, FbError> {
+        self.socket.write_all(&info_transaction(
+            tr_handle.handle,
+            request_items,
+            buffer_len.min(u16::MAX as usize) as u32,
+        ))?;
+        self.socket.flush()?;
+
+        let resp = self.read_response()?;
+
+        Ok(resp.data.to_vec())
+    }
+
     /// Execute a sql immediately, without returning rows
     pub fn exec_immediate(
         &mut self,
@@ -480,7 +1137,7 @@ impl FirebirdWireConnection {
         sql: &str,
     ) -> Result<(), FbError> {
         self.socket.write_all(&exec_immediate(
-            tr_handle.0,
+            tr_handle.handle,
             dialect as u32,
             sql,
             &self.charset,
@@ -503,10 +1160,11 @@ impl FirebirdWireConnection {
         sql: &str,
     ) -> Result<(StmtType, StmtHandleData), FbError> {
         // Alloc statement
-        self.socket.write_all(&allocate_statement(db_handle.0))?;
+        self.socket
+            .write_all(&allocate_statement(db_handle.handle))?;
         // Prepare statement
         self.socket.write_all(&prepare_statement(
-            tr_handle.0,
+            tr_handle.handle,
             u32::MAX,
             dialect as u32,
             sql,
@@ -550,47 +1208,148 @@ impl FirebirdWireConnection {
             mut truncated,
         } = parse_xsqlda(&mut resp.data, &mut xsqlda)?;
 
-        while truncated {
-            // Get more info on the types
-            let next_index = (xsqlda.len() as u16).to_le_bytes();
+        while truncated {
+            // Get more info on the types
+            let next_index = (xsqlda.len() as u16).to_le_bytes();
+
+            self.socket.write_all(&info_sql(
+                stmt_handle.0,
+                &[
+                    &[
+                        ibase::isc_info_sql_sqlda_start as u8, // Describe a xsqlda
+                        2,
+                        next_index[0], // Index, first byte
+                        next_index[1], // Index, second byte
+                    ],
+                    &XSQLDA_DESCRIBE_VARS[..], // Data to be returned
+                ]
+                .concat(),
+                BUFFER_LENGTH,
+            ))?;
+            self.socket.flush()?;
+
+            let mut data = self.read_response()?.data;
+
+            let parse_resp = parse_xsqlda(&mut data, &mut xsqlda)?;
+            truncated = parse_resp.truncated;
+            param_count = parse_resp.param_count;
+        }
+
+        let columns = xsqlda.iter().map(XSqlVar::meta).collect();
+
+        // Coerce the output columns and transform to blr
+        for var in xsqlda.iter_mut() {
+            var.coerce()?;
+        }
+        let blr = xsqlda_to_blr(&xsqlda)?;
+
+        Ok((
+            stmt_type,
+            StmtHandleData {
+                handle: stmt_handle,
+                xsqlda,
+                columns,
+                blr,
+                param_count,
+                rows: VecDeque::new(),
+                end_of_stream: false,
+                sql: sql.to_string(),
+                dialect,
+                generation: self.generation,
+                timeout_ms: 0,
+                params_meta: None,
+            },
+        ))
+    }
+
+    /// Description of the parameters, to send the `NULL`s with their types. Only made,
+    /// once for each statement, when a `NULL` is sent, as it needs a round trip
+    fn null_params_meta<'s>(
+        &mut self,
+        stmt_handle: &'s mut StmtHandleData,
+        params: &[SqlType],
+    ) -> Result<&'s [ParamMeta], FbError> {
+        if stmt_handle.params_meta.is_none() && params.iter().any(SqlType::is_null) {
+            stmt_handle.params_meta = Some(self.describe_params(stmt_handle)?);
+        }
+
+        Ok(stmt_handle.params_meta.as_deref().unwrap_or_default())
+    }
+
+    /// Describe the parameters of a prepared statement
+    pub fn describe_params(
+        &mut self,
+        stmt_handle: &mut StmtHandleData,
+    ) -> Result<Vec<ParamMeta>, FbError> {
+        let mut xsqlda = Vec::new();
+
+        loop {
+            let req = if xsqlda.is_empty() {
+                XSQLDA_DESCRIBE_BIND.to_vec()
+            } else {
+                let next_index = (xsqlda.len() as u16).to_le_bytes();
 
-            self.socket.write_all(&info_sql(
-                stmt_handle.0,
-                &[
+                [
                     &[
                         ibase::isc_info_sql_sqlda_start as u8, // Describe a xsqlda
                         2,
                         next_index[0], // Index, first byte
                         next_index[1], // Index, second byte
                     ],
-                    &XSQLDA_DESCRIBE_VARS[..], // Data to be returned
+                    &XSQLDA_DESCRIBE_BIND[..], // Data to be returned
                 ]
-                .concat(),
-            ))?;
+                .concat()
+            };
+
+            self.socket
+                .write_all(&info_sql(stmt_handle.handle.0, &req, BUFFER_LENGTH))?;
             self.socket.flush()?;
 
             let mut data = self.read_response()?.data;
 
-            let parse_resp = parse_xsqlda(&mut data, &mut xsqlda)?;
-            truncated = parse_resp.truncated;
-            param_count = parse_resp.param_count;
+            if !parse_bind_xsqlda(&mut data, &mut xsqlda)? {
+                break;
+            }
         }
 
-        // Coerce the output columns and transform to blr
-        for var in xsqlda.iter_mut() {
-            var.coerce()?;
-        }
-        let blr = xsqlda_to_blr(&xsqlda)?;
+        Ok(xsqlda.iter().map(XSqlVar::param_meta).collect())
+    }
 
-        Ok((
-            stmt_type,
-            StmtHandleData {
-                handle: stmt_handle,
-                xsqlda,
-                blr,
-                param_count,
-            },
-        ))
+    /// Name the cursor of a prepared statement
+    pub fn set_cursor_name(
+        &mut self,
+        stmt_handle: &mut StmtHandleData,
+        name: &str,
+    ) -> Result<(), FbError> {
+        let name = self.charset.encode(name)?;
+
+        self.socket
+            .write_all(&set_cursor(stmt_handle.handle.0, &name))?;
+        self.socket.flush()?;
+
+        self.read_response()?;
+
+        Ok(())
+    }
+
+    /// Query the prepared statement for the requested info items,
+    /// returning the raw response buffer
+    pub fn statement_info(
+        &mut self,
+        stmt_handle: &mut StmtHandleData,
+        request_items: &[u8],
+        buffer_len: usize,
+    ) -> Result<Vec<u8>, FbError> {
+        self.socket.write_all(&info_sql(
+            stmt_handle.handle.0,
+            request_items,
+            buffer_len.min(u16::MAX as usize) as u32,
+        ))?;
+        self.socket.flush()?;
+
+        let resp = self.read_response()?;
+
+        Ok(resp.data.to_vec())
     }
 
     /// Closes or drops a statement
@@ -599,6 +1358,9 @@ impl FirebirdWireConnection {
         stmt_handle: &mut StmtHandleData,
         op: FreeStmtOp,
     ) -> Result<(), FbError> {
+        stmt_handle.rows.clear();
+        stmt_handle.end_of_stream = false;
+
         self.socket
             .write_all(&free_statement(stmt_handle.handle.0, op))?;
         // Obs.: Lazy response
@@ -614,6 +1376,34 @@ impl FirebirdWireConnection {
         tr_handle: &mut TrHandle,
         stmt_handle: &mut StmtHandleData,
         params: &[SqlType],
+    ) -> Result<usize, FbError> {
+        self.execute_cursor(tr_handle, stmt_handle, params, 0)
+    }
+
+    /// Execute the prepared statement with parameters, opening a scrollable cursor.
+    /// Requires Firebird 5+
+    pub fn execute_scrollable(
+        &mut self,
+        tr_handle: &mut TrHandle,
+        stmt_handle: &mut StmtHandleData,
+        params: &[SqlType],
+    ) -> Result<(), FbError> {
+        if self.version < ProtocolVersion::V18 {
+            return err_scroll_protocol();
+        }
+
+        self.execute_cursor(tr_handle, stmt_handle, params, CURSOR_TYPE_SCROLLABLE)?;
+
+        Ok(())
+    }
+
+    /// Execute the prepared statement with parameters and the cursor flags
+    fn execute_cursor(
+        &mut self,
+        tr_handle: &mut TrHandle,
+        stmt_handle: &mut StmtHandleData,
+        params: &[SqlType],
+        cursor_flags: u32,
     ) -> Result<usize, FbError> {
         if params.len() != stmt_handle.param_count {
             return Err(format!(
@@ -624,14 +1414,22 @@ impl FirebirdWireConnection {
             .into());
         }
 
+        // Discard the rows left from a previous execution
+        stmt_handle.rows.clear();
+        stmt_handle.end_of_stream = false;
+
         // Execute
-        let params = blr::params_to_blr(self, tr_handle, params)?;
+        let params_meta = self.null_params_meta(stmt_handle, params)?;
+        let params = blr::params_to_blr(self, tr_handle, params, params_meta)?;
 
         self.socket.write_all(&execute(
-            tr_handle.0,
+            tr_handle.handle,
             stmt_handle.handle.0,
             &params.blr,
             &params.values,
+            self.version,
+            stmt_handle.timeout_ms,
+            cursor_flags,
         ))?;
         self.socket.flush()?;
 
@@ -641,6 +1439,7 @@ impl FirebirdWireConnection {
         self.socket.write_all(&info_sql(
             stmt_handle.handle.0,
             &[ibase::isc_info_sql_records as u8], // Request affected rows,
+            BUFFER_LENGTH,
         ))?;
         self.socket.flush()?;
 
@@ -665,14 +1464,29 @@ impl FirebirdWireConnection {
             .into());
         }
 
-        let params = blr::params_to_blr(self, tr_handle, params)?;
+        // Discard the rows left from a previous execution
+        stmt_handle.rows.clear();
+        stmt_handle.end_of_stream = false;
+
+        let params_meta = self.null_params_meta(stmt_handle, params)?;
+        let params = blr::params_to_blr(self, tr_handle, params, params_meta)?;
+
+        // Without output values, like an `EXECUTE PROCEDURE` of a procedure without
+        // output parameters, no message is requested, so the server returns none
+        let output_blr: &[u8] = if stmt_handle.xsqlda.is_empty() {
+            &[]
+        } else {
+            &stmt_handle.blr
+        };
 
         self.socket.write_all(&execute2(
-            tr_handle.0,
+            tr_handle.handle,
             stmt_handle.handle.0,
             &params.blr,
             &params.values,
-            &stmt_handle.blr,
+            output_blr,
+            self.version,
+            stmt_handle.timeout_ms,
         ))?;
         self.socket.flush()?;
 
@@ -698,29 +1512,117 @@ impl FirebirdWireConnection {
             return err_conn_rejected(op_code);
         }
 
+        if stmt_handle.xsqlda.is_empty() {
+            // Number of messages, always 0
+            resp.get_u32()?;
+            parse_response(&mut resp)?;
+
+            return Ok(vec![]);
+        }
+
         let parsed_cols =
             parse_sql_response(&mut resp, &stmt_handle.xsqlda, self.version, &self.charset)?;
 
         parse_response(&mut resp)?;
 
-        let mut cols = Vec::with_capacity(parsed_cols.len());
+        let mut values = Vec::with_capacity(parsed_cols.len());
 
         for pc in parsed_cols {
-            cols.push(pc.into_column(self, tr_handle)?);
+            values.push(pc.into_value(self, tr_handle)?);
         }
 
-        Ok(cols)
+        Ok(with_names(values, stmt_handle))
     }
 
     /// Fetch rows from the executed statement, coercing the types
-    /// according to the provided blr
+    /// according to the provided blr.
+    ///
+    /// Up to `fetch_size` rows are received on each round trip
+    /// to the server, and returned one by one in the next calls
     pub fn fetch(
         &mut self,
         tr_handle: &mut TrHandle,
         stmt_handle: &mut StmtHandleData,
+        fetch_size: u32,
     ) -> Result<Option<Vec<Column>>, FbError> {
-        self.socket
-            .write_all(&fetch(stmt_handle.handle.0, &stmt_handle.blr))?;
+        Ok(self
+            .fetch_values(tr_handle, stmt_handle, fetch_size)?
+            .map(|values| with_names(values, stmt_handle)))
+    }
+
+    /// Move the scrollable cursor, fetching the row in the new position.
+    /// Requires Firebird 5+
+    pub fn fetch_scroll(
+        &mut self,
+        tr_handle: &mut TrHandle,
+        stmt_handle: &mut StmtHandleData,
+        op: FetchScroll,
+    ) -> Result<Option<Vec<Column>>, FbError> {
+        if self.version < ProtocolVersion::V18 {
+            return err_scroll_protocol();
+        }
+
+        // Only the row in the requested position is received, as the
+        // end of the result set doesn't stop the cursor from moving
+        stmt_handle.rows.clear();
+
+        let req = fetch_scroll(stmt_handle.handle.0, &stmt_handle.blr, op);
+        self.fetch_rows(stmt_handle, req)?;
+
+        stmt_handle.end_of_stream = false;
+
+        self.next_row(tr_handle, stmt_handle)
+    }
+
+    /// Fetch the values of the next row, like [`fetch`](Self::fetch),
+    /// without the column names
+    pub fn fetch_values(
+        &mut self,
+        tr_handle: &mut TrHandle,
+        stmt_handle: &mut StmtHandleData,
+        fetch_size: u32,
+    ) -> Result<Option<Vec<SqlType>>, FbError> {
+        if stmt_handle.rows.is_empty() && !stmt_handle.end_of_stream {
+            let req = fetch(stmt_handle.handle.0, &stmt_handle.blr, fetch_size);
+            self.fetch_rows(stmt_handle, req)?;
+        }
+
+        self.next_values(tr_handle, stmt_handle)
+    }
+
+    /// Convert the next row received to columns
+    fn next_row(
+        &mut self,
+        tr_handle: &mut TrHandle,
+        stmt_handle: &mut StmtHandleData,
+    ) -> Result<Option<Vec<Column>>, FbError> {
+        Ok(self
+            .next_values(tr_handle, stmt_handle)?
+            .map(|values| with_names(values, stmt_handle)))
+    }
+
+    /// Convert the next row received to values
+    fn next_values(
+        &mut self,
+        tr_handle: &mut TrHandle,
+        stmt_handle: &mut StmtHandleData,
+    ) -> Result<Option<Vec<SqlType>>, FbError> {
+        if let Some(parsed_cols) = stmt_handle.rows.pop_front() {
+            let mut values = Vec::with_capacity(parsed_cols.len());
+
+            for pc in parsed_cols {
+                values.push(pc.into_value(self, tr_handle)?);
+            }
+
+            Ok(Some(values))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Send the fetch request and store the received rows in the statement buffer
+    fn fetch_rows(&mut self, stmt_handle: &mut StmtHandleData, req: Bytes) -> Result<(), FbError> {
+        self.socket.write_all(&req)?;
         self.socket.flush()?;
 
         let (mut op_code, mut resp) = read_packet(&mut self.socket, &mut self.buff)?;
@@ -736,28 +1638,49 @@ impl FirebirdWireConnection {
             op_code = resp.get_u32()?;
         }
 
-        if op_code == WireOp::Response as u32 {
-            // An error ocurred
-            parse_response(&mut resp)?;
-        }
-
-        if op_code != WireOp::FetchResponse as u32 {
-            return err_conn_rejected(op_code);
-        }
+        loop {
+            if op_code == WireOp::Response as u32 {
+                // An error ocurred
+                parse_response(&mut resp)?;
+            }
 
-        if let Some(parsed_cols) =
-            parse_fetch_response(&mut resp, &stmt_handle.xsqlda, self.version, &self.charset)?
-        {
-            let mut cols = Vec::with_capacity(parsed_cols.len());
+            if op_code != WireOp::FetchResponse as u32 {
+                return err_conn_rejected(op_code);
+            }
 
-            for pc in parsed_cols {
-                cols.push(pc.into_column(self, tr_handle)?);
+            let mut msg = resp.clone();
+            match parse_fetch_response(&mut msg, &stmt_handle.xsqlda, self.version, &self.charset) {
+                Ok(FetchResponse::Row(parsed_cols)) => stmt_handle.rows.push_back(parsed_cols),
+                Ok(FetchResponse::EndOfBatch) => break,
+                Ok(FetchResponse::EndOfStream) => {
+                    stmt_handle.end_of_stream = true;
+                    break;
+                }
+                Err(e) if is_missing_bytes(&e) => {
+                    // The message was split, so wait for the rest of it
+                    resp = read_more(&mut self.socket, &mut self.buff, resp)?;
+                    continue;
+                }
+                Err(e) => return Err(e),
             }
+            resp = msg;
 
-            Ok(Some(cols))
-        } else {
-            Ok(None)
+            // Each row is sent in a separated message
+            op_code = loop {
+                if resp.remaining() < 4 {
+                    resp = read_more(&mut self.socket, &mut self.buff, resp)?;
+                    continue;
+                }
+
+                let op_code = resp.get_u32()?;
+
+                if op_code != WireOp::Dummy as u32 {
+                    break op_code;
+                }
+            };
         }
+
+        Ok(())
     }
 
     /// Create a new blob, returning the blob handle and id
@@ -765,7 +1688,7 @@ impl FirebirdWireConnection {
         &mut self,
         tr_handle: &mut TrHandle,
     ) -> Result<(BlobHandle, BlobId), FbError> {
-        self.socket.write_all(&create_blob(tr_handle.0))?;
+        self.socket.write_all(&create_blob(tr_handle.handle))?;
         self.socket.flush()?;
 
         let resp = self.read_response()?;
@@ -792,7 +1715,8 @@ impl FirebirdWireConnection {
         tr_handle: &mut TrHandle,
         blob_id: BlobId,
     ) -> Result<BlobHandle, FbError> {
-        self.socket.write_all(&open_blob(tr_handle.0, blob_id.0))?;
+        self.socket
+            .write_all(&open_blob(tr_handle.handle, blob_id.0))?;
         self.socket.flush()?;
 
         let resp = self.read_response()?;
@@ -835,9 +1759,54 @@ impl FirebirdWireConnection {
         Ok(())
     }
 
-    /// Read a server response
+    /// Read a server response, keeping the warnings up to `MAX_WARNINGS`
     fn read_response(&mut self) -> Result<Response, FbError> {
-        read_response(&mut self.socket, &mut self.buff, &mut self.lazy_count)
+        let mut resp = read_response(&mut self.socket, &mut self.buff, &mut self.lazy_count)?;
+
+        let free = MAX_WARNINGS.saturating_sub(self.warnings.len());
+        self.warnings.extend(resp.warnings.drain(..).take(free));
+
+        Ok(resp)
+    }
+
+    /// Read the response of an attach or create, answering the requests of the database
+    /// encryption key made by the key holder plugin of the server. Without a key, an empty
+    /// one is sent, so the server fails with the missing key error
+    fn read_attach_response(
+        &mut self,
+        db_crypt_keys: &[(String, Vec<u8>)],
+    ) -> Result<Response, FbError> {
+        loop {
+            let (op_code, mut resp) = self.read_packet()?;
+
+            if op_code == WireOp::CryptKeyCallback as u32 {
+                // Data sent by the database crypt plugin
+                let data = resp.get_wire_bytes()?;
+
+                self.socket.write_all(&crypt_key_callback(
+                    select_crypt_key(db_crypt_keys, &data),
+                    self.version,
+                ))?;
+                self.socket.flush()?;
+                continue;
+            }
+
+            if op_code != WireOp::Response as u32 {
+                return err_conn_rejected(op_code);
+            }
+
+            let mut resp = parse_response(&mut resp)?;
+
+            let free = MAX_WARNINGS.saturating_sub(self.warnings.len());
+            self.warnings.extend(resp.warnings.drain(..).take(free));
+
+            return Ok(resp);
+        }
+    }
+
+    /// Take the warnings returned by the server since the last call
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
     }
 
     /// Reads a packet from the socket
@@ -872,9 +1841,42 @@ fn read_response(
     parse_response(&mut resp)
 }
 
+/// Reads more data from the socket, appending it to the data not yet parsed
+fn read_more(socket: &mut impl Read, buff: &mut [u8], data: Bytes) -> Result<Bytes, FbError> {
+    let len = socket.read(buff)?;
+    if len == 0 {
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+    }
+
+    let mut resp = BytesMut::with_capacity(data.len() + len);
+    resp.put_slice(&data);
+    resp.put_slice(&buff[..len]);
+
+    Ok(resp.freeze())
+}
+
+/// Select the key to answer a key callback: the one of the plugin named in the
+/// data sent by the server, or else the first one
+fn select_crypt_key<'a>(keys: &'a [(String, Vec<u8>)], data: &[u8]) -> &'a [u8] {
+    keys.iter()
+        .find(|(plugin, _)| {
+            !plugin.is_empty()
+                && data
+                    .windows(plugin.len())
+                    .any(|w| w.eq_ignore_ascii_case(plugin.as_bytes()))
+        })
+        .or_else(|| keys.first())
+        .map(|(_, key)| key.as_slice())
+        .unwrap_or_default()
+}
+
 /// Reads a packet from the socket
 fn read_packet(socket: &mut impl Read, buff: &mut [u8]) -> Result<(u32, Bytes), FbError> {
     let mut len = socket.read(buff)?;
+    if len == 0 {
+        // Socket closed by the server
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+    }
     let mut resp = BytesMut::from(&buff[..len]);
 
     loop {
@@ -938,9 +1940,9 @@ where
     socket.write_all(&crypt("Arc4", "Symmetric"))?;
     socket.flush()?;
 
-    socket = FbStream::Arc4(Arc4Stream::new(
-        match socket {
-            FbStream::Plain(s) => s,
+    socket.kind = FbStreamKind::Arc4(Arc4Stream::new(
+        match socket.kind {
+            FbStreamKind::Plain(s) => s,
             _ => unreachable!("Stream was already encrypted!"),
         },
         &verifier.get_key(),
@@ -954,11 +1956,20 @@ where
 
 #[derive(Debug, Clone, Copy)]
 /// A database handle
-pub struct DbHandle(u32);
+pub struct DbHandle {
+    handle: u32,
+    /// Connection generation that created the handle
+    generation: u32,
+}
 
 #[derive(Debug, Clone, Copy)]
 /// A transaction handle
-pub struct TrHandle(u32);
+pub struct TrHandle {
+    handle: u32,
+    /// Connection generation that created the handle
+    generation: u32,
+    conf: TransactionConfiguration,
+}
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 /// A statement handle
@@ -972,56 +1983,353 @@ pub struct BlobHandle(u32);
 /// A blob Identificator
 pub struct BlobId(pub(crate) u64);
 
-/// Firebird tcp stream, may be encrypted
-enum FbStream {
+/// Firebird stream, may be encrypted
+struct FbStream {
+    kind: FbStreamKind,
+
+    /// Shared with the clones of the stream, held while a whole packet is
+    /// encrypted and written, so the packets of the clones are not interleaved
+    write_lock: Arc<Mutex<()>>,
+}
+
+/// Encryption of the firebird stream
+enum FbStreamKind {
     /// Plaintext stream
-    Plain(TcpStream),
+    Plain(Transport),
 
     /// Arc4 ecrypted stream
-    Arc4(Arc4Stream<TcpStream>),
+    Arc4(Arc4Stream<Transport>),
 }
 
-impl Read for FbStream {
+impl FbStream {
+    /// Plaintext stream of the connection
+    fn new(socket: Transport) -> Self {
+        Self {
+            kind: FbStreamKind::Plain(socket),
+            write_lock: Default::default(),
+        }
+    }
+
+    /// Creates a new stream to write in the same socket
+    fn try_clone(&self) -> std::io::Result<Self> {
+        let kind = match &self.kind {
+            FbStreamKind::Plain(s) => FbStreamKind::Plain(s.try_clone()?),
+            FbStreamKind::Arc4(s) => FbStreamKind::Arc4(s.try_clone_with(Transport::try_clone)?),
+        };
+
+        Ok(Self {
+            kind,
+            write_lock: self.write_lock.clone(),
+        })
+    }
+}
+
+/// Connection with the server used by the wire protocol
+enum Transport {
+    /// Tcp socket connected by the client
+    Tcp(TcpStream),
+
+    /// Stream provided already connected
+    Stream(Box<dyn WireStream>),
+}
+
+impl Transport {
+    /// Creates a new handle of the same socket
+    fn try_clone(&self) -> std::io::Result<Self> {
+        match self {
+            Transport::Tcp(s) => Ok(Transport::Tcp(s.try_clone()?)),
+            Transport::Stream(_) => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "The connections over a provided stream can't be cloned",
+            )),
+        }
+    }
+}
+
+impl Read for Transport {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
         match self {
-            FbStream::Plain(s) => s.read(buf),
-            FbStream::Arc4(s) => s.read(buf),
+            Transport::Tcp(s) => s.read(buf).map_err(|e| match e.kind() {
+                // Read timeout expired. The response may be received later,
+                // out of sync with the next requests, so the socket is closed
+                std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut => {
+                    s.shutdown(std::net::Shutdown::Both).ok();
+
+                    std::io::Error::new(
+                        std::io::ErrorKind::TimedOut,
+                        "No data received from the server in the read timeout, the connection was closed",
+                    )
+                }
+                _ => e,
+            }),
+            Transport::Stream(s) => s.read(buf),
         }
     }
 }
 
-impl Write for FbStream {
+impl Write for Transport {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
         match self {
-            FbStream::Plain(s) => s.write(buf),
-            FbStream::Arc4(s) => s.write(buf),
+            Transport::Tcp(s) => s.write(buf),
+            Transport::Stream(s) => s.write(buf),
         }
     }
 
     fn flush(&mut self) -> std::io::Result<()> {
         match self {
-            FbStream::Plain(s) => s.flush(),
-            FbStream::Arc4(s) => s.flush(),
+            Transport::Tcp(s) => s.flush(),
+            Transport::Stream(s) => s.flush(),
+        }
+    }
+}
+
+impl FirebirdClientCancelOps for RustCancelHandle {
+    fn cancel_operation(&self) -> Result<(), FbError> {
+        let mut socket = self
+            .socket
+            .lock()
+            .map_err(|_| FbError::from("Poisoned cancel handle"))?;
+
+        // The server does not send a response for the cancel request,
+        // the cancelled operation will receive the error instead
+        socket.write_all(&cancel(ibase::fb_cancel_raise))?;
+        socket.flush()?;
+
+        Ok(())
+    }
+}
+
+impl Read for FbStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match &mut self.kind {
+            FbStreamKind::Plain(s) => s.read(buf),
+            FbStreamKind::Arc4(s) => s.read(buf),
+        }
+    }
+}
+
+impl FbStream {
+    /// Run the write operation holding the lock shared with the clones
+    fn locked<T>(
+        &mut self,
+        op: impl FnOnce(&mut dyn Write) -> std::io::Result<T>,
+    ) -> std::io::Result<T> {
+        let _guard = self
+            .write_lock
+            .lock()
+            .map_err(|_| std::io::Error::other("Poisoned write lock"))?;
+
+        match &mut self.kind {
+            FbStreamKind::Plain(s) => op(s),
+            FbStreamKind::Arc4(s) => op(s),
+        }
+    }
+}
+
+impl Write for FbStream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.locked(|s| s.write(buf))
+    }
+
+    /// Writes the whole packet at once, without the writes of the clones in the middle
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        self.locked(|s| s.write_all(buf))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.locked(|s| s.flush())
+    }
+}
+
+#[test]
+fn read_timeout() {
+    use rsfbclient_core::charset::UTF_8;
+    use std::net::TcpListener;
+
+    // Accepts the connection, but never answers
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = thread::spawn(move || listener.accept().map(|(socket, _)| socket));
+
+    let res = FirebirdWireConnection::connect(
+        "127.0.0.1",
+        port,
+        "test.fdb",
+        "SYSDBA",
+        "masterkey",
+        UTF_8,
+        ProtocolVersion::V18,
+        Some(Duration::from_millis(100)),
+    );
+
+    match res {
+        Err(FbError::Io(e)) => assert_eq!(std::io::ErrorKind::TimedOut, e.kind()),
+        Err(e) => panic!("Unexpected error: {}", e),
+        Ok(_) => panic!("Connected without a server response"),
+    }
+    assert!(FbError::from(std::io::Error::from(std::io::ErrorKind::TimedOut)).is_connection_lost());
+
+    server.join().unwrap().unwrap();
+
+    // Zero disables the timeout, as it is rejected by the socket
+    let mut cli = RustFbClient::new(UTF_8);
+    cli.set_read_timeout(Some(Duration::ZERO));
+    assert_eq!(None, cli.read_timeout);
+    cli.set_read_timeout(Some(Duration::from_secs(1)));
+    assert_eq!(Some(Duration::from_secs(1)), cli.read_timeout);
+}
+
+#[test]
+fn cloned_stream_writes() {
+    use std::net::TcpListener;
+
+    const PACKET_LEN: usize = 16 * 1024;
+    const PACKETS: usize = 200;
+    let key = b"a key";
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = thread::spawn(move || {
+        let (socket, _) = listener.accept().unwrap();
+        let mut stream = Arc4Stream::new(socket, key, PACKET_LEN);
+
+        let mut data = vec![];
+        stream.read_to_end(&mut data).unwrap();
+        data
+    });
+
+    let mut stream = FbStream::new(Transport::Tcp(
+        TcpStream::connect(("127.0.0.1", port)).unwrap(),
+    ));
+    stream.kind = match stream.kind {
+        // Smaller buffer than the packets, encrypting and writing each in many steps
+        FbStreamKind::Plain(s) => FbStreamKind::Arc4(Arc4Stream::new(s, key, 1024)),
+        _ => unreachable!(),
+    };
+
+    // Same as the cancel handle, writing from another thread
+    let mut clone = stream.try_clone().unwrap();
+    let other = thread::spawn(move || {
+        for _ in 0..PACKETS {
+            clone.write_all(&[b'b'; PACKET_LEN]).unwrap();
         }
+    });
+    for _ in 0..PACKETS {
+        stream.write_all(&[b'a'; PACKET_LEN]).unwrap();
     }
+    other.join().unwrap();
+    drop(stream);
+
+    let data = server.join().unwrap();
+    assert_eq!(2 * PACKETS * PACKET_LEN, data.len());
+    for packet in data.chunks(PACKET_LEN) {
+        assert!(
+            packet.iter().all(|b| *b == packet[0]),
+            "Interleaved packets"
+        );
+    }
+}
+
+#[test]
+fn crypt_key_callback_exchange() {
+    use rsfbclient_core::charset::UTF_8;
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = thread::spawn(move || {
+        let (mut socket, _) = listener.accept().unwrap();
+        let mut buff = [0; 1024];
+
+        // Asks the key twice, naming the plugin only on the second
+        let mut replies = vec![];
+        for data in [&b""[..], &b"KeyHolder2"[..]] {
+            let mut req = BytesMut::new();
+            req.put_u32(WireOp::CryptKeyCallback as u32);
+            req.put_wire_bytes(data);
+            socket.write_all(&req).unwrap();
+
+            let len = socket.read(&mut buff).unwrap();
+            replies.push(buff[..len].to_vec());
+        }
+
+        // Attach response, without errors
+        let mut resp = BytesMut::new();
+        resp.put_u32(WireOp::Response as u32);
+        resp.put_u32(7); // Handle
+        resp.put_u64(0); // Object id
+        resp.put_wire_bytes(&[]);
+        resp.put_u32(ibase::isc_arg_end);
+        socket.write_all(&resp).unwrap();
+
+        replies
+    });
+
+    let mut conn = FirebirdWireConnection {
+        socket: FbStream::new(Transport::Tcp(
+            TcpStream::connect(("127.0.0.1", port)).unwrap(),
+        )),
+        version: ProtocolVersion::V16,
+        buff: vec![0; BUFFER_LENGTH as usize * 2].into_boxed_slice(),
+        lazy_count: 0,
+        charset: UTF_8,
+        generation: 0,
+        warnings: vec![],
+    };
+
+    let keys = vec![
+        ("KeyHolder1".to_string(), b"key1".to_vec()),
+        ("KeyHolder2".to_string(), b"key2".to_vec()),
+    ];
+    let resp = conn.read_attach_response(&keys).unwrap();
+    assert_eq!(7, resp.handle);
+
+    let replies = server.join().unwrap();
+    assert_eq!(
+        vec![
+            crypt_key_callback(b"key1", ProtocolVersion::V16).to_vec(),
+            crypt_key_callback(b"key2", ProtocolVersion::V16).to_vec(),
+        ],
+        replies
+    );
+    // Key, followed by the reply size
+    let mut reply = Bytes::from(replies[1].clone());
+    assert_eq!(WireOp::CryptKeyCallback as u32, reply.get_u32().unwrap());
+    assert_eq!(b"key2", &reply.get_wire_bytes().unwrap()[..]);
+    assert_eq!(4, reply.get_u32().unwrap());
+
+    assert_eq!(&b""[..], select_crypt_key(&[], b"KeyHolder2"));
 }
 
 #[test]
 #[ignore]
 fn connection_test() {
-    use rsfbclient_core::charset::UTF_8;
+    use rsfbclient_core::{charset::UTF_8, TrIsolationLevel};
 
     let db_name = "test.fdb";
     let user = "SYSDBA";
     let pass = "masterkey";
 
-    let mut conn =
-        FirebirdWireConnection::connect("127.0.0.1", 3050, db_name, user, pass, UTF_8).unwrap();
+    let mut conn = FirebirdWireConnection::connect(
+        "127.0.0.1",
+        3050,
+        db_name,
+        user,
+        pass,
+        UTF_8,
+        ProtocolVersion::V18,
+        None,
+    )
+    .unwrap();
 
-    let mut db_handle = conn.attach_database(db_name, user, pass).unwrap();
+    let mut db_handle = conn.attach_database(db_name, user, pass, &[], &[]).unwrap();
 
     let mut tr_handle = conn
-        .begin_transaction(&mut db_handle, TrIsolationLevel::Concurrency)
+        .begin_transaction(
+            &mut db_handle,
+            TrIsolationLevel::Concurrency.into(),
+            TrLockResolution::Wait,
+        )
         .unwrap();
 
     let (stmt_type, mut stmt_handle) = conn
@@ -1055,7 +2363,7 @@ fn connection_test() {
         .unwrap();
 
     loop {
-        let resp = conn.fetch(&mut tr_handle, &mut stmt_handle).unwrap();
+        let resp = conn.fetch(&mut tr_handle, &mut stmt_handle, 1).unwrap();
 
         if resp.is_none() {
             break;
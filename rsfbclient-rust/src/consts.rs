@@ -6,11 +6,20 @@ use num_enum::TryFromPrimitive;
 
 #[derive(Debug, Clone, Copy, Ord, PartialOrd, Eq, PartialEq, TryFromPrimitive)]
 #[repr(u32)]
+/// Wire protocol version, negotiated with the server on the connection
 pub enum ProtocolVersion {
+    /// Firebird 1.0+
     V10 = 0x0000000A,
+    /// Firebird 2.1+
     V11 = 0xFFFF800B,
+    /// Firebird 2.5+
     V12 = 0xFFFF800C,
+    /// Firebird 3+
     V13 = 0xFFFF800D,
+    /// Firebird 4+, with the statement timeouts
+    V16 = 0xFFFF8010,
+    /// Firebird 5+, with the scrollable cursors
+    V18 = 0xFFFF8012,
 }
 
 #[derive(Debug, TryFromPrimitive)]
@@ -93,6 +102,8 @@ pub enum WireOp {
     FreeStatement = 67,
     /// Prepare a statement
     PrepareStatement = 68,
+    /// Set the cursor name of a statement
+    SetCursor = 69,
     /// Statement info
     InfoSql = 70,
 
@@ -134,6 +145,9 @@ pub enum WireOp {
     /// Server accepts connection, returns some data to client
     /// and asks client to continue authentication before attach call
     CondAccept = 98,
+
+    /// Fetch from a scrollable cursor
+    FetchScroll = 99,
 }
 
 #[derive(Debug)]
@@ -1669,6 +1683,10 @@ pub mod blr {
     pub const COLUMN_NAME: u8 = 21;
     pub const COLUMN_NAME2: u8 = 22;
     pub const BOOL: u8 = 23;
+    pub const INT128: u8 = 26;
+    pub const TIME_TZ: u8 = 28;
+    pub const TIMESTAMP_TZ: u8 = 29;
+    pub const EX_TIMESTAMP_TZ: u8 = 31;
     // first sub parameter for domain_name[2]
     pub const DOMAIN_TYPE_OF: u8 = 0;
     pub const DOMAIN_FULL: u8 = 1;
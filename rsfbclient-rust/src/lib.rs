@@ -9,7 +9,11 @@ mod util;
 mod wire;
 mod xsqlda;
 
-pub use client::{DbHandle, RustFbClient, RustFbClientAttachmentConfig, StmtHandle, TrHandle};
+pub use client::{
+    DbHandle, RustCancelHandle, RustFbClient, RustFbClientAttachmentConfig, StmtHandle, TrHandle,
+    WireStream,
+};
+pub use consts::ProtocolVersion;
 
 #[cfg(feature = "fuzz_testing")]
 pub use self::{blr::*, wire::*, xsqlda::*};
@@ -2,7 +2,7 @@
 
 use bytes::Bytes;
 use rsfbclient_core::FbError;
-use std::convert::TryFrom;
+use std::{convert::TryFrom, fmt, io};
 
 use crate::consts::WireOp;
 
@@ -62,6 +62,9 @@ pub trait BytesWireExt {
     /// Gets an signed 64 bit integer from `self` in the big-endian byte order
     fn get_i64(&mut self) -> Result<i64, FbError>;
 
+    /// Gets an signed 128 bit integer from `self` in the big-endian byte order
+    fn get_i128(&mut self) -> Result<i128, FbError>;
+
     /// Gets an IEEE754 double-precision (8 bytes) floating point number from `self` in big-endian byte order
     fn get_f64(&mut self) -> Result<f64, FbError>;
 
@@ -159,6 +162,13 @@ impl BytesWireExt for Bytes {
         Ok(bytes::Buf::get_i64(self))
     }
 
+    fn get_i128(&mut self) -> Result<i128, FbError> {
+        if self.remaining() < 16 {
+            return err_invalid_response();
+        }
+        Ok(bytes::Buf::get_i128(self))
+    }
+
     fn get_f64(&mut self) -> Result<f64, FbError> {
         if self.remaining() < 8 {
             return err_invalid_response();
@@ -176,8 +186,26 @@ impl BytesWireExt for Bytes {
     }
 }
 
+/// Cause of the errors of an incomplete response, identifying them apart from the others
+#[derive(Debug)]
+struct MissingBytes;
+
+impl fmt::Display for MissingBytes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid server response, missing bytes")
+    }
+}
+
+impl std::error::Error for MissingBytes {}
+
 pub fn err_invalid_response<T>() -> Result<T, FbError> {
-    Err("Invalid server response, missing bytes".into())
+    Err(io::Error::new(io::ErrorKind::InvalidData, MissingBytes).into())
+}
+
+/// Returns true if the error was caused by an incomplete response,
+/// so the parsing can be retried after reading more data
+pub fn is_missing_bytes(e: &FbError) -> bool {
+    matches!(e, FbError::Io(e) if e.get_ref().is_some_and(|cause| cause.is::<MissingBytes>()))
 }
 
 pub fn err_conn_rejected<T>(op_code: u32) -> Result<T, FbError> {
@@ -190,3 +218,17 @@ pub fn err_conn_rejected<T>(op_code: u32) -> Result<T, FbError> {
     )
     .into())
 }
+
+#[test]
+fn missing_bytes() {
+    let mut data = Bytes::from_static(&[0, 0, 1]);
+    let e = BytesWireExt::get_u32(&mut data).unwrap_err();
+    assert!(is_missing_bytes(&e));
+
+    // Identified by the cause, not the message
+    assert!(!is_missing_bytes(&FbError::from(e.to_string())));
+    assert!(!is_missing_bytes(&FbError::from(io::Error::new(
+        io::ErrorKind::InvalidData,
+        e.to_string()
+    ))));
+}
@@ -12,20 +12,37 @@ use crate::{
     util::*,
     xsqlda::{XSqlVar, XSQLDA_DESCRIBE_VARS},
 };
-use rsfbclient_core::{ibase, Charset, Column, FbError, FreeStmtOp, SqlType, TrOp};
+use rsfbclient_core::{
+    ibase, Charset, CreateDatabaseConfig, Dialect, FbError, FetchScroll, FreeStmtOp, SqlType,
+    StatusArg, StatusItem, TrOp,
+};
 
 /// Buffer length to use in the connection
 pub const BUFFER_LENGTH: u32 = 1024;
 
 /// Connection request
-pub fn connect(db_name: &str, user: &str, username: &str, hostname: &str, srp_key: &[u8]) -> Bytes {
-    let protocols = [
+pub fn connect(
+    db_name: &str,
+    user: &str,
+    username: &str,
+    hostname: &str,
+    srp_key: &[u8],
+    max_protocol: ProtocolVersion,
+) -> Bytes {
+    let protocols: Vec<_> = [
         // PROTOCOL_VERSION, Arch type (Generic=1), min, max, weight
-        [ProtocolVersion::V10 as u32, 1, 0, 5, 2],
-        [ProtocolVersion::V11 as u32, 1, 0, 5, 4],
-        [ProtocolVersion::V12 as u32, 1, 0, 5, 6],
-        [ProtocolVersion::V13 as u32, 1, 0, 5, 8],
-    ];
+        (ProtocolVersion::V10, [1, 0, 5, 2]),
+        (ProtocolVersion::V11, [1, 0, 5, 4]),
+        (ProtocolVersion::V12, [1, 0, 5, 6]),
+        (ProtocolVersion::V13, [1, 0, 5, 8]),
+        (ProtocolVersion::V16, [1, 0, 5, 9]),
+        (ProtocolVersion::V18, [1, 0, 5, 10]),
+    ]
+    .iter()
+    // Only the versions up to the maximum are offered to the server
+    .filter(|(version, _)| *version <= max_protocol)
+    .map(|(version, [arch, min, max, weight])| [*version as u32, *arch, *min, *max, *weight])
+    .collect();
 
     let mut connect = BytesMut::with_capacity(256);
 
@@ -131,15 +148,30 @@ pub fn crypt(algo: &str, kind: &str) -> Bytes {
     req.freeze()
 }
 
+/// Answer to a request of the database encryption key, made by the key holder plugin
+pub fn crypt_key_callback(key: &[u8], protocol: ProtocolVersion) -> Bytes {
+    let mut req = BytesMut::with_capacity(12 + key.len());
+
+    req.put_u32(WireOp::CryptKeyCallback as u32);
+    req.put_wire_bytes(key);
+    if protocol > ProtocolVersion::V13 {
+        // Size of the reply
+        req.put_u32(key.len() as u32);
+    }
+
+    req.freeze()
+}
+
 /// Attach request
 pub fn attach(
     db_name: &str,
     user: &str,
     pass: &str,
+    dpb_items: &[(u8, Vec<u8>)],
     protocol: ProtocolVersion,
     charset: Charset,
 ) -> Bytes {
-    let dpb = build_dpb(user, pass, protocol, charset, None);
+    let dpb = build_dpb(db_name, user, pass, dpb_items, protocol, charset);
 
     let mut attach = BytesMut::with_capacity(16 + db_name.len() + dpb.len());
 
@@ -154,15 +186,37 @@ pub fn attach(
 }
 
 /// Create db request
+#[allow(clippy::too_many_arguments)]
 pub fn create(
     db_name: &str,
     user: &str,
     pass: &str,
+    dpb_items: &[(u8, Vec<u8>)],
     protocol: ProtocolVersion,
     charset: Charset,
-    page_size: Option<u32>,
+    create_config: &CreateDatabaseConfig,
+    dialect: Dialect,
 ) -> Bytes {
-    let dpb = build_dpb(user, pass, protocol, charset, page_size);
+    let mut dpb = build_dpb(db_name, user, pass, dpb_items, protocol, charset);
+
+    if let Some(ps) = create_config.page_size {
+        dpb.put_slice(&[ibase::isc_dpb_page_size as u8, 4]);
+        dpb.put_u32_le(ps);
+    }
+
+    if let Some(db_charset) = &create_config.db_charset {
+        let db_charset = db_charset.on_firebird.as_bytes();
+
+        dpb.put_slice(&[ibase::isc_dpb_set_db_charset as u8, db_charset.len() as u8]);
+        dpb.put_slice(db_charset);
+    }
+
+    dpb.put_slice(&[ibase::isc_dpb_sql_dialect as u8, 4]);
+    dpb.put_u32_le(dialect as u32);
+
+    if create_config.overwrite {
+        dpb.put_slice(&[ibase::isc_dpb_overwrite as u8, 1, 1]);
+    }
 
     let mut create = BytesMut::with_capacity(16 + db_name.len() + dpb.len());
 
@@ -178,26 +232,28 @@ pub fn create(
 
 /// Dpb builder
 fn build_dpb(
+    db_name: &str,
     user: &str,
     pass: &str,
+    dpb_items: &[(u8, Vec<u8>)],
     protocol: ProtocolVersion,
     charset: Charset,
-    page_size: Option<u32>,
-) -> Bytes {
+) -> BytesMut {
     let mut dpb = BytesMut::with_capacity(64);
 
     dpb.put_u8(1); //Version
 
-    if let Some(ps) = page_size {
-        dpb.put_slice(&[ibase::isc_dpb_page_size as u8, 4]);
-        dpb.put_u32(ps);
-    }
-
     let charset = charset.on_firebird.as_bytes();
 
     dpb.put_slice(&[ibase::isc_dpb_lc_ctype as u8, charset.len() as u8]);
     dpb.put_slice(charset);
 
+    // The path is sent in utf-8, so it must not be read in the system codepage.
+    // The ascii paths are the same in both, so the flag is only needed for the others
+    if !db_name.is_ascii() {
+        dpb.put_slice(&[ibase::isc_dpb_utf8_filename as u8, 0]);
+    }
+
     dpb.put_slice(&[ibase::isc_dpb_user_name as u8, user.len() as u8]);
     dpb.put_slice(user.as_bytes());
 
@@ -219,10 +275,15 @@ fn build_dpb(
         }
 
         // Password already verified
-        ProtocolVersion::V13 => {}
+        ProtocolVersion::V13 | ProtocolVersion::V16 | ProtocolVersion::V18 => {}
+    }
+
+    for (tag, value) in dpb_items {
+        dpb.put_slice(&[*tag, value.len() as u8]);
+        dpb.put_slice(value);
     }
 
-    dpb.freeze()
+    dpb
 }
 
 /// Detach from the database request
@@ -235,6 +296,16 @@ pub fn detach(db_handle: u32) -> Bytes {
     tr.freeze()
 }
 
+/// Cancel the operation running in the attachment
+pub fn cancel(kind: u32) -> Bytes {
+    let mut req = BytesMut::with_capacity(8);
+
+    req.put_u32(WireOp::Cancel as u32);
+    req.put_u32(kind);
+
+    req.freeze()
+}
+
 /// Drop database request
 pub fn drop_database(db_handle: u32) -> Bytes {
     let mut tr = BytesMut::with_capacity(8);
@@ -328,15 +399,41 @@ pub fn prepare_statement(
     Ok(req.freeze())
 }
 
+/// Database information request
+pub fn info_database(db_handle: u32, requested_items: &[u8], buffer_len: u32) -> Bytes {
+    let mut req = BytesMut::with_capacity(20 + requested_items.len());
+
+    req.put_u32(WireOp::InfoDatabase as u32);
+    req.put_u32(db_handle);
+    req.put_u32(0); // Incarnation of object
+    req.put_wire_bytes(requested_items);
+    req.put_u32(buffer_len);
+
+    req.freeze()
+}
+
+/// Transaction information request
+pub fn info_transaction(tr_handle: u32, requested_items: &[u8], buffer_len: u32) -> Bytes {
+    let mut req = BytesMut::with_capacity(20 + requested_items.len());
+
+    req.put_u32(WireOp::InfoTransaction as u32);
+    req.put_u32(tr_handle);
+    req.put_u32(0); // Incarnation of object
+    req.put_wire_bytes(requested_items);
+    req.put_u32(buffer_len);
+
+    req.freeze()
+}
+
 /// Statement information request
-pub fn info_sql(stmt_handle: u32, requested_items: &[u8]) -> Bytes {
+pub fn info_sql(stmt_handle: u32, requested_items: &[u8], buffer_len: u32) -> Bytes {
     let mut req = BytesMut::with_capacity(24 + requested_items.len());
 
     req.put_u32(WireOp::InfoSql as u32);
     req.put_u32(stmt_handle);
     req.put_u32(0); // Incarnation of object
     req.put_wire_bytes(requested_items);
-    req.put_u32(BUFFER_LENGTH);
+    req.put_u32(buffer_len);
 
     req.freeze()
 }
@@ -352,9 +449,30 @@ pub fn free_statement(stmt_handle: u32, op: FreeStmtOp) -> Bytes {
     req.freeze()
 }
 
+/// Cursor name request
+pub fn set_cursor(stmt_handle: u32, name: &[u8]) -> Bytes {
+    let mut req = BytesMut::with_capacity(16 + name.len());
+
+    req.put_u32(WireOp::SetCursor as u32);
+    req.put_u32(stmt_handle);
+    // The server expects the name to be null terminated
+    req.put_wire_bytes(&[name, &[0]].concat());
+    req.put_u32(0); // Cursor type, unused
+
+    req.freeze()
+}
+
 /// Execute prepared statement request.
-pub fn execute(tr_handle: u32, stmt_handle: u32, input_blr: &[u8], input_data: &[u8]) -> Bytes {
-    let mut req = BytesMut::with_capacity(36 + input_blr.len() + input_data.len());
+pub fn execute(
+    tr_handle: u32,
+    stmt_handle: u32,
+    input_blr: &[u8],
+    input_data: &[u8],
+    version: ProtocolVersion,
+    timeout_ms: u32,
+    cursor_flags: u32,
+) -> Bytes {
+    let mut req = BytesMut::with_capacity(44 + input_blr.len() + input_data.len());
 
     req.put_u32(WireOp::Execute as u32);
     req.put_u32(stmt_handle);
@@ -366,6 +484,13 @@ pub fn execute(tr_handle: u32, stmt_handle: u32, input_blr: &[u8], input_data: &
 
     req.put_slice(input_data);
 
+    if version >= ProtocolVersion::V16 {
+        req.put_u32(timeout_ms);
+    }
+    if version >= ProtocolVersion::V18 {
+        req.put_u32(cursor_flags);
+    }
+
     req.freeze()
 }
 
@@ -376,6 +501,8 @@ pub fn execute2(
     input_blr: &[u8],
     input_data: &[u8],
     output_blr: &[u8],
+    version: ProtocolVersion,
+    timeout_ms: u32,
 ) -> Bytes {
     let mut req =
         BytesMut::with_capacity(40 + input_blr.len() + input_data.len() + output_blr.len());
@@ -393,18 +520,49 @@ pub fn execute2(
     req.put_wire_bytes(output_blr);
     req.put_u32(0); // Output message number
 
+    if version >= ProtocolVersion::V16 {
+        req.put_u32(timeout_ms);
+    }
+    if version >= ProtocolVersion::V18 {
+        req.put_u32(0); // Cursor flags
+    }
+
     req.freeze()
 }
 
-/// Fetch row request
-pub fn fetch(stmt_handle: u32, blr: &[u8]) -> Bytes {
+/// Fetch rows request, `count` is the maximum number of rows returned
+pub fn fetch(stmt_handle: u32, blr: &[u8], count: u32) -> Bytes {
     let mut req = BytesMut::with_capacity(20 + blr.len());
 
     req.put_u32(WireOp::Fetch as u32);
     req.put_u32(stmt_handle);
     req.put_wire_bytes(blr);
     req.put_u32(0); // Message number
-    req.put_u32(1); // Message count TODO: increase to return more rows in one fetch request
+    req.put_u32(count); // Message count
+
+    req.freeze()
+}
+
+/// Fetch from a scrollable cursor request
+pub fn fetch_scroll(stmt_handle: u32, blr: &[u8], op: FetchScroll) -> Bytes {
+    let mut req = BytesMut::with_capacity(28 + blr.len());
+
+    let (fetch_op, position) = match op {
+        FetchScroll::Next => (0, 0),
+        FetchScroll::Prior => (1, 0),
+        FetchScroll::First => (2, 0),
+        FetchScroll::Last => (3, 0),
+        FetchScroll::Absolute(position) => (4, position),
+        FetchScroll::Relative(offset) => (5, offset),
+    };
+
+    req.put_u32(WireOp::FetchScroll as u32);
+    req.put_u32(stmt_handle);
+    req.put_wire_bytes(blr);
+    req.put_u32(0); // Message number
+    req.put_u32(1); // Message count
+    req.put_u32(fetch_op);
+    req.put_i32(position);
 
     req.freeze()
 }
@@ -471,6 +629,8 @@ pub struct Response {
     pub handle: u32,
     pub object_id: u64,
     pub data: Bytes,
+    /// Warnings returned alongside the successful result
+    pub warnings: Vec<String>,
 }
 
 /// Parse a server response (`WireOp::Response`)
@@ -480,31 +640,50 @@ pub fn parse_response(resp: &mut Bytes) -> Result<Response, FbError> {
 
     let data = resp.get_wire_bytes()?;
 
-    parse_status_vector(resp)?;
+    let warnings = parse_status_vector(resp)?;
 
     Ok(Response {
         handle,
         object_id,
         data,
+        warnings,
     })
 }
 
+/// Message of a server fetch response
+pub enum FetchResponse {
+    /// A row of the result set
+    Row(Vec<ParsedColumn>),
+    /// All requested rows were sent, more can be fetched
+    EndOfBatch,
+    /// All rows of the result set were sent
+    EndOfStream,
+}
+
 /// Parse a server sql response (`WireOp::FetchResponse`)
 pub fn parse_fetch_response(
     resp: &mut Bytes,
     xsqlda: &[XSqlVar],
     version: ProtocolVersion,
     charset: &Charset,
-) -> Result<Option<Vec<ParsedColumn>>, FbError> {
+) -> Result<FetchResponse, FbError> {
     const END_OF_STREAM: u32 = 100;
 
     let status = resp.get_u32()?;
 
     if status == END_OF_STREAM {
-        return Ok(None);
+        resp.get_u32()?; // Message count
+        return Ok(FetchResponse::EndOfStream);
     }
 
-    Ok(Some(parse_sql_response(resp, xsqlda, version, charset)?))
+    if resp.remaining() >= 4 && resp[..4] == [0, 0, 0, 0] {
+        resp.get_u32()?; // Message count
+        return Ok(FetchResponse::EndOfBatch);
+    }
+
+    Ok(FetchResponse::Row(parse_sql_response(
+        resp, xsqlda, version, charset,
+    )?))
 }
 
 /// Parse a server sql response (`WireOp::SqlResponse`)
@@ -556,10 +735,7 @@ pub fn parse_sql_response(
     for (col_index, var) in xsqlda.iter().enumerate() {
         if version >= ProtocolVersion::V13 && read_null(resp, col_index)? {
             // There is no data in protocol 13 if null, so just continue
-            data.push(ParsedColumn::Complete(Column::new(
-                var.alias_name.clone(),
-                SqlType::Null,
-            )));
+            data.push(ParsedColumn::Complete(SqlType::Null));
             continue;
         }
 
@@ -572,15 +748,12 @@ pub fn parse_sql_response(
 
                 let null = read_null(resp, col_index)?;
                 if null {
-                    data.push(ParsedColumn::Complete(Column::new(
-                        var.alias_name.clone(),
-                        SqlType::Null,
-                    )))
+                    data.push(ParsedColumn::Complete(SqlType::Null))
+                } else if var.sqlsubtype & 0xff == ibase::CS_BINARY as i16 {
+                    // The lower byte of the subtype is the charset id
+                    data.push(ParsedColumn::Complete(SqlType::Binary(d.to_vec())))
                 } else {
-                    data.push(ParsedColumn::Complete(Column::new(
-                        var.alias_name.clone(),
-                        SqlType::Text(charset.decode(&d[..])?),
-                    )))
+                    data.push(ParsedColumn::Complete(charset.decode_text(&d[..])?))
                 }
             }
 
@@ -589,15 +762,30 @@ pub fn parse_sql_response(
 
                 let null = read_null(resp, col_index)?;
                 if null {
-                    data.push(ParsedColumn::Complete(Column::new(
-                        var.alias_name.clone(),
-                        SqlType::Null,
-                    )))
+                    data.push(ParsedColumn::Complete(SqlType::Null))
+                } else if var.scale != 0 {
+                    data.push(ParsedColumn::Complete(SqlType::Numeric {
+                        value: i as i128,
+                        scale: var.scale,
+                    }))
                 } else {
-                    data.push(ParsedColumn::Complete(Column::new(
-                        var.alias_name.clone(),
-                        SqlType::Integer(i),
-                    )))
+                    data.push(ParsedColumn::Complete(SqlType::Integer(i)))
+                }
+            }
+
+            ibase::SQL_INT128 => {
+                let i = resp.get_i128()?;
+
+                let null = read_null(resp, col_index)?;
+                if null {
+                    data.push(ParsedColumn::Complete(SqlType::Null))
+                } else if var.scale != 0 {
+                    data.push(ParsedColumn::Complete(SqlType::Numeric {
+                        value: i,
+                        scale: var.scale,
+                    }))
+                } else {
+                    data.push(ParsedColumn::Complete(SqlType::Int128(i)))
                 }
             }
 
@@ -606,15 +794,11 @@ pub fn parse_sql_response(
 
                 let null = read_null(resp, col_index)?;
                 if null {
-                    data.push(ParsedColumn::Complete(Column::new(
-                        var.alias_name.clone(),
-                        SqlType::Null,
-                    )))
+                    data.push(ParsedColumn::Complete(SqlType::Null))
                 } else {
-                    data.push(ParsedColumn::Complete(Column::new(
-                        var.alias_name.clone(),
-                        SqlType::Floating(f),
-                    )))
+                    data.push(ParsedColumn::Complete(
+                        rsfbclient_core::scaled_float_to_sql(f, var.scale),
+                    ))
                 }
             }
 
@@ -626,14 +810,31 @@ pub fn parse_sql_response(
 
                 let null = read_null(resp, col_index)?;
                 if null {
-                    data.push(ParsedColumn::Complete(Column::new(
-                        var.alias_name.clone(),
-                        SqlType::Null,
+                    data.push(ParsedColumn::Complete(SqlType::Null))
+                } else {
+                    data.push(ParsedColumn::Complete(SqlType::Timestamp(
+                        rsfbclient_core::date_time::decode_timestamp(ts),
                     )))
+                }
+            }
+
+            ibase::SQL_TIMESTAMP_TZ_EX => {
+                let ts = ibase::ISC_TIMESTAMP_TZ_EX {
+                    utc_timestamp: ibase::ISC_TIMESTAMP {
+                        timestamp_date: resp.get_i32()?,
+                        timestamp_time: resp.get_u32()?,
+                    },
+                    // Shorts are sent as 4 bytes
+                    time_zone: resp.get_u32()? as u16,
+                    ext_offset: resp.get_i32()? as i16,
+                };
+
+                let null = read_null(resp, col_index)?;
+                if null {
+                    data.push(ParsedColumn::Complete(SqlType::Null))
                 } else {
-                    data.push(ParsedColumn::Complete(Column::new(
-                        var.alias_name.clone(),
-                        SqlType::Timestamp(rsfbclient_core::date_time::decode_timestamp(ts)),
+                    data.push(ParsedColumn::Complete(SqlType::TimestampTz(
+                        rsfbclient_core::date_time::decode_timestamp_tz(ts)?,
                     )))
                 }
             }
@@ -643,15 +844,11 @@ pub fn parse_sql_response(
 
                 let null = read_null(resp, col_index)?;
                 if null {
-                    data.push(ParsedColumn::Complete(Column::new(
-                        var.alias_name.clone(),
-                        SqlType::Null,
-                    )))
+                    data.push(ParsedColumn::Complete(SqlType::Null))
                 } else {
                     data.push(ParsedColumn::Blob {
                         binary: var.sqlsubtype == 0,
                         id: BlobId(id),
-                        col_name: var.alias_name.clone(),
                     })
                 }
             }
@@ -663,15 +860,9 @@ pub fn parse_sql_response(
                 let null = read_null(resp, col_index)?;
 
                 if null {
-                    data.push(ParsedColumn::Complete(Column::new(
-                        var.alias_name.clone(),
-                        SqlType::Null,
-                    )))
+                    data.push(ParsedColumn::Complete(SqlType::Null))
                 } else {
-                    data.push(ParsedColumn::Complete(Column::new(
-                        var.alias_name.clone(),
-                        SqlType::Boolean(b),
-                    )))
+                    data.push(ParsedColumn::Complete(SqlType::Boolean(b)))
                 }
             }
 
@@ -688,35 +879,29 @@ pub fn parse_sql_response(
     Ok(data)
 }
 
-/// Column data parsed from a fetch response
+/// Column value parsed from a fetch response, without the column name
 pub enum ParsedColumn {
     /// All data received
-    Complete(Column),
+    Complete(SqlType),
     /// Blobs need more requests to get the actual data
     Blob {
         /// True if blob type 0
         binary: bool,
         /// Blob id
         id: BlobId,
-        /// Column name
-        col_name: String,
     },
 }
 
 impl ParsedColumn {
-    /// Get the rest of the data needed for the columns if necessary
-    pub fn into_column(
+    /// Get the rest of the data needed for the value if necessary
+    pub fn into_value(
         self,
         conn: &mut FirebirdWireConnection,
         tr_handle: &mut crate::TrHandle,
-    ) -> Result<Column, FbError> {
+    ) -> Result<SqlType, FbError> {
         Ok(match self {
-            ParsedColumn::Complete(c) => c,
-            ParsedColumn::Blob {
-                binary,
-                id,
-                col_name,
-            } => {
+            ParsedColumn::Complete(value) => value,
+            ParsedColumn::Blob { binary, id } => {
                 let mut data = Vec::with_capacity(256);
 
                 let blob_handle = conn.open_blob(tr_handle, id)?;
@@ -733,53 +918,79 @@ impl ParsedColumn {
 
                 conn.close_blob(blob_handle)?;
 
-                Column::new(
-                    col_name,
-                    if binary {
-                        SqlType::Binary(data)
-                    } else {
-                        SqlType::Text(conn.charset.decode(data)?)
-                    },
-                )
+                if binary {
+                    SqlType::Binary(data)
+                } else {
+                    conn.charset.decode_text(data)?
+                }
             }
         })
     }
 }
 
 /// Parses the error messages from the response
-pub fn parse_status_vector(resp: &mut Bytes) -> Result<(), FbError> {
+pub fn parse_status_vector(resp: &mut Bytes) -> Result<Vec<String>, FbError> {
     // Sql error code (default to -1)
     let mut sql_code = -1;
     // Error messages
     let mut message = String::new();
+    // Warning messages, returned when there is no error
+    let mut warnings: Vec<String> = vec![];
 
     // Code of the last error message
     let mut gds_code = 0;
+    // Codes and arguments of all error messages
+    let mut status: Vec<StatusItem> = vec![];
     // Error message argument index
     let mut num_arg = 0;
+    // The arguments are of the last warning
+    let mut in_warning = false;
 
     loop {
+        // Message to replace the arguments
+        let current = match warnings.last_mut() {
+            Some(warning) if in_warning => warning,
+            _ => &mut message,
+        };
+
         match resp.get_u32()? {
             // New error message
             ibase::isc_arg_gds => {
                 gds_code = resp.get_u32()?;
+                in_warning = false;
 
                 if gds_code != 0 {
+                    status.push(StatusItem {
+                        code: gds_code,
+                        args: vec![],
+                    });
                     message += gds_to_msg(gds_code);
                     num_arg = 0;
                 }
             }
 
+            // New warning message
+            ibase::isc_arg_warning => {
+                let code = resp.get_u32()?;
+
+                warnings.push(gds_to_msg(code).trim_end().to_string());
+                in_warning = true;
+                num_arg = 0;
+            }
+
             // Error message arg number
             ibase::isc_arg_number => {
                 let num = resp.get_i32()?;
                 // Sql error code
-                if gds_code == 335544436 {
+                if gds_code == 335544436 && !in_warning {
                     sql_code = num
                 }
+                if let (Some(item), false) = (status.last_mut(), in_warning) {
+                    item.args.push(StatusArg::Number(num));
+                }
 
                 num_arg += 1;
-                message = message.replace(&format!("@{}", num_arg), &format!("{}", num));
+                *current = current.replace(&format!("@{}", num_arg), &format!("{}", num));
             }
 
             // Error message arg string
@@ -787,8 +998,12 @@ pub fn parse_status_vector(resp: &mut Bytes) -> Result<(), FbError> {
                 let msg = resp.get_wire_bytes()?;
                 let msg = std::str::from_utf8(&msg[..]).unwrap_or("**Invalid message**");
 
+                if let (Some(item), false) = (status.last_mut(), in_warning) {
+                    item.args.push(StatusArg::String(msg.to_string()));
+                }
+
                 num_arg += 1;
-                message = message.replace(&format!("@{}", num_arg), msg);
+                *current = current.replace(&format!("@{}", num_arg), msg);
             }
 
             // Aditional error message string
@@ -796,7 +1011,11 @@ pub fn parse_status_vector(resp: &mut Bytes) -> Result<(), FbError> {
                 let msg = resp.get_wire_bytes()?;
                 let msg = std::str::from_utf8(&msg[..]).unwrap_or("**Invalid message**");
 
-                message += msg;
+                if let (Some(item), false) = (status.last_mut(), in_warning) {
+                    item.args.push(StatusArg::String(msg.to_string()));
+                }
+
+                *current += msg;
             }
 
             ibase::isc_arg_sql_state => {
@@ -817,12 +1036,9 @@ pub fn parse_status_vector(resp: &mut Bytes) -> Result<(), FbError> {
     }
 
     if !message.is_empty() {
-        Err(FbError::Sql {
-            code: sql_code,
-            msg: message,
-        })
+        Err(FbError::from_status(sql_code, message, status))
     } else {
-        Ok(())
+        Ok(warnings)
     }
 }
 
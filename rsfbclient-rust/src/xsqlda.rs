@@ -4,7 +4,7 @@
 
 use crate::util::*;
 use bytes::{BufMut, Bytes, BytesMut};
-use rsfbclient_core::{ibase, FbError, StmtType};
+use rsfbclient_core::{ibase, ColumnMeta, FbError, ParamMeta, StmtType};
 use std::{convert::TryFrom, mem};
 
 use crate::consts;
@@ -30,6 +30,18 @@ pub const XSQLDA_DESCRIBE_VARS: [u8; 17] = [
     ibase::isc_info_sql_describe_end as u8, // End of column data
 ];
 
+/// Data to return about the statement parameters
+pub const XSQLDA_DESCRIBE_BIND: [u8; 8] = [
+    ibase::isc_info_sql_bind as u8,          // Select params
+    ibase::isc_info_sql_describe_vars as u8, // Param count
+    ibase::isc_info_sql_sqlda_seq as u8,     // Param index
+    ibase::isc_info_sql_type as u8,          // Sql Type code
+    ibase::isc_info_sql_sub_type as u8,      // Blob subtype
+    ibase::isc_info_sql_scale as u8,         // Decimal / Numeric scale
+    ibase::isc_info_sql_length as u8,        // Data length
+    ibase::isc_info_sql_describe_end as u8,  // End of param data
+];
+
 #[derive(Debug, Default)]
 /// Sql query column information
 pub struct XSqlVar {
@@ -59,6 +71,31 @@ pub struct XSqlVar {
 }
 
 impl XSqlVar {
+    /// Describe the parameter
+    pub fn param_meta(&self) -> ParamMeta {
+        ParamMeta {
+            sql_type: (self.sqltype & !1) as u32,
+            sub_type: self.sqlsubtype,
+            length: self.data_length as u16,
+            scale: self.scale,
+            nullable: self.sqltype & 1 == 1,
+        }
+    }
+
+    /// Describe the column, must be called before the coercion
+    pub fn meta(&self) -> ColumnMeta {
+        ColumnMeta {
+            name: self.field_name.clone(),
+            alias: self.alias_name.clone(),
+            relation: self.relation_name.clone(),
+            sql_type: (self.sqltype & !1) as u32,
+            sub_type: self.sqlsubtype,
+            length: self.data_length as u16,
+            scale: self.scale,
+            nullable: self.sqltype & 1 == 1,
+        }
+    }
+
     /// Coerces the data types of this XSqlVar as necessary
     pub fn coerce(&mut self) -> Result<(), FbError> {
         // Remove nullable type indicator
@@ -75,13 +112,14 @@ impl XSqlVar {
             ibase::SQL_SHORT | ibase::SQL_LONG | ibase::SQL_INT64 => {
                 self.data_length = mem::size_of::<i64>() as i16;
 
-                if self.scale == 0 {
-                    self.sqltype = ibase::SQL_INT64 as i16 + 1;
-                } else {
-                    // Is actually a decimal or numeric value, so coerce as double
-                    self.scale = 0;
-                    self.sqltype = ibase::SQL_DOUBLE as i16 + 1;
-                }
+                // If is actually a decimal or numeric value, the scale is kept to not lose precision
+                self.sqltype = ibase::SQL_INT64 as i16 + 1;
+            }
+
+            ibase::SQL_INT128 => {
+                self.data_length = mem::size_of::<i128>() as i16;
+
+                self.sqltype = ibase::SQL_INT128 as i16 + 1;
             }
 
             ibase::SQL_FLOAT | ibase::SQL_DOUBLE => {
@@ -96,6 +134,16 @@ impl XSqlVar {
                 self.sqltype = ibase::SQL_TIMESTAMP as i16 + 1;
             }
 
+            ibase::SQL_TIMESTAMP_TZ
+            | ibase::SQL_TIME_TZ
+            | ibase::SQL_TIMESTAMP_TZ_EX
+            | ibase::SQL_TIME_TZ_EX => {
+                self.data_length = mem::size_of::<ibase::ISC_TIMESTAMP_TZ_EX>() as i16;
+
+                // The extended type has the offset of region time zones resolved by the server
+                self.sqltype = ibase::SQL_TIMESTAMP_TZ_EX as i16 + 1;
+            }
+
             ibase::SQL_BLOB if (sqlsubtype == 0 || sqlsubtype == 1) => {
                 self.sqltype = ibase::SQL_BLOB as i16 + 1;
             }
@@ -104,6 +152,10 @@ impl XSqlVar {
                 self.sqltype = ibase::SQL_BOOLEAN as i16 + 1;
             }
 
+            ibase::SQL_ARRAY => {
+                return Err("Array columns are not supported by the pure rust client".into());
+            }
+
             sqltype => {
                 return Err(format!("Unsupported column type ({})", sqltype).into());
             }
@@ -130,20 +182,29 @@ pub fn xsqlda_to_blr(xsqlda: &[XSqlVar]) -> Result<Bytes, FbError> {
         let sqltype = var.sqltype as u32 & (!1);
 
         match sqltype as u32 {
+            // The binary data is requested with its own charset, so the server
+            // doesn't transliterate it to the connection charset
+            ibase::SQL_VARYING if var.sqlsubtype & 0xff == ibase::CS_BINARY as i16 => {
+                blr.put_u8(consts::blr::VARYING2);
+                blr.put_u16_le(ibase::CS_BINARY as u16);
+                blr.put_i16_le(var.data_length);
+            }
+
             ibase::SQL_VARYING => {
                 blr.put_u8(consts::blr::VARYING);
                 blr.put_i16_le(var.data_length);
             }
 
-            ibase::SQL_INT64 => blr.put_slice(&[
-                consts::blr::INT64,
-                0, // Scale
-            ]),
+            ibase::SQL_INT64 => blr.put_slice(&[consts::blr::INT64, var.scale as u8]),
+
+            ibase::SQL_INT128 => blr.put_slice(&[consts::blr::INT128, var.scale as u8]),
 
             ibase::SQL_DOUBLE => blr.put_u8(consts::blr::DOUBLE),
 
             ibase::SQL_TIMESTAMP => blr.put_u8(consts::blr::TIMESTAMP),
 
+            ibase::SQL_TIMESTAMP_TZ_EX => blr.put_u8(consts::blr::EX_TIMESTAMP_TZ),
+
             ibase::SQL_BLOB => blr.put_slice(&[consts::blr::QUAD, var.sqlsubtype as u8]),
 
             ibase::SQL_BOOLEAN => blr.put_u8(consts::blr::BOOL),
@@ -239,6 +300,38 @@ pub fn parse_xsqlda(resp: &mut Bytes, xsqlda: &mut Vec<XSqlVar>) -> Result<Prepa
     })
 }
 
+/// Parses the data from the parameters description.
+///
+/// Returns `true` if the data was truncated (xsqlda not entirely filled)
+pub fn parse_bind_xsqlda(resp: &mut Bytes, xsqlda: &mut Vec<XSqlVar>) -> Result<bool, FbError> {
+    // Asserts that the first 8 bytes are the start of the parameters data
+    if resp.remaining() < 8
+        || resp[..2]
+            != [
+                ibase::isc_info_sql_bind as u8,          // Start of param data
+                ibase::isc_info_sql_describe_vars as u8, // Param count
+            ]
+    {
+        return err_invalid_xsqlda();
+    }
+    resp.advance(2)?;
+    // Parameter count
+
+    // Assume 0x04 0x00
+    resp.advance(2)?;
+
+    let param_count = resp.get_u32_le()? as usize;
+    if param_count > 1024 {
+        // Absurd quantity of params, so must be an error
+        return err_invalid_xsqlda();
+    }
+    if xsqlda.is_empty() {
+        xsqlda.reserve(param_count);
+    }
+
+    parse_select_items(resp, xsqlda)
+}
+
 /// Fill the xsqlda with data from the cursor, return `true` if the data was truncated (needs more data to fill the xsqlda)
 pub fn parse_select_items(resp: &mut Bytes, xsqlda: &mut Vec<XSqlVar>) -> Result<bool, FbError> {
     if resp.remaining() == 0 {
@@ -1,6 +1,8 @@
 use super::*;
 use crate::connection::conn_string;
+use rsfbclient_core::{ibase, TrLockResolution, TransactionConfiguration};
 use std::marker::PhantomData;
+use std::time::Duration;
 
 #[doc(hidden)]
 pub use rsfbclient_native::{DynLink, DynLoad};
@@ -22,6 +24,9 @@ pub struct ConnEmbedded;
 pub struct ConnRemote;
 #[doc(hidden)]
 #[derive(Clone)]
+pub struct ConnLocal;
+#[doc(hidden)]
+#[derive(Clone)]
 pub struct ConnByString;
 #[doc(hidden)]
 #[cfg(feature = "linking")]
@@ -40,6 +45,8 @@ impl ConfiguredConnType for ConnEmbedded {}
 #[doc(hidden)]
 impl ConfiguredConnType for ConnRemote {}
 #[doc(hidden)]
+impl ConfiguredConnType for ConnLocal {}
+#[doc(hidden)]
 impl ConfiguredConnType for ConnByString {}
 
 #[doc(hidden)]
@@ -56,7 +63,7 @@ impl ConfiguredLinkage for DynLoad {}
 /// Use the `builder_native()` method to get a new builder instance, and the
 /// provided configuration methods to change the default configuration params.
 ///
-/// Note that one of `with_remote()`/`with_embedded()` and one of
+/// Note that one of `with_remote()`/`with_local()`/`with_embedded()` and one of
 /// `with_dyn_link()`/`with_dyn_load(...)` **must** be called in order to
 /// enable creating a connection or calling other configuration methods.
 #[derive(Clone)]
@@ -67,7 +74,9 @@ pub struct NativeConnectionBuilder<LinkageType, ConnectionType> {
 
     charset: Charset,
     lib_path: Option<String>,
-    page_size: Option<u32>,
+    create_conf: CreateDatabaseConfig,
+    describe_hint: u16,
+    lock_resolution: TrLockResolution,
 }
 
 impl<A, B> From<&NativeConnectionBuilder<A, B>>
@@ -104,11 +113,17 @@ where
             .as_ref()
             .ok_or_else(|| FbError::from("The lib path is required to use the dynload loading"))?;
 
-        rsfbclient_native::DynLoad {
+        self.charset.validate()?;
+
+        let mut cli = rsfbclient_native::DynLoad {
             charset: self.charset.clone(),
             lib_path: path.clone(),
         }
-        .try_to_client()
+        .try_to_client()?;
+        cli.set_describe_hint(self.describe_hint);
+        cli.set_lock_resolution(self.lock_resolution);
+
+        Ok(cli)
     }
 
     fn get_conn_conf(&self) -> &ConnectionConfiguration<NativeFbAttachmentConfig> {
@@ -125,7 +140,13 @@ where
     type C = NativeFbClient<rsfbclient_native::DynLink>;
 
     fn new_instance(&self) -> Result<Self::C, FbError> {
-        Ok(rsfbclient_native::DynLink(self.charset.clone()).to_client())
+        self.charset.validate()?;
+
+        let mut cli = rsfbclient_native::DynLink(self.charset.clone()).to_client();
+        cli.set_describe_hint(self.describe_hint);
+        cli.set_lock_resolution(self.lock_resolution);
+
+        Ok(cli)
     }
 
     fn get_conn_conf(&self) -> &ConnectionConfiguration<NativeFbAttachmentConfig> {
@@ -146,9 +167,30 @@ where
         Connection::open(self.new_instance()?, &self.conn_conf)
     }
 
+    /// Get a connection that is only opened on the first use, from the fully-built builder
+    pub fn connect_lazy(&self) -> LazyConnection<Self>
+    where
+        Self: Clone,
+    {
+        LazyConnection::new(self.clone())
+    }
+
     /// Create the database and start new connection from the fully-built builder
     pub fn create_database(&self) -> Result<Connection<NativeFbClient<A>>, FbError> {
-        Connection::create_database(self.new_instance()?, &self.conn_conf, self.page_size)
+        Connection::create_database(self.new_instance()?, &self.conn_conf, &self.create_conf)
+    }
+
+    /// Attach to the services manager of the server from the fully-built builder.
+    ///
+    /// Requires feature `services`
+    #[cfg(feature = "services")]
+    pub fn service_manager(
+        &self,
+    ) -> Result<crate::services::ServiceManager<NativeFbClient<A>>, FbError> {
+        crate::services::ServiceManager::attach(
+            self.new_instance()?,
+            &self.conn_conf.attachment_conf,
+        )
     }
 }
 
@@ -187,9 +229,202 @@ where
         self
     }
 
+    /// How the sql text is normalized to find the statements in the cache, to reuse
+    /// the prepared statements of sqls that differ only in whitespace, comments or case.
+    /// Default: `SqlNormalization::Exact`
+    pub fn stmt_cache_normalization(&mut self, normalization: SqlNormalization) -> &mut Self {
+        self.conn_conf.stmt_cache_normalization = normalization;
+        self
+    }
+
+    /// Default timeout for the statement executions, sent with each statement prepared.
+    /// Requires Firebird 4+. Default: no timeout
+    pub fn with_statement_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.conn_conf.stmt_timeout = Some(timeout);
+        self
+    }
+
+    /// Isolation level and data access mode of the transactions started implicitly
+    /// by the connection, used by the `query` and `execute` methods of the connection
+    /// and by `with_transaction` / `begin_transaction`. The detached transactions and
+    /// the ones from `Transaction::new_with_config` use their own parameters.
+    /// Default: read committed, read-write
+    pub fn with_default_transaction(&mut self, conf: TransactionConfiguration) -> &mut Self {
+        self.conn_conf.default_tr_config = conf;
+        self
+    }
+
+    /// Record the sql text in the statements traces. Requires the `tracing` feature. Default: true
+    pub fn with_trace_sql(&mut self, trace_sql: bool) -> &mut Self {
+        self.conn_conf.trace.sql = trace_sql;
+        self
+    }
+
+    /// Record the parameter values in the statements traces, disable it to not expose
+    /// sensitive data. Requires the `tracing` feature. Default: true
+    pub fn with_trace_params(&mut self, trace_params: bool) -> &mut Self {
+        self.conn_conf.trace.params = trace_params;
+        self
+    }
+
+    /// Emit a warning in the traces when a query leaves more than `threshold` cursors open
+    /// in the connection, to catch the iterators leaked early, before the resources of the
+    /// server are exhausted. See [`Connection::open_cursor_count`][crate::Connection::open_cursor_count].
+    /// Requires the `tracing` feature. Default: no warning
+    pub fn with_open_cursors_warning(&mut self, threshold: usize) -> &mut Self {
+        self.conn_conf.trace.open_cursors_warning = Some(threshold);
+        self
+    }
+
+    /// Number of columns expected in the statements, allocated before describing them
+    /// on the preparation. The statements with more columns are described again with the
+    /// needed size, so a hint covering the widest queries avoids the second description,
+    /// while the narrower ones keep the unused space allocated. Default: 1
+    pub fn with_describe_hint(&mut self, columns: u16) -> &mut Self {
+        self.describe_hint = columns;
+        self
+    }
+
+    /// What the transactions do when they need a record locked by another transaction,
+    /// like on an update or a `SELECT ... WITH LOCK` of a record changed and not yet
+    /// committed. With `NoWait` or a `WaitTimeout`, the conflicts fail fast, identified
+    /// by [`FbError::is_lock_conflict`]. Default: `TrLockResolution::Wait`
+    pub fn with_lock_resolution(&mut self, lock_resolution: TrLockResolution) -> &mut Self {
+        self.lock_resolution = lock_resolution;
+        self
+    }
+
     /// Database page size. Used on db creation. Default: depends on firebird version
     pub fn page_size(&mut self, size: u32) -> &mut Self {
-        self.page_size = Some(size);
+        self.create_conf.page_size = Some(size);
+        self
+    }
+
+    /// Default charset of the database. Used on db creation. Default: NONE
+    pub fn db_charset(&mut self, charset: Charset) -> &mut Self {
+        self.create_conf.db_charset = Some(charset);
+        self
+    }
+
+    /// Replace the database if it already exists. Used on db creation. Default: false
+    pub fn overwrite(&mut self, overwrite: bool) -> &mut Self {
+        self.create_conf.overwrite = overwrite;
+        self
+    }
+
+    /// Number of pages in the database cache of the attachment, replacing the value
+    /// configured in the database and in the server. Used on the attachment and on
+    /// the db creation. With the SuperServer, the cache is shared, so the value is only
+    /// used by the first attachment with the database. Default: the database setting
+    pub fn with_num_buffers(&mut self, num_buffers: u32) -> &mut Self {
+        self.set_dpb_item(
+            ibase::isc_dpb_num_buffers,
+            num_buffers.to_le_bytes().to_vec(),
+        )
+    }
+
+    /// Enable or disable the forced writes of the database, writing the changes to the
+    /// disk before the commit returns. The setting is stored in the database, so it
+    /// requires the owner or the SYSDBA, and changes the mode for all attachments.
+    /// Default: the database setting
+    pub fn with_forced_writes(&mut self, forced_writes: bool) -> &mut Self {
+        self.set_dpb_item(ibase::isc_dpb_force_write, vec![forced_writes as u8])
+    }
+
+    /// Disable the garbage collection of the old record versions in the attachment,
+    /// like done by the backups, avoiding its cost on big reads. The garbage left is
+    /// collected by the other attachments or by a sweep. Default: enabled
+    pub fn with_no_garbage_collect(&mut self) -> &mut Self {
+        self.set_dpb_item(ibase::isc_dpb_no_garbage_collect, vec![])
+    }
+
+    /// Don't fire the database triggers (`ON CONNECT`, `ON DISCONNECT` and the transaction
+    /// ones) in the attachment, to fix a database with a failing trigger or for maintenance.
+    /// Requires the SYSDBA, the database owner or the `RDB$ADMIN` role, otherwise the
+    /// attachment fails with the server error
+    /// `unable to perform operation. You must be either SYSDBA or owner of the database`.
+    /// Default: the triggers are fired
+    pub fn with_no_db_triggers(&mut self) -> &mut Self {
+        self.set_dpb_item(ibase::isc_dpb_no_db_triggers, vec![1])
+    }
+
+    /// Time zone of the session, like `America/Sao_Paulo` or `-03:00`, instead of the
+    /// server default. Used by `CURRENT_TIMESTAMP` and by the conversions between the
+    /// timestamps with and without time zone, so they don't depend on the server setting.
+    /// The `TIMESTAMP WITH TIME ZONE` values are read with the offset of their own zone,
+    /// resolved by the server. Used on the attachment and on the db creation.
+    /// Requires Firebird 4+. Default: the server time zone
+    ///
+    /// Fails if the name is longer than 255 bytes, the maximum allowed in the dpb
+    pub fn with_session_time_zone(&mut self, time_zone: &str) -> Result<&mut Self, FbError> {
+        if time_zone.len() > u8::MAX as usize {
+            return Err(format!(
+                "The time zone name has {} bytes, the maximum is {}",
+                time_zone.len(),
+                u8::MAX
+            )
+            .into());
+        }
+
+        Ok(self.set_dpb_item(
+            ibase::isc_dpb_session_time_zone,
+            time_zone.as_bytes().to_vec(),
+        ))
+    }
+
+    /// Name of the client process reported to the server, with the id of the current
+    /// process, shown in `MON$ATTACHMENTS.MON$REMOTE_PROCESS` and `MON$REMOTE_PID` and in
+    /// the trace logs, to identify the service in the monitoring. Used on the attachment
+    /// and on the db creation. Default: the executable path with the native client, and
+    /// nothing with the pure rust client
+    ///
+    /// Fails if the name is longer than 255 bytes, the maximum allowed in the dpb
+    pub fn with_client_info(&mut self, name: &str) -> Result<&mut Self, FbError> {
+        if name.len() > u8::MAX as usize {
+            return Err(format!(
+                "The process name has {} bytes, the maximum is {}",
+                name.len(),
+                u8::MAX
+            )
+            .into());
+        }
+
+        self.set_dpb_item(
+            ibase::isc_dpb_process_id,
+            std::process::id().to_le_bytes().to_vec(),
+        );
+        Ok(self.set_dpb_item(ibase::isc_dpb_process_name, name.as_bytes().to_vec()))
+    }
+
+    /// Append an item to the database parameter buffer (dpb), used on the attachment
+    /// and on the db creation, to set options not covered by the other methods, like
+    /// `isc_dpb_sql_role_name` or `isc_dpb_set_bind`. The integer values are
+    /// sent in little endian, like `&100u32.to_le_bytes()`.
+    ///
+    /// Fails if the value is longer than 255 bytes, the maximum allowed in the dpb
+    pub fn with_dpb_item(&mut self, tag: u8, value: &[u8]) -> Result<&mut Self, FbError> {
+        if value.len() > u8::MAX as usize {
+            return Err(format!(
+                "The dpb item {} value has {} bytes, the maximum is {}",
+                tag,
+                value.len(),
+                u8::MAX
+            )
+            .into());
+        }
+
+        self.conn_conf
+            .attachment_conf
+            .dpb_items
+            .push((tag, value.to_vec()));
+        Ok(self)
+    }
+
+    /// Set a dpb item, replacing the previous value of the same item
+    fn set_dpb_item(&mut self, tag: u32, value: Vec<u8>) -> &mut Self {
+        let items = &mut self.conn_conf.attachment_conf.dpb_items;
+        items.retain(|(t, _)| *t != tag as u8);
+        items.push((tag as u8, value));
         self
     }
 }
@@ -203,7 +438,9 @@ impl<A, B> NativeConnectionBuilder<A, B> {
             conn_conf: self.conn_conf,
             charset: self.charset,
             lib_path: self.lib_path,
-            page_size: self.page_size,
+            create_conf: self.create_conf,
+            describe_hint: self.describe_hint,
+            lock_resolution: self.lock_resolution,
         }
     }
 }
@@ -216,7 +453,9 @@ impl Default for NativeConnectionBuilder<LinkageNotConfigured, ConnTypeNotConfig
             conn_conf: Default::default(),
             charset: charset::UTF_8,
             lib_path: None,
-            page_size: None,
+            create_conf: Default::default(),
+            describe_hint: 1,
+            lock_resolution: TrLockResolution::Wait,
         };
 
         self_result.conn_conf.dialect = Dialect::D3;
@@ -240,15 +479,21 @@ impl<A> NativeConnectionBuilder<A, ConnRemote> {
             .get_or_insert(Default::default())
     }
 
-    /// Hostname or IP address of the server. Default: localhost
+    /// Hostname or IP address of the server, replacing the hosts set by `hosts`.
+    /// Default: localhost
     pub fn host<S: Into<String>>(&mut self, host: S) -> &mut Self {
-        self.get_initialized_remote().host = host.into();
+        let remote = self.get_initialized_remote();
+        remote.host = host.into();
+        remote.failover_hosts.clear();
         self
     }
 
-    /// TCP Port of the server. Default: 3050
+    /// TCP Port of the server, replacing the hosts set by `hosts` with a single one.
+    /// Default: 3050
     pub fn port(&mut self, port: u16) -> &mut Self {
-        self.get_initialized_remote().port = port;
+        let remote = self.get_initialized_remote();
+        remote.port = port;
+        remote.failover_hosts.clear();
         self
     }
 
@@ -257,6 +502,37 @@ impl<A> NativeConnectionBuilder<A, ConnRemote> {
         self.get_initialized_remote().pass = pass.into();
         self
     }
+
+    /// Hosts to try, in order, when connecting. Each entry is a `host` or
+    /// `host:port` (`[::1]:3050` for IPv6), using the port set by `port`
+    /// (or 3050) when omitted.
+    ///
+    /// The first host that accepts the connection is used, and if all of them
+    /// fail the error lists the failure of each host. Each attempt waits for
+    /// the OS connect timeout before moving to the next host.
+    ///
+    /// With the native client, the whole attachment is retried on the next host.
+    pub fn hosts(&mut self, hosts: &[&str]) -> Result<&mut Self, FbError> {
+        let remote = self.get_initialized_remote();
+        let default_port = if remote.port == 0 { 3050 } else { remote.port };
+        let ((host, port), failover) = conn_string::parse_hosts(hosts, default_port)?;
+
+        remote.host = host;
+        remote.port = port;
+        remote.failover_hosts = failover;
+        Ok(self)
+    }
+}
+
+//can only use these methods on a local builder
+impl<A> NativeConnectionBuilder<A, ConnLocal> {
+    /// Password. Default: masterkey
+    pub fn pass<S: Into<String>>(&mut self, pass: S) -> &mut Self {
+        if let Some(remote) = &mut self.conn_conf.attachment_conf.remote {
+            remote.pass = pass.into();
+        }
+        self
+    }
 }
 
 impl<A> NativeConnectionBuilder<A, ConnTypeNotConfigured> {
@@ -267,6 +543,25 @@ impl<A> NativeConnectionBuilder<A, ConnTypeNotConfigured> {
             host: "localhost".to_string(),
             port: 3050,
             pass: "masterkey".to_string(),
+            ..Default::default()
+        };
+        self.conn_conf.attachment_conf.remote = Some(remote);
+        self.safe_transmute()
+    }
+
+    /// Configure the native client for connections with a server in the same
+    /// machine, using the local protocol instead of TCP, like XNET on windows.
+    /// This will allow configuration via the 'pass' method.
+    ///
+    /// The database name is used without a host and port, so the client dll
+    /// chooses the connection method allowed by the `Providers` config parameter
+    /// of `firebird.conf`, that can include the embedded engine.
+    pub fn with_local(mut self) -> NativeConnectionBuilder<A, ConnLocal> {
+        let remote = rsfbclient_native::RemoteConfig {
+            host: String::new(),
+            port: 0,
+            pass: "masterkey".to_string(),
+            ..Default::default()
         };
         self.conn_conf.attachment_conf.remote = Some(remote);
         self.safe_transmute()
@@ -1,12 +1,41 @@
 use super::*;
 use crate::connection::conn_string;
 use crate::{charset, Charset};
-use rsfbclient_rust::{RustFbClient, RustFbClientAttachmentConfig};
+use rsfbclient_core::{ibase, TrLockResolution, TransactionConfiguration};
+pub use rsfbclient_rust::ProtocolVersion;
+use rsfbclient_rust::{RustFbClient, RustFbClientAttachmentConfig, WireStream};
+use std::{
+    io::{Read, Write},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 impl FirebirdClientFactory for PureRustConnectionBuilder {
     type C = RustFbClient;
     fn new_instance(&self) -> Result<Self::C, FbError> {
-        Ok(RustFbClient::new(self.1.clone()))
+        self.1.validate()?;
+
+        let mut cli = RustFbClient::new(self.1.clone());
+        cli.set_fetch_size(self.3);
+        cli.set_auto_reconnect(self.4);
+        cli.set_max_protocol_version(self.5);
+        cli.set_lock_resolution(self.6);
+        cli.set_read_timeout(self.8);
+
+        if let Some(stream) = &self.7 {
+            let stream = stream
+                .lock()
+                .map_err(|_| FbError::from("Poisoned stream lock"))?
+                .take()
+                .ok_or_else(|| {
+                    FbError::from(
+                        "The stream was already used by another connection, each one needs a new stream",
+                    )
+                })?;
+            cli.set_stream(stream);
+        }
+
+        Ok(cli)
     }
 
     fn get_conn_conf(&self) -> &ConnectionConfiguration<RustFbClientAttachmentConfig> {
@@ -18,12 +47,22 @@ impl FirebirdClientFactory for PureRustConnectionBuilder {
 /// Does not currently support embedded connections.
 ///
 /// Use `builder_pure_rust()` to obtain a new instance.
+#[derive(Clone)]
 pub struct PureRustConnectionBuilder(
     ConnectionConfiguration<RustFbClientAttachmentConfig>,
     Charset,
-    Option<u32>,
+    CreateDatabaseConfig,
+    u32,
+    u32,
+    ProtocolVersion,
+    TrLockResolution,
+    Option<SharedStream>,
+    Option<Duration>,
 );
 
+/// Stream provided to the builder, taken by the first connection
+type SharedStream = Arc<Mutex<Option<Box<dyn WireStream>>>>;
+
 impl From<&PureRustConnectionBuilder> for ConnectionConfiguration<RustFbClientAttachmentConfig> {
     fn from(arg: &PureRustConnectionBuilder) -> Self {
         arg.0.clone()
@@ -40,8 +79,13 @@ impl PureRustConnectionBuilder {
         Connection::open(self.new_instance()?, &self.0)
     }
 
+    /// Get a connection that is only opened on the first use
+    pub fn connect_lazy(&self) -> LazyConnection<Self> {
+        LazyConnection::new(self.clone())
+    }
+
     pub fn create_database(&self) -> Result<Connection<RustFbClient>, FbError> {
-        Connection::create_database(self.new_instance()?, &self.0, self.2)
+        Connection::create_database(self.new_instance()?, &self.0, &self.2)
     }
 
     /// Username. Default: SYSDBA
@@ -56,18 +100,41 @@ impl PureRustConnectionBuilder {
         self
     }
 
-    /// Hostname or IP address of the server. Default: localhost
+    /// Hostname or IP address of the server, replacing the hosts set by `hosts`.
+    /// Default: localhost
     pub fn host<S: Into<String>>(&mut self, host: S) -> &mut Self {
         self.0.attachment_conf.host = host.into();
+        self.0.attachment_conf.failover_hosts.clear();
         self
     }
 
-    /// TCP Port of the server. Default: 3050
+    /// TCP Port of the server, replacing the hosts set by `hosts` with a single one.
+    /// Default: 3050
     pub fn port(&mut self, port: u16) -> &mut Self {
         self.0.attachment_conf.port = port;
+        self.0.attachment_conf.failover_hosts.clear();
         self
     }
 
+    /// Hosts to try, in order, when connecting. Each entry is a `host` or
+    /// `host:port` (`[::1]:3050` for IPv6), using the port set by `port`
+    /// (or 3050) when omitted.
+    ///
+    /// The first host that accepts the connection is used, and if all of them
+    /// fail the error lists the failure of each host. Each attempt waits for
+    /// the OS connect timeout before moving to the next host.
+    ///
+    /// The next host is tried when the connection or the authentication fails.
+    pub fn hosts(&mut self, hosts: &[&str]) -> Result<&mut Self, FbError> {
+        let ((host, port), failover) =
+            conn_string::parse_hosts(hosts, self.0.attachment_conf.port)?;
+
+        self.0.attachment_conf.host = host;
+        self.0.attachment_conf.port = port;
+        self.0.attachment_conf.failover_hosts = failover;
+        Ok(self)
+    }
+
     /// Password. Default: masterkey
     pub fn pass<S: Into<String>>(&mut self, pass: S) -> &mut Self {
         self.0.attachment_conf.pass = pass.into();
@@ -92,9 +159,273 @@ impl PureRustConnectionBuilder {
         self
     }
 
+    /// How the sql text is normalized to find the statements in the cache, to reuse
+    /// the prepared statements of sqls that differ only in whitespace, comments or case.
+    /// Default: `SqlNormalization::Exact`
+    pub fn stmt_cache_normalization(&mut self, normalization: SqlNormalization) -> &mut Self {
+        self.0.stmt_cache_normalization = normalization;
+        self
+    }
+
+    /// Default timeout for the statement executions, sent with each statement prepared.
+    /// Requires Firebird 4+ and the wire protocol 16, see `with_protocol_version`.
+    /// Default: no timeout
+    pub fn with_statement_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.0.stmt_timeout = Some(timeout);
+        self
+    }
+
+    /// Isolation level and data access mode of the transactions started implicitly
+    /// by the connection, used by the `query` and `execute` methods of the connection
+    /// and by `with_transaction` / `begin_transaction`. The detached transactions and
+    /// the ones from `Transaction::new_with_config` use their own parameters.
+    /// Default: read committed, read-write
+    pub fn with_default_transaction(&mut self, conf: TransactionConfiguration) -> &mut Self {
+        self.0.default_tr_config = conf;
+        self
+    }
+
+    /// Record the sql text in the statements traces. Requires the `tracing` feature. Default: true
+    pub fn with_trace_sql(&mut self, trace_sql: bool) -> &mut Self {
+        self.0.trace.sql = trace_sql;
+        self
+    }
+
+    /// Record the parameter values in the statements traces, disable it to not expose
+    /// sensitive data. Requires the `tracing` feature. Default: true
+    pub fn with_trace_params(&mut self, trace_params: bool) -> &mut Self {
+        self.0.trace.params = trace_params;
+        self
+    }
+
+    /// Emit a warning in the traces when a query leaves more than `threshold` cursors open
+    /// in the connection, to catch the iterators leaked early, before the resources of the
+    /// server are exhausted. See [`Connection::open_cursor_count`][crate::Connection::open_cursor_count].
+    /// Requires the `tracing` feature. Default: no warning
+    pub fn with_open_cursors_warning(&mut self, threshold: usize) -> &mut Self {
+        self.0.trace.open_cursors_warning = Some(threshold);
+        self
+    }
+
+    /// Maximum number of rows received from the server on each fetch. Default: 1
+    ///
+    /// Larger values reduce the round trips needed to read big result sets
+    pub fn with_fetch_size(&mut self, fetch_size: u32) -> &mut Self {
+        self.3 = fetch_size;
+        self
+    }
+
+    /// Attach again, up to `max_retries` attempts, when the connection with the server
+    /// is lost, like on a server restart. Default: 0, disabled
+    ///
+    /// The transaction active when the connection was lost is rolled back by the server,
+    /// so the operation returns a `FbError::TransactionLost` error. Outside of an explicit
+    /// transaction the next operations will run normally, preparing the statements again.
+    /// After a `begin_transaction`, the transaction must be rolled back before continuing.
+    /// The session state, like the context variables, is not restored
+    pub fn with_auto_reconnect(&mut self, max_retries: u32) -> &mut Self {
+        self.4 = max_retries;
+        self
+    }
+
+    /// Highest wire protocol version offered to the server, to enable the newer features
+    /// or to pin an older version for an old server. The server picks the highest one it
+    /// supports, and the connection is rejected if it doesn't support any of them.
+    /// The statement timeouts require `ProtocolVersion::V16` and the scrollable cursors
+    /// `ProtocolVersion::V18`. Default: `ProtocolVersion::V13`
+    pub fn with_protocol_version(&mut self, max: ProtocolVersion) -> &mut Self {
+        self.5 = max;
+        self
+    }
+
+    /// What the transactions do when they need a record locked by another transaction,
+    /// like on an update or a `SELECT ... WITH LOCK` of a record changed and not yet
+    /// committed. With `NoWait` or a `WaitTimeout`, the conflicts fail fast, identified
+    /// by [`FbError::is_lock_conflict`]. Default: `TrLockResolution::Wait`
+    pub fn with_lock_resolution(&mut self, lock_resolution: TrLockResolution) -> &mut Self {
+        self.6 = lock_resolution;
+        self
+    }
+
+    /// Maximum time waiting for the server data, so a stalled server fails the operations
+    /// instead of blocking them. On a timeout the connection is closed, as the late response
+    /// would be out of sync with the next requests, and the operation fails with an error
+    /// identified by [`FbError::is_connection_lost`], restored by `with_auto_reconnect`.
+    /// Must be longer than the slowest statement. A zero duration disables the timeout,
+    /// like the default. Default: no timeout
+    pub fn with_read_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.8 = Some(timeout).filter(|t| !t.is_zero());
+        self
+    }
+
     /// Database page size. Used on db creation. Default: depends on firebird version
     pub fn page_size(&mut self, size: u32) -> &mut Self {
-        self.2 = Some(size);
+        self.2.page_size = Some(size);
+        self
+    }
+
+    /// Default charset of the database. Used on db creation. Default: NONE
+    pub fn db_charset(&mut self, charset: Charset) -> &mut Self {
+        self.2.db_charset = Some(charset);
+        self
+    }
+
+    /// Replace the database if it already exists. Used on db creation. Default: false
+    pub fn overwrite(&mut self, overwrite: bool) -> &mut Self {
+        self.2.overwrite = overwrite;
+        self
+    }
+
+    /// Number of pages in the database cache of the attachment, replacing the value
+    /// configured in the database and in the server. Used on the attachment and on
+    /// the db creation. With the SuperServer, the cache is shared, so the value is only
+    /// used by the first attachment with the database. Default: the database setting
+    pub fn with_num_buffers(&mut self, num_buffers: u32) -> &mut Self {
+        self.set_dpb_item(
+            ibase::isc_dpb_num_buffers,
+            num_buffers.to_le_bytes().to_vec(),
+        )
+    }
+
+    /// Enable or disable the forced writes of the database, writing the changes to the
+    /// disk before the commit returns. The setting is stored in the database, so it
+    /// requires the owner or the SYSDBA, and changes the mode for all attachments.
+    /// Default: the database setting
+    pub fn with_forced_writes(&mut self, forced_writes: bool) -> &mut Self {
+        self.set_dpb_item(ibase::isc_dpb_force_write, vec![forced_writes as u8])
+    }
+
+    /// Disable the garbage collection of the old record versions in the attachment,
+    /// like done by the backups, avoiding its cost on big reads. The garbage left is
+    /// collected by the other attachments or by a sweep. Default: enabled
+    pub fn with_no_garbage_collect(&mut self) -> &mut Self {
+        self.set_dpb_item(ibase::isc_dpb_no_garbage_collect, vec![])
+    }
+
+    /// Don't fire the database triggers (`ON CONNECT`, `ON DISCONNECT` and the transaction
+    /// ones) in the attachment, to fix a database with a failing trigger or for maintenance.
+    /// Requires the SYSDBA, the database owner or the `RDB$ADMIN` role, otherwise the
+    /// attachment fails with the server error
+    /// `unable to perform operation. You must be either SYSDBA or owner of the database`.
+    /// Default: the triggers are fired
+    pub fn with_no_db_triggers(&mut self) -> &mut Self {
+        self.set_dpb_item(ibase::isc_dpb_no_db_triggers, vec![1])
+    }
+
+    /// Time zone of the session, like `America/Sao_Paulo` or `-03:00`, instead of the
+    /// server default. Used by `CURRENT_TIMESTAMP` and by the conversions between the
+    /// timestamps with and without time zone, so they don't depend on the server setting.
+    /// The `TIMESTAMP WITH TIME ZONE` values are read with the offset of their own zone,
+    /// resolved by the server. Used on the attachment and on the db creation.
+    /// Requires Firebird 4+. Default: the server time zone
+    ///
+    /// Fails if the name is longer than 255 bytes, the maximum allowed in the dpb
+    pub fn with_session_time_zone(&mut self, time_zone: &str) -> Result<&mut Self, FbError> {
+        if time_zone.len() > u8::MAX as usize {
+            return Err(format!(
+                "The time zone name has {} bytes, the maximum is {}",
+                time_zone.len(),
+                u8::MAX
+            )
+            .into());
+        }
+
+        Ok(self.set_dpb_item(
+            ibase::isc_dpb_session_time_zone,
+            time_zone.as_bytes().to_vec(),
+        ))
+    }
+
+    /// Name of the client process reported to the server, with the id of the current
+    /// process, shown in `MON$ATTACHMENTS.MON$REMOTE_PROCESS` and `MON$REMOTE_PID` and in
+    /// the trace logs, to identify the service in the monitoring. Used on the attachment
+    /// and on the db creation. Default: the executable path with the native client, and
+    /// nothing with the pure rust client
+    ///
+    /// Fails if the name is longer than 255 bytes, the maximum allowed in the dpb
+    pub fn with_client_info(&mut self, name: &str) -> Result<&mut Self, FbError> {
+        if name.len() > u8::MAX as usize {
+            return Err(format!(
+                "The process name has {} bytes, the maximum is {}",
+                name.len(),
+                u8::MAX
+            )
+            .into());
+        }
+
+        self.set_dpb_item(
+            ibase::isc_dpb_process_id,
+            std::process::id().to_le_bytes().to_vec(),
+        );
+        Ok(self.set_dpb_item(ibase::isc_dpb_process_name, name.as_bytes().to_vec()))
+    }
+
+    /// Append an item to the database parameter buffer (dpb), used on the attachment
+    /// and on the db creation, to set options not covered by the other methods, like
+    /// `isc_dpb_sql_role_name` or `isc_dpb_set_bind`. The integer values are
+    /// sent in little endian, like `&100u32.to_le_bytes()`.
+    ///
+    /// Fails if the value is longer than 255 bytes, the maximum allowed in the dpb
+    pub fn with_dpb_item(&mut self, tag: u8, value: &[u8]) -> Result<&mut Self, FbError> {
+        if value.len() > u8::MAX as usize {
+            return Err(format!(
+                "The dpb item {} value has {} bytes, the maximum is {}",
+                tag,
+                value.len(),
+                u8::MAX
+            )
+            .into());
+        }
+
+        self.0.attachment_conf.dpb_items.push((tag, value.to_vec()));
+        Ok(self)
+    }
+
+    /// Key of an encrypted database, sent when requested by the server. The `plugin` is the
+    /// name of the key holder plugin, as in the `KeyHolderPlugin` parameter of the
+    /// `databases.conf` or `firebird.conf` of the server, like the `fbSampleKeyHolder` of
+    /// the Firebird examples. When the server request names one of the configured plugins,
+    /// its key is sent, otherwise the key of the first one. The key format depends on the
+    /// plugin: `fbSampleKeyHolder` takes a single byte, while the commercial ones usually
+    /// take the key text. Can be called many times, one for each plugin.
+    ///
+    /// Requires Firebird 3+. Only supported by the pure rust client, the native one uses the
+    /// key holder plugins configured in the `firebird.conf` of the client.
+    ///
+    /// Default: no key, so the attachment with an encrypted database fails with the missing
+    /// key error
+    pub fn with_db_crypt_key(&mut self, plugin: &str, key: &[u8]) -> &mut Self {
+        let keys = &mut self.0.attachment_conf.db_crypt_keys;
+        match keys.iter_mut().find(|(p, _)| p == plugin) {
+            Some((_, k)) => *k = key.to_vec(),
+            None => keys.push((plugin.to_string(), key.to_vec())),
+        }
+        self
+    }
+
+    /// Set a dpb item, replacing the previous value of the same item
+    fn set_dpb_item(&mut self, tag: u32, value: Vec<u8>) -> &mut Self {
+        let items = &mut self.0.attachment_conf.dpb_items;
+        items.retain(|(t, _)| *t != tag as u8);
+        items.push((tag as u8, value));
+        self
+    }
+
+    /// Connect over a stream already connected with the server, like a socket handed
+    /// over by a proxy or a ssh port forward, instead of connecting to the `host` and
+    /// `port`. The authentication, with the `user` and `pass`, and the wire encryption
+    /// still run over the stream, so it must carry the firebird wire protocol as is.
+    ///
+    /// The stream is used by the next connection only, the others made with this builder
+    /// fail. The lost connections can't be restored, so the auto reconnect is disabled,
+    /// and the `cancel_handle` of the connection is not available
+    #[allow(clippy::wrong_self_convention)]
+    pub fn from_stream<S>(&mut self, stream: S) -> &mut Self
+    where
+        S: Read + Write + Send + 'static,
+    {
+        let stream: Box<dyn WireStream> = Box::new(stream);
+        self.7 = Some(Arc::new(Mutex::new(Some(stream))));
         self
     }
 
@@ -147,8 +478,24 @@ impl Default for PureRustConnectionBuilder {
     fn default() -> Self {
         let conn_conf = Default::default();
         let charset = charset::UTF_8;
-        let page_size = None;
-        let mut result = Self(conn_conf, charset, page_size);
+        let create_conf = Default::default();
+        let fetch_size = 1;
+        let max_reconnects = 0;
+        let max_protocol = ProtocolVersion::V13;
+        let lock_resolution = TrLockResolution::Wait;
+        let stream = None;
+        let read_timeout = None;
+        let mut result = Self(
+            conn_conf,
+            charset,
+            create_conf,
+            fetch_size,
+            max_reconnects,
+            max_protocol,
+            lock_resolution,
+            stream,
+            read_timeout,
+        );
 
         result
             .host("localhost")
@@ -0,0 +1,443 @@
+//! Query results in a column oriented layout
+
+use chrono::{DateTime, FixedOffset, NaiveDateTime};
+use rsfbclient_core::{ibase, ColumnMeta, FbError, FirebirdClient, IntoParams, Row, SqlType};
+use std::mem;
+
+use crate::{Statement, Transaction};
+
+/// Run the query, appending the values of each row fetched to the columns,
+/// typed by the columns description
+pub fn query<C, P>(tr: &mut Transaction<C>, sql: &str, params: P) -> Result<ColumnarResult, FbError>
+where
+    C: FirebirdClient,
+    P: IntoParams,
+{
+    let params = params.to_params();
+    let mut stmt = Statement::prepare(tr, sql, params.named())?;
+
+    let mut columns: Vec<ColumnarData> = stmt
+        .columns()?
+        .into_iter()
+        .map(|meta| ColumnarData {
+            name: meta.alias.clone(),
+            values: ColumnarValues::of_column(&meta),
+            meta,
+            validity: vec![],
+        })
+        .collect();
+
+    let mut rows = stmt.query::<Row, _>(params)?;
+    let mut count = 0;
+
+    while let Some(values) = rows.fetch_values()? {
+        for (data, value) in columns.iter_mut().zip(values) {
+            data.push(value)?;
+        }
+
+        count += 1;
+    }
+
+    Ok(ColumnarResult {
+        columns,
+        rows: count,
+    })
+}
+
+/// Result of a query with the values of each column in its own vector, like the
+/// `Arrow` arrays, from [`Connection::query_columns`][`crate::Connection::query_columns`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnarResult {
+    /// Columns of the result, in the select order
+    pub columns: Vec<ColumnarData>,
+
+    /// Number of rows, the length of all columns
+    pub rows: usize,
+}
+
+impl ColumnarResult {
+    /// Get the column by the name.
+    ///
+    /// The names are compared ignoring the case, as firebird
+    /// uppercases the unquoted column names and aliases
+    pub fn column(&self, name: &str) -> Option<&ColumnarData> {
+        self.columns
+            .iter()
+            .find(|col| col.name.eq_ignore_ascii_case(name))
+    }
+}
+
+/// Values of a column, with the `NULL`s marked in a bitmap
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnarData {
+    /// Column alias
+    pub name: String,
+
+    /// Description of the column
+    pub meta: ColumnMeta,
+
+    /// Values of all rows. The `NULL` positions are filled with a default value
+    pub values: ColumnarValues,
+
+    /// Bitmap of the non null values, the bit `i % 8` of the byte `i / 8` is set
+    /// if the value of the row `i` is not `NULL`. The same layout of the `Arrow` validity
+    pub validity: Vec<u8>,
+}
+
+impl ColumnarData {
+    /// The value of the row is `NULL`
+    pub fn is_null(&self, row: usize) -> bool {
+        self.validity
+            .get(row / 8)
+            .map(|byte| byte & (1 << (row % 8)) == 0)
+            .unwrap_or(true)
+    }
+
+    /// Number of `NULL` values
+    pub fn null_count(&self) -> usize {
+        self.values.len()
+            - self
+                .validity
+                .iter()
+                .map(|b| b.count_ones() as usize)
+                .sum::<usize>()
+    }
+
+    /// Append the value of the next row
+    fn push(&mut self, value: SqlType) -> Result<(), FbError> {
+        let row = self.values.len();
+
+        // Typed by the value when the description doesn't match the
+        // values returned, as long as only `NULL`s were found
+        if !value.is_null() && self.null_count() == row && !self.values.has_type(&value) {
+            self.values = ColumnarValues::with_nulls(&value, row)?;
+        }
+
+        // The text of the connections with the `NONE` charset is returned
+        // as binary when it isn't valid utf-8
+        if let (ColumnarValues::Text(texts), SqlType::Binary(_)) = (&mut self.values, &value) {
+            self.values = ColumnarValues::Binary(
+                mem::take(texts)
+                    .into_iter()
+                    .map(String::into_bytes)
+                    .collect(),
+            );
+        }
+
+        if row / 8 == self.validity.len() {
+            self.validity.push(0);
+        }
+        if !value.is_null() {
+            self.validity[row / 8] |= 1 << (row % 8);
+        }
+
+        self.values
+            .push(value)
+            .map_err(|e| format!("Column {}: {}", self.name, e).into())
+    }
+}
+
+/// Typed values of a column, with the variant of the values returned by the server
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnarValues {
+    /// The type is unknown and all values are `NULL`. Has the number of rows
+    Null(usize),
+
+    Integer(Vec<i64>),
+
+    Int128(Vec<i128>),
+
+    /// Exact numerics, representing `value * 10^scale`
+    Numeric {
+        values: Vec<i128>,
+        scale: i16,
+    },
+
+    Floating(Vec<f64>),
+
+    Text(Vec<String>),
+
+    Binary(Vec<Vec<u8>>),
+
+    Boolean(Vec<bool>),
+
+    Timestamp(Vec<NaiveDateTime>),
+
+    TimestampTz(Vec<DateTime<FixedOffset>>),
+}
+
+impl ColumnarValues {
+    /// Number of values
+    pub fn len(&self) -> usize {
+        match self {
+            ColumnarValues::Null(len) => *len,
+            ColumnarValues::Integer(v) => v.len(),
+            ColumnarValues::Int128(v) => v.len(),
+            ColumnarValues::Numeric { values, .. } => values.len(),
+            ColumnarValues::Floating(v) => v.len(),
+            ColumnarValues::Text(v) => v.len(),
+            ColumnarValues::Binary(v) => v.len(),
+            ColumnarValues::Boolean(v) => v.len(),
+            ColumnarValues::Timestamp(v) => v.len(),
+            ColumnarValues::TimestampTz(v) => v.len(),
+        }
+    }
+
+    /// There are no values
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Empty vector of the type of the values of the column, by its description.
+    /// Unknown for the types without a mapping
+    fn of_column(meta: &ColumnMeta) -> Self {
+        let numeric = |scale: i16| ColumnarValues::Numeric {
+            values: vec![],
+            scale,
+        };
+
+        match meta.sql_type {
+            ibase::SQL_SHORT | ibase::SQL_LONG | ibase::SQL_INT64 if meta.scale == 0 => {
+                ColumnarValues::Integer(vec![])
+            }
+            ibase::SQL_INT128 if meta.scale == 0 => ColumnarValues::Int128(vec![]),
+            ibase::SQL_SHORT | ibase::SQL_LONG | ibase::SQL_INT64 | ibase::SQL_INT128 => {
+                numeric(meta.scale)
+            }
+            // The doubles with a scale of the dialect 1 are read as exact numerics
+            ibase::SQL_FLOAT | ibase::SQL_DOUBLE | ibase::SQL_D_FLOAT if meta.scale != 0 => {
+                numeric(meta.scale)
+            }
+            ibase::SQL_FLOAT | ibase::SQL_DOUBLE | ibase::SQL_D_FLOAT => {
+                ColumnarValues::Floating(vec![])
+            }
+            // The lower byte of the subtype is the charset id
+            ibase::SQL_TEXT | ibase::SQL_VARYING
+                if meta.sub_type & 0xff == ibase::CS_BINARY as i16 =>
+            {
+                ColumnarValues::Binary(vec![])
+            }
+            ibase::SQL_TEXT | ibase::SQL_VARYING => ColumnarValues::Text(vec![]),
+            ibase::SQL_BLOB if meta.sub_type == 1 => ColumnarValues::Text(vec![]),
+            ibase::SQL_BLOB if meta.sub_type == 0 => ColumnarValues::Binary(vec![]),
+            ibase::SQL_BOOLEAN => ColumnarValues::Boolean(vec![]),
+            ibase::SQL_TIMESTAMP | ibase::SQL_TYPE_DATE | ibase::SQL_TYPE_TIME => {
+                ColumnarValues::Timestamp(vec![])
+            }
+            ibase::SQL_TIMESTAMP_TZ
+            | ibase::SQL_TIMESTAMP_TZ_EX
+            | ibase::SQL_TIME_TZ
+            | ibase::SQL_TIME_TZ_EX => ColumnarValues::TimestampTz(vec![]),
+            _ => ColumnarValues::Null(0),
+        }
+    }
+
+    /// The value has the type of the column
+    fn has_type(&self, value: &SqlType) -> bool {
+        match (self.default_value(), value) {
+            (SqlType::Numeric { scale, .. }, SqlType::Numeric { scale: s, .. }) => scale == *s,
+            (default, value) => mem::discriminant(&default) == mem::discriminant(value),
+        }
+    }
+
+    /// Empty vector of the type of the value, with the default
+    /// value repeated for the `NULL`s already found
+    fn with_nulls(value: &SqlType, nulls: usize) -> Result<Self, FbError> {
+        Ok(match value {
+            SqlType::Integer(_) => ColumnarValues::Integer(vec![0; nulls]),
+            SqlType::Int128(_) => ColumnarValues::Int128(vec![0; nulls]),
+            SqlType::Numeric { scale, .. } => ColumnarValues::Numeric {
+                values: vec![0; nulls],
+                scale: *scale,
+            },
+            SqlType::Floating(_) => ColumnarValues::Floating(vec![0.0; nulls]),
+            SqlType::Text(_) => ColumnarValues::Text(vec![String::new(); nulls]),
+            SqlType::Binary(_) => ColumnarValues::Binary(vec![vec![]; nulls]),
+            SqlType::Boolean(_) => ColumnarValues::Boolean(vec![false; nulls]),
+            SqlType::Timestamp(_) => ColumnarValues::Timestamp(vec![Default::default(); nulls]),
+            SqlType::TimestampTz(_) => {
+                ColumnarValues::TimestampTz(vec![DateTime::<FixedOffset>::default(); nulls])
+            }
+            SqlType::Array(_) => return Err("The array columns are not supported".into()),
+            SqlType::Null => ColumnarValues::Null(nulls),
+        })
+    }
+
+    /// Append the value, that must have the type of the column.
+    /// The `NULL`s are appended as the default value of the type
+    fn push(&mut self, value: SqlType) -> Result<(), FbError> {
+        let value = if value.is_null() {
+            self.default_value()
+        } else {
+            value
+        };
+
+        if let ColumnarValues::Null(nulls) = *self {
+            *self = Self::with_nulls(&value, nulls)?;
+        }
+
+        match (self, value) {
+            (ColumnarValues::Null(len), SqlType::Null) => *len += 1,
+            (ColumnarValues::Integer(v), SqlType::Integer(i)) => v.push(i),
+            (ColumnarValues::Int128(v), SqlType::Int128(i)) => v.push(i),
+            (ColumnarValues::Numeric { values, scale }, SqlType::Numeric { value, scale: s })
+                if *scale == s =>
+            {
+                values.push(value)
+            }
+            (ColumnarValues::Floating(v), SqlType::Floating(f)) => v.push(f),
+            (ColumnarValues::Text(v), SqlType::Text(t)) => v.push(t),
+            (ColumnarValues::Binary(v), SqlType::Binary(b)) => v.push(b),
+            (ColumnarValues::Boolean(v), SqlType::Boolean(b)) => v.push(b),
+            (ColumnarValues::Timestamp(v), SqlType::Timestamp(ts)) => v.push(ts),
+            (ColumnarValues::TimestampTz(v), SqlType::TimestampTz(ts)) => v.push(ts),
+
+            (_, value) => {
+                return Err(format!(
+                    "The column has values of different types, found {:?}",
+                    value
+                )
+                .into())
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Default value of the type of the column, used in the place of the `NULL`s
+    fn default_value(&self) -> SqlType {
+        match self {
+            ColumnarValues::Null(_) => SqlType::Null,
+            ColumnarValues::Integer(_) => SqlType::Integer(0),
+            ColumnarValues::Int128(_) => SqlType::Int128(0),
+            ColumnarValues::Numeric { scale, .. } => SqlType::Numeric {
+                value: 0,
+                scale: *scale,
+            },
+            ColumnarValues::Floating(_) => SqlType::Floating(0.0),
+            ColumnarValues::Text(_) => SqlType::Text(String::new()),
+            ColumnarValues::Binary(_) => SqlType::Binary(vec![]),
+            ColumnarValues::Boolean(_) => SqlType::Boolean(false),
+            ColumnarValues::Timestamp(_) => SqlType::Timestamp(Default::default()),
+            ColumnarValues::TimestampTz(_) => SqlType::TimestampTz(Default::default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn meta(sql_type: u32, sub_type: i16, scale: i16) -> ColumnMeta {
+        ColumnMeta {
+            name: "A".to_string(),
+            alias: "A".to_string(),
+            relation: String::new(),
+            sql_type,
+            sub_type,
+            length: 8,
+            scale,
+            nullable: true,
+        }
+    }
+
+    #[test]
+    fn append_values() -> Result<(), FbError> {
+        let mut data = ColumnarData {
+            name: "A".to_string(),
+            meta: meta(ibase::SQL_INT64, 0, -2),
+            values: ColumnarValues::Null(0),
+            validity: vec![],
+        };
+
+        data.push(SqlType::Null)?;
+        data.push(SqlType::Null)?;
+        assert_eq!(ColumnarValues::Null(2), data.values);
+
+        for i in 0..8 {
+            data.push(SqlType::Numeric {
+                value: i,
+                scale: -2,
+            })?;
+        }
+        data.push(SqlType::Null)?;
+
+        assert_eq!(
+            ColumnarValues::Numeric {
+                values: vec![0, 0, 0, 1, 2, 3, 4, 5, 6, 7, 0],
+                scale: -2
+            },
+            data.values
+        );
+        assert_eq!(vec![0b1111_1100, 0b0000_0011], data.validity);
+        assert!(data.is_null(0));
+        assert!(!data.is_null(9));
+        assert!(data.is_null(10));
+        assert_eq!(3, data.null_count());
+
+        assert!(data.push(SqlType::Integer(1)).is_err());
+        assert!(data
+            .push(SqlType::Numeric {
+                value: 1,
+                scale: -3
+            })
+            .is_err());
+
+        Ok(())
+    }
+    #[test]
+    fn column_types() -> Result<(), FbError> {
+        let of_column =
+            |sql_type, sub_type, scale| ColumnarValues::of_column(&meta(sql_type, sub_type, scale));
+
+        assert_eq!(
+            ColumnarValues::Integer(vec![]),
+            of_column(ibase::SQL_LONG, 0, 0)
+        );
+        assert_eq!(
+            ColumnarValues::Numeric {
+                values: vec![],
+                scale: -2
+            },
+            of_column(ibase::SQL_INT128, 0, -2)
+        );
+        assert_eq!(
+            ColumnarValues::Binary(vec![]),
+            of_column(ibase::SQL_VARYING, ibase::CS_BINARY as i16, 0)
+        );
+        assert_eq!(
+            ColumnarValues::Text(vec![]),
+            of_column(ibase::SQL_BLOB, 1, 0)
+        );
+        assert_eq!(ColumnarValues::Null(0), of_column(ibase::SQL_NULL, 0, 0));
+
+        // Only `NULL`s, typed by the description
+        let mut data = ColumnarData {
+            name: "A".to_string(),
+            meta: meta(ibase::SQL_BOOLEAN, 0, 0),
+            values: of_column(ibase::SQL_BOOLEAN, 0, 0),
+            validity: vec![],
+        };
+        data.push(SqlType::Null)?;
+        data.push(SqlType::Null)?;
+        assert_eq!(ColumnarValues::Boolean(vec![false, false]), data.values);
+
+        // Typed again by the first value that doesn't match the description
+        data.push(SqlType::Text("a".to_string()))?;
+        assert_eq!(
+            ColumnarValues::Text(vec![String::new(), String::new(), "a".to_string()]),
+            data.values
+        );
+        assert!(data.push(SqlType::Boolean(true)).is_err());
+
+        // Binary after a text not valid as utf-8
+        data.push(SqlType::Binary(vec![0xff]))?;
+        assert_eq!(
+            ColumnarValues::Binary(vec![vec![], vec![], b"a".to_vec(), vec![0xff]]),
+            data.values
+        );
+        assert_eq!(2, data.null_count());
+
+        Ok(())
+    }
+}
@@ -111,10 +111,7 @@ pub fn parse(sconn: &str) -> Result<ConnStringSettings, FbError> {
                 };
             }
             "charset" => {
-                charset = match Charset::from_str(&val) {
-                    Ok(d) => Some(d),
-                    _ => None,
-                };
+                charset = Some(Charset::from_str(&val)?);
             }
             "stmt_cache_size" => {
                 stmt_cache_size = match val.parse::<usize>() {
@@ -139,11 +136,81 @@ pub fn parse(sconn: &str) -> Result<ConnStringSettings, FbError> {
     })
 }
 
+type HostPort = (String, u16);
+
+/// Parse a `host` or `host:port` entry, using `default_port` when
+/// no port was informed. IPv6 addresses can be enclosed in brackets,
+/// as `[::1]:3050`, to inform the port.
+pub(crate) fn parse_host(host: &str, default_port: u16) -> Result<HostPort, FbError> {
+    let parse_port = |port: &str| {
+        u16::from_str(port).map_err(|e| FbError::from(format!("Invalid port in '{}': {}", host, e)))
+    };
+
+    let (name, port) = match host.strip_prefix('[').and_then(|h| h.split_once(']')) {
+        Some((name, "")) => (name, default_port),
+        Some((name, rest)) => match rest.strip_prefix(':') {
+            Some(port) => (name, parse_port(port)?),
+            None => return Err(FbError::from(format!("Invalid host '{}'", host))),
+        },
+        None => match host.rsplit_once(':') {
+            Some((name, port)) if !name.contains(':') => (name, parse_port(port)?),
+            _ => (host, default_port),
+        },
+    };
+
+    if name.is_empty() {
+        return Err(FbError::from(format!("Invalid host '{}'", host)));
+    }
+
+    Ok((name.to_string(), port))
+}
+
+/// Split a list of hosts into the primary host and the failover ones.
+pub(crate) fn parse_hosts(
+    hosts: &[&str],
+    default_port: u16,
+) -> Result<(HostPort, Vec<HostPort>), FbError> {
+    let mut hosts = hosts
+        .iter()
+        .map(|h| parse_host(h, default_port))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if hosts.is_empty() {
+        return Err(FbError::from("At least one host must be informed"));
+    }
+
+    let primary = hosts.remove(0);
+    Ok((primary, hosts))
+}
+
 #[cfg(test)]
 mod test {
-    use super::parse;
+    use super::{parse, parse_host, parse_hosts};
     use crate::*;
 
+    #[test]
+    fn hosts() -> Result<(), FbError> {
+        assert_eq!(("db1".to_string(), 3050), parse_host("db1", 3050)?);
+        assert_eq!(("db1".to_string(), 3051), parse_host("db1:3051", 3050)?);
+        assert_eq!(("::1".to_string(), 3050), parse_host("::1", 3050)?);
+        assert_eq!(("::1".to_string(), 3050), parse_host("[::1]", 3050)?);
+        assert_eq!(("::1".to_string(), 3051), parse_host("[::1]:3051", 3050)?);
+        assert!(parse_host("[::1]3051", 3050).is_err());
+        assert!(parse_host("[]:3051", 3050).is_err());
+        assert!(parse_host("db1:abc", 3050).is_err());
+        assert!(parse_host(":3050", 3050).is_err());
+
+        let (primary, failover) = parse_hosts(&["db1", "db2:3051", "db3"], 3050)?;
+        assert_eq!(("db1".to_string(), 3050), primary);
+        assert_eq!(
+            vec![("db2".to_string(), 3051), ("db3".to_string(), 3050)],
+            failover
+        );
+        assert!(parse_hosts(&[], 3050).is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn special_chars() -> Result<(), FbError> {
         // User with an ã
@@ -249,6 +316,8 @@ mod test {
         let conn = parse("firebird:///srv/db/database_name.fdb?charset=UTF_8")?;
         assert_eq!(Some(charset::UTF_8), conn.charset);
 
+        assert!(parse("firebird:///srv/db/database_name.fdb?charset=utf16").is_err());
+
         Ok(())
     }
 
@@ -0,0 +1,773 @@
+//! Export of the query results to csv, and import of csv files into tables
+
+use rsfbclient_core::{
+    ibase, Column, ColumnToVal, FbError, FirebirdClient, IntoParams, ParamMeta, Row, SqlType,
+};
+use std::{
+    fmt,
+    io::{BufRead, Write},
+    mem,
+};
+
+use super::{quote_identifier, script};
+use crate::{Execute, Statement, Transaction};
+
+/// Options of the csv export and import
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    /// Separator of the fields. Default: `,`
+    pub delimiter: char,
+    /// Character used to quote the fields, doubled when found inside of them. Default: `"`
+    pub quote: char,
+    /// Quote all fields, not only the ones with the delimiter, the quote
+    /// or a line break. Default: false
+    pub quote_all: bool,
+    /// Text written for the `NULL` values. On the import, only the unquoted
+    /// fields are read as `NULL`, so the values equal to it and the empty ones
+    /// are always written quoted. Default: empty
+    pub null: String,
+    /// Format of the timestamps, dates and times, with the `chrono` specifiers.
+    /// Default: `%Y-%m-%d %H:%M:%S%.f`
+    pub timestamp_format: String,
+    /// Write a first line with the column names. On the import, the first line
+    /// has the names of the table columns, otherwise all columns are filled in
+    /// the table order. Default: true
+    pub header: bool,
+    /// How the binary values, like the blobs, are written. Default: base64
+    pub binary: CsvBinary,
+}
+
+/// How the binary values are written in the csv
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvBinary {
+    /// Encoded in base64
+    Base64,
+    /// Written as an empty field, and imported as `NULL`
+    Skip,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            quote: '"',
+            quote_all: false,
+            null: String::new(),
+            timestamp_format: "%Y-%m-%d %H:%M:%S%.f".to_string(),
+            header: true,
+            binary: CsvBinary::Base64,
+        }
+    }
+}
+
+/// Run the query, writing each row fetched to the writer. Returns the number of rows
+pub fn export<C, P, W>(
+    tr: &mut Transaction<C>,
+    sql: &str,
+    params: P,
+    writer: &mut W,
+    options: &CsvOptions,
+) -> Result<usize, FbError>
+where
+    C: FirebirdClient,
+    P: IntoParams,
+    W: Write,
+{
+    let params = params.to_params();
+    let mut stmt = Statement::prepare(tr, sql, params.named())?;
+
+    if options.header {
+        let names: Vec<String> = stmt
+            .columns()?
+            .into_iter()
+            .map(|col| quote(col.alias, options))
+            .collect();
+        write_line(writer, &names, options)?;
+    }
+
+    let mut rows = stmt.query::<Row, _>(params)?;
+    let mut count = 0;
+
+    while let Some(row) = rows.fetch()? {
+        let fields: Vec<String> = row
+            .cols
+            .into_iter()
+            .map(|col| field(col, options))
+            .collect::<Result<_, _>>()?;
+        write_line(writer, &fields, options)?;
+
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// Insert the csv records in the table, reusing the prepared insert for all of them.
+/// Returns the number of rows inserted
+pub fn import<C, R>(
+    tr: &mut Transaction<C>,
+    table: &str,
+    reader: R,
+    options: &CsvOptions,
+) -> Result<usize, FbError>
+where
+    C: FirebirdClient,
+    R: BufRead,
+{
+    // Undo the rows inserted by a failed import also inside of an
+    // explicit transaction, that is not rolled back
+    tr.execute("SAVEPOINT RSFBCLIENT_CSV_IMPORT", ())?;
+
+    match insert_records(tr, table, reader, options) {
+        Ok(count) => {
+            tr.execute("RELEASE SAVEPOINT RSFBCLIENT_CSV_IMPORT", ())?;
+            Ok(count)
+        }
+        Err(e) => {
+            tr.execute("ROLLBACK TO SAVEPOINT RSFBCLIENT_CSV_IMPORT", ())
+                .ok();
+            Err(e)
+        }
+    }
+}
+
+/// Insert the csv records in the table
+fn insert_records<C, R>(
+    tr: &mut Transaction<C>,
+    table: &str,
+    reader: R,
+    options: &CsvOptions,
+) -> Result<usize, FbError>
+where
+    C: FirebirdClient,
+    R: BufRead,
+{
+    let mut records = Records::new(reader, options);
+
+    let columns = if options.header {
+        match records.next()? {
+            Some((_, names)) => Some(names.into_iter().map(|f| f.value).collect::<Vec<_>>()),
+            None => return Ok(0),
+        }
+    } else {
+        None
+    };
+
+    let mut record = records.next()?;
+    let (mut stmt, meta) = match &record {
+        Some((_, fields)) => {
+            let sql = insert_sql(table, columns.as_deref(), fields.len())?;
+            let mut stmt = Statement::prepare(tr, &sql, false)?;
+            let meta = stmt.params_meta()?;
+
+            (stmt, meta)
+        }
+        None => return Ok(0),
+    };
+
+    let mut count = 0;
+
+    while let Some((line, fields)) = record {
+        if fields.len() != meta.len() {
+            return Err(format!(
+                "line {}: expected {} fields, found {}",
+                line,
+                meta.len(),
+                fields.len()
+            )
+            .into());
+        }
+
+        let params = fields
+            .into_iter()
+            .zip(meta.iter())
+            .enumerate()
+            .map(|(i, (field, meta))| {
+                param(field, meta, options).map_err(|e| {
+                    script::error_context(&format!("line {}, field {}", line, i + 1), e)
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        stmt.execute(params)
+            .map_err(|e| script::error_context(&format!("line {}", line), e))?;
+
+        count += 1;
+        record = records.next()?;
+    }
+
+    Ok(count)
+}
+
+/// Build the insert, with the columns from the header or all columns of the table
+fn insert_sql(table: &str, columns: Option<&[String]>, fields: usize) -> Result<String, FbError> {
+    let (columns, fields) = match columns {
+        Some(columns) => (
+            format!(
+                " ({})",
+                columns
+                    .iter()
+                    .map(|c| quote_identifier(c))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .join(", ")
+            ),
+            columns.len(),
+        ),
+        None => (String::new(), fields),
+    };
+
+    Ok(format!(
+        "INSERT INTO {}{} VALUES ({})",
+        quote_identifier(table)?,
+        columns,
+        vec!["?"; fields].join(", ")
+    ))
+}
+
+/// Convert the csv field to the type of the parameter
+fn param(field: Field, meta: &ParamMeta, options: &CsvOptions) -> Result<SqlType, FbError> {
+    if !field.quoted && field.value == options.null {
+        return Ok(SqlType::Null);
+    }
+
+    let value = field.value;
+    let invalid = |kind: &str| FbError::from(format!("invalid {}: '{}'", kind, value));
+
+    Ok(match meta.sql_type {
+        ibase::SQL_SHORT | ibase::SQL_LONG | ibase::SQL_INT64 | ibase::SQL_INT128
+            if meta.scale != 0 =>
+        {
+            let (value, scale) = parse_decimal(&value).ok_or_else(|| invalid("number"))?;
+            SqlType::Numeric { value, scale }
+        }
+        ibase::SQL_SHORT | ibase::SQL_LONG | ibase::SQL_INT64 => {
+            SqlType::Integer(value.trim().parse().map_err(|_| invalid("integer"))?)
+        }
+        ibase::SQL_INT128 => SqlType::Int128(value.trim().parse().map_err(|_| invalid("integer"))?),
+        ibase::SQL_FLOAT | ibase::SQL_DOUBLE => {
+            SqlType::Floating(value.trim().parse().map_err(|_| invalid("number"))?)
+        }
+        ibase::SQL_BOOLEAN => match value.trim().to_ascii_lowercase().as_str() {
+            "true" | "1" => SqlType::Boolean(true),
+            "false" | "0" => SqlType::Boolean(false),
+            _ => return Err(invalid("boolean")),
+        },
+        ibase::SQL_TIMESTAMP | ibase::SQL_TYPE_DATE | ibase::SQL_TYPE_TIME => {
+            // Values in other formats are sent as text, for firebird to parse them
+            match chrono::NaiveDateTime::parse_from_str(&value, &options.timestamp_format) {
+                Ok(ts) => SqlType::Timestamp(ts),
+                Err(_) => SqlType::Text(value),
+            }
+        }
+        ibase::SQL_TIMESTAMP_TZ => {
+            match chrono::DateTime::parse_from_str(&value, &options.timestamp_format) {
+                Ok(ts) => SqlType::TimestampTz(ts),
+                Err(_) => SqlType::Text(value),
+            }
+        }
+        // Binary blobs and `CHAR` / `VARCHAR` with the `OCTETS` charset, exported as binary
+        ibase::SQL_BLOB if meta.sub_type == 0 => binary_param(&value, options)?,
+        ibase::SQL_TEXT | ibase::SQL_VARYING if meta.sub_type & 0xff == ibase::CS_BINARY as i16 => {
+            binary_param(&value, options)?
+        }
+        _ => SqlType::Text(value),
+    })
+}
+
+/// Value of a binary parameter, written as base64 or skipped
+fn binary_param(value: &str, options: &CsvOptions) -> Result<SqlType, FbError> {
+    Ok(match options.binary {
+        CsvBinary::Base64 => SqlType::Binary(
+            base64_decode(value).ok_or_else(|| format!("invalid base64: '{}'", value))?,
+        ),
+        CsvBinary::Skip => SqlType::Null,
+    })
+}
+
+/// Parse a decimal number, like `-1.50`, into the digits and the scale
+fn parse_decimal(value: &str) -> Option<(i128, i16)> {
+    let value = value.trim();
+    let (int, frac) = value.split_once('.').unwrap_or((value, ""));
+    let (negative, int) = match int.strip_prefix('-') {
+        Some(int) => (true, int),
+        None => (false, int.strip_prefix('+').unwrap_or(int)),
+    };
+
+    if (int.is_empty() && frac.is_empty())
+        || !int.chars().chain(frac.chars()).all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+
+    let mut digits: i128 = format!("{}{}", int, frac).parse().ok()?;
+    if negative {
+        digits = -digits;
+    }
+
+    Some((digits, -(frac.len() as i16)))
+}
+
+/// Field read from the csv
+#[derive(Debug, PartialEq)]
+struct Field {
+    value: String,
+    /// The field was quoted, so it is never read as `NULL`
+    quoted: bool,
+}
+
+/// Reader of the csv records, that can have line breaks inside of the quoted fields
+struct Records<'o, R> {
+    reader: R,
+    options: &'o CsvOptions,
+    /// Number of lines read
+    line: usize,
+}
+
+impl<'o, R: BufRead> Records<'o, R> {
+    fn new(reader: R, options: &'o CsvOptions) -> Self {
+        Self {
+            reader,
+            options,
+            line: 0,
+        }
+    }
+
+    /// Read the next record, with the number of its first line. The empty lines are skipped
+    fn next(&mut self) -> Result<Option<(usize, Vec<Field>)>, FbError> {
+        let mut buf = String::new();
+
+        loop {
+            buf.clear();
+            if self.reader.read_line(&mut buf)? == 0 {
+                return Ok(None);
+            }
+            self.line += 1;
+
+            if !buf.trim_end_matches(['\r', '\n']).is_empty() {
+                break;
+            }
+        }
+
+        let (quote, delimiter) = (self.options.quote, self.options.delimiter);
+        let start = self.line;
+        let mut fields = vec![];
+        let mut value = String::new();
+        let mut quoted = false;
+        let mut in_quotes = false;
+
+        loop {
+            let mut chars = buf.chars().peekable();
+
+            while let Some(c) = chars.next() {
+                if in_quotes {
+                    if c != quote {
+                        value.push(c);
+                    } else if chars.peek() == Some(&quote) {
+                        value.push(quote);
+                        chars.next();
+                    } else {
+                        in_quotes = false;
+                    }
+                } else if c == quote && value.is_empty() && !quoted {
+                    in_quotes = true;
+                    quoted = true;
+                } else if c == delimiter {
+                    fields.push(Field {
+                        value: mem::take(&mut value),
+                        quoted: mem::take(&mut quoted),
+                    });
+                } else if c != '\r' && c != '\n' {
+                    value.push(c);
+                }
+            }
+
+            if !in_quotes {
+                break;
+            }
+
+            buf.clear();
+            if self.reader.read_line(&mut buf)? == 0 {
+                return Err(format!("line {}: unterminated quoted field", start).into());
+            }
+            self.line += 1;
+        }
+
+        fields.push(Field { value, quoted });
+
+        Ok(Some((start, fields)))
+    }
+}
+
+fn write_line<W: Write>(
+    writer: &mut W,
+    fields: &[String],
+    options: &CsvOptions,
+) -> Result<(), FbError> {
+    let mut delimiter = [0; 4];
+    let delimiter = options.delimiter.encode_utf8(&mut delimiter);
+
+    writer.write_all(fields.join(delimiter).as_bytes())?;
+    writer.write_all(b"\n")?;
+
+    Ok(())
+}
+
+/// Format the column value as a csv field
+fn field(col: Column, options: &CsvOptions) -> Result<String, FbError> {
+    let value = match col.value {
+        SqlType::Null => return Ok(options.null.clone()),
+        SqlType::Binary(bytes) => match options.binary {
+            CsvBinary::Base64 => base64(&bytes),
+            // Unquoted, to be imported as `NULL`
+            CsvBinary::Skip => return Ok(String::new()),
+        },
+        SqlType::Timestamp(ts) => format_ts(ts.format(&options.timestamp_format), options)?,
+        SqlType::TimestampTz(ts) => format_ts(ts.format(&options.timestamp_format), options)?,
+        SqlType::Array(items) => {
+            let items = items
+                .into_iter()
+                .map(|value| field(Column::new(String::new(), value), options))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            format!("[{}]", items.join(", "))
+        }
+        value => Column::new(col.name, value).to_val()?,
+    };
+
+    Ok(quote(value, options))
+}
+
+/// Format the timestamp, failing on an invalid format
+fn format_ts(ts: impl fmt::Display, options: &CsvOptions) -> Result<String, FbError> {
+    use fmt::Write;
+
+    let mut formatted = String::new();
+    write!(formatted, "{}", ts).map_err(|_| {
+        FbError::from(format!(
+            "Invalid timestamp format: {}",
+            options.timestamp_format
+        ))
+    })?;
+
+    Ok(formatted)
+}
+
+/// Quote the field if needed.
+///
+/// The empty values and the ones equal to the `NULL` text are always quoted,
+/// as the import reads the unquoted ones as `NULL`
+fn quote(value: String, options: &CsvOptions) -> String {
+    let needs_quotes = options.quote_all
+        || value.is_empty()
+        || value == options.null
+        || value.contains([options.delimiter, options.quote, '\n', '\r']);
+
+    if !needs_quotes {
+        return value;
+    }
+
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push(options.quote);
+    for c in value.chars() {
+        if c == options.quote {
+            quoted.push(c);
+        }
+        quoted.push(c);
+    }
+    quoted.push(options.quote);
+
+    quoted
+}
+
+/// Encode the bytes in base64, with padding
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as usize) << 16 | (b[1] as usize) << 8 | b[2] as usize;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i)) & 0x3f] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+/// Decode the base64 text, with or without padding
+fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim().trim_end_matches('=');
+    let mut decoded = Vec::with_capacity(text.len() * 3 / 4);
+    let (mut bits, mut n_bits) = (0u32, 0);
+
+    for c in text.bytes() {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+
+        bits = bits << 6 | v as u32;
+        n_bits += 6;
+        if n_bits >= 8 {
+            n_bits -= 8;
+            decoded.push((bits >> n_bits) as u8);
+            bits &= (1 << n_bits) - 1;
+        }
+    }
+
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn format_fields() -> Result<(), FbError> {
+        let options = CsvOptions {
+            null: "NULL".to_string(),
+            timestamp_format: "%d/%m/%Y".to_string(),
+            ..Default::default()
+        };
+        let col = |value| Column::new("col".to_string(), value);
+
+        assert_eq!("NULL", field(col(SqlType::Null), &options)?);
+        assert_eq!("abc", field(col(SqlType::Text("abc".into())), &options)?);
+        assert_eq!("\"\"", field(col(SqlType::Text("".into())), &options)?);
+        assert_eq!(
+            "\"NULL\"",
+            field(col(SqlType::Text("NULL".into())), &options)?
+        );
+        assert_eq!(
+            "\"a,\"\"b\"\"\"",
+            field(col(SqlType::Text("a,\"b\"".into())), &options)?
+        );
+        assert_eq!(
+            "\"a\nb\"",
+            field(col(SqlType::Text("a\nb".into())), &options)?
+        );
+        assert_eq!(
+            "-1.50",
+            field(
+                col(SqlType::Numeric {
+                    value: -150,
+                    scale: -2
+                }),
+                &options
+            )?
+        );
+        assert_eq!(
+            "04/03/2021",
+            field(
+                col(SqlType::Timestamp(
+                    chrono::NaiveDate::from_ymd_opt(2021, 3, 4)
+                        .unwrap()
+                        .and_hms_opt(10, 20, 30)
+                        .unwrap()
+                )),
+                &options
+            )?
+        );
+        assert!(field(
+            col(SqlType::Timestamp(chrono::NaiveDateTime::default())),
+            &CsvOptions {
+                timestamp_format: "%Q".to_string(),
+                ..Default::default()
+            }
+        )
+        .is_err());
+        assert_eq!(
+            "\"[1, 2]\"",
+            field(
+                col(SqlType::Array(vec![
+                    SqlType::Integer(1),
+                    SqlType::Integer(2)
+                ])),
+                &options
+            )?
+        );
+
+        assert_eq!("", base64(b""));
+        assert_eq!("Zg==", base64(b"f"));
+        assert_eq!("Zm8=", base64(b"fo"));
+        assert_eq!("Zm9vYmFy", base64(b"foobar"));
+        assert_eq!(
+            "",
+            field(
+                col(SqlType::Binary(vec![1, 2])),
+                &CsvOptions {
+                    binary: CsvBinary::Skip,
+                    ..Default::default()
+                }
+            )?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_records() -> Result<(), FbError> {
+        let options = CsvOptions {
+            delimiter: ';',
+            ..Default::default()
+        };
+        let text = "ID;NAME\r\n1;\"a;\"\"b\"\"\"\n\n2;\"c\nd\";\n3;\"\"\n";
+        let mut records = Records::new(text.as_bytes(), &options);
+
+        let mut read = |expected_line, expected: &[(&str, bool)]| -> Result<(), FbError> {
+            let (line, fields) = records.next()?.unwrap();
+            assert_eq!(expected_line, line);
+            assert_eq!(
+                expected
+                    .iter()
+                    .map(|(value, quoted)| Field {
+                        value: value.to_string(),
+                        quoted: *quoted
+                    })
+                    .collect::<Vec<_>>(),
+                fields
+            );
+            Ok(())
+        };
+
+        read(1, &[("ID", false), ("NAME", false)])?;
+        read(2, &[("1", false), ("a;\"b\"", true)])?;
+        read(4, &[("2", false), ("c\nd", true), ("", false)])?;
+        read(6, &[("3", false), ("", true)])?;
+        assert!(records.next()?.is_none());
+
+        let mut records = Records::new("1;\"a\n".as_bytes(), &options);
+        assert!(records.next().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn convert_params() -> Result<(), FbError> {
+        let options = CsvOptions {
+            null: "NULL".to_string(),
+            timestamp_format: "%d/%m/%Y %H:%M".to_string(),
+            ..Default::default()
+        };
+        let meta = |sql_type, sub_type, scale| ParamMeta {
+            sql_type,
+            sub_type,
+            length: 8,
+            scale,
+            nullable: true,
+        };
+        let convert = |value: &str, quoted, meta| {
+            param(
+                Field {
+                    value: value.to_string(),
+                    quoted,
+                },
+                &meta,
+                &options,
+            )
+            .map(|value| format!("{:?}", value))
+        };
+        let expected = |value: SqlType| format!("{:?}", value);
+
+        assert_eq!(
+            expected(SqlType::Null),
+            convert("NULL", false, meta(ibase::SQL_LONG, 0, 0))?
+        );
+        assert_eq!(
+            expected(SqlType::Text("NULL".into())),
+            convert("NULL", true, meta(ibase::SQL_VARYING, 0, 0))?
+        );
+        assert_eq!(
+            expected(SqlType::Integer(-12)),
+            convert("-12", false, meta(ibase::SQL_LONG, 0, 0))?
+        );
+        assert!(convert("1.5", false, meta(ibase::SQL_LONG, 0, 0)).is_err());
+        assert_eq!(
+            expected(SqlType::Numeric {
+                value: -150,
+                scale: -2
+            }),
+            convert("-1.50", false, meta(ibase::SQL_INT64, 0, -2))?
+        );
+        assert!(convert("1.a", false, meta(ibase::SQL_INT64, 0, -2)).is_err());
+        assert_eq!(
+            expected(SqlType::Floating(0.5)),
+            convert("0.5", false, meta(ibase::SQL_DOUBLE, 0, 0))?
+        );
+        assert_eq!(
+            expected(SqlType::Boolean(true)),
+            convert("TRUE", false, meta(ibase::SQL_BOOLEAN, 0, 0))?
+        );
+        assert_eq!(
+            expected(SqlType::Timestamp(
+                chrono::NaiveDate::from_ymd_opt(2021, 3, 4)
+                    .unwrap()
+                    .and_hms_opt(10, 20, 0)
+                    .unwrap()
+            )),
+            convert("04/03/2021 10:20", false, meta(ibase::SQL_TIMESTAMP, 0, 0))?
+        );
+        assert_eq!(
+            expected(SqlType::Text("2021-03-04".into())),
+            convert("2021-03-04", false, meta(ibase::SQL_TYPE_DATE, 0, 0))?
+        );
+        assert_eq!(
+            expected(SqlType::Binary(b"foobar".to_vec())),
+            convert("Zm9vYmFy", false, meta(ibase::SQL_BLOB, 0, 0))?
+        );
+        assert!(convert("Zm9v!", false, meta(ibase::SQL_BLOB, 0, 0)).is_err());
+        assert_eq!(
+            expected(SqlType::Text("abc".into())),
+            convert("abc", false, meta(ibase::SQL_BLOB, 1, 0))?
+        );
+        assert_eq!(
+            expected(SqlType::Binary(b"foobar".to_vec())),
+            convert(
+                "Zm9vYmFy",
+                false,
+                meta(ibase::SQL_VARYING, ibase::CS_BINARY as i16, 0)
+            )?
+        );
+        assert_eq!(
+            expected(SqlType::Text("Zm9vYmFy".into())),
+            convert("Zm9vYmFy", false, meta(ibase::SQL_TEXT, 4, 0))?
+        );
+
+        assert_eq!(Some(b"fo".to_vec()), base64_decode("Zm8="));
+        assert_eq!(Some(b"f".to_vec()), base64_decode("Zg"));
+        assert_eq!(
+            "INSERT INTO \"T\" (\"ID\", \"a b\") VALUES (?, ?)",
+            insert_sql("T", Some(&["id".to_string(), "a b".to_string()]), 0)?
+        );
+        assert_eq!(
+            "INSERT INTO \"MY_T\" VALUES (?)",
+            insert_sql("my_t", None, 1)?
+        );
+        assert_eq!(
+            "INSERT INTO \"My table$1\" VALUES (?)",
+            insert_sql("My table$1", None, 1)?
+        );
+
+        for invalid in &["", "T\" (ID) VALUES (1); --", "T\n", &"T".repeat(64)] {
+            assert!(insert_sql(invalid, None, 1).is_err());
+            assert!(insert_sql("T", Some(&[invalid.to_string()]), 1).is_err());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,153 @@
+//! Database, transaction and statement info response parser
+
+use rsfbclient_core::ibase;
+
+use crate::FbError;
+
+/// Find the integer value of an item in a database or transaction info response.
+///
+/// The response is a sequence of `<item> <length: u16 le> <value>` clusters,
+/// ending with `isc_info_end`. The integers are in little endian, with variable length
+pub fn parse_int(buffer: &[u8], item: u8) -> Result<u64, FbError> {
+    let value = find_item(buffer, item)?;
+
+    if value.len() > 8 {
+        return Err(format!(
+            "The info item {} value has {} bytes, expected an integer",
+            item,
+            value.len()
+        )
+        .into());
+    }
+
+    Ok(value.iter().rev().fold(0, |acc, &b| (acc << 8) | b as u64))
+}
+
+/// Sum the per table counters of an item in a database info response,
+/// like `isc_info_read_seq_count`.
+///
+/// The value is a sequence of `<table id: u16 le> <count: u32 le>` entries,
+/// only for the tables used by the attachment
+pub fn parse_table_counts(buffer: &[u8], item: u8) -> Result<u64, FbError> {
+    let value = find_item(buffer, item)?;
+
+    if value.len() % 6 != 0 {
+        return Err(format!("Invalid table counters in the info item {}", item).into());
+    }
+
+    Ok(value
+        .chunks(6)
+        .map(|entry| u32::from_le_bytes([entry[2], entry[3], entry[4], entry[5]]) as u64)
+        .sum())
+}
+
+/// Rows counts of an `isc_info_sql_records` statement info response,
+/// as `(selected, inserted, updated, deleted)`.
+///
+/// All zeros if the statement has no counts, like the ddl statements
+pub fn parse_records(buffer: &[u8]) -> Result<(u64, u64, u64, u64), FbError> {
+    if buffer.first() == Some(&(ibase::isc_info_end as u8)) {
+        return Ok((0, 0, 0, 0));
+    }
+
+    let records = find_item(buffer, ibase::isc_info_sql_records as u8)?;
+
+    Ok((
+        parse_int(records, ibase::isc_info_req_select_count as u8)?,
+        parse_int(records, ibase::isc_info_req_insert_count as u8)?,
+        parse_int(records, ibase::isc_info_req_update_count as u8)?,
+        parse_int(records, ibase::isc_info_req_delete_count as u8)?,
+    ))
+}
+
+/// Find the value of an item in an info response
+fn find_item(buffer: &[u8], item: u8) -> Result<&[u8], FbError> {
+    let mut pos = 0;
+
+    while let Some(&tag) = buffer.get(pos) {
+        if tag == ibase::isc_info_end as u8 {
+            break;
+        }
+        if tag == ibase::isc_info_truncated as u8 {
+            return Err("The info response was truncated, use a larger buffer".into());
+        }
+
+        let len = buffer
+            .get(pos + 1..pos + 3)
+            .map(|len| u16::from_le_bytes([len[0], len[1]]) as usize)
+            .ok_or("Invalid info response")?;
+        let value = buffer
+            .get(pos + 3..pos + 3 + len)
+            .ok_or("Invalid info response")?;
+
+        if tag == ibase::isc_info_error as u8 {
+            return Err(format!("The info item {} is not supported", item).into());
+        }
+
+        if tag == item {
+            return Ok(value);
+        }
+
+        pos += 3 + len;
+    }
+
+    Err(format!("The info item {} was not returned", item).into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_int, parse_records, parse_table_counts};
+    use crate::*;
+
+    #[test]
+    fn parse_items() -> Result<(), FbError> {
+        let buffer = [
+            14, 4, 0, 0, 0x20, 0, 0, // page size: 8192
+            104, 2, 0, 0x34, 0x12, // oldest transaction: 0x1234
+            1, 0, 0, 0,
+        ];
+
+        assert_eq!(8192, parse_int(&buffer, 14)?);
+        assert_eq!(0x1234, parse_int(&buffer, 104)?);
+        assert!(parse_int(&buffer, 17).is_err());
+
+        assert!(parse_int(&[14, 4, 0, 0, 0x20, 0, 0, 2], 17).is_err());
+        assert!(parse_int(&[14, 4, 0, 0], 14).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_counts() -> Result<(), FbError> {
+        let buffer = [
+            23, 12, 0, // sequential reads
+            128, 0, 10, 0, 0, 0, // table 128: 10
+            129, 0, 0, 1, 0, 0, // table 129: 256
+            24, 0, 0, // indexed reads, none
+            1,
+        ];
+
+        assert_eq!(266, parse_table_counts(&buffer, 23)?);
+        assert_eq!(0, parse_table_counts(&buffer, 24)?);
+        assert!(parse_table_counts(&[23, 2, 0, 128, 0, 1], 23).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_statement_records() -> Result<(), FbError> {
+        let buffer = [
+            23, 29, 0, // records
+            13, 4, 0, 5, 0, 0, 0, // selected: 5
+            14, 4, 0, 0, 0, 0, 0, // inserted: 0
+            15, 4, 0, 2, 0, 0, 0, // updated: 2
+            16, 4, 0, 1, 0, 0, 0, // deleted: 1
+            1, 1,
+        ];
+
+        assert_eq!((5, 0, 2, 1), parse_records(&buffer)?);
+        assert_eq!((0, 0, 0, 0), parse_records(&[1, 0, 0])?);
+
+        Ok(())
+    }
+}
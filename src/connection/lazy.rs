@@ -0,0 +1,115 @@
+//!
+//! Rust Firebird Client
+//!
+//! Connection opened on the first use
+//!
+
+use rsfbclient_core::{FbError, FromRow, IntoParams};
+
+use super::{Connection, FirebirdClientFactory};
+use crate::{Execute, Queryable};
+
+/// A connection opened only when the first statement runs, to avoid the cost of
+/// the attachment when it is never used, like in the invocations of a serverless
+/// function that don't touch the database.
+///
+/// If opening the connection fails, like when the server is not reachable yet,
+/// the error is returned and the next use tries to open it again.
+///
+/// Use the `connect_lazy()` method of the builders to obtain a new instance
+pub struct LazyConnection<F: FirebirdClientFactory> {
+    factory: F,
+    conn: Option<Connection<F::C>>,
+}
+
+impl<F: FirebirdClientFactory> LazyConnection<F> {
+    /// Create the lazy connection, without opening it
+    pub fn new(factory: F) -> Self {
+        Self {
+            factory,
+            conn: None,
+        }
+    }
+
+    /// True if the connection was already opened
+    pub fn is_connected(&self) -> bool {
+        self.conn.is_some()
+    }
+
+    /// Get the connection, opening it if needed
+    pub fn connection(&mut self) -> Result<&mut Connection<F::C>, FbError> {
+        let conn = match self.conn.take() {
+            Some(conn) => conn,
+            None => Connection::open(self.factory.new_instance()?, self.factory.get_conn_conf())?,
+        };
+
+        Ok(self.conn.insert(conn))
+    }
+
+    /// Close the connection, if it was opened
+    pub fn close(mut self) -> Result<(), FbError> {
+        match self.conn.take() {
+            Some(conn) => conn.close(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<F: FirebirdClientFactory> Queryable for LazyConnection<F> {
+    fn query_iter<'a, P, R>(
+        &'a mut self,
+        sql: &str,
+        params: P,
+    ) -> Result<Box<dyn Iterator<Item = Result<R, FbError>> + 'a>, FbError>
+    where
+        P: IntoParams,
+        R: FromRow + 'static,
+    {
+        self.connection()?.query_iter(sql, params)
+    }
+}
+
+impl<F: FirebirdClientFactory> Execute for LazyConnection<F> {
+    fn execute<P>(&mut self, sql: &str, params: P) -> Result<usize, FbError>
+    where
+        P: IntoParams,
+    {
+        self.connection()?.execute(sql, params)
+    }
+
+    fn execute_returnable<P, R>(&mut self, sql: &str, params: P) -> Result<R, FbError>
+    where
+        P: IntoParams,
+        R: FromRow + 'static,
+    {
+        self.connection()?.execute_returnable(sql, params)
+    }
+}
+
+#[cfg(test)]
+mk_tests_default! {
+    use crate::*;
+
+    #[test]
+    fn lazy_connection() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect_lazy();
+        assert!(!conn.is_connected());
+
+        let (one,): (i32,) = conn
+            .query_first("select 1 from rdb$database", ())?
+            .unwrap();
+        assert_eq!(1, one);
+        assert!(conn.is_connected());
+
+        conn.execute("select 1 from rdb$database", ())?;
+        conn.close()?;
+
+        // The failure is not kept, trying again on the next use
+        let mut conn = cbuilder().db_name("lazy_connection_missing.fdb").connect_lazy();
+        assert!(conn.execute("select 1 from rdb$database", ()).is_err());
+        assert!(!conn.is_connected());
+        assert!(conn.connection().is_err());
+
+        Ok(())
+    }
+}
@@ -3,12 +3,25 @@
 //!
 //! Connection functions
 //!
-use rsfbclient_core::{Dialect, FbError, FirebirdClient, FirebirdClientDbOps, FromRow, IntoParams};
-use std::{marker, mem};
+use rsfbclient_core::{
+    ibase, CreateDatabaseConfig, Dialect, FbError, FirebirdClient, FirebirdClientCancelOps,
+    FirebirdClientDbOps, FromRow, IntoParams, ParamsType, TransactionConfiguration,
+};
+use std::{
+    io::{BufReader, Read, Write},
+    marker, mem,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
 
 use crate::{
-    query::Queryable, statement::StatementData, transaction::TransactionData, Execute, Transaction,
+    query::Queryable, statement::StatementData, trace::TraceConfig, transaction::TransactionData,
+    DetachedTransaction, EngineVersion, Execute, Statement, SystemInfos, Transaction,
 };
+pub use stmt_cache::SqlNormalization;
 use stmt_cache::{StmtCache, StmtCacheData};
 
 pub mod builders {
@@ -16,8 +29,8 @@ pub mod builders {
     #![allow(unused_imports)]
     use super::{
         super::{charset, Charset},
-        Connection, ConnectionConfiguration, Dialect, FbError, FirebirdClient,
-        FirebirdClientFactory,
+        Connection, ConnectionConfiguration, CreateDatabaseConfig, Dialect, FbError,
+        FirebirdClient, FirebirdClientFactory, LazyConnection, SqlNormalization,
     };
 
     #[cfg(feature = "native_client")]
@@ -31,10 +44,23 @@ pub mod builders {
     pub use builder_pure_rust::*;
 }
 
+pub(crate) mod columnar;
 pub(crate) mod conn_string;
+pub(crate) mod csv;
+pub(crate) mod db_info;
+pub(crate) mod lazy;
+pub(crate) mod monitoring;
+#[cfg(feature = "arrow")]
+pub(crate) mod record_batch;
+pub(crate) mod script;
 pub(crate) mod stmt_cache;
 
 pub(crate) mod simple;
+pub use columnar::{ColumnarData, ColumnarResult, ColumnarValues};
+pub use csv::{CsvBinary, CsvOptions};
+pub use lazy::LazyConnection;
+pub use monitoring::{AttachmentInfo, StatementInfo};
+pub use script::{ScriptError, StatementOutcome};
 pub use simple::SimpleConnection;
 
 /// A generic factory for creating multiple preconfigured instances of a particular client implementation
@@ -58,6 +84,10 @@ pub struct ConnectionConfiguration<A> {
     attachment_conf: A,
     dialect: Dialect,
     stmt_cache_size: usize,
+    stmt_cache_normalization: SqlNormalization,
+    stmt_timeout: Option<Duration>,
+    trace: TraceConfig,
+    default_tr_config: TransactionConfiguration,
 }
 
 impl<A: Default> Default for ConnectionConfiguration<A> {
@@ -66,6 +96,10 @@ impl<A: Default> Default for ConnectionConfiguration<A> {
             attachment_conf: Default::default(),
             dialect: Dialect::D3,
             stmt_cache_size: 20,
+            stmt_cache_normalization: SqlNormalization::Exact,
+            stmt_timeout: None,
+            trace: Default::default(),
+            default_tr_config: Default::default(),
         }
     }
 }
@@ -81,6 +115,16 @@ pub struct Connection<C: FirebirdClient> {
     /// Cache for the prepared statements
     pub(crate) stmt_cache: StmtCache<StatementData<C>>,
 
+    /// Default timeout for the statements, Firebird 4+ only
+    pub(crate) stmt_timeout: Option<Duration>,
+
+    /// Data recorded in the statements traces
+    pub(crate) trace: TraceConfig,
+
+    /// Parameters of the transactions started implicitly by the
+    /// connection, like the default transaction
+    pub(crate) default_tr_config: TransactionConfiguration,
+
     /// Default transaction to be used when no explicit
     /// transaction is used
     pub(crate) def_tr: Option<TransactionData<C>>,
@@ -91,8 +135,29 @@ pub struct Connection<C: FirebirdClient> {
 
     /// Firebird client
     pub(crate) cli: C,
+
+    /// Unique id of the connection in the process, to check
+    /// the detached transactions are used with their connection
+    pub(crate) id: u64,
+
+    /// First error of the cleanups done when dropping the statements
+    /// and iterators, returned by `close`
+    pub(crate) cleanup_error: Option<FbError>,
+
+    /// An operation failed due to a connection loss
+    pub(crate) broken: bool,
+
+    /// Number of cursors open by the statements executed
+    pub(crate) open_cursors: usize,
+
+    /// Detached transactions dropped without being finished, rolled back
+    /// before starting another one and when the connection is closed
+    pub(crate) dropped_trs: Arc<Mutex<Vec<TransactionData<C>>>>,
 }
 
+/// Source of the connection ids
+static CONNECTION_ID: AtomicU64 = AtomicU64::new(0);
+
 impl<C: FirebirdClient> Connection<C> {
     /// Open the client connection.
     pub fn open(
@@ -100,37 +165,62 @@ impl<C: FirebirdClient> Connection<C> {
         conf: &ConnectionConfiguration<C::AttachmentConfig>,
     ) -> Result<Connection<C>, FbError> {
         let handle = cli.attach_database(&conf.attachment_conf)?;
-        let stmt_cache = StmtCache::new(conf.stmt_cache_size);
 
-        Ok(Connection {
-            handle,
-            dialect: conf.dialect,
-            stmt_cache,
-            def_tr: None,
-            in_transaction: false,
-            cli,
-        })
+        Self::setup(cli, handle, conf)
     }
 
     /// Create the database and start the client connection.
+    ///
+    /// Fails if the database already exists, unless `overwrite`
+    /// is enabled in the creation configuration.
     pub fn create_database(
         mut cli: C,
         conf: &ConnectionConfiguration<C::AttachmentConfig>,
-        page_size: Option<u32>,
+        create_conf: &CreateDatabaseConfig,
     ) -> Result<Connection<C>, FbError> {
-        let handle = cli.create_database(&conf.attachment_conf, page_size)?;
-        let stmt_cache = StmtCache::new(conf.stmt_cache_size);
+        if let Some(db_charset) = &create_conf.db_charset {
+            db_charset.validate()?;
+        }
+
+        let handle = cli.create_database(&conf.attachment_conf, create_conf, conf.dialect)?;
+
+        Self::setup(cli, handle, conf)
+    }
+
+    // Builds the connection over an attached database handle
+    fn setup(
+        cli: C,
+        handle: <C as FirebirdClientDbOps>::DbHandle,
+        conf: &ConnectionConfiguration<C::AttachmentConfig>,
+    ) -> Result<Connection<C>, FbError> {
+        let stmt_cache = StmtCache::new(conf.stmt_cache_size, conf.stmt_cache_normalization);
 
         Ok(Connection {
             handle,
             dialect: conf.dialect,
             stmt_cache,
+            stmt_timeout: conf.stmt_timeout,
+            trace: conf.trace,
+            default_tr_config: conf.default_tr_config,
             def_tr: None,
             in_transaction: false,
             cli,
+            id: CONNECTION_ID.fetch_add(1, Ordering::Relaxed),
+            cleanup_error: None,
+            broken: false,
+            open_cursors: 0,
+            dropped_trs: Default::default(),
         })
     }
 
+    /// Number of cursors open by the queries, closed when their iterators, statements
+    /// or cursors are dropped. A count that keeps growing is a leak, like iterators
+    /// kept alive in a long running service, that keeps resources allocated in the server.
+    /// See the `with_open_cursors_warning` of the builders to be warned about it
+    pub fn open_cursor_count(&self) -> usize {
+        self.open_cursors
+    }
+
     /// Drop the current database
     pub fn drop_database(mut self) -> Result<(), FbError> {
         self.cli.drop_database(&mut self.handle)?;
@@ -138,25 +228,413 @@ impl<C: FirebirdClient> Connection<C> {
         Ok(())
     }
 
+    /// Handle of the database attachment in the client, to use the client api directly.
+    ///
+    /// For the native client this is the `isc_db_handle`, that together with
+    /// [`Transaction::handle`] allows to create and read blobs in an existing transaction.
+    /// The attachment must not be detached or otherwise modified directly with the handle
+    pub fn handle(&self) -> <C as FirebirdClientDbOps>::DbHandle
+    where
+        <C as FirebirdClientDbOps>::DbHandle: Copy,
+    {
+        self.handle
+    }
+
+    /// Create a token that can cancel the operation running in this connection
+    /// from another thread, like a long `SELECT`.
+    ///
+    /// The [`Connection`] itself can not be shared between threads, but the
+    /// returned [`CancelHandle`] is `Send` and only touches the attachment in a way
+    /// that is safe while other thread is running statements. The cancelled operation
+    /// returns a `FbError::Cancelled` error. Once the connection is closed, the
+    /// handle must not be used anymore.
+    pub fn cancel_handle(&mut self) -> Result<CancelHandle<C>, FbError> {
+        let handle = self.cli.cancel_handle(&mut self.handle)?;
+
+        Ok(CancelHandle(handle))
+    }
+
+    /// Check if the connection is still alive, using a cheap database info
+    /// request that doesn't start a transaction.
+    ///
+    /// If the connection with the server was lost, returns a `FbError::ConnectionLost`
+    /// error and the connection should be discarded
+    pub fn ping(&mut self) -> Result<(), FbError> {
+        match self.cli.ping(&mut self.handle) {
+            Err(FbError::Io(e)) => Err(FbError::ConnectionLost {
+                msg: e.to_string(),
+                status: vec![],
+            }),
+            res => res,
+        }
+    }
+
+    /// Query the database for the info items (`isc_info_*` constants from
+    /// `rsfbclient_core::ibase`), with `isc_database_info`, without starting a transaction.
+    ///
+    /// Returns the raw response buffer, with a `<item> <length: u16 le> <value>` cluster for
+    /// each item, ending with `isc_info_end`. If the buffer is too small, the response ends
+    /// with `isc_info_truncated` instead
+    pub fn db_info(&mut self, items: &[u8], buf_size: usize) -> Result<Vec<u8>, FbError> {
+        self.cli.database_info(&mut self.handle, items, buf_size)
+    }
+
+    /// Database page size in bytes
+    pub fn page_size(&mut self) -> Result<u32, FbError> {
+        self.db_info_int(ibase::db_info_types_isc_info_page_size)
+            .map(|size| size as u32)
+    }
+
+    /// Number of pages allocated for the database
+    pub fn page_count(&mut self) -> Result<u64, FbError> {
+        self.db_info_int(ibase::db_info_types_isc_info_db_size_in_pages)
+    }
+
+    /// Number of database pages allocated in the main database file
+    pub fn allocation(&mut self) -> Result<u64, FbError> {
+        self.db_info_int(ibase::db_info_types_isc_info_allocation)
+    }
+
+    /// Amount of server memory in use, in bytes
+    pub fn current_memory(&mut self) -> Result<u64, FbError> {
+        self.db_info_int(ibase::db_info_types_isc_info_current_memory)
+    }
+
+    /// Number of pages in the database cache used by the attachment
+    pub fn num_buffers(&mut self) -> Result<u32, FbError> {
+        self.db_info_int(ibase::db_info_types_isc_info_num_buffers)
+            .map(|buffers| buffers as u32)
+    }
+
+    /// True if the database is in the forced writes mode, writing the
+    /// changes to the disk synchronously
+    pub fn forced_writes(&mut self) -> Result<bool, FbError> {
+        self.db_info_int(ibase::db_info_types_isc_info_forced_writes)
+            .map(|forced_writes| forced_writes != 0)
+    }
+
+    /// Number of the oldest interesting transaction
+    pub fn oldest_transaction(&mut self) -> Result<u64, FbError> {
+        self.db_info_int(ibase::db_info_types_isc_info_oldest_transaction)
+    }
+
+    /// Query a single integer info item
+    fn db_info_int(&mut self, item: u32) -> Result<u64, FbError> {
+        let buffer = self.db_info(&[item as u8, ibase::isc_info_end as u8], 32)?;
+
+        db_info::parse_int(&buffer, item as u8)
+    }
+
+    /// Attachments with the database, from the `MON$ATTACHMENTS` monitoring table,
+    /// including the attachment of this connection if `include_current` is true.
+    ///
+    /// Only the SYSDBA, the database owner and users with the `MON$` privileges see the
+    /// attachments of the other users
+    pub fn active_attachments(
+        &mut self,
+        include_current: bool,
+    ) -> Result<Vec<AttachmentInfo>, FbError> {
+        monitoring::attachments(self, include_current)
+    }
+
+    /// Statements running in the database, from the `MON$STATEMENTS` monitoring table,
+    /// including the statements of this connection if `include_current` is true.
+    ///
+    /// Like in `active_attachments`, the statements of the other users are only seen
+    /// with the monitoring privileges
+    pub fn active_statements(
+        &mut self,
+        include_current: bool,
+    ) -> Result<Vec<StatementInfo>, FbError> {
+        monitoring::running_statements(self, include_current)
+    }
+
+    /// Take the warnings returned by the server since the last call, like a string
+    /// truncation or the use of a deprecated syntax.
+    ///
+    /// The warnings don't fail the operations, and are kept up to a limit of 100,
+    /// discarding the newer ones, until taken
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        self.cli.take_warnings()
+    }
+
+    /// Execute a script with many statements, like a migration file, in a single transaction.
+    ///
+    /// The statements are separated by `;`, and the terminator can be changed with
+    /// `SET TERM`, like in isql, to create procedures and triggers. Terminators inside of
+    /// comments and string literals are ignored.
+    ///
+    /// Like the `AUTODDL` of isql, the ddl statements are committed right after executed,
+    /// along with the changes before them, so the next statements can use the objects
+    /// created. On error, the changes after the last ddl are rolled back, and the
+    /// [`ScriptError`] has the index of the failed statement. Inside of an explicit
+    /// transaction, nothing is committed or rolled back.
+    ///
+    /// Returns the outcome of each statement, in order: the rows returned, for the
+    /// statements with columns, like a select, or the number of rows changed by the others.
+    /// The rows are all kept in memory, so avoid big result sets
+    pub fn execute_script(&mut self, script: &str) -> Result<Vec<StatementOutcome>, ScriptError> {
+        let stmts = script::split(script)?;
+        let autoddl = !self.in_transaction;
+        let mut failed = None;
+
+        self.with_transaction(|tr| {
+            let mut outcomes = Vec::with_capacity(stmts.len());
+
+            for (i, sql) in stmts.iter().enumerate() {
+                let outcome =
+                    script::execute(tr, sql, autoddl).inspect_err(|_| failed = Some(i))?;
+                outcomes.push(outcome);
+            }
+
+            Ok(outcomes)
+        })
+        .map_err(|error| ScriptError {
+            index: failed,
+            error,
+        })
+    }
+
+    /// Run the query, writing the rows to the writer in the csv format, as they are
+    /// fetched, without keeping the whole result in memory. Returns the number of rows.
+    ///
+    /// The writes are not buffered, so a `std::io::BufWriter` is recommended for files
+    /// and sockets. See [`CsvOptions`] for the delimiter, quoting, `NULL`, timestamps
+    /// and binary values formats
+    pub fn export_csv<P, W>(
+        &mut self,
+        sql: &str,
+        params: P,
+        writer: &mut W,
+        options: &CsvOptions,
+    ) -> Result<usize, FbError>
+    where
+        P: IntoParams,
+        W: Write,
+    {
+        self.with_transaction(|tr| csv::export(tr, sql, params, writer, options))
+    }
+
+    /// Run the query, returning the values of each column in its own typed vector, with a
+    /// bitmap of the `NULL`s, instead of a value per row, for the column oriented
+    /// processing, like the `Arrow` arrays.
+    ///
+    /// The values are moved to the columns as they are fetched, without the conversion
+    /// to rust types or the column names of each row. The type of each column is given
+    /// by its description, so the columns with only `NULL`s are typed too. The rows are
+    /// all kept in memory, so avoid big result sets
+    pub fn query_columns<P>(&mut self, sql: &str, params: P) -> Result<ColumnarResult, FbError>
+    where
+        P: IntoParams,
+    {
+        self.with_transaction(|tr| columnar::query(tr, sql, params))
+    }
+
+    /// Run the query, returning the rows in an `arrow` record batch, to feed the
+    /// results to the arrow based tools. Requires the `arrow` feature.
+    ///
+    /// See [`ColumnarResult::into_record_batch`] for the types mapping. The rows are
+    /// all kept in memory, so avoid big result sets
+    #[cfg(feature = "arrow")]
+    pub fn query_arrow<P>(
+        &mut self,
+        sql: &str,
+        params: P,
+    ) -> Result<arrow::record_batch::RecordBatch, FbError>
+    where
+        P: IntoParams,
+    {
+        self.query_columns(sql, params)?.into_record_batch()
+    }
+
+    /// Insert the csv records in the table, in a single transaction. Returns the number
+    /// of rows inserted.
+    ///
+    /// With `options.header`, the first line has the names of the columns filled,
+    /// otherwise all columns of the table are filled in order. The fields are converted
+    /// to the types of the columns, reading the timestamps with `options.timestamp_format`
+    /// and the binary blobs and `OCTETS` texts as base64. On error, the rows of the file
+    /// already inserted are undone, also inside of an explicit transaction, with a
+    /// savepoint, and the message starts with the line of the failed record.
+    ///
+    /// The table and column names are quoted, uppercasing the regular identifiers,
+    /// so `my_table` is the same as `MY_TABLE`, but `My table` is used exactly
+    pub fn import_csv<R>(
+        &mut self,
+        table: &str,
+        reader: R,
+        options: &CsvOptions,
+    ) -> Result<usize, FbError>
+    where
+        R: Read,
+    {
+        let reader = BufReader::new(reader);
+        self.with_transaction(|tr| csv::import(tr, table, reader, options))
+    }
+
+    /// Execute the statement without returning any row, parsing the sql with the dialect
+    /// instead of the one configured in the connection, like a dialect 1 statement in a
+    /// dialect 3 connection.
+    ///
+    /// The dialect only changes how the sql text is parsed: the meaning of the double
+    /// quotes (string literals in dialect 1, quoted identifiers in dialect 3), of `DATE`
+    /// (a timestamp in dialect 1), of the integer division and of the exact numerics
+    /// with a precision above 9 (stored as double precision in dialect 1). The stored
+    /// procedures, triggers, views and computed columns always run with the dialect of the
+    /// database, and some metadata changes are rejected when the dialect differs from the
+    /// database one.
+    ///
+    /// The statement is prepared on each call, without using the statement cache
+    pub fn execute_with_dialect<P>(
+        &mut self,
+        sql: &str,
+        params: P,
+        dialect: Dialect,
+    ) -> Result<usize, FbError>
+    where
+        P: IntoParams,
+    {
+        let params = params.to_params();
+
+        self.with_transaction(|tr| {
+            let mut stmt = Statement::prepare_with_dialect(tr, sql, params.named(), dialect)?;
+
+            stmt.execute(params)
+        })
+    }
+
+    /// Execute the query and return all the rows, parsing the sql with the dialect
+    /// instead of the one configured in the connection.
+    ///
+    /// See [`execute_with_dialect`][`Connection::execute_with_dialect`] for the
+    /// effects of the dialect. The statement is prepared on each call, without using
+    /// the statement cache
+    pub fn query_with_dialect<P, R>(
+        &mut self,
+        sql: &str,
+        params: P,
+        dialect: Dialect,
+    ) -> Result<Vec<R>, FbError>
+    where
+        P: IntoParams,
+        R: FromRow + 'static,
+    {
+        let params = params.to_params();
+
+        self.with_transaction(|tr| {
+            let mut stmt = Statement::prepare_with_dialect(tr, sql, params.named(), dialect)?;
+
+            let rows = stmt.query(params)?.collect();
+
+            rows
+        })
+    }
+
+    /// Free all the prepared statements in the statement cache, so the next
+    /// executions prepare them again.
+    ///
+    /// Useful after changing the metadata of the database, like with an `ALTER TABLE`.
+    /// The cache is cleared automatically when an operation returns a
+    /// `FbError::MetadataChanged` error, raised by firebird as `isc_obsolete_metadata`
+    /// (a prepared statement using an altered object) or `isc_obj_in_use`
+    /// (a ddl blocked by the prepared statements using the object). A cached statement
+    /// failing with this error is also prepared again and executed once more
+    pub fn clear_statement_cache(&mut self) -> Result<(), FbError> {
+        StmtCache::clear(self)
+    }
+
+    /// Clears the statement cache if the operation failed due to a metadata change,
+    /// and marks the connection as broken if it failed due to a connection loss
+    pub(crate) fn check_error<T>(&mut self, res: Result<T, FbError>) -> Result<T, FbError> {
+        match &res {
+            Err(FbError::MetadataChanged { .. }) => {
+                self.clear_statement_cache().ok();
+            }
+            Err(e) if e.is_connection_lost() => self.broken = true,
+            _ => {}
+        }
+
+        res
+    }
+
+    /// True after an operation failed because the connection with the server was lost,
+    /// like on a server restart or a killed attachment, so it shouldn't be used anymore.
+    ///
+    /// Only the errors of the `Queryable` and `Execute` methods, of the transactions and of
+    /// the closures of `with_transaction` are checked. Kept even if the pure rust client
+    /// reconnects later with `with_auto_reconnect`
+    pub fn is_broken(&self) -> bool {
+        self.broken
+    }
+
     /// Close the current connection.
+    ///
+    /// The connection is always released, but an error is returned if a transaction
+    /// started with [`begin_transaction`][`Connection::begin_transaction`] was not
+    /// committed or rolled back, as its changes are lost (`FbError::TransactionLost`),
+    /// or if a statement, cursor or transaction couldn't be cleaned up, including the
+    /// failures ignored before, when dropping the statements and the query iterators.
+    /// When the connection is dropped instead, all these errors are ignored
     pub fn close(mut self) -> Result<(), FbError> {
         let res = self.cleanup_and_detach();
         mem::forget(self);
         res
     }
 
-    // Cleans up statement cache and releases the database handle
+    // Cleans up statement cache and releases the database handle,
+    // continuing after the errors and returning the first one
     fn cleanup_and_detach(&mut self) -> Result<(), FbError> {
-        StmtCache::close_all(self);
+        let mut res = match self.cleanup_error.take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        };
+
+        let close_res = StmtCache::close_all(self);
+        res = res.and(close_res);
+
+        // The server refuses to detach with active transactions
+        let dropped_res = self.rollback_dropped_trs();
+        res = res.and(dropped_res);
 
         // Drop the default transaction
         if let Some(mut tr) = self.def_tr.take() {
-            tr.rollback(self).ok();
+            let rollback_res = tr.rollback(self);
+            res = res.and(rollback_res);
+
+            if self.in_transaction {
+                res = res.and(Err(FbError::TransactionLost {
+                    msg: "The connection was closed with an active transaction, rolling it back"
+                        .into(),
+                }));
+            }
         }
 
-        self.cli.detach_database(&mut self.handle)?;
+        let detach_res = self.cli.detach_database(&mut self.handle);
 
-        Ok(())
+        res.and(detach_res)
+    }
+
+    /// Rollback the detached transactions dropped without being finished
+    pub(crate) fn rollback_dropped_trs(&mut self) -> Result<(), FbError> {
+        let dropped = match self.dropped_trs.lock() {
+            Ok(mut dropped) => mem::take(&mut *dropped),
+            Err(poisoned) => mem::take(&mut *poisoned.into_inner()),
+        };
+
+        let mut res = Ok(());
+        for mut tr in dropped {
+            let rollback_res = tr.rollback(self);
+            res = res.and(rollback_res);
+        }
+
+        res
+    }
+
+    /// Keep the first error of the cleanups done when dropping, to be returned by `close`
+    pub(crate) fn keep_cleanup_error(&mut self, res: Result<(), FbError>) {
+        if let (Err(e), None) = (res, &self.cleanup_error) {
+            self.cleanup_error = Some(e);
+        }
     }
 
     /// Run a closure with a transaction, if the closure returns an error
@@ -176,10 +654,15 @@ impl<C: FirebirdClient> Connection<C> {
         let res = closure(&mut tr);
 
         if !in_transaction {
-            if res.is_ok() {
-                tr.commit_retaining()?;
+            let tr_res = if res.is_ok() {
+                tr.commit_retaining()
             } else {
-                tr.rollback_retaining()?;
+                tr.rollback_retaining()
+            };
+
+            if let Err(e) = tr_res {
+                drop(tr);
+                return self.check_error(Err(e));
             }
         }
 
@@ -190,7 +673,7 @@ impl<C: FirebirdClient> Connection<C> {
             tr.rollback(self).ok();
         }
 
-        res
+        self.check_error(res)
     }
 
     /// Run a closure with the default transaction, no rollback or commit will be automatically performed
@@ -240,6 +723,16 @@ impl<C: FirebirdClient> Connection<C> {
         Ok(())
     }
 
+    /// Start a transaction that doesn't keep the connection borrowed, so many of them
+    /// can be active at the same time, independent of the default transaction used
+    /// by the `query` and `execute` methods. See [`DetachedTransaction`]
+    pub fn detached_transaction(
+        &mut self,
+        conf: TransactionConfiguration,
+    ) -> Result<DetachedTransaction<C>, FbError> {
+        DetachedTransaction::new(self, conf)
+    }
+
     /// Commit the default transaction
     pub fn commit(&mut self) -> Result<(), FbError> {
         self.in_transaction = false;
@@ -253,6 +746,254 @@ impl<C: FirebirdClient> Connection<C> {
 
         self.use_transaction(|tr| tr.rollback_retaining())
     }
+
+    /// Increment the sequence (generator) by its configured step and return the new value.
+    ///
+    /// The name is quoted, uppercasing the regular identifiers, so `my_seq` is the same as
+    /// `MY_SEQ`, but `My seq` is used exactly. Fails if the sequence doesn't exist.
+    /// Requires Firebird 2+
+    pub fn next_value(&mut self, sequence_name: &str) -> Result<i64, FbError> {
+        let sql = format!(
+            "SELECT NEXT VALUE FOR {} FROM RDB$DATABASE",
+            quote_identifier(sequence_name)?
+        );
+
+        self.query_first(&sql, ())?
+            .map(|(val,)| val)
+            .ok_or_else(|| "The sequence value was not returned".into())
+    }
+
+    /// Increment the sequence (generator) by `step` and return the new value.
+    /// A zero `step` returns the current value.
+    ///
+    /// The name is quoted, uppercasing the regular identifiers, so `my_seq` is the same as
+    /// `MY_SEQ`, but `My seq` is used exactly. Fails if the sequence doesn't exist
+    pub fn gen_id(&mut self, sequence_name: &str, step: i64) -> Result<i64, FbError> {
+        let sql = format!(
+            "SELECT GEN_ID({}, {}) FROM RDB$DATABASE",
+            quote_identifier(sequence_name)?,
+            step
+        );
+
+        self.query_first(&sql, ())?
+            .map(|(val,)| val)
+            .ok_or_else(|| "The sequence value was not returned".into())
+    }
+
+    /// Call the stored procedure with the positional parameters. A selectable procedure is
+    /// called with `SELECT * FROM`, returning the rows suspended, while an executable one
+    /// is called with `EXECUTE PROCEDURE`, returning a single row with the output parameters,
+    /// or no rows if it has none. The kind of the procedure is read from `RDB$PROCEDURES`,
+    /// ignoring the packaged procedures.
+    ///
+    /// The name is quoted like in [`Connection::next_value`].
+    /// Fails if the procedure doesn't exist, or with named parameters
+    pub fn call_procedure<'a, P, R>(
+        &'a mut self,
+        procedure_name: &str,
+        params: P,
+    ) -> Result<Box<dyn Iterator<Item = Result<R, FbError>> + 'a>, FbError>
+    where
+        P: IntoParams,
+        R: FromRow + 'static,
+    {
+        let ident = quote_identifier(procedure_name)?;
+        let stored_name = stored_identifier(procedure_name)?;
+
+        let params = params.to_params();
+        let args = match &params {
+            ParamsType::Positional(p) if p.is_empty() => String::new(),
+            ParamsType::Positional(p) => format!("({})", vec!["?"; p.len()].join(", ")),
+            ParamsType::Named(_) => {
+                return Err("The procedure parameters must be positional".into())
+            }
+        };
+
+        let mut kind_sql = "SELECT RDB$PROCEDURE_TYPE, COALESCE(RDB$PROCEDURE_OUTPUTS, 0) FROM RDB$PROCEDURES WHERE RDB$PROCEDURE_NAME = ?".to_string();
+        // The packaged procedures can have the same name, but are not called by it
+        if self.server_engine()? >= EngineVersion::V3 {
+            kind_sql.push_str(" AND RDB$PACKAGE_NAME IS NULL");
+        }
+
+        let kind: Option<(Option<i16>, i16)> = self.query_first(&kind_sql, (stored_name,))?;
+
+        let selectable = match kind {
+            Some((Some(proc_type), _)) => proc_type == 1,
+            // Created before Firebird 2.1, without the type
+            Some((None, outputs)) => outputs > 0,
+            None => return Err(format!("The procedure {} doesn't exist", procedure_name).into()),
+        };
+
+        let sql = if selectable {
+            format!("SELECT * FROM {}{}", ident, args)
+        } else {
+            format!("EXECUTE PROCEDURE {}{}", ident, args)
+        };
+
+        self.query_iter(&sql, params)
+    }
+
+    /// Set the value of a context variable, with `RDB$SET_CONTEXT`, to be read by the
+    /// statements, triggers and procedures, like for an audit trail or a row level security.
+    /// A `None` value removes the variable. Returns true if the variable already existed.
+    ///
+    /// The variables of `UserTransaction` are only visible in the transaction that set them,
+    /// the default transaction of the connection. The name can have up to 80 characters
+    /// and the value up to 255. The `System` namespace is read only, and a session can
+    /// have up to 1000 variables. Requires Firebird 2+
+    pub fn set_context(
+        &mut self,
+        namespace: ContextNamespace,
+        name: &str,
+        value: Option<&str>,
+    ) -> Result<bool, FbError> {
+        let sql = format!(
+            "SELECT RDB$SET_CONTEXT('{}', ?, ?) FROM RDB$DATABASE",
+            namespace.name()
+        );
+
+        if let Some(value) = value {
+            if value.chars().count() > 255 {
+                return Err(format!(
+                    "The value of the context variable {} is longer than 255 characters",
+                    name
+                )
+                .into());
+            }
+        }
+
+        self.query_first(&sql, (context_var(name)?, value))?
+            .map(|(existed,): (i32,)| existed == 1)
+            .ok_or_else(|| "The context variable result was not returned".into())
+    }
+
+    /// Get the value of a context variable, with `RDB$GET_CONTEXT`, or `None` if it is not set.
+    ///
+    /// Fails if the variable doesn't exist in the `System` namespace. Requires Firebird 2+
+    pub fn get_context(
+        &mut self,
+        namespace: ContextNamespace,
+        name: &str,
+    ) -> Result<Option<String>, FbError> {
+        let sql = format!(
+            "SELECT RDB$GET_CONTEXT('{}', ?) FROM RDB$DATABASE",
+            namespace.name()
+        );
+
+        self.query_first(&sql, (context_var(name)?,))?
+            .map(|(value,)| value)
+            .ok_or_else(|| "The context variable value was not returned".into())
+    }
+}
+
+/// Namespace of the context variables read and changed by [`Connection::get_context`]
+/// and [`Connection::set_context`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextNamespace {
+    /// Variables kept until the end of the connection
+    UserSession,
+
+    /// Variables kept until the end of the transaction
+    UserTransaction,
+
+    /// Read only variables with information about the session,
+    /// like `CLIENT_ADDRESS`, `DB_NAME` or `ISOLATION_LEVEL`
+    System,
+}
+
+impl ContextNamespace {
+    /// Name of the namespace in firebird
+    fn name(self) -> &'static str {
+        match self {
+            ContextNamespace::UserSession => "USER_SESSION",
+            ContextNamespace::UserTransaction => "USER_TRANSACTION",
+            ContextNamespace::System => "SYSTEM",
+        }
+    }
+}
+
+/// Token to cancel the operation running in a [`Connection`] from another thread.
+///
+/// Obtained with [`Connection::cancel_handle`]
+pub struct CancelHandle<C: FirebirdClient>(<C as FirebirdClientDbOps>::CancelHandle);
+
+impl<C: FirebirdClient> CancelHandle<C> {
+    /// Cancel the operation currently running in the connection.
+    /// Does nothing if the connection is idle
+    pub fn cancel(&self) -> Result<(), FbError> {
+        self.0.cancel_operation()
+    }
+}
+
+/// Statement timeout in milliseconds, as sent to the server. A zero
+/// duration disables the timeout, and the others are rounded up
+/// to 1 ms, as a zero would disable it
+pub(crate) fn stmt_timeout_millis(timeout: Duration) -> u32 {
+    if timeout.is_zero() {
+        0
+    } else {
+        timeout.as_millis().clamp(1, u32::MAX as u128) as u32
+    }
+}
+
+/// Validates the name of a context variable, which must have from 1 to 80 characters
+pub(crate) fn context_var(name: &str) -> Result<&str, FbError> {
+    let len = name.chars().count();
+
+    if len == 0 || len > 80 || name.chars().any(char::is_control) {
+        return Err(format!("Invalid context variable name: {:?}", name).into());
+    }
+
+    Ok(name)
+}
+
+/// Maximum length of the identifiers, in characters. Firebird 3 and older
+/// have a lower limit, 31 bytes, enforced by the server
+const MAX_IDENTIFIER_LEN: usize = 63;
+
+/// Validates an object name, like a table, column or sequence, to be interpolated in
+/// a statement, always wrapped in double quotes. Regular identifiers (`my_seq`) are
+/// uppercased, so they are case insensitive like in the sql, and the others (`My seq`)
+/// are used exactly.
+///
+/// Fails on empty or too long names, and on names with double quotes or
+/// control characters, that could change the statement
+pub(crate) fn quote_identifier(name: &str) -> Result<String, FbError> {
+    Ok(format!("\"{}\"", stored_identifier(name)?))
+}
+
+/// Validates an object name like [`quote_identifier`], returning it as stored
+/// in the system tables: uppercased if it is a regular identifier
+pub(crate) fn stored_identifier(name: &str) -> Result<String, FbError> {
+    if name.is_empty() {
+        return Err("Invalid identifier: the name is empty".into());
+    }
+
+    if name.chars().count() > MAX_IDENTIFIER_LEN {
+        return Err(format!(
+            "Invalid identifier {:?}: longer than {} characters",
+            name, MAX_IDENTIFIER_LEN
+        )
+        .into());
+    }
+
+    if name.contains('"') || name.chars().any(char::is_control) {
+        return Err(format!(
+            "Invalid identifier {:?}: double quotes and control characters are not allowed",
+            name
+        )
+        .into());
+    }
+
+    let mut chars = name.chars();
+    let regular = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+
+    if regular {
+        Ok(name.to_ascii_uppercase())
+    } else {
+        Ok(name.to_string())
+    }
 }
 
 impl<C: FirebirdClient> Drop for Connection<C> {
@@ -278,19 +1019,22 @@ where
 {
     fn drop(&mut self) {
         // Close the cursor
-        self.stmt_cache_data
+        let res = self
+            .stmt_cache_data
             .as_mut()
             .unwrap()
             .stmt
-            .close_cursor(self.conn)
-            .ok();
+            .close_cursor(self.conn);
+        self.conn.keep_cleanup_error(res);
 
         // Send the statement back to the cache
-        StmtCache::insert_and_close(self.conn, self.stmt_cache_data.take().unwrap()).ok();
+        let res = StmtCache::insert_and_close(self.conn, self.stmt_cache_data.take().unwrap());
+        self.conn.keep_cleanup_error(res);
 
         if !self.conn.in_transaction {
             // Commit the transaction
-            self.conn.commit().ok();
+            let res = self.conn.commit();
+            self.conn.keep_cleanup_error(res);
         }
     }
 }
@@ -334,9 +1078,13 @@ where
             let params = params.to_params();
 
             // Get a statement from the cache
-            let mut stmt_cache_data = StmtCache::get_or_prepare(tr, sql, params.named())?;
+            let (stmt_cache_data, res) =
+                StmtCache::run(tr, sql, params, |stmt, conn, tr, params| {
+                    stmt.check_columns::<R>(conn)?;
+                    stmt.query(conn, tr, params)
+                })?;
 
-            match stmt_cache_data.stmt.query(tr.conn, &mut tr.data, params) {
+            match res {
                 Ok(_) => Ok(stmt_cache_data),
                 Err(e) => {
                     // Return the statement to the cache
@@ -349,7 +1097,8 @@ where
                     Err(e)
                 }
             }
-        })?;
+        });
+        let stmt_cache_data = self.check_error(stmt_cache_data)?;
 
         let iter = StmtIter {
             stmt_cache_data: Some(stmt_cache_data),
@@ -372,11 +1121,12 @@ where
         let params = params.to_params();
 
         self.with_transaction(|tr| {
-            // Get a statement from the cache
-            let mut stmt_cache_data = StmtCache::get_or_prepare(tr, sql, params.named())?;
-
-            // Do not return now in case of error, because we need to return the statement to the cache
-            let res = stmt_cache_data.stmt.execute(tr.conn, &mut tr.data, params);
+            // Get a statement from the cache. Do not return now in case of error,
+            // because we need to return the statement to the cache
+            let (stmt_cache_data, res) =
+                StmtCache::run(tr, sql, params, |stmt, conn, tr, params| {
+                    stmt.execute(conn, tr, params)
+                })?;
 
             // Return the statement to the cache
             StmtCache::insert_and_close(tr.conn, stmt_cache_data)?;
@@ -393,11 +1143,12 @@ where
         let params = params.to_params();
 
         self.with_transaction(|tr| {
-            // Get a statement from the cache
-            let mut stmt_cache_data = StmtCache::get_or_prepare(tr, sql, params.named())?;
-
-            // Do not return now in case of error, because we need to return the statement to the cache
-            let res = stmt_cache_data.stmt.execute2(tr.conn, &mut tr.data, params);
+            // Get a statement from the cache. Do not return now in case of error,
+            // because we need to return the statement to the cache
+            let (stmt_cache_data, res) =
+                StmtCache::run(tr, sql, params, |stmt, conn, tr, params| {
+                    stmt.execute2(conn, tr, params)
+                })?;
 
             // Return the statement to the cache
             StmtCache::insert_and_close(tr.conn, stmt_cache_data)?;
@@ -422,6 +1173,579 @@ mk_tests_default! {
         Ok(())
     }
 
+    #[test]
+    fn ping() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        conn.ping()?;
+        assert!(conn.def_tr.is_none());
+
+        let (v,): (i32,) = conn.query_first("SELECT 1 FROM RDB$DATABASE", ())?.unwrap();
+        assert_eq!(1, v);
+
+        conn.ping()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn db_info() -> Result<(), FbError> {
+        use rsfbclient_core::ibase;
+
+        let mut conn = cbuilder().connect()?;
+
+        let page_size = conn.page_size()?;
+        assert!([4096, 8192, 16384, 32768].contains(&page_size));
+        assert!(conn.page_count()? > 0);
+        assert!(conn.allocation()? > 0);
+        assert!(conn.current_memory()? > 0);
+        conn.oldest_transaction()?;
+        assert!(conn.def_tr.is_none());
+
+        let buffer = conn.db_info(&[ibase::db_info_types_isc_info_page_size as u8], 32)?;
+        assert_eq!(ibase::db_info_types_isc_info_page_size as u8, buffer[0]);
+
+        let truncated = conn.db_info(&[ibase::db_info_types_isc_info_page_size as u8], 2)?;
+        assert!(truncated.contains(&(ibase::isc_info_truncated as u8)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn take_warnings() -> Result<(), FbError> {
+        use rsfbclient_core::ibase;
+
+        let mut conn = cbuilder().dialect(Dialect::D1).connect()?;
+
+        conn.take_warnings();
+
+        // The dialect 1 interprets the literal as a floating point, with a warning
+        let (v,): (f64,) = conn
+            .query_first("select 12345678901.5 from rdb$database", ())?
+            .unwrap();
+        assert_eq!(12345678901.5, v);
+
+        let warnings = conn.take_warnings();
+        assert!(
+            warnings.iter().any(|w| w.contains("floating-point")),
+            "{:?}",
+            warnings
+        );
+        assert!(conn.take_warnings().is_empty());
+
+        // The server reports the string truncation as an error, so it must not
+        // be kept as a warning, and the connection is still usable after it
+        let err = conn
+            .query_first::<_, (String,)>("select cast('abcdef' as varchar(3)) from rdb$database", ())
+            .unwrap_err();
+        let status = err.status_vector().expect("The status vector is missing");
+        assert!(
+            status
+                .iter()
+                .any(|item| item.code == ibase::isc_string_truncation),
+            "{:?}",
+            err
+        );
+        assert!(conn.take_warnings().is_empty());
+
+        let (v,): (String,) = conn
+            .query_first("select cast('abc' as varchar(3)) from rdb$database", ())?
+            .unwrap();
+        assert_eq!("abc", v);
+
+        Ok(())
+    }
+
+    #[test]
+    fn status_vector() -> Result<(), FbError> {
+        use rsfbclient_core::{ibase, StatusArg};
+
+        let mut conn = cbuilder().connect()?;
+
+        conn.execute("DROP TABLE STATUS_VECTOR_TEST", ()).ok();
+        conn.execute(
+            "CREATE TABLE STATUS_VECTOR_TEST (A INT CONSTRAINT PK_STATUS_VECTOR PRIMARY KEY)",
+            (),
+        )?;
+        conn.execute("INSERT INTO STATUS_VECTOR_TEST (A) VALUES (1)", ())?;
+
+        let e = conn
+            .execute("INSERT INTO STATUS_VECTOR_TEST (A) VALUES (1)", ())
+            .unwrap_err();
+        let status = e.status_vector().expect("The status vector is missing");
+
+        let violation = status
+            .iter()
+            .find(|item| item.code == ibase::isc_unique_key_violation)
+            .expect("The unique key violation is missing");
+        assert!(violation
+            .args
+            .contains(&StatusArg::String("PK_STATUS_VECTOR".to_string())));
+
+        assert!(FbError::from("other").status_vector().is_none());
+
+        conn.execute("DROP TABLE STATUS_VECTOR_TEST", ())?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn monitoring() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+        let mut other = cbuilder().connect()?;
+
+        let sql = "select current_connection from rdb$database";
+        let (id,): (i64,) = conn.query_first(sql, ())?.unwrap();
+        let (other_id,): (i64,) = other.query_first(sql, ())?.unwrap();
+
+        let attachments = conn.active_attachments(true)?;
+        assert!(attachments.iter().any(|att| att.id == id && att.active));
+        assert!(attachments.iter().any(|att| att.id == other_id));
+
+        let attachments = conn.active_attachments(false)?;
+        assert!(attachments.iter().all(|att| att.id != id));
+        assert!(attachments.iter().any(|att| att.id == other_id));
+
+        // The monitoring query itself is running
+        let stmts = conn.active_statements(true)?;
+        assert!(stmts.iter().any(|stmt| stmt.attachment_id == id
+            && stmt.sql.as_deref().unwrap_or("").contains("MON$STATEMENTS")));
+
+        let stmts = conn.active_statements(false)?;
+        assert!(stmts.iter().all(|stmt| stmt.attachment_id != id));
+
+        Ok(())
+    }
+
+    #[test]
+    fn stmt_cache_normalization() -> Result<(), FbError> {
+        let mut conn = cbuilder()
+            .stmt_cache_normalization(SqlNormalization::Uppercase)
+            .connect()?;
+
+        let _: Vec<(i32,)> = conn.query("select 1 from rdb$database", ())?;
+        let _: Vec<(i32,)> = conn.query("SELECT 1\n  FROM RDB$DATABASE -- same", ())?;
+        assert_eq!(1, conn.stmt_cache.len());
+
+        let _: Vec<(String,)> = conn.query("select 'a' from rdb$database", ())?;
+        let _: Vec<(String,)> = conn.query("select 'A' from rdb$database", ())?;
+        assert_eq!(3, conn.stmt_cache.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn clear_statement_cache() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        conn.execute("DROP TABLE CLEAR_CACHE_TEST", ()).ok();
+        conn.execute("CREATE TABLE CLEAR_CACHE_TEST (A INT)", ())?;
+        conn.execute("INSERT INTO CLEAR_CACHE_TEST (A) VALUES (1)", ())?;
+
+        // The cached statement keeps the table in use
+        let _: Vec<(i32,)> = conn.query("SELECT A FROM CLEAR_CACHE_TEST", ())?;
+        conn.clear_statement_cache()?;
+        conn.execute("ALTER TABLE CLEAR_CACHE_TEST ADD B INT", ())?;
+
+        let rows: Vec<(i32, Option<i32>)> = conn.query("SELECT A, B FROM CLEAR_CACHE_TEST", ())?;
+        assert_eq!(vec![(1, None)], rows);
+
+        // Blocked by the cached statement, clearing the cache automatically
+        let err = conn
+            .execute("DROP TABLE CLEAR_CACHE_TEST", ())
+            .unwrap_err();
+        assert!(matches!(err, FbError::MetadataChanged { .. }));
+        assert!(err
+            .status_vector()
+            .unwrap()
+            .iter()
+            .any(|item| item.code == rsfbclient_core::ibase::isc_obj_in_use));
+
+        conn.execute("DROP TABLE CLEAR_CACHE_TEST", ())?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn ddl_not_cached() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        conn.execute("DROP TABLE DDL_CACHE_TEST", ()).ok();
+        conn.clear_statement_cache()?;
+
+        conn.execute("CREATE TABLE DDL_CACHE_TEST (A INT)", ())?;
+        assert_eq!(0, conn.stmt_cache.len());
+
+        conn.execute("INSERT INTO DDL_CACHE_TEST (A) VALUES (1)", ())?;
+        let rows: Vec<(i32,)> = conn.query("SELECT A FROM DDL_CACHE_TEST", ())?;
+        assert_eq!(vec![(1,)], rows);
+        assert_eq!(2, conn.stmt_cache.len());
+
+        conn.clear_statement_cache()?;
+        conn.execute("DROP TABLE DDL_CACHE_TEST", ())?;
+        assert_eq!(0, conn.stmt_cache.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn stmt_cache_reprepare() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        conn.execute("DROP TABLE REPREPARE_TEST", ()).ok();
+        conn.execute("CREATE TABLE REPREPARE_TEST (A INT)", ())?;
+        conn.execute("INSERT INTO REPREPARE_TEST (A) VALUES (?)", (1,))?;
+
+        let rows: Vec<(i32,)> = conn.query("SELECT A FROM REPREPARE_TEST", ())?;
+        assert_eq!(vec![(1,)], rows);
+
+        // Altered by another connection, with the statements still in the cache
+        let mut other = cbuilder().connect()?;
+        other.execute("ALTER TABLE REPREPARE_TEST ADD B INT", ())?;
+        other.close()?;
+
+        conn.execute("INSERT INTO REPREPARE_TEST (A) VALUES (?)", (2,))?;
+        let rows: Vec<(i32,)> = conn.query("SELECT A FROM REPREPARE_TEST", ())?;
+        assert_eq!(vec![(1,), (2,)], rows);
+
+        conn.clear_statement_cache()?;
+        conn.execute("DROP TABLE REPREPARE_TEST", ())?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn export_csv() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        let mut out = vec![];
+        let rows = conn.export_csv(
+            "select 1 as id, 'a;b' as name, cast(null as int) as empty, cast('2021-03-04 10:20:30' as timestamp) as ts
+             from rdb$database
+             union all
+             select 2, 'c', 3, cast('2021-03-05' as timestamp) from rdb$database",
+            (),
+            &mut out,
+            &CsvOptions {
+                delimiter: ';',
+                null: "NULL".to_string(),
+                ..Default::default()
+            },
+        )?;
+
+        assert_eq!(2, rows);
+        assert_eq!(
+            "ID;NAME;EMPTY;TS\n\
+             1;\"a;b\";NULL;2021-03-04 10:20:30\n\
+             2;c;3;2021-03-05 00:00:00\n",
+            String::from_utf8(out).unwrap()
+        );
+
+        let mut out = vec![];
+        conn.export_csv(
+            "select cast('abc' as blob sub_type binary) from rdb$database where 1 = ?",
+            (1,),
+            &mut out,
+            &CsvOptions {
+                header: false,
+                ..Default::default()
+            },
+        )?;
+        assert_eq!("YWJj\n", String::from_utf8(out).unwrap());
+
+        Ok(())
+    }
+
+    #[test]
+    fn query_columns() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        let res = conn.query_columns(
+            "select 1 as id, cast('a' as varchar(5)) as name, cast(null as int) as empty
+             from rdb$database
+             union all
+             select 2, cast(null as varchar(5)), cast(null as int) from rdb$database
+             union all
+             select 3, cast('c' as varchar(5)), cast(null as int) from rdb$database where 1 = ?",
+            (1,),
+        )?;
+
+        assert_eq!(3, res.rows);
+        assert_eq!(3, res.columns.len());
+
+        let id = res.column("id").unwrap();
+        assert_eq!(ColumnarValues::Integer(vec![1, 2, 3]), id.values);
+        assert_eq!(0, id.null_count());
+
+        let name = res.column("NAME").unwrap();
+        assert_eq!(
+            ColumnarValues::Text(vec!["a".to_string(), String::new(), "c".to_string()]),
+            name.values
+        );
+        assert_eq!(vec![0b101], name.validity);
+        assert!(name.is_null(1));
+        assert_eq!(1, name.null_count());
+
+        // Typed by the description
+        let empty = res.column("EMPTY").unwrap();
+        assert_eq!(ColumnarValues::Integer(vec![0, 0, 0]), empty.values);
+        assert_eq!(3, empty.null_count());
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "arrow")]
+    fn query_arrow() -> Result<(), FbError> {
+        use arrow::{
+            array::{Array, Decimal128Array, Int32Array, StringArray},
+            datatypes::DataType,
+        };
+
+        let mut conn = cbuilder().connect()?;
+
+        let batch = conn.query_arrow(
+            "select 1 as id, cast('a' as varchar(5)) as name, cast(1.5 as numeric(10, 2)) as price,
+                cast(null as date) as day
+             from rdb$database
+             union all
+             select 2, cast(null as varchar(5)), cast(null as numeric(10, 2)), cast(null as date)
+             from rdb$database",
+            (),
+        )?;
+
+        assert_eq!(2, batch.num_rows());
+        let schema = batch.schema();
+        assert_eq!("ID", schema.field(0).name());
+        assert_eq!(&DataType::Int32, schema.field(0).data_type());
+        assert_eq!(&DataType::Utf8, schema.field(1).data_type());
+        assert_eq!(&DataType::Decimal128(18, 2), schema.field(2).data_type());
+        assert_eq!(&DataType::Date32, schema.field(3).data_type());
+
+        let ids = batch.column(0).as_any().downcast_ref::<Int32Array>().unwrap();
+        assert_eq!(vec![Some(1), Some(2)], ids.iter().collect::<Vec<_>>());
+
+        let names = batch.column(1).as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(vec![Some("a"), None], names.iter().collect::<Vec<_>>());
+
+        let prices = batch.column(2).as_any().downcast_ref::<Decimal128Array>().unwrap();
+        assert_eq!(vec![Some(150), None], prices.iter().collect::<Vec<_>>());
+
+        assert_eq!(2, batch.column(3).null_count());
+
+        Ok(())
+    }
+
+    #[test]
+    fn import_csv() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        conn.execute("DROP TABLE IMPORT_CSV_TEST", ()).ok();
+        conn.execute(
+            "CREATE TABLE IMPORT_CSV_TEST (ID INT, NAME VARCHAR(10), PRICE NUMERIC(10, 2), TS TIMESTAMP, DATA BLOB SUB_TYPE BINARY)",
+            (),
+        )?;
+
+        let rows = conn.import_csv(
+            "IMPORT_CSV_TEST",
+            "ID;NAME;PRICE;TS;DATA\n\
+             1;\"a;b\";1.50;2021-03-04 10:20:30;YWJj\n\
+             2;;;2021-03-05;\n"
+                .as_bytes(),
+            &CsvOptions {
+                delimiter: ';',
+                ..Default::default()
+            },
+        )?;
+        assert_eq!(2, rows);
+
+        let mut out = vec![];
+        conn.export_csv(
+            "select id, name, price, ts, data from import_csv_test order by id",
+            (),
+            &mut out,
+            &CsvOptions {
+                delimiter: ';',
+                null: "NULL".to_string(),
+                ..Default::default()
+            },
+        )?;
+        assert_eq!(
+            "ID;NAME;PRICE;TS;DATA\n\
+             1;\"a;b\";1.50;2021-03-04 10:20:30;YWJj\n\
+             2;NULL;NULL;2021-03-05 00:00:00;NULL\n",
+            String::from_utf8(out).unwrap()
+        );
+
+        // Nothing is inserted on error, reporting the line
+        let res = conn.import_csv(
+            "IMPORT_CSV_TEST",
+            "3,c\n4,d\nx,e\n".as_bytes(),
+            &CsvOptions {
+                header: false,
+                ..Default::default()
+            },
+        );
+        assert!(res.unwrap_err().to_string().contains("line 3"));
+
+        let (count,): (i32,) = conn
+            .query_first("select count(*) from import_csv_test", ())?
+            .unwrap();
+        assert_eq!(2, count);
+
+        // Also undone inside of an explicit transaction, keeping its other changes
+        conn.begin_transaction()?;
+        conn.execute("insert into import_csv_test (id) values (5)", ())?;
+        let res = conn.import_csv(
+            "IMPORT_CSV_TEST",
+            "ID\n6\nx\n".as_bytes(),
+            &CsvOptions::default(),
+        );
+        assert!(res.is_err());
+        conn.commit()?;
+
+        let ids: Vec<(i32,)> = conn.query("select id from import_csv_test order by id", ())?;
+        assert_eq!(vec![(1,), (2,), (5,)], ids);
+
+        conn.execute("DROP TABLE IMPORT_CSV_TEST", ())?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn csv_round_trip() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        conn.execute("DROP TABLE CSV_ROUND_TRIP", ()).ok();
+        conn.execute("CREATE TABLE CSV_ROUND_TRIP (ID INT, NAME VARCHAR(10))", ())?;
+
+        for null in ["", "NULL"] {
+            let options = CsvOptions {
+                null: null.to_string(),
+                ..Default::default()
+            };
+
+            let mut out = vec![];
+            conn.export_csv(
+                "select 1 as id, '' as name from rdb$database
+                 union all select 2, 'NULL' from rdb$database
+                 union all select 3, cast(null as varchar(10)) from rdb$database",
+                (),
+                &mut out,
+                &options,
+            )?;
+
+            conn.execute("DELETE FROM CSV_ROUND_TRIP", ())?;
+            conn.import_csv("CSV_ROUND_TRIP", out.as_slice(), &options)?;
+
+            let rows: Vec<(i32, Option<String>)> =
+                conn.query("select id, name from csv_round_trip order by id", ())?;
+            assert_eq!(
+                vec![
+                    (1, Some(String::new())),
+                    (2, Some("NULL".to_string())),
+                    (3, None)
+                ],
+                rows
+            );
+        }
+
+        conn.execute("DROP TABLE CSV_ROUND_TRIP", ())?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn execute_script() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        conn.execute("DROP PROCEDURE SCRIPT_TEST_PROC", ()).ok();
+        conn.execute("DROP TABLE SCRIPT_TEST", ()).ok();
+        conn.execute("CREATE TABLE SCRIPT_TEST (A VARCHAR(10))", ())?;
+
+        let outcomes = conn.execute_script(
+            "-- Test script; with comments\n\
+             INSERT INTO SCRIPT_TEST (A) VALUES ('a;b');\n\
+             /* ; */ INSERT INTO SCRIPT_TEST (A) VALUES ('it''s');\n\
+             SET TERM ^ ;\n\
+             CREATE PROCEDURE SCRIPT_TEST_PROC AS\n\
+             BEGIN\n\
+               INSERT INTO SCRIPT_TEST (A) VALUES ('proc');\n\
+             END^\n\
+             SET TERM ; ^\n\
+             UPDATE SCRIPT_TEST SET A = A;\n\
+             SELECT A FROM SCRIPT_TEST ORDER BY A;",
+        )?;
+
+        let counts: Vec<_> = outcomes[..4]
+            .iter()
+            .map(|outcome| match outcome {
+                StatementOutcome::RowCount(count) => *count,
+                outcome => panic!("Unexpected outcome: {:?}", outcome),
+            })
+            .collect();
+        assert_eq!(vec![1, 1, 0, 2], counts);
+
+        match &outcomes[4] {
+            StatementOutcome::Rows(rows) => {
+                let names = rows
+                    .iter()
+                    .map(|row| row.get::<String>(0))
+                    .collect::<Result<Vec<_>, _>>()?;
+                assert_eq!(vec!["a;b", "it's"], names);
+            }
+            outcome => panic!("Unexpected outcome: {:?}", outcome),
+        }
+        assert_eq!(5, outcomes.len());
+
+        let rows: Vec<(String,)> = conn.query("SELECT A FROM SCRIPT_TEST ORDER BY A", ())?;
+        assert_eq!(
+            vec![("a;b".to_string(),), ("it's".to_string(),)],
+            rows
+        );
+
+        let res = conn.execute_script(
+            "INSERT INTO SCRIPT_TEST (A) VALUES ('c');\n\
+             INSERT INTO NOT_EXISTS (A) VALUES (1);",
+        );
+        match res {
+            Err(e @ ScriptError {
+                index: Some(1),
+                error: FbError::Sql { .. },
+            }) => {
+                assert!(e.to_string().starts_with("statement 2:"));
+                assert!(FbError::from(e).to_string().contains("statement 2:"));
+            }
+            res => panic!("Unexpected result: {:?}", res),
+        }
+
+        // Rolled back
+        let rows: Vec<(String,)> = conn.query("SELECT A FROM SCRIPT_TEST", ())?;
+        assert_eq!(2, rows.len());
+
+        // The ddl is committed with the changes before it, so only the
+        // changes after it are rolled back
+        conn.execute("DROP TABLE SCRIPT_TEST_DDL", ()).ok();
+        let res = conn.execute_script(
+            "INSERT INTO SCRIPT_TEST (A) VALUES ('c');\n\
+             CREATE TABLE SCRIPT_TEST_DDL (B INT);\n\
+             INSERT INTO SCRIPT_TEST_DDL (B) VALUES (1);\n\
+             INSERT INTO SCRIPT_TEST (A) VALUES ('d');\n\
+             INSERT INTO NOT_EXISTS (A) VALUES (1);",
+        );
+        assert!(matches!(res, Err(ScriptError { index: Some(4), .. })));
+
+        let rows: Vec<(String,)> = conn.query("SELECT A FROM SCRIPT_TEST ORDER BY A", ())?;
+        assert_eq!(3, rows.len());
+        assert_eq!("c", rows[2].0);
+        let rows: Vec<(i32,)> = conn.query("SELECT B FROM SCRIPT_TEST_DDL", ())?;
+        assert!(rows.is_empty());
+
+        conn.execute("DROP TABLE SCRIPT_TEST_DDL", ())?;
+
+        Ok(())
+    }
+
     #[test]
     fn query_iter() -> Result<(), FbError> {
         let mut conn = cbuilder().connect()?;
@@ -442,4 +1766,278 @@ mk_tests_default! {
 
         Ok(())
     }
+
+    #[test]
+    fn query_rows() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        let rows = conn
+            .query_rows(
+                "SELECT -3 AS A, 'abc' AS B, CAST(NULL AS INT) AS C FROM RDB$DATABASE",
+                (),
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        assert_eq!(1, rows.len());
+
+        let row = &rows[0];
+        assert_eq!(vec!["A", "B", "C"], row.column_names());
+        assert_eq!(-3, row.get::<i32>("A")?);
+        assert_eq!("abc", row.get::<String>(1)?);
+        assert!(row.cols[2].value.is_null());
+
+        Ok(())
+    }
+
+    #[test]
+    fn cancel_running_query() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        conn.execute("DROP SEQUENCE CANCEL_GEN", ()).ok();
+        conn.execute("CREATE SEQUENCE CANCEL_GEN", ())?;
+
+        let cancel = conn.cancel_handle()?;
+
+        let canceller = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(500));
+            cancel.cancel()
+        });
+
+        let res: Result<Option<(i64,)>, _> = conn.query_first(
+            "SELECT MAX(GEN_ID(CANCEL_GEN, 1)) FROM RDB$TYPES A, RDB$TYPES B, RDB$TYPES C",
+            (),
+        );
+
+        canceller.join().unwrap()?;
+
+        assert!(matches!(res, Err(FbError::Cancelled { .. })));
+
+        Ok(())
+    }
+
+    #[test]
+    fn context_variables() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        let ns = ContextNamespace::UserSession;
+        assert_eq!(None, conn.get_context(ns, "RSFB_CTX")?);
+
+        assert!(!conn.set_context(ns, "RSFB_CTX", Some("value 1"))?);
+        assert!(conn.set_context(ns, "RSFB_CTX", Some("value 2"))?);
+        assert_eq!(Some("value 2".to_string()), conn.get_context(ns, "RSFB_CTX")?);
+
+        // Read by the statements
+        let (value,): (String,) = conn
+            .query_first(
+                "select rdb$get_context('USER_SESSION', 'RSFB_CTX') from rdb$database",
+                (),
+            )?
+            .unwrap();
+        assert_eq!("value 2", value);
+
+        // Removed
+        assert!(conn.set_context(ns, "RSFB_CTX", None)?);
+        assert_eq!(None, conn.get_context(ns, "RSFB_CTX")?);
+
+        // Only visible in the transaction
+        let ns = ContextNamespace::UserTransaction;
+        conn.set_context(ns, "RSFB_CTX", Some("in tr"))?;
+        assert_eq!(Some("in tr".to_string()), conn.get_context(ns, "RSFB_CTX")?);
+
+        let sql = "select rdb$get_context('USER_TRANSACTION', 'RSFB_CTX') from rdb$database";
+        let mut other = conn.detached_transaction(TrIsolationLevel::ReadCommited.into())?;
+        let value: Option<(Option<String>,)> =
+            other.with_connection(&mut conn, |tr| tr.query_first(sql, ()))?;
+        other.rollback(&mut conn)?;
+        assert_eq!(Some((None,)), value);
+
+        let db_name = conn.get_context(ContextNamespace::System, "DB_NAME")?;
+        assert!(db_name.is_some());
+        assert!(conn
+            .set_context(ContextNamespace::System, "DB_NAME", Some("other"))
+            .is_err());
+
+        let long = "a".repeat(81);
+        assert!(conn.get_context(ns, "").is_err());
+        assert!(conn.get_context(ns, &long).is_err());
+        assert!(conn.set_context(ns, "RSFB_CTX", Some(&long.repeat(4))).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn open_cursor_count() -> Result<(), FbError> {
+        let mut conn = cbuilder().with_open_cursors_warning(1).connect()?;
+        assert_eq!(0, conn.open_cursor_count());
+
+        {
+            let mut rows = conn.query_iter::<_, (i32,)>("select 1 from rdb$database", ())?;
+            rows.next().transpose()?;
+        }
+        assert_eq!(0, conn.open_cursor_count());
+
+        conn.with_transaction(|tr| {
+            let mut stmt = Statement::prepare(tr, "select 1 from rdb$database", false)?;
+            let mut rows = stmt.query::<(i32,), _>(())?;
+            rows.fetch()?;
+            assert_eq!(1, rows.transaction().conn.open_cursor_count());
+
+            Ok(())
+        })?;
+        assert_eq!(0, conn.open_cursor_count());
+
+        // Executions without cursors
+        conn.execute("select 1 from rdb$database", ())?;
+        let _: Option<(i32,)> = conn.query_first("select 1 from rdb$database", ())?;
+        assert_eq!(0, conn.open_cursor_count());
+
+        Ok(())
+    }
+
+    #[test]
+    fn call_procedure() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        conn.execute(
+            "create or alter procedure call_range(n int)
+                returns (i int)
+                as
+                begin
+                    i = 1;
+                    while (i <= n) do
+                    begin
+                        suspend;
+                        i = i + 1;
+                    end
+                end",
+            (),
+        )?;
+        conn.execute(
+            "create or alter procedure call_sum(a int, b int)
+                returns (total int)
+                as
+                begin
+                    total = a + b;
+                end",
+            (),
+        )?;
+        conn.execute(
+            "create or alter procedure call_nothing
+                as
+                begin
+                end",
+            (),
+        )?;
+
+        let rows: Vec<(i32,)> = conn
+            .call_procedure("call_range", (3,))?
+            .collect::<Result<_, _>>()?;
+        assert_eq!(vec![(1,), (2,), (3,)], rows);
+
+        let rows: Vec<(i32,)> = conn
+            .call_procedure("CALL_SUM", (2, 3))?
+            .collect::<Result<_, _>>()?;
+        assert_eq!(vec![(5,)], rows);
+
+        let rows: Vec<()> = conn
+            .call_procedure("call_nothing", ())?
+            .collect::<Result<_, _>>()?;
+        assert!(rows.is_empty());
+
+        assert!(conn.call_procedure::<_, (i32,)>("call_missing", ()).is_err());
+
+        if conn.server_engine()? >= EngineVersion::V3 {
+            // Selectable, unlike the standalone one with the same name
+            conn.execute(
+                "create or alter package call_pkg
+                    as
+                    begin
+                        procedure call_sum(a int, b int) returns (total int);
+                    end",
+                (),
+            )?;
+            conn.execute(
+                "recreate package body call_pkg
+                    as
+                    begin
+                        procedure call_sum(a int, b int) returns (total int)
+                        as
+                        begin
+                            total = a - b;
+                            suspend;
+                        end
+                    end",
+                (),
+            )?;
+
+            let rows: Vec<(i32,)> = conn
+                .call_procedure("call_sum", (2, 3))?
+                .collect::<Result<_, _>>()?;
+            assert_eq!(vec![(5,)], rows);
+        }
+        assert!(conn.call_procedure::<_, (i32,)>("call_sum; drop table x", ()).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn sequence_values() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        conn.execute("DROP SEQUENCE NEXT_VAL_GEN", ()).ok();
+        conn.execute("CREATE SEQUENCE NEXT_VAL_GEN", ())?;
+
+        assert_eq!(1, conn.next_value("NEXT_VAL_GEN")?);
+        assert_eq!(2, conn.next_value("next_val_gen")?);
+        assert_eq!(12, conn.gen_id("NEXT_VAL_GEN", 10)?);
+        assert_eq!(12, conn.gen_id("NEXT_VAL_GEN", 0)?);
+
+        conn.execute("DROP SEQUENCE \"Next val gen\"", ()).ok();
+        conn.execute("CREATE SEQUENCE \"Next val gen\"", ())?;
+        assert_eq!(1, conn.next_value("Next val gen")?);
+
+        assert!(conn.next_value("NOT_EXISTS_GEN").is_err());
+
+        for invalid in &[
+            "",
+            "1GEN",
+            "NEXT_VAL_GEN\0",
+            "NEXT_VAL_GEN FROM RDB$DATABASE; --",
+            "GEN_ID(NEXT_VAL_GEN, 1)",
+            "\"NEXT_VAL_GEN\"",
+            "\"A\" FROM RDB$DATABASE --\"",
+        ] {
+            assert!(conn.next_value(invalid).is_err());
+            assert!(conn.gen_id(invalid, 1).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn statement_dialect() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        conn.execute("DROP TABLE STMT_DIALECT", ()).ok();
+        conn.execute("CREATE TABLE STMT_DIALECT (ID INT, NAME VARCHAR(10))", ())?;
+
+        // In the dialect 1 the double quotes delimit strings, not identifiers
+        let sql = "INSERT INTO STMT_DIALECT (ID, NAME) VALUES (?, \"abc\")";
+        assert!(conn.execute(sql, (1,)).is_err());
+        assert_eq!(1, conn.execute_with_dialect(sql, (1,), Dialect::D1)?);
+
+        let sql = "SELECT NAME FROM STMT_DIALECT WHERE NAME = \"abc\"";
+        assert!(conn.query::<_, (String,)>(sql, ()).is_err());
+        let rows: Vec<(String,)> = conn.query_with_dialect(sql, (), Dialect::D1)?;
+        assert_eq!(vec![("abc".to_string(),)], rows);
+
+        // The integer division returns a floating point in the dialect 1
+        let sql = "SELECT 1 / 2 FROM RDB$DATABASE";
+        let (v,): (f64,) = conn.query_with_dialect(sql, (), Dialect::D1)?.remove(0);
+        assert_eq!(0.5, v);
+        let (v,): (i64,) = conn.query_with_dialect(sql, (), Dialect::D3)?.remove(0);
+        assert_eq!(0, v);
+
+        Ok(())
+    }
 }
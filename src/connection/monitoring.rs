@@ -0,0 +1,134 @@
+//! Monitoring tables (`MON$`) queries
+
+use chrono::NaiveDateTime;
+
+use crate::{FbError, Queryable};
+
+/// Attachment with the database, from the `MON$ATTACHMENTS` monitoring table
+#[derive(Debug, Clone)]
+pub struct AttachmentInfo {
+    /// Id of the attachment, the same returned by `CURRENT_CONNECTION`
+    pub id: i64,
+    /// User connected
+    pub user: String,
+    /// Role used in the attachment, `NONE` if not set
+    pub role: String,
+    /// Network protocol, like `TCPv4`. `None` for the embedded attachments
+    pub remote_protocol: Option<String>,
+    /// Address of the client
+    pub remote_address: Option<String>,
+    /// Path of the client executable, if sent by the client
+    pub remote_process: Option<String>,
+    /// Running a statement right now
+    pub active: bool,
+    /// When the attachment was made, in the server time zone
+    pub timestamp: NaiveDateTime,
+}
+
+/// Statement running in the database, from the `MON$STATEMENTS` monitoring table
+#[derive(Debug, Clone)]
+pub struct StatementInfo {
+    /// Id of the statement
+    pub id: i64,
+    /// Id of the attachment running the statement
+    pub attachment_id: i64,
+    /// Id of the transaction running the statement
+    pub transaction_id: i64,
+    /// When the execution started, in the server time zone
+    pub timestamp: NaiveDateTime,
+    /// Sql text of the statement, if available
+    pub sql: Option<String>,
+}
+
+/// Type of the `MON$ATTACHMENTS` row
+type AttachmentRow = (
+    i64,
+    String,
+    String,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    i16,
+    NaiveDateTime,
+);
+
+/// Query the attachments with the database
+pub fn attachments<Q: Queryable>(
+    conn: &mut Q,
+    include_current: bool,
+) -> Result<Vec<AttachmentInfo>, FbError> {
+    let sql = format!(
+        "SELECT MON$ATTACHMENT_ID, TRIM(MON$USER), TRIM(MON$ROLE), TRIM(MON$REMOTE_PROTOCOL),
+                TRIM(MON$REMOTE_ADDRESS), TRIM(MON$REMOTE_PROCESS), MON$STATE,
+                CAST(MON$TIMESTAMP AS TIMESTAMP)
+         FROM MON$ATTACHMENTS {}
+         ORDER BY MON$ATTACHMENT_ID",
+        current_filter(include_current, "WHERE")
+    );
+
+    let rows: Vec<AttachmentRow> = conn.query(&sql, ())?;
+
+    Ok(rows
+        .into_iter()
+        .map(
+            |(
+                id,
+                user,
+                role,
+                remote_protocol,
+                remote_address,
+                remote_process,
+                state,
+                timestamp,
+            )| AttachmentInfo {
+                id,
+                user,
+                role,
+                remote_protocol,
+                remote_address,
+                remote_process,
+                active: state == 1,
+                timestamp,
+            },
+        )
+        .collect())
+}
+
+/// Query the statements running in the database
+pub fn running_statements<Q: Queryable>(
+    conn: &mut Q,
+    include_current: bool,
+) -> Result<Vec<StatementInfo>, FbError> {
+    let sql = format!(
+        "SELECT MON$STATEMENT_ID, MON$ATTACHMENT_ID, MON$TRANSACTION_ID,
+                CAST(MON$TIMESTAMP AS TIMESTAMP), MON$SQL_TEXT
+         FROM MON$STATEMENTS
+         WHERE MON$STATE = 1 {}
+         ORDER BY MON$TIMESTAMP",
+        current_filter(include_current, "AND")
+    );
+
+    let rows: Vec<(i64, i64, i64, NaiveDateTime, Option<String>)> = conn.query(&sql, ())?;
+
+    Ok(rows
+        .into_iter()
+        .map(
+            |(id, attachment_id, transaction_id, timestamp, sql)| StatementInfo {
+                id,
+                attachment_id,
+                transaction_id,
+                timestamp,
+                sql,
+            },
+        )
+        .collect())
+}
+
+/// Condition excluding the current attachment
+fn current_filter(include_current: bool, keyword: &str) -> String {
+    if include_current {
+        String::new()
+    } else {
+        format!("{} MON$ATTACHMENT_ID <> CURRENT_CONNECTION", keyword)
+    }
+}
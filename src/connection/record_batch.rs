@@ -0,0 +1,370 @@
+//! Conversion of the columnar results to `arrow` record batches
+
+use arrow::{
+    array::{
+        ArrayRef, BinaryArray, BooleanArray, Date32Array, Decimal128Array, Float64Array,
+        Int16Array, Int32Array, Int64Array, NullArray, StringArray, Time64MicrosecondArray,
+        TimestampMicrosecondArray,
+    },
+    buffer::{BooleanBuffer, Buffer, NullBuffer},
+    datatypes::{DataType, Field, Schema, TimeUnit},
+    record_batch::{RecordBatch, RecordBatchOptions},
+};
+use chrono::{NaiveDateTime, Timelike};
+use rsfbclient_core::{ibase, ColumnMeta, FbError};
+use std::sync::Arc;
+
+use super::columnar::{ColumnarData, ColumnarResult, ColumnarValues};
+
+/// Time zone of the `TIMESTAMP WITH TIME ZONE` arrays, as the instants are kept in utc
+const UTC: &str = "+00:00";
+
+/// Precision of the exact numerics stored in a `BIGINT` and in a `INT128`
+const INT64_PRECISION: u8 = 18;
+const INT128_PRECISION: u8 = 38;
+
+const SECS_PER_DAY: i64 = 24 * 60 * 60;
+
+impl ColumnarResult {
+    /// Convert the result to an `arrow` record batch, with a field for each column.
+    /// Requires the `arrow` feature.
+    ///
+    /// The types are mapped from the values returned by the server:
+    ///
+    /// | Firebird                                  | Arrow                            |
+    /// |-------------------------------------------|----------------------------------|
+    /// | `SMALLINT`, `INTEGER`, `BIGINT`           | `Int16`, `Int32`, `Int64`        |
+    /// | `NUMERIC`, `DECIMAL`, `INT128`            | `Decimal128`, with the scale     |
+    /// | `FLOAT`, `DOUBLE PRECISION`               | `Float64`                        |
+    /// | `CHAR`, `VARCHAR`, text `BLOB`            | `Utf8`                           |
+    /// | `CHARACTER SET OCTETS`, binary `BLOB`     | `Binary`                         |
+    /// | `BOOLEAN`                                 | `Boolean`                        |
+    /// | `DATE`                                    | `Date32`                         |
+    /// | `TIME`                                    | `Time64(Microsecond)`            |
+    /// | `TIMESTAMP`                               | `Timestamp(Microsecond)`         |
+    /// | `TIMESTAMP WITH TIME ZONE`                | `Timestamp(Microsecond, +00:00)` |
+    ///
+    /// The `NULL`s are kept in the null buffers. The columns with only `NULL` values are
+    /// typed by their description, and the types without a mapping have the `Null` type.
+    /// The time zones of the `TIMESTAMP WITH TIME ZONE` values are lost, as the arrow
+    /// arrays have a single time zone, so the instants are kept in utc
+    pub fn into_record_batch(self) -> Result<RecordBatch, FbError> {
+        let mut fields = Vec::with_capacity(self.columns.len());
+        let mut arrays = Vec::with_capacity(self.columns.len());
+
+        for col in self.columns {
+            let name = col.name.clone();
+            let array = array(col).map_err(|e| format!("Column {}: {}", name, e))?;
+
+            fields.push(Field::new(name, array.data_type().clone(), true));
+            arrays.push(array);
+        }
+
+        RecordBatch::try_new_with_options(
+            Arc::new(Schema::new(fields)),
+            arrays,
+            &RecordBatchOptions::new().with_row_count(Some(self.rows)),
+        )
+        .map_err(|e| e.to_string().into())
+    }
+}
+
+/// Convert the column values to an arrow array of the mapped type
+fn array(col: ColumnarData) -> Result<ArrayRef, FbError> {
+    let len = col.values.len();
+    let nulls = if col.null_count() > 0 {
+        let bits = BooleanBuffer::new(Buffer::from_vec(col.validity), 0, len);
+        Some(NullBuffer::new(bits))
+    } else {
+        None
+    };
+    let meta = col.meta;
+
+    let array: ArrayRef = match col.values {
+        ColumnarValues::Null(len) => return null_array(&meta, len),
+
+        ColumnarValues::Integer(values) => match meta.sql_type {
+            ibase::SQL_SHORT => Arc::new(Int16Array::new(narrow(values)?.into(), nulls)),
+            ibase::SQL_LONG => Arc::new(Int32Array::new(narrow(values)?.into(), nulls)),
+            _ => Arc::new(Int64Array::new(values.into(), nulls)),
+        },
+
+        ColumnarValues::Int128(values) => decimal_array(values, nulls, INT128_PRECISION, 0)?,
+
+        ColumnarValues::Numeric { values, scale } => {
+            decimal_array(values, nulls, precision(&meta), scale)?
+        }
+
+        ColumnarValues::Floating(values) => Arc::new(Float64Array::new(values.into(), nulls)),
+
+        ColumnarValues::Text(values) => {
+            Arc::new(StringArray::from_iter(with_nulls(values, nulls.as_ref())))
+        }
+
+        ColumnarValues::Binary(values) => {
+            Arc::new(BinaryArray::from_iter(with_nulls(values, nulls.as_ref())))
+        }
+
+        ColumnarValues::Boolean(values) => Arc::new(BooleanArray::new(values.into(), nulls)),
+
+        ColumnarValues::Timestamp(values) => match meta.sql_type {
+            ibase::SQL_TYPE_DATE => {
+                let days = values
+                    .iter()
+                    .map(|ts| ts.and_utc().timestamp().div_euclid(SECS_PER_DAY) as i32)
+                    .collect::<Vec<_>>();
+
+                Arc::new(Date32Array::new(days.into(), nulls))
+            }
+
+            ibase::SQL_TYPE_TIME => {
+                let micros = values
+                    .iter()
+                    .map(|ts| {
+                        ts.num_seconds_from_midnight() as i64 * 1_000_000
+                            + ts.nanosecond() as i64 / 1_000
+                    })
+                    .collect::<Vec<_>>();
+
+                Arc::new(Time64MicrosecondArray::new(micros.into(), nulls))
+            }
+
+            _ => {
+                let micros = values.iter().map(timestamp_micros).collect::<Vec<_>>();
+
+                Arc::new(TimestampMicrosecondArray::new(micros.into(), nulls))
+            }
+        },
+
+        ColumnarValues::TimestampTz(values) => {
+            let micros = values
+                .iter()
+                .map(|ts| ts.timestamp_micros())
+                .collect::<Vec<_>>();
+
+            Arc::new(TimestampMicrosecondArray::new(micros.into(), nulls).with_timezone(UTC))
+        }
+    };
+
+    Ok(array)
+}
+
+/// Array of the column with only `NULL` values, typed by the column description
+fn null_array(meta: &ColumnMeta, len: usize) -> Result<ArrayRef, FbError> {
+    let data_type = match meta.sql_type {
+        ibase::SQL_SHORT | ibase::SQL_LONG | ibase::SQL_INT64 | ibase::SQL_INT128
+            if meta.scale != 0 =>
+        {
+            DataType::Decimal128(precision(meta), decimal_scale(meta.scale)?)
+        }
+        ibase::SQL_SHORT => DataType::Int16,
+        ibase::SQL_LONG => DataType::Int32,
+        ibase::SQL_INT64 => DataType::Int64,
+        ibase::SQL_INT128 => DataType::Decimal128(INT128_PRECISION, 0),
+        ibase::SQL_FLOAT | ibase::SQL_DOUBLE | ibase::SQL_D_FLOAT => DataType::Float64,
+        // The charset id 1 is the `OCTETS`
+        ibase::SQL_TEXT | ibase::SQL_VARYING if meta.sub_type == 1 => DataType::Binary,
+        ibase::SQL_TEXT | ibase::SQL_VARYING => DataType::Utf8,
+        ibase::SQL_BLOB if meta.sub_type == 1 => DataType::Utf8,
+        ibase::SQL_BLOB => DataType::Binary,
+        ibase::SQL_BOOLEAN => DataType::Boolean,
+        ibase::SQL_TYPE_DATE => DataType::Date32,
+        ibase::SQL_TYPE_TIME => DataType::Time64(TimeUnit::Microsecond),
+        ibase::SQL_TIMESTAMP => DataType::Timestamp(TimeUnit::Microsecond, None),
+        ibase::SQL_TIMESTAMP_TZ | ibase::SQL_TIMESTAMP_TZ_EX => {
+            DataType::Timestamp(TimeUnit::Microsecond, Some(UTC.into()))
+        }
+        _ => return Ok(Arc::new(NullArray::new(len))),
+    };
+
+    Ok(arrow::array::new_null_array(&data_type, len))
+}
+
+/// Decimal array of the scaled integers
+fn decimal_array(
+    values: Vec<i128>,
+    nulls: Option<NullBuffer>,
+    precision: u8,
+    scale: i16,
+) -> Result<ArrayRef, FbError> {
+    let array = Decimal128Array::new(values.into(), nulls)
+        .with_precision_and_scale(precision, decimal_scale(scale)?)
+        .map_err(|e| FbError::from(e.to_string()))?;
+
+    Ok(Arc::new(array))
+}
+
+/// Precision of the exact numerics of the column
+fn precision(meta: &ColumnMeta) -> u8 {
+    if meta.sql_type == ibase::SQL_INT128 {
+        INT128_PRECISION
+    } else {
+        INT64_PRECISION
+    }
+}
+
+/// Arrow decimal scale, the digits after the point, of the firebird scale
+fn decimal_scale(scale: i16) -> Result<i8, FbError> {
+    i8::try_from(-scale).map_err(|_| format!("Invalid numeric scale: {}", scale).into())
+}
+
+/// Convert the integers to a smaller type
+fn narrow<T: TryFrom<i64>>(values: Vec<i64>) -> Result<Vec<T>, FbError> {
+    values
+        .into_iter()
+        .map(|i| T::try_from(i).map_err(|_| format!("Integer out of range: {}", i).into()))
+        .collect()
+}
+
+/// Replace the values marked as `NULL` with `None`
+fn with_nulls<'n, T: 'n>(
+    values: Vec<T>,
+    nulls: Option<&'n NullBuffer>,
+) -> impl Iterator<Item = Option<T>> + 'n {
+    values
+        .into_iter()
+        .enumerate()
+        .map(move |(i, v)| match nulls {
+            Some(nulls) if nulls.is_null(i) => None,
+            _ => Some(v),
+        })
+}
+
+/// Microseconds since the unix epoch
+fn timestamp_micros(ts: &NaiveDateTime) -> i64 {
+    ts.and_utc().timestamp_micros()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use arrow::array::{Array, Int64Array, TimestampMicrosecondArray};
+    use chrono::{DateTime, NaiveDate};
+
+    fn meta(alias: &str, sql_type: u32, scale: i16) -> ColumnMeta {
+        ColumnMeta {
+            name: alias.to_string(),
+            alias: alias.to_string(),
+            relation: String::new(),
+            sql_type,
+            sub_type: 0,
+            length: 8,
+            scale,
+            nullable: true,
+        }
+    }
+
+    #[test]
+    fn convert_columns() -> Result<(), FbError> {
+        let ts = NaiveDate::from_ymd_opt(2021, 3, 4)
+            .unwrap()
+            .and_hms_opt(10, 20, 30)
+            .unwrap();
+
+        let res = ColumnarResult {
+            columns: vec![
+                ColumnarData {
+                    name: "A".to_string(),
+                    meta: meta("A", ibase::SQL_INT64, 0),
+                    values: ColumnarValues::Integer(vec![1, 0, 3]),
+                    validity: vec![0b101],
+                },
+                ColumnarData {
+                    name: "B".to_string(),
+                    meta: meta("B", ibase::SQL_TIMESTAMP, 0),
+                    values: ColumnarValues::Timestamp(vec![ts; 3]),
+                    validity: vec![0b111],
+                },
+                ColumnarData {
+                    name: "C".to_string(),
+                    meta: meta("C", ibase::SQL_TYPE_DATE, 0),
+                    values: ColumnarValues::Timestamp(vec![ts; 3]),
+                    validity: vec![0b111],
+                },
+                ColumnarData {
+                    name: "D".to_string(),
+                    meta: meta("D", ibase::SQL_LONG, -2),
+                    values: ColumnarValues::Null(3),
+                    validity: vec![0],
+                },
+                ColumnarData {
+                    name: "E".to_string(),
+                    meta: meta("E", ibase::SQL_NULL, 0),
+                    values: ColumnarValues::Null(3),
+                    validity: vec![0],
+                },
+            ],
+            rows: 3,
+        };
+
+        let batch = res.into_record_batch()?;
+        assert_eq!(3, batch.num_rows());
+
+        let a = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(vec![Some(1), None, Some(3)], a.iter().collect::<Vec<_>>());
+
+        let b = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<TimestampMicrosecondArray>()
+            .unwrap();
+        assert_eq!(Some(ts), b.value_as_datetime(0),);
+
+        let schema = batch.schema();
+        assert_eq!(&DataType::Date32, schema.field(2).data_type());
+        assert_eq!(
+            18_690,
+            batch
+                .column(2)
+                .as_any()
+                .downcast_ref::<Date32Array>()
+                .unwrap()
+                .value(0)
+        );
+        assert_eq!(&DataType::Decimal128(18, 2), schema.field(3).data_type());
+        assert_eq!(3, batch.column(3).null_count());
+        assert_eq!(&DataType::Null, schema.field(4).data_type());
+
+        let tz = ColumnarResult {
+            columns: vec![
+                ColumnarData {
+                    name: "A".to_string(),
+                    meta: meta("A", ibase::SQL_TIMESTAMP_TZ, 0),
+                    values: ColumnarValues::TimestampTz(vec![DateTime::parse_from_rfc3339(
+                        "2021-03-04T10:20:30-03:00",
+                    )
+                    .unwrap()]),
+                    validity: vec![1],
+                },
+                ColumnarData {
+                    name: "B".to_string(),
+                    meta: meta("B", ibase::SQL_TIMESTAMP_TZ, 0),
+                    values: ColumnarValues::Null(1),
+                    validity: vec![0],
+                },
+            ],
+            rows: 1,
+        }
+        .into_record_batch()?;
+
+        // Same time zone with only `NULL`s
+        let schema = tz.schema();
+        assert_eq!(schema.field(0).data_type(), schema.field(1).data_type());
+
+        let a = tz
+            .column(0)
+            .as_any()
+            .downcast_ref::<TimestampMicrosecondArray>()
+            .unwrap();
+        assert_eq!(
+            Some(ts + chrono::Duration::hours(3)),
+            a.value_as_datetime(0)
+        );
+
+        Ok(())
+    }
+}
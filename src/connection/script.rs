@@ -0,0 +1,366 @@
+//! Sql script splitter
+
+use crate::{FbError, Row, Statement, Transaction};
+use rsfbclient_core::{FirebirdClient, StmtType};
+use std::fmt;
+
+/// Result of a statement of a script
+#[derive(Debug, Clone)]
+pub enum StatementOutcome {
+    /// Number of rows changed by the statement. Zero for the
+    /// statements that don't change rows, like the ddl
+    RowCount(usize),
+
+    /// Rows returned by the statement, like a select
+    Rows(Vec<Row>),
+}
+
+/// Failure of a script execution
+#[derive(Debug)]
+pub struct ScriptError {
+    /// Index of the failed statement in the script, starting from 0. `None` when the
+    /// failure is not of a statement, like an unterminated string in the script
+    pub index: Option<usize>,
+
+    /// Error returned by the statement
+    pub error: FbError,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.index {
+            Some(index) => write!(f, "statement {}: {}", index + 1, self.error),
+            None => self.error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ScriptError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<FbError> for ScriptError {
+    fn from(error: FbError) -> Self {
+        Self { index: None, error }
+    }
+}
+
+/// Keeps the number of the failed statement in the start of the error message
+impl From<ScriptError> for FbError {
+    fn from(e: ScriptError) -> Self {
+        match e.index {
+            Some(index) => error_context(&format!("statement {}", index + 1), e.error),
+            None => e.error,
+        }
+    }
+}
+
+/// Split a script in its statements.
+///
+/// The statements are separated by the terminator, `;` by default, that can be changed
+/// with `SET TERM <new terminator> <current terminator>`, like in isql. Terminators inside
+/// of comments, string literals, delimited identifiers and `Q'...'` strings are ignored
+pub fn split(script: &str) -> Result<Vec<&str>, FbError> {
+    let mut stmts = vec![];
+    let mut term = ";";
+
+    // Start of the current statement
+    let mut start = None;
+    let mut pos = 0;
+
+    while let Some(ch) = script[pos..].chars().next() {
+        let rest = &script[pos..];
+
+        if rest.starts_with("--") {
+            pos += rest.find('\n').map(|i| i + 1).unwrap_or(rest.len());
+            continue;
+        }
+
+        if let Some(comment) = rest.strip_prefix("/*") {
+            let end = comment
+                .find("*/")
+                .ok_or("Unterminated comment in the script")?;
+            pos += end + 4;
+            continue;
+        }
+
+        if start.is_none() && ch.is_whitespace() {
+            pos += ch.len_utf8();
+            continue;
+        }
+
+        if rest.starts_with(term) {
+            if let Some(start) = start.take() {
+                push_stmt(&mut stmts, &mut term, &script[start..pos])?;
+            }
+            pos += term.len();
+            continue;
+        }
+
+        if start.is_none() {
+            start = Some(pos);
+        }
+
+        pos += match ch {
+            '\'' | '"' => quoted_len(rest, ch)?,
+            'q' | 'Q' if rest[1..].starts_with('\'') && !ident_before(script, pos) => {
+                q_string_len(rest)?
+            }
+            _ => ch.len_utf8(),
+        };
+    }
+
+    if let Some(start) = start {
+        push_stmt(&mut stmts, &mut term, &script[start..])?;
+    }
+
+    Ok(stmts)
+}
+
+/// Add the statement to the list, or change the terminator if it is a `SET TERM`
+fn push_stmt<'s>(
+    stmts: &mut Vec<&'s str>,
+    term: &mut &'s str,
+    stmt: &'s str,
+) -> Result<(), FbError> {
+    let stmt = stmt.trim();
+
+    let mut words = stmt.split_whitespace();
+    let is_set_term = matches!(
+        (words.next(), words.next()),
+        (Some(set), Some(t)) if set.eq_ignore_ascii_case("SET") && t.eq_ignore_ascii_case("TERM")
+    );
+
+    if is_set_term {
+        match (words.next(), words.next()) {
+            (Some(new_term), None) => *term = new_term,
+            _ => return Err(format!("Invalid terminator change: {}", stmt).into()),
+        }
+    } else if !stmt.is_empty() {
+        stmts.push(stmt);
+    }
+
+    Ok(())
+}
+
+/// Length of a string literal or delimited identifier, with the quotes
+/// inside escaped by doubling them
+pub fn quoted_len(rest: &str, quote: char) -> Result<usize, FbError> {
+    let mut chars = rest.char_indices().skip(1).peekable();
+
+    while let Some((i, ch)) = chars.next() {
+        if ch == quote {
+            if matches!(chars.peek(), Some((_, next)) if *next == quote) {
+                chars.next();
+            } else {
+                return Ok(i + 1);
+            }
+        }
+    }
+
+    Err(format!("Unterminated {} in the script", quote_name(quote)).into())
+}
+
+fn quote_name(quote: char) -> &'static str {
+    if quote == '"' {
+        "delimited identifier"
+    } else {
+        "string literal"
+    }
+}
+
+/// Length of a `Q'<delimiter>...<delimiter>'` string literal
+pub fn q_string_len(rest: &str) -> Result<usize, FbError> {
+    let open = rest[2..]
+        .chars()
+        .next()
+        .ok_or("Unterminated string literal in the script")?;
+
+    let close = match open {
+        '(' => ')',
+        '[' => ']',
+        '{' => '}',
+        '<' => '>',
+        c => c,
+    };
+
+    let body = 2 + open.len_utf8();
+    let mut end = String::with_capacity(2);
+    end.push(close);
+    end.push('\'');
+
+    rest[body..]
+        .find(&end)
+        .map(|i| body + i + end.len())
+        .ok_or_else(|| "Unterminated string literal in the script".into())
+}
+
+/// True if the position is in the middle of an identifier
+pub fn ident_before(script: &str, pos: usize) -> bool {
+    script[..pos]
+        .chars()
+        .next_back()
+        .map(|c| c.is_alphanumeric() || c == '_' || c == '$')
+        .unwrap_or(false)
+}
+
+/// Execute a statement of the script, returning the rows if it has columns.
+///
+/// With `autoddl`, the ddl statements are committed right after executed, along with
+/// the changes before them, like the `AUTODDL` of isql, so the next statements can use
+/// the objects created
+pub fn execute<C: FirebirdClient>(
+    tr: &mut Transaction<C>,
+    sql: &str,
+    autoddl: bool,
+) -> Result<StatementOutcome, FbError> {
+    let mut stmt = Statement::prepare(tr, sql, false)?;
+
+    if stmt.columns()?.is_empty() {
+        let is_ddl = stmt.stmt_type() == StmtType::Ddl;
+        let count = stmt.execute(())?;
+        drop(stmt);
+
+        if autoddl && is_ddl {
+            tr.commit_retaining()?;
+        }
+
+        return Ok(StatementOutcome::RowCount(count));
+    }
+
+    let rows = stmt.query(())?.collect::<Result<_, _>>()?;
+
+    Ok(StatementOutcome::Rows(rows))
+}
+
+/// Add the context, like the position of the failure, to the start of the error message
+pub fn error_context(context: &str, err: FbError) -> FbError {
+    let ctx = |msg: String| format!("{}: {}", context, msg);
+
+    match err {
+        FbError::Sql { msg, code, status } => FbError::Sql {
+            msg: ctx(msg),
+            code,
+            status,
+        },
+        FbError::Timeout { msg, status } => FbError::Timeout {
+            msg: ctx(msg),
+            status,
+        },
+        FbError::Cancelled { msg, status } => FbError::Cancelled {
+            msg: ctx(msg),
+            status,
+        },
+        FbError::ConnectionLost { msg, status } => FbError::ConnectionLost {
+            msg: ctx(msg),
+            status,
+        },
+        FbError::TransactionLost { msg } => FbError::TransactionLost { msg: ctx(msg) },
+        FbError::MetadataChanged { msg, status } => FbError::MetadataChanged {
+            msg: ctx(msg),
+            status,
+        },
+        FbError::Other(msg) => FbError::Other(ctx(msg)),
+        FbError::Io(e) => FbError::Io(std::io::Error::new(e.kind(), ctx(e.to_string()))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{split, ScriptError};
+    use crate::*;
+
+    #[test]
+    fn script_error() {
+        let e = ScriptError {
+            index: Some(2),
+            error: FbError::Sql {
+                msg: "Table unknown".to_string(),
+                code: -204,
+                status: vec![],
+            },
+        };
+        assert_eq!("statement 3: sql error -204: Table unknown", e.to_string());
+        match FbError::from(e) {
+            FbError::Sql { msg, code, .. } => {
+                assert_eq!("statement 3: Table unknown", msg);
+                assert_eq!(-204, code);
+            }
+            e => panic!("Unexpected error: {:?}", e),
+        }
+
+        let e = ScriptError::from(FbError::from("Unterminated comment in the script"));
+        assert_eq!(None, e.index);
+        assert_eq!("error: Unterminated comment in the script", e.to_string());
+    }
+
+    #[test]
+    fn simple_statements() -> Result<(), FbError> {
+        let stmts =
+            split("CREATE TABLE A (X INT);\n\nINSERT INTO A VALUES (1) ;;\n  SELECT * FROM A")?;
+
+        assert_eq!(
+            vec![
+                "CREATE TABLE A (X INT)",
+                "INSERT INTO A VALUES (1)",
+                "SELECT * FROM A"
+            ],
+            stmts
+        );
+
+        assert!(split("  ; \n -- only a comment; \n")?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn literals_and_comments() -> Result<(), FbError> {
+        let stmts = split(
+            "-- header; comment\n\
+             INSERT INTO A VALUES ('a;b', 'it''s;');\n\
+             /* block; comment */ SELECT \"Col;\" FROM A /* ; */;\n\
+             SELECT Q'{x;'y}' FROM RDB$DATABASE;\n\
+             SELECT 'é;' FROM RDB$DATABASE",
+        )?;
+
+        assert_eq!(
+            vec![
+                "INSERT INTO A VALUES ('a;b', 'it''s;')",
+                "SELECT \"Col;\" FROM A /* ; */",
+                "SELECT Q'{x;'y}' FROM RDB$DATABASE",
+                "SELECT 'é;' FROM RDB$DATABASE",
+            ],
+            stmts
+        );
+
+        assert!(split("SELECT 'abc FROM A;").is_err());
+        assert!(split("SELECT 1 /* FROM A;").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn set_term() -> Result<(), FbError> {
+        let stmts = split(
+            "SET TERM ^ ;\n\
+             CREATE PROCEDURE P AS\n\
+             BEGIN\n  EXIT;\nEND^\n\
+             set term ; ^\n\
+             EXECUTE PROCEDURE P;",
+        )?;
+
+        assert_eq!(
+            vec![
+                "CREATE PROCEDURE P AS\nBEGIN\n  EXIT;\nEND",
+                "EXECUTE PROCEDURE P",
+            ],
+            stmts
+        );
+
+        assert!(split("SET TERM;").is_err());
+
+        Ok(())
+    }
+}
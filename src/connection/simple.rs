@@ -3,7 +3,11 @@
 //! multiple connection types/variations.
 //!
 
-use crate::{Connection, Execute, FbError, FromRow, IntoParams, Queryable, SimpleTransaction};
+use crate::{
+    AttachmentInfo, ColumnarResult, Connection, ContextNamespace, CsvOptions, Dialect, Execute,
+    FbError, FromRow, IntoParams, Queryable, ScriptError, SimpleTransaction, StatementInfo,
+    StatementOutcome,
+};
 
 #[cfg(feature = "linking")]
 use rsfbclient_native::DynLink;
@@ -14,6 +18,7 @@ use rsfbclient_native::NativeFbClient;
 #[cfg(feature = "pure_rust")]
 use rsfbclient_rust::RustFbClient;
 use std::convert::{From, TryFrom};
+use std::io::{Read, Write};
 
 /// A connection API without client types
 pub struct SimpleConnection {
@@ -108,6 +113,331 @@ impl SimpleConnection {
         }
     }
 
+    /// Number of cursors open by the queries, closed when their iterators,
+    /// statements or cursors are dropped
+    pub fn open_cursor_count(&self) -> usize {
+        match &self.inner {
+            #[cfg(feature = "linking")]
+            TypeConnectionContainer::NativeDynLink(c) => c.open_cursor_count(),
+            #[cfg(feature = "dynamic_loading")]
+            TypeConnectionContainer::NativeDynLoad(c) => c.open_cursor_count(),
+            #[cfg(feature = "pure_rust")]
+            TypeConnectionContainer::PureRust(c) => c.open_cursor_count(),
+        }
+    }
+
+    /// Check if the connection is still alive, using a cheap database info
+    /// request that doesn't start a transaction.
+    ///
+    /// If the connection with the server was lost, returns a `FbError::ConnectionLost`
+    /// error and the connection should be discarded
+    pub fn ping(&mut self) -> Result<(), FbError> {
+        match &mut self.inner {
+            #[cfg(feature = "linking")]
+            TypeConnectionContainer::NativeDynLink(c) => c.ping(),
+            #[cfg(feature = "dynamic_loading")]
+            TypeConnectionContainer::NativeDynLoad(c) => c.ping(),
+            #[cfg(feature = "pure_rust")]
+            TypeConnectionContainer::PureRust(c) => c.ping(),
+        }
+    }
+
+    /// Query the database for the info items, returning the raw response buffer
+    pub fn db_info(&mut self, items: &[u8], buf_size: usize) -> Result<Vec<u8>, FbError> {
+        match &mut self.inner {
+            #[cfg(feature = "linking")]
+            TypeConnectionContainer::NativeDynLink(c) => c.db_info(items, buf_size),
+            #[cfg(feature = "dynamic_loading")]
+            TypeConnectionContainer::NativeDynLoad(c) => c.db_info(items, buf_size),
+            #[cfg(feature = "pure_rust")]
+            TypeConnectionContainer::PureRust(c) => c.db_info(items, buf_size),
+        }
+    }
+
+    /// Database page size in bytes
+    pub fn page_size(&mut self) -> Result<u32, FbError> {
+        match &mut self.inner {
+            #[cfg(feature = "linking")]
+            TypeConnectionContainer::NativeDynLink(c) => c.page_size(),
+            #[cfg(feature = "dynamic_loading")]
+            TypeConnectionContainer::NativeDynLoad(c) => c.page_size(),
+            #[cfg(feature = "pure_rust")]
+            TypeConnectionContainer::PureRust(c) => c.page_size(),
+        }
+    }
+
+    /// Number of pages in the database cache used by the attachment
+    pub fn num_buffers(&mut self) -> Result<u32, FbError> {
+        match &mut self.inner {
+            #[cfg(feature = "linking")]
+            TypeConnectionContainer::NativeDynLink(c) => c.num_buffers(),
+            #[cfg(feature = "dynamic_loading")]
+            TypeConnectionContainer::NativeDynLoad(c) => c.num_buffers(),
+            #[cfg(feature = "pure_rust")]
+            TypeConnectionContainer::PureRust(c) => c.num_buffers(),
+        }
+    }
+
+    /// True if the database is in the forced writes mode
+    pub fn forced_writes(&mut self) -> Result<bool, FbError> {
+        match &mut self.inner {
+            #[cfg(feature = "linking")]
+            TypeConnectionContainer::NativeDynLink(c) => c.forced_writes(),
+            #[cfg(feature = "dynamic_loading")]
+            TypeConnectionContainer::NativeDynLoad(c) => c.forced_writes(),
+            #[cfg(feature = "pure_rust")]
+            TypeConnectionContainer::PureRust(c) => c.forced_writes(),
+        }
+    }
+
+    /// Number of pages allocated for the database
+    pub fn page_count(&mut self) -> Result<u64, FbError> {
+        match &mut self.inner {
+            #[cfg(feature = "linking")]
+            TypeConnectionContainer::NativeDynLink(c) => c.page_count(),
+            #[cfg(feature = "dynamic_loading")]
+            TypeConnectionContainer::NativeDynLoad(c) => c.page_count(),
+            #[cfg(feature = "pure_rust")]
+            TypeConnectionContainer::PureRust(c) => c.page_count(),
+        }
+    }
+
+    /// Number of database pages allocated in the main database file
+    pub fn allocation(&mut self) -> Result<u64, FbError> {
+        match &mut self.inner {
+            #[cfg(feature = "linking")]
+            TypeConnectionContainer::NativeDynLink(c) => c.allocation(),
+            #[cfg(feature = "dynamic_loading")]
+            TypeConnectionContainer::NativeDynLoad(c) => c.allocation(),
+            #[cfg(feature = "pure_rust")]
+            TypeConnectionContainer::PureRust(c) => c.allocation(),
+        }
+    }
+
+    /// Amount of server memory in use, in bytes
+    pub fn current_memory(&mut self) -> Result<u64, FbError> {
+        match &mut self.inner {
+            #[cfg(feature = "linking")]
+            TypeConnectionContainer::NativeDynLink(c) => c.current_memory(),
+            #[cfg(feature = "dynamic_loading")]
+            TypeConnectionContainer::NativeDynLoad(c) => c.current_memory(),
+            #[cfg(feature = "pure_rust")]
+            TypeConnectionContainer::PureRust(c) => c.current_memory(),
+        }
+    }
+
+    /// Number of the oldest interesting transaction
+    pub fn oldest_transaction(&mut self) -> Result<u64, FbError> {
+        match &mut self.inner {
+            #[cfg(feature = "linking")]
+            TypeConnectionContainer::NativeDynLink(c) => c.oldest_transaction(),
+            #[cfg(feature = "dynamic_loading")]
+            TypeConnectionContainer::NativeDynLoad(c) => c.oldest_transaction(),
+            #[cfg(feature = "pure_rust")]
+            TypeConnectionContainer::PureRust(c) => c.oldest_transaction(),
+        }
+    }
+
+    /// Attachments with the database, from the `MON$ATTACHMENTS` monitoring table
+    pub fn active_attachments(
+        &mut self,
+        include_current: bool,
+    ) -> Result<Vec<AttachmentInfo>, FbError> {
+        match &mut self.inner {
+            #[cfg(feature = "linking")]
+            TypeConnectionContainer::NativeDynLink(c) => c.active_attachments(include_current),
+            #[cfg(feature = "dynamic_loading")]
+            TypeConnectionContainer::NativeDynLoad(c) => c.active_attachments(include_current),
+            #[cfg(feature = "pure_rust")]
+            TypeConnectionContainer::PureRust(c) => c.active_attachments(include_current),
+        }
+    }
+
+    /// Statements running in the database, from the `MON$STATEMENTS` monitoring table
+    pub fn active_statements(
+        &mut self,
+        include_current: bool,
+    ) -> Result<Vec<StatementInfo>, FbError> {
+        match &mut self.inner {
+            #[cfg(feature = "linking")]
+            TypeConnectionContainer::NativeDynLink(c) => c.active_statements(include_current),
+            #[cfg(feature = "dynamic_loading")]
+            TypeConnectionContainer::NativeDynLoad(c) => c.active_statements(include_current),
+            #[cfg(feature = "pure_rust")]
+            TypeConnectionContainer::PureRust(c) => c.active_statements(include_current),
+        }
+    }
+
+    /// Take the warnings returned by the server since the last call, like a string
+    /// truncation or the use of a deprecated syntax
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        match &mut self.inner {
+            #[cfg(feature = "linking")]
+            TypeConnectionContainer::NativeDynLink(c) => c.take_warnings(),
+            #[cfg(feature = "dynamic_loading")]
+            TypeConnectionContainer::NativeDynLoad(c) => c.take_warnings(),
+            #[cfg(feature = "pure_rust")]
+            TypeConnectionContainer::PureRust(c) => c.take_warnings(),
+        }
+    }
+
+    /// Run the query, writing the rows to the writer in the csv format, as they are fetched
+    pub fn export_csv<P, W>(
+        &mut self,
+        sql: &str,
+        params: P,
+        writer: &mut W,
+        options: &CsvOptions,
+    ) -> Result<usize, FbError>
+    where
+        P: IntoParams,
+        W: Write,
+    {
+        match &mut self.inner {
+            #[cfg(feature = "linking")]
+            TypeConnectionContainer::NativeDynLink(c) => c.export_csv(sql, params, writer, options),
+            #[cfg(feature = "dynamic_loading")]
+            TypeConnectionContainer::NativeDynLoad(c) => c.export_csv(sql, params, writer, options),
+            #[cfg(feature = "pure_rust")]
+            TypeConnectionContainer::PureRust(c) => c.export_csv(sql, params, writer, options),
+        }
+    }
+
+    /// Run the query, returning the values of each column in its own typed vector
+    pub fn query_columns<P>(&mut self, sql: &str, params: P) -> Result<ColumnarResult, FbError>
+    where
+        P: IntoParams,
+    {
+        match &mut self.inner {
+            #[cfg(feature = "linking")]
+            TypeConnectionContainer::NativeDynLink(c) => c.query_columns(sql, params),
+            #[cfg(feature = "dynamic_loading")]
+            TypeConnectionContainer::NativeDynLoad(c) => c.query_columns(sql, params),
+            #[cfg(feature = "pure_rust")]
+            TypeConnectionContainer::PureRust(c) => c.query_columns(sql, params),
+        }
+    }
+
+    /// Run the query, returning the rows in an `arrow` record batch
+    #[cfg(feature = "arrow")]
+    pub fn query_arrow<P>(
+        &mut self,
+        sql: &str,
+        params: P,
+    ) -> Result<arrow::record_batch::RecordBatch, FbError>
+    where
+        P: IntoParams,
+    {
+        match &mut self.inner {
+            #[cfg(feature = "linking")]
+            TypeConnectionContainer::NativeDynLink(c) => c.query_arrow(sql, params),
+            #[cfg(feature = "dynamic_loading")]
+            TypeConnectionContainer::NativeDynLoad(c) => c.query_arrow(sql, params),
+            #[cfg(feature = "pure_rust")]
+            TypeConnectionContainer::PureRust(c) => c.query_arrow(sql, params),
+        }
+    }
+
+    /// Insert the csv records in the table, in a single transaction
+    pub fn import_csv<R>(
+        &mut self,
+        table: &str,
+        reader: R,
+        options: &CsvOptions,
+    ) -> Result<usize, FbError>
+    where
+        R: Read,
+    {
+        match &mut self.inner {
+            #[cfg(feature = "linking")]
+            TypeConnectionContainer::NativeDynLink(c) => c.import_csv(table, reader, options),
+            #[cfg(feature = "dynamic_loading")]
+            TypeConnectionContainer::NativeDynLoad(c) => c.import_csv(table, reader, options),
+            #[cfg(feature = "pure_rust")]
+            TypeConnectionContainer::PureRust(c) => c.import_csv(table, reader, options),
+        }
+    }
+
+    /// Execute the statement without returning any row, parsing the sql with the dialect
+    /// instead of the one configured in the connection.
+    ///
+    /// See `Connection::execute_with_dialect` for the effects of the dialect
+    pub fn execute_with_dialect<P>(
+        &mut self,
+        sql: &str,
+        params: P,
+        dialect: Dialect,
+    ) -> Result<usize, FbError>
+    where
+        P: IntoParams,
+    {
+        match &mut self.inner {
+            #[cfg(feature = "linking")]
+            TypeConnectionContainer::NativeDynLink(c) => {
+                c.execute_with_dialect(sql, params, dialect)
+            }
+            #[cfg(feature = "dynamic_loading")]
+            TypeConnectionContainer::NativeDynLoad(c) => {
+                c.execute_with_dialect(sql, params, dialect)
+            }
+            #[cfg(feature = "pure_rust")]
+            TypeConnectionContainer::PureRust(c) => c.execute_with_dialect(sql, params, dialect),
+        }
+    }
+
+    /// Execute the query and return all the rows, parsing the sql with the dialect
+    /// instead of the one configured in the connection
+    pub fn query_with_dialect<P, R>(
+        &mut self,
+        sql: &str,
+        params: P,
+        dialect: Dialect,
+    ) -> Result<Vec<R>, FbError>
+    where
+        P: IntoParams,
+        R: FromRow + 'static,
+    {
+        match &mut self.inner {
+            #[cfg(feature = "linking")]
+            TypeConnectionContainer::NativeDynLink(c) => c.query_with_dialect(sql, params, dialect),
+            #[cfg(feature = "dynamic_loading")]
+            TypeConnectionContainer::NativeDynLoad(c) => c.query_with_dialect(sql, params, dialect),
+            #[cfg(feature = "pure_rust")]
+            TypeConnectionContainer::PureRust(c) => c.query_with_dialect(sql, params, dialect),
+        }
+    }
+
+    /// Execute a script with many statements, separated by `;` or the terminator
+    /// set with `SET TERM`, committing the ddl statements right after executed.
+    ///
+    /// On error, the [`ScriptError`] has the index of the failed statement.
+    /// Returns the rows or the number of rows changed of each statement
+    pub fn execute_script(&mut self, script: &str) -> Result<Vec<StatementOutcome>, ScriptError> {
+        match &mut self.inner {
+            #[cfg(feature = "linking")]
+            TypeConnectionContainer::NativeDynLink(c) => c.execute_script(script),
+            #[cfg(feature = "dynamic_loading")]
+            TypeConnectionContainer::NativeDynLoad(c) => c.execute_script(script),
+            #[cfg(feature = "pure_rust")]
+            TypeConnectionContainer::PureRust(c) => c.execute_script(script),
+        }
+    }
+
+    /// Free all the prepared statements in the statement cache, so the next
+    /// executions prepare them again.
+    ///
+    /// The cache is also cleared automatically on a `FbError::MetadataChanged` error
+    pub fn clear_statement_cache(&mut self) -> Result<(), FbError> {
+        match &mut self.inner {
+            #[cfg(feature = "linking")]
+            TypeConnectionContainer::NativeDynLink(c) => c.clear_statement_cache(),
+            #[cfg(feature = "dynamic_loading")]
+            TypeConnectionContainer::NativeDynLoad(c) => c.clear_statement_cache(),
+            #[cfg(feature = "pure_rust")]
+            TypeConnectionContainer::PureRust(c) => c.clear_statement_cache(),
+        }
+    }
+
     /// Close the current connection.
     pub fn close(self) -> Result<(), FbError> {
         match self.inner {
@@ -176,6 +506,94 @@ impl SimpleConnection {
             TypeConnectionContainer::PureRust(c) => c.rollback(),
         }
     }
+
+    /// Increment the sequence (generator) by its configured step and return the new value.
+    ///
+    /// The name is quoted, uppercasing the regular identifiers, so `my_seq` is the same as
+    /// `MY_SEQ`, but `My seq` is used exactly. Fails if the sequence doesn't exist.
+    /// Requires Firebird 2+
+    pub fn next_value(&mut self, sequence_name: &str) -> Result<i64, FbError> {
+        match &mut self.inner {
+            #[cfg(feature = "linking")]
+            TypeConnectionContainer::NativeDynLink(c) => c.next_value(sequence_name),
+            #[cfg(feature = "dynamic_loading")]
+            TypeConnectionContainer::NativeDynLoad(c) => c.next_value(sequence_name),
+            #[cfg(feature = "pure_rust")]
+            TypeConnectionContainer::PureRust(c) => c.next_value(sequence_name),
+        }
+    }
+
+    /// Increment the sequence (generator) by `step` and return the new value.
+    /// A zero `step` returns the current value.
+    ///
+    /// The name is quoted, uppercasing the regular identifiers, so `my_seq` is the same as
+    /// `MY_SEQ`, but `My seq` is used exactly. Fails if the sequence doesn't exist
+    pub fn gen_id(&mut self, sequence_name: &str, step: i64) -> Result<i64, FbError> {
+        match &mut self.inner {
+            #[cfg(feature = "linking")]
+            TypeConnectionContainer::NativeDynLink(c) => c.gen_id(sequence_name, step),
+            #[cfg(feature = "dynamic_loading")]
+            TypeConnectionContainer::NativeDynLoad(c) => c.gen_id(sequence_name, step),
+            #[cfg(feature = "pure_rust")]
+            TypeConnectionContainer::PureRust(c) => c.gen_id(sequence_name, step),
+        }
+    }
+
+    /// Call the stored procedure with the positional parameters, with `SELECT * FROM` if it's
+    /// selectable, returning the rows suspended, or with `EXECUTE PROCEDURE` if it's
+    /// executable, returning the output parameters in a single row
+    pub fn call_procedure<'a, P, R>(
+        &'a mut self,
+        procedure_name: &str,
+        params: P,
+    ) -> Result<Box<dyn Iterator<Item = Result<R, FbError>> + 'a>, FbError>
+    where
+        P: IntoParams,
+        R: FromRow + 'static,
+    {
+        match &mut self.inner {
+            #[cfg(feature = "linking")]
+            TypeConnectionContainer::NativeDynLink(c) => c.call_procedure(procedure_name, params),
+            #[cfg(feature = "dynamic_loading")]
+            TypeConnectionContainer::NativeDynLoad(c) => c.call_procedure(procedure_name, params),
+            #[cfg(feature = "pure_rust")]
+            TypeConnectionContainer::PureRust(c) => c.call_procedure(procedure_name, params),
+        }
+    }
+
+    /// Set the value of a context variable, with `RDB$SET_CONTEXT`. A `None` value
+    /// removes the variable. Returns true if the variable already existed
+    pub fn set_context(
+        &mut self,
+        namespace: ContextNamespace,
+        name: &str,
+        value: Option<&str>,
+    ) -> Result<bool, FbError> {
+        match &mut self.inner {
+            #[cfg(feature = "linking")]
+            TypeConnectionContainer::NativeDynLink(c) => c.set_context(namespace, name, value),
+            #[cfg(feature = "dynamic_loading")]
+            TypeConnectionContainer::NativeDynLoad(c) => c.set_context(namespace, name, value),
+            #[cfg(feature = "pure_rust")]
+            TypeConnectionContainer::PureRust(c) => c.set_context(namespace, name, value),
+        }
+    }
+
+    /// Get the value of a context variable, with `RDB$GET_CONTEXT`, or `None` if it is not set
+    pub fn get_context(
+        &mut self,
+        namespace: ContextNamespace,
+        name: &str,
+    ) -> Result<Option<String>, FbError> {
+        match &mut self.inner {
+            #[cfg(feature = "linking")]
+            TypeConnectionContainer::NativeDynLink(c) => c.get_context(namespace, name),
+            #[cfg(feature = "dynamic_loading")]
+            TypeConnectionContainer::NativeDynLoad(c) => c.get_context(namespace, name),
+            #[cfg(feature = "pure_rust")]
+            TypeConnectionContainer::PureRust(c) => c.get_context(namespace, name),
+        }
+    }
 }
 
 impl Execute for SimpleConnection {
@@ -7,8 +7,27 @@
 use lru_cache::LruCache;
 use std::{collections::HashSet, mem};
 
-use crate::{statement::StatementData, Connection, FbError, Transaction};
-use rsfbclient_core::FirebirdClient;
+use super::script;
+use crate::{
+    statement::StatementData, transaction::TransactionData, Connection, FbError, Transaction,
+};
+use rsfbclient_core::{FirebirdClient, ParamsType, StmtType};
+
+/// How the sql text is normalized to find the statements in the cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SqlNormalization {
+    /// Only the exact same sql text uses the cached statement
+    #[default]
+    Exact,
+
+    /// Ignore the comments and the differences in whitespace, outside of the
+    /// string literals and delimited identifiers
+    Whitespace,
+
+    /// Like `Whitespace`, also ignoring the case of the keywords and the
+    /// identifiers that are not delimited. The named parameters keep their case
+    Uppercase,
+}
 
 /// Cache of prepared statements.
 ///
@@ -16,19 +35,43 @@ use rsfbclient_core::FirebirdClient;
 pub struct StmtCache<T> {
     cache: LruCache<String, T>,
     sqls: HashSet<String>,
+    normalization: SqlNormalization,
 }
 
 pub struct StmtCacheData<T> {
+    /// Sql text, normalized to be used as the cache key
     pub(crate) sql: String,
     pub(crate) stmt: T,
 }
 
+/// Statement taken from the cache
+type CachedStmt<C> = StmtCacheData<StatementData<C>>;
+
 /// General functions
 impl<T> StmtCache<T> {
-    pub fn new(capacity: usize) -> Self {
+    pub fn new(capacity: usize, normalization: SqlNormalization) -> Self {
         Self {
             cache: LruCache::new(capacity),
             sqls: HashSet::with_capacity(capacity),
+            normalization,
+        }
+    }
+
+    /// Number of statements in the cache
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.sqls.len()
+    }
+
+    /// Normalize the sql to be used as the cache key. Invalid sqls, like with an
+    /// unterminated string literal, are kept as they are
+    fn key(&self, sql: &str) -> String {
+        match self.normalization {
+            SqlNormalization::Exact => sql.to_string(),
+            SqlNormalization::Whitespace => {
+                normalize(sql, false).unwrap_or_else(|_| sql.to_string())
+            }
+            SqlNormalization::Uppercase => normalize(sql, true).unwrap_or_else(|_| sql.to_string()),
         }
     }
 
@@ -80,28 +123,83 @@ impl<C> StmtCache<StatementData<C>>
 where
     C: FirebirdClient,
 {
-    /// Get a prepared statement from the cache, or prepare one
-    pub fn get_or_prepare(
+    /// Get a prepared statement from the cache, or prepare one.
+    /// Also returns if the statement was found in the cache
+    fn get_or_prepare(
         tr: &mut Transaction<C>,
         sql: &str,
         named_params: bool,
-    ) -> Result<StmtCacheData<StatementData<C>>, FbError> {
-        if let Some(data) = tr.conn.stmt_cache.get(sql) {
-            Ok(data)
+    ) -> Result<(StmtCacheData<StatementData<C>>, bool), FbError> {
+        let key = tr.conn.stmt_cache.key(sql);
+
+        if let Some(data) = tr.conn.stmt_cache.get(&key) {
+            Ok((data, true))
         } else {
-            Ok(StmtCacheData {
-                sql: sql.to_string(),
+            let data = StmtCacheData {
+                sql: key,
                 stmt: StatementData::prepare(tr.conn, &mut tr.data, sql, named_params)?,
-            })
+            };
+
+            Ok((data, false))
+        }
+    }
+
+    /// Run the operation with a statement from the cache, or prepared now.
+    ///
+    /// If a cached statement fails because the metadata it uses was changed, like
+    /// a table altered by another connection, the statement is freed and prepared
+    /// again, retrying the operation once. Returns the statement, to be returned
+    /// to the cache, with the result of the operation
+    pub fn run<T, F>(
+        tr: &mut Transaction<C>,
+        sql: &str,
+        params: ParamsType,
+        mut op: F,
+    ) -> Result<(CachedStmt<C>, Result<T, FbError>), FbError>
+    where
+        F: FnMut(
+            &mut StatementData<C>,
+            &mut Connection<C>,
+            &mut TransactionData<C>,
+            &ParamsType,
+        ) -> Result<T, FbError>,
+    {
+        let named_params = params.named();
+        let (mut data, cached) = Self::get_or_prepare(tr, sql, named_params)?;
+
+        if !cached {
+            let res = op(&mut data.stmt, tr.conn, &mut tr.data, &params);
+
+            return Ok((data, res));
+        }
+
+        match op(&mut data.stmt, tr.conn, &mut tr.data, &params) {
+            Err(FbError::MetadataChanged { .. }) => {
+                data.stmt.close(tr.conn).ok();
+                data.stmt = StatementData::prepare(tr.conn, &mut tr.data, sql, named_params)?;
+
+                let res = op(&mut data.stmt, tr.conn, &mut tr.data, &params);
+
+                Ok((data, res))
+            }
+            res => Ok((data, res)),
         }
     }
 
     /// Adds a prepared statement to the cache, closing the previous one for this sql
-    /// or another if the cache is full
+    /// or another if the cache is full.
+    ///
+    /// The ddl statements are closed instead, as they are rarely executed again
+    /// and would keep the objects they change in use
     pub fn insert_and_close(
         conn: &mut Connection<C>,
         data: StmtCacheData<StatementData<C>>,
     ) -> Result<(), FbError> {
+        if data.stmt.stmt_type == StmtType::Ddl {
+            let mut stmt = data.stmt;
+            return stmt.close(conn);
+        }
+
         conn.stmt_cache.sqls.insert(data.sql.clone());
 
         // Insert the new one and close the old if exists
@@ -112,20 +210,156 @@ where
         Ok(())
     }
 
+    /// Closes all statements in the cache, keeping its capacity.
+    /// Returns the first error after trying to close all of them
+    pub fn clear(conn: &mut Connection<C>) -> Result<(), FbError> {
+        let capacity = conn.stmt_cache.cache.capacity();
+        let normalization = conn.stmt_cache.normalization;
+        let mut stmt_cache = mem::replace(
+            &mut conn.stmt_cache,
+            StmtCache::new(capacity, normalization),
+        );
+
+        let mut res = Ok(());
+        for (_, stmt) in stmt_cache.cache.iter_mut() {
+            let close_res = stmt.close(conn);
+            if res.is_ok() {
+                res = close_res;
+            }
+        }
+
+        res
+    }
+
     /// Closes all statements in the cache.
     /// Needs to be called before dropping the cache.
-    pub fn close_all(conn: &mut Connection<C>) {
-        let mut stmt_cache = mem::replace(&mut conn.stmt_cache, StmtCache::new(0));
-
+    /// Returns the first error after trying to close all of them
+    pub fn close_all(conn: &mut Connection<C>) -> Result<(), FbError> {
+        let mut stmt_cache = mem::replace(
+            &mut conn.stmt_cache,
+            StmtCache::new(0, SqlNormalization::Exact),
+        );
+
+        let mut res = Ok(());
         for (_, stmt) in stmt_cache.cache.iter_mut() {
-            stmt.close(conn).ok();
+            let close_res = stmt.close(conn);
+            if res.is_ok() {
+                res = close_res;
+            }
         }
+
+        res
     }
 }
 
+/// Remove the comments and collapse the whitespace sequences into a single space,
+/// optionally converting to uppercase, skipping the string literals, delimited
+/// identifiers and named parameters
+fn normalize(sql: &str, uppercase: bool) -> Result<String, FbError> {
+    let mut key = String::with_capacity(sql.len());
+    let mut space = false;
+    let mut pos = 0;
+
+    while let Some(ch) = sql[pos..].chars().next() {
+        let rest = &sql[pos..];
+
+        // The comments are handled like whitespace
+        let skip = if rest.starts_with("--") {
+            Some(rest.find('\n').map(|i| i + 1).unwrap_or(rest.len()))
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            let end = comment.find("*/").ok_or("Unterminated comment")?;
+            Some(end + 4)
+        } else if ch.is_whitespace() {
+            Some(ch.len_utf8())
+        } else {
+            None
+        };
+
+        if let Some(len) = skip {
+            space = !key.is_empty();
+            pos += len;
+            continue;
+        }
+
+        if space {
+            key.push(' ');
+            space = false;
+        }
+
+        let verbatim = match ch {
+            '\'' | '"' => Some(script::quoted_len(rest, ch)?),
+            'q' | 'Q' if rest[1..].starts_with('\'') && !script::ident_before(sql, pos) => {
+                Some(script::q_string_len(rest)?)
+            }
+            ':' => Some(
+                1 + rest[1..]
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .unwrap_or(rest.len() - 1),
+            ),
+            _ => None,
+        };
+
+        if let Some(len) = verbatim {
+            key.push_str(&rest[..len]);
+            pos += len;
+        } else {
+            key.push(if uppercase {
+                ch.to_ascii_uppercase()
+            } else {
+                ch
+            });
+            pos += ch.len_utf8();
+        }
+    }
+
+    Ok(key)
+}
+
+#[test]
+fn normalize_test() -> Result<(), FbError> {
+    let sql = "  select a,\n\t b -- comment\nfrom  T /* block */ where c = 'x  y' and :Param_1 = \"d  e\"\n";
+
+    assert_eq!(
+        "select a, b from T where c = 'x  y' and :Param_1 = \"d  e\"",
+        normalize(sql, false)?
+    );
+    assert_eq!(
+        "SELECT A, B FROM T WHERE C = 'x  y' AND :Param_1 = \"d  e\"",
+        normalize(sql, true)?
+    );
+    assert_eq!(
+        "SELECT Q'{a  b}' FROM RDB$DATABASE",
+        normalize("select Q'{a  b}'\nfrom rdb$database", true)?
+    );
+
+    // The comment text stays a comment
+    assert_ne!(
+        normalize("select 1 -- x\nfrom a", false)?,
+        normalize("select 1 -- x from a", false)?
+    );
+
+    assert!(normalize("select 'a", false).is_err());
+    assert!(normalize("select 1 /* a", false).is_err());
+
+    let mut cache = StmtCache::<usize>::new(2, SqlNormalization::Uppercase);
+    assert_eq!(cache.key("select 1 /* a"), "select 1 /* a");
+    cache.insert(StmtCacheData {
+        sql: cache.key("select 1\nfrom rdb$database"),
+        stmt: 1,
+    });
+    assert_eq!(
+        cache
+            .get(&cache.key("SELECT 1 FROM RDB$DATABASE"))
+            .map(|data| data.stmt),
+        Some(1)
+    );
+
+    Ok(())
+}
+
 #[test]
 fn stmt_cache_test() {
-    let mut cache = StmtCache::new(2);
+    let mut cache = StmtCache::new(2, SqlNormalization::Exact);
 
     let mk_test_data = |n: usize| StmtCacheData {
         sql: format!("sql {}", n),
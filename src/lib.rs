@@ -62,6 +62,50 @@
 //!     .into();
 //! ```
 //!
+//! # Custom types
+//!
+//! Your own types, like an enum stored in a domain, can be used in the parameters
+//! and in the rows implementing [IntoParam](trait.IntoParam.html) for the type and
+//! [ColumnToVal](trait.ColumnToVal.html) for the [Column](struct.Column.html), usually
+//! delegating to the conversions of an existing type. They are then accepted in the
+//! tuples, in `Option` and in the derives, like the built-in types:
+//! ```rust,ignore
+//! #[derive(Debug, Clone, Copy, PartialEq)]
+//! enum Status {
+//!     Active = 1,
+//!     Blocked = 2,
+//! }
+//!
+//! impl IntoParam for Status {
+//!     fn into_param(self) -> SqlType {
+//!         (self as i64).into_param()
+//!     }
+//! }
+//!
+//! impl ColumnToVal<Status> for Column {
+//!     fn to_val(self) -> Result<Status, FbError> {
+//!         match ColumnToVal::<i64>::to_val(self)? {
+//!             1 => Ok(Status::Active),
+//!             2 => Ok(Status::Blocked),
+//!             v => Err(format!("Invalid status: {}", v).into()),
+//!         }
+//!     }
+//! }
+//!
+//! let rows: Vec<(i32, Option<Status>)> = conn.query("select id, status from users", ())?;
+//! ```
+//!
+//! The conversions can't be provided for all the types implementing `TryFrom<SqlType>`
+//! and `Into<SqlType>`, as those generic implementations would conflict with the existing
+//! ones: `std` implements `From<T>` for `Option<T>`, overlapping with the implementation
+//! for the nullable values, and `SqlType` already implements `From<T>` for all the
+//! `IntoParam` types.
+//!
+//! The elapsed times stored as a count of seconds or milliseconds in a `BIGINT` or
+//! `NUMERIC` column can be read and bound as `chrono::Duration` with the
+//! [DurationSecs](struct.DurationSecs.html) and [DurationMillis](struct.DurationMillis.html)
+//! wrappers. Firebird has no interval type, so the unit is only known by the wrapper used.
+//!
 //! # Cargo features
 //! All features can be used at the same time if needed.
 //!
@@ -71,6 +115,25 @@
 //! Can find the official `fbclient` native library by path at runtime, does not need the library at compiletime. Useful when you need to build in a system without a firebird client installed.
 //! ### `pure_rust`
 //! Uses a pure rust implementation of the firebird wire protocol, does not need the native library at all. Useful for cross-compilation and allow a single binary to be deployed without needing to install the firebird client.
+//! ### `decimal`
+//! Enables the conversion of the `NUMERIC` and `DECIMAL` columns and parameters to and from `rust_decimal::Decimal`, without losing precision.
+//! ### `serde`
+//! Implements `Serialize` for the rows, and enables the [query_json](prelude/trait.Queryable.html#method.query_json) method to return the query results as json.
+//! ### `uuid`
+//! Enables the conversion of `uuid::Uuid` parameters and columns, stored as `CHAR(16) CHARACTER SET OCTETS`. The bytes are kept in the same order.
+//! ### `arrow`
+//! Enables the conversion of the query results to `arrow` record batches, with [query_arrow](struct.Connection.html#method.query_arrow), to use them with the arrow based tools, like DataFusion and Polars.
+//! ### `tracing`
+//! Emits a `tracing` span for each statement execution, with the sql text, the parameters, the rows count and the elapsed time. The errors are emitted as events. The sql and the parameters can be omitted with the builders `with_trace_sql` and `with_trace_params` methods.
+//! ### `metrics`
+//! Records the statements executions with the `metrics` crate, to be exported by any `metrics` recorder, like the prometheus one. All have the `op` label, with the operation: `prepare`, `execute`, `execute2`, `query` or `query_scrollable`.
+//! - `fb_queries_total`: counter of the executions and queries finished, without the prepares. The queries finish when all the rows are fetched or the cursor is closed.
+//! - `fb_query_duration_seconds`: histogram of the operations elapsed time, including the fetch of the rows in the queries.
+//! - `fb_errors_total`: counter of the failed operations, with the `class` label: the class of the error, one of `constraint`, `conflict` (deadlocks and lock conflicts), `permission`, `data` (arithmetic errors and truncations), `sql` (the other sql errors), `timeout`, `cancelled`, `connection_lost`, `transaction_lost`, `metadata_changed`, `io` or `other`.
+//!
+//! The `metrics` feature of the `r2d2_firebird` crate also records the pool checkouts, in the `fb_pool_checkout_duration_seconds` histogram and the `fb_pool_checkout_timeouts_total` counter.
+//! ### `services`
+//! Enables the [services](services/index.html) module, to administrate the server using the services manager, like running backups and restores. Currently only supported by the native client.
 
 #[cfg(test)]
 #[macro_use]
@@ -78,24 +141,35 @@ pub(crate) mod tests;
 
 pub mod prelude {
     pub use crate::query::{Execute, Queryable};
-    pub use rsfbclient_derive::IntoParams;
+    pub use rsfbclient_derive::{FromRow, IntoParams};
 }
 
 mod connection;
 mod query;
+#[cfg(feature = "services")]
+pub mod services;
 mod statement;
+mod trace;
 mod transaction;
 mod utils;
 
 pub use crate::{
-    connection::{Connection, ConnectionConfiguration, FirebirdClientFactory, SimpleConnection},
+    connection::{
+        AttachmentInfo, CancelHandle, ColumnarData, ColumnarResult, ColumnarValues, Connection,
+        ConnectionConfiguration, ContextNamespace, CsvBinary, CsvOptions, FirebirdClientFactory,
+        LazyConnection, ScriptError, SimpleConnection, SqlNormalization, StatementInfo,
+        StatementOutcome,
+    },
     query::{Execute, Queryable},
-    statement::Statement,
-    transaction::{SimpleTransaction, Transaction},
-    utils::{EngineVersion, SystemInfos},
+    statement::{ScrollableCursor, Statement, StatementStats},
+    transaction::{DetachedTransaction, MultiDbTransaction, SimpleTransaction, Transaction},
+    utils::{paginate, EngineVersion, SystemInfos},
 };
+pub use rsfbclient_core::duration::{DurationMillis, DurationSecs};
 pub use rsfbclient_core::{
-    Column, ColumnToVal, Dialect, FbError, FromRow, IntoParam, IntoParams, ParamsType, Row, SqlType,
+    null, Column, ColumnIndex, ColumnMeta, ColumnToVal, CreateDatabaseConfig, Dialect, FbError,
+    FromRow, IntoParam, IntoParams, NullParam, ParamMeta, ParamsType, Row, SqlType, StmtType,
+    TrDataAccessMode, TrIsolationLevel, TrLockResolution, TransactionConfiguration,
 };
 
 #[doc(hidden)]
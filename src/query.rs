@@ -4,7 +4,7 @@
 //! High level api
 //!
 
-use rsfbclient_core::{FbError, FromRow, IntoParams};
+use rsfbclient_core::{Column, ColumnToVal, FbError, FromRow, IntoParams, Row};
 
 /// Implemented for types that can be used to execute sql queries
 pub trait Queryable {
@@ -29,6 +29,30 @@ pub trait Queryable {
         P: IntoParams,
         R: FromRow + 'static;
 
+    /// Returns the results of the query as an iterator of untyped rows.
+    ///
+    /// Same as `query_iter` with the `Row` type, useful when the columns are only known
+    /// at runtime. The column names and values are available in `Row::cols`, and each value
+    /// can be converted later with `Row::get`
+    ///
+    /// possible values for argument `params`:
+    ///
+    /// `()`: no parameters,
+    ///
+    /// `(param0, param1, param2...)`: a tuple of `IntoParam` values corresponding to positional `?` sql parameters
+    ///
+    /// A struct for which `IntoParams` has been derived ([see there for details](prelude/derive.IntoParams.html))
+    fn query_rows<'a, P>(
+        &'a mut self,
+        sql: &str,
+        params: P,
+    ) -> Result<Box<dyn Iterator<Item = Result<Row, FbError>> + 'a>, FbError>
+    where
+        P: IntoParams,
+    {
+        self.query_iter(sql, params)
+    }
+
     /// Returns the results of the query as a `Vec`
     ///
     /// The query must be return an open cursor, so for cases like 'insert .. returning'
@@ -68,6 +92,159 @@ pub trait Queryable {
     {
         self.query_iter(sql, params)?.next().transpose()
     }
+
+    /// Returns the only result of the query, or None if there are no results.
+    ///
+    /// Unlike `query_first`, fails if the query returns more than one row.
+    ///
+    /// possible values for argument `params`:
+    ///
+    /// `()`: no parameters,
+    ///
+    /// `(param0, param1, param2...)`: a tuple of `IntoParam` values corresponding to positional `?` sql parameters
+    ///
+    /// A struct for which `IntoParams` has been derived ([see there for details](prelude/derive.IntoParams.html))
+    fn query_opt<P, R>(&mut self, sql: &str, params: P) -> Result<Option<R>, FbError>
+    where
+        P: IntoParams,
+        R: FromRow + 'static,
+    {
+        single_row(self.query_iter(sql, params)?)
+    }
+
+    /// Returns the only result of the query.
+    ///
+    /// Fails if the query returns no rows or more than one row.
+    ///
+    /// possible values for argument `params`:
+    ///
+    /// `()`: no parameters,
+    ///
+    /// `(param0, param1, param2...)`: a tuple of `IntoParam` values corresponding to positional `?` sql parameters
+    ///
+    /// A struct for which `IntoParams` has been derived ([see there for details](prelude/derive.IntoParams.html))
+    fn query_one<P, R>(&mut self, sql: &str, params: P) -> Result<R, FbError>
+    where
+        P: IntoParams,
+        R: FromRow + 'static,
+    {
+        self.query_opt(sql, params)?
+            .ok_or_else(|| "The query returned no rows, expected exactly one".into())
+    }
+
+    /// Returns the single value of a query that must return exactly one row
+    /// with one column, like a `SELECT COUNT(*)`.
+    ///
+    /// Fails if the query returns no rows, more than one row or more than one column.
+    ///
+    /// possible values for argument `params`:
+    ///
+    /// `()`: no parameters,
+    ///
+    /// `(param0, param1, param2...)`: a tuple of `IntoParam` values corresponding to positional `?` sql parameters
+    ///
+    /// A struct for which `IntoParams` has been derived ([see there for details](prelude/derive.IntoParams.html))
+    fn query_scalar<P, T>(&mut self, sql: &str, params: P) -> Result<T, FbError>
+    where
+        P: IntoParams,
+        Column: ColumnToVal<T>,
+    {
+        let row: Row = self.query_one(sql, params)?;
+
+        single_column(row)
+    }
+
+    /// Returns the values of a query with one column, like a `SELECT ID FROM TABLE`,
+    /// as an iterator of the values instead of one element tuples.
+    ///
+    /// Fails right away if the query returns more than one column, even without rows.
+    ///
+    /// possible values for argument `params`:
+    ///
+    /// `()`: no parameters,
+    ///
+    /// `(param0, param1, param2...)`: a tuple of `IntoParam` values corresponding to positional `?` sql parameters
+    ///
+    /// A struct for which `IntoParams` has been derived ([see there for details](prelude/derive.IntoParams.html))
+    fn query_column<'a, P, T>(
+        &'a mut self,
+        sql: &str,
+        params: P,
+    ) -> Result<Box<dyn Iterator<Item = Result<T, FbError>> + 'a>, FbError>
+    where
+        P: IntoParams,
+        Column: ColumnToVal<T>,
+        T: 'static,
+    {
+        let rows = self.query_iter::<P, SingleColumn<T>>(sql, params)?;
+
+        Ok(Box::new(rows.map(|row| row.map(|col| col.0))))
+    }
+
+    /// Returns the results of the query as a json array of objects,
+    /// with the column names as keys.
+    ///
+    /// `NULL` values are returned as `null`, the timestamps as ISO-8601 strings and
+    /// the `NUMERIC` and `DECIMAL` values as strings, to keep the precision.
+    ///
+    /// possible values for argument `params`:
+    ///
+    /// `()`: no parameters,
+    ///
+    /// `(param0, param1, param2...)`: a tuple of `IntoParam` values corresponding to positional `?` sql parameters
+    ///
+    /// A struct for which `IntoParams` has been derived ([see there for details](prelude/derive.IntoParams.html))
+    #[cfg(feature = "serde")]
+    fn query_json<P>(&mut self, sql: &str, params: P) -> Result<serde_json::Value, FbError>
+    where
+        P: IntoParams,
+    {
+        let rows: Vec<Row> = self.query(sql, params)?;
+
+        serde_json::to_value(rows).map_err(|e| FbError::from(e.to_string()))
+    }
+}
+
+/// Value of the only column of the row, with the number of columns
+/// of the query checked after the prepare
+struct SingleColumn<T>(T);
+
+impl<T> FromRow for SingleColumn<T>
+where
+    Column: ColumnToVal<T>,
+{
+    const COLUMNS: Option<usize> = Some(1);
+
+    fn try_from(row: Vec<Column>) -> Result<Self, FbError> {
+        single_column(Row { cols: row }).map(SingleColumn)
+    }
+}
+
+/// Takes the value of the only column of the row, failing if there are more
+fn single_column<T>(row: Row) -> Result<T, FbError>
+where
+    Column: ColumnToVal<T>,
+{
+    if row.cols.len() != 1 {
+        return Err(format!(
+            "The query returned {} columns, expected exactly one",
+            row.cols.len()
+        )
+        .into());
+    }
+
+    row.get_by_index(0)
+}
+
+/// Takes the only row of the results, failing if there are more
+fn single_row<R>(mut rows: impl Iterator<Item = Result<R, FbError>>) -> Result<Option<R>, FbError> {
+    let row = rows.next().transpose()?;
+
+    if row.is_some() && rows.next().transpose()?.is_some() {
+        return Err("The query returned more than one row, expected at most one".into());
+    }
+
+    Ok(row)
 }
 
 /// Implemented for types that can be used to execute sql statements
@@ -103,4 +280,25 @@ pub trait Execute {
     where
         P: IntoParams,
         R: FromRow + 'static;
+
+    /// Execute a query that will return data, like the 'insert ... returning ..' or 'execute procedure',
+    /// returning an untyped row.
+    ///
+    /// Same as `execute_returnable` with the `Row` type, useful when the returned columns are only
+    /// known at runtime. The `NULL` values are kept as `SqlType::Null`, and the row can be converted
+    /// to a map of the column names to the values with `Row::into_map`, or serialized with the `serde` feature.
+    ///
+    /// possible values for argument `params`:
+    ///
+    /// `()`: no parameters,
+    ///
+    /// `(param0, param1, param2...)`: a tuple of `IntoParam` values corresponding to positional `?` sql parameters
+    ///
+    /// A struct for which `IntoParams` has been derived ([see there for details](prelude/derive.IntoParams.html))
+    fn execute_returning_row<P>(&mut self, sql: &str, params: P) -> Result<Row, FbError>
+    where
+        P: IntoParams,
+    {
+        self.execute_returnable(sql, params)
+    }
 }
@@ -0,0 +1,571 @@
+//!
+//! Rust Firebird Client
+//!
+//! Services manager operations, like backup, restore, statistics and sweep
+//!
+
+use rsfbclient_core::{ibase, FbError, FirebirdClientServiceOps};
+use std::mem;
+
+/// Size of the buffer used to read the service output
+const SERVICE_BUFFER_LEN: usize = 4096;
+
+/// A connection to the services manager of a firebird server.
+///
+/// Use the `service_manager()` method of a connection builder to get a new instance.
+pub struct ServiceManager<C: FirebirdClientServiceOps> {
+    /// Services manager handle
+    handle: C::SvcHandle,
+
+    /// Firebird client
+    cli: C,
+}
+
+impl<C: FirebirdClientServiceOps> ServiceManager<C> {
+    /// Attach to the services manager of the server
+    pub fn attach(mut cli: C, conf: &C::AttachmentConfig) -> Result<Self, FbError> {
+        let handle = cli.attach_service(conf)?;
+
+        Ok(Self { handle, cli })
+    }
+
+    /// Start a backup of the database, like `gbak -b`.
+    ///
+    /// The returned iterator yields the output lines of the service and
+    /// must be consumed until the end, as the backup is only completed then
+    pub fn backup(&mut self, options: &BackupOptions) -> Result<ServiceOutput<'_, C>, FbError> {
+        self.start(&options.to_spb())
+    }
+
+    /// Restore a backup into a database, like `gbak -c`.
+    ///
+    /// The returned iterator yields the output lines of the service and
+    /// must be consumed until the end, as the restore is only completed then
+    pub fn restore(&mut self, options: &RestoreOptions) -> Result<ServiceOutput<'_, C>, FbError> {
+        self.start(&options.to_spb())
+    }
+
+    /// Read the statistics of the database, like `gstat`.
+    ///
+    /// Blocks until the service completes, returning the full report
+    /// and the values parsed from the header page
+    pub fn database_stats(
+        &mut self,
+        db_name: &str,
+        options: &StatsOptions,
+    ) -> Result<DatabaseStats, FbError> {
+        let report = self
+            .start(&options.to_spb(db_name))?
+            .collect::<Result<Vec<_>, _>>()?
+            .join("\n");
+
+        Ok(DatabaseStats::parse(report))
+    }
+
+    /// Sweep the database, like `gfix -sweep`, removing the outdated record versions.
+    ///
+    /// Blocks until the sweep completes
+    pub fn sweep(&mut self, db_name: &str) -> Result<(), FbError> {
+        self.sweep_with_progress(db_name, |_| ())
+    }
+
+    /// Sweep the database, like `gfix -sweep`, calling the closure with
+    /// each output line of the service.
+    ///
+    /// Blocks until the sweep completes
+    pub fn sweep_with_progress<F>(&mut self, db_name: &str, mut progress: F) -> Result<(), FbError>
+    where
+        F: FnMut(String),
+    {
+        let mut spb = vec![ibase::isc_action_svc_repair as u8];
+        spb_string(&mut spb, ibase::isc_spb_dbname, db_name);
+        spb_int(
+            &mut spb,
+            ibase::isc_spb_options,
+            ibase::isc_spb_rpr_sweep_db,
+        );
+
+        for line in self.start(&spb)? {
+            progress(line?);
+        }
+
+        Ok(())
+    }
+
+    /// Change the database to read-only, or back to read-write, like `gfix -mode`.
+    ///
+    /// Needs exclusive access to the database, failing if there are other
+    /// attachments, including the connections of this process
+    pub fn set_read_only(&mut self, db_name: &str, read_only: bool) -> Result<(), FbError> {
+        let output = self
+            .start(&access_mode_spb(db_name, read_only))
+            .map_err(err_db_in_use)?;
+
+        for line in output {
+            line.map_err(err_db_in_use)?;
+        }
+
+        Ok(())
+    }
+
+    /// Start a service action and return its output
+    fn start(&mut self, spb: &[u8]) -> Result<ServiceOutput<'_, C>, FbError> {
+        self.cli.start_service(&mut self.handle, spb)?;
+
+        Ok(ServiceOutput {
+            svc: self,
+            finished: false,
+        })
+    }
+
+    /// Close the services manager connection
+    pub fn close(mut self) -> Result<(), FbError> {
+        let res = self.cli.detach_service(&mut self.handle);
+        mem::forget(self);
+        res
+    }
+}
+
+impl<C: FirebirdClientServiceOps> Drop for ServiceManager<C> {
+    fn drop(&mut self) {
+        // Ignore the possible error value
+        let _ = self.cli.detach_service(&mut self.handle);
+    }
+}
+
+/// Output lines of a running service action
+pub struct ServiceOutput<'s, C: FirebirdClientServiceOps> {
+    svc: &'s mut ServiceManager<C>,
+    finished: bool,
+}
+
+impl<C: FirebirdClientServiceOps> Iterator for ServiceOutput<'_, C> {
+    type Item = Result<String, FbError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let res = self
+            .svc
+            .cli
+            .query_service(
+                &mut self.svc.handle,
+                &[ibase::isc_info_svc_line as u8],
+                SERVICE_BUFFER_LEN,
+            )
+            .and_then(|resp| parse_line(&resp));
+
+        match res {
+            Ok(Some(line)) => Some(Ok(line)),
+            Ok(None) => {
+                self.finished = true;
+                None
+            }
+            Err(e) => {
+                self.finished = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Options for the backup service
+#[derive(Debug, Clone, Default)]
+pub struct BackupOptions {
+    /// Database name or path, as seen by the server
+    pub db_name: String,
+    /// Backup file path, as seen by the server
+    pub backup_file: String,
+    /// Output the progress of the backup
+    pub verbose: bool,
+}
+
+impl BackupOptions {
+    pub fn new<S: Into<String>>(db_name: S, backup_file: S) -> Self {
+        Self {
+            db_name: db_name.into(),
+            backup_file: backup_file.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Build the service parameter buffer
+    fn to_spb(&self) -> Vec<u8> {
+        let mut spb = vec![ibase::isc_action_svc_backup as u8];
+
+        spb_string(&mut spb, ibase::isc_spb_dbname, &self.db_name);
+        spb_string(&mut spb, ibase::isc_spb_bkp_file, &self.backup_file);
+
+        if self.verbose {
+            spb.push(ibase::isc_spb_verbose as u8);
+        }
+
+        spb
+    }
+}
+
+/// Options for the restore service
+#[derive(Debug, Clone, Default)]
+pub struct RestoreOptions {
+    /// Backup file path, as seen by the server
+    pub backup_file: String,
+    /// Database name or path to restore into, as seen by the server
+    pub db_name: String,
+    /// Page size of the restored database. Default: same of the backup
+    pub page_size: Option<u32>,
+    /// Replace the database if it already exists
+    pub overwrite: bool,
+    /// Output the progress of the restore
+    pub verbose: bool,
+}
+
+impl RestoreOptions {
+    pub fn new<S: Into<String>>(backup_file: S, db_name: S) -> Self {
+        Self {
+            backup_file: backup_file.into(),
+            db_name: db_name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Build the service parameter buffer
+    fn to_spb(&self) -> Vec<u8> {
+        let mut spb = vec![ibase::isc_action_svc_restore as u8];
+
+        spb_string(&mut spb, ibase::isc_spb_bkp_file, &self.backup_file);
+        spb_string(&mut spb, ibase::isc_spb_dbname, &self.db_name);
+
+        if let Some(page_size) = self.page_size {
+            spb_int(&mut spb, ibase::isc_spb_res_page_size, page_size);
+        }
+
+        let options = if self.overwrite {
+            ibase::isc_spb_res_replace
+        } else {
+            ibase::isc_spb_res_create
+        };
+        spb_int(&mut spb, ibase::isc_spb_options, options);
+
+        if self.verbose {
+            spb.push(ibase::isc_spb_verbose as u8);
+        }
+
+        spb
+    }
+}
+
+/// Options for the database statistics service
+#[derive(Debug, Clone)]
+pub struct StatsOptions {
+    /// Include the header page information. Default: true
+    pub header_pages: bool,
+    /// Include the data pages information of the tables. Default: false
+    pub data_pages: bool,
+    /// Include the index pages information. Default: false
+    pub index_pages: bool,
+    /// Include the record versions information. Default: false
+    pub record_versions: bool,
+    /// Include the system tables. Default: false
+    pub system_relations: bool,
+}
+
+impl Default for StatsOptions {
+    fn default() -> Self {
+        Self {
+            header_pages: true,
+            data_pages: false,
+            index_pages: false,
+            record_versions: false,
+            system_relations: false,
+        }
+    }
+}
+
+impl StatsOptions {
+    /// Build the service parameter buffer
+    fn to_spb(&self, db_name: &str) -> Vec<u8> {
+        let mut spb = vec![ibase::isc_action_svc_db_stats as u8];
+
+        spb_string(&mut spb, ibase::isc_spb_dbname, db_name);
+
+        let mut options = 0;
+        for (enabled, flag) in [
+            (self.header_pages, ibase::isc_spb_sts_hdr_pages),
+            (self.data_pages, ibase::isc_spb_sts_data_pages),
+            (self.index_pages, ibase::isc_spb_sts_idx_pages),
+            (self.record_versions, ibase::isc_spb_sts_record_versions),
+            (self.system_relations, ibase::isc_spb_sts_sys_relations),
+        ] {
+            if enabled {
+                options |= flag;
+            }
+        }
+        spb_int(&mut spb, ibase::isc_spb_options, options);
+
+        spb
+    }
+}
+
+/// Database statistics, as reported by the statistics service.
+///
+/// The values are parsed from the header page information, and will
+/// be `None` if it was not requested or the server did not report them
+#[derive(Debug, Clone, Default)]
+pub struct DatabaseStats {
+    /// Full textual report
+    pub report: String,
+    /// Database page size
+    pub page_size: Option<u64>,
+    /// Page buffers configured in the database header
+    pub page_buffers: Option<u64>,
+    /// Oldest interesting transaction (OIT)
+    pub oldest_transaction: Option<u64>,
+    /// Oldest active transaction (OAT)
+    pub oldest_active: Option<u64>,
+    /// Oldest snapshot transaction (OST)
+    pub oldest_snapshot: Option<u64>,
+    /// Next transaction number
+    pub next_transaction: Option<u64>,
+    /// Sweep interval, 0 if the automatic sweep is disabled
+    pub sweep_interval: Option<u64>,
+}
+
+impl DatabaseStats {
+    /// Parse the header values from the report
+    fn parse(report: String) -> Self {
+        let mut stats = DatabaseStats::default();
+
+        for line in report.lines() {
+            let mut parts = line.split('\t').map(str::trim).filter(|p| !p.is_empty());
+
+            let (label, value) = match (parts.next(), parts.next()) {
+                (Some(label), Some(value)) => (label.trim_end_matches(':'), value),
+                _ => continue,
+            };
+
+            let field = match label {
+                "Page size" => &mut stats.page_size,
+                "Page buffers" => &mut stats.page_buffers,
+                "Oldest transaction" => &mut stats.oldest_transaction,
+                "Oldest active" => &mut stats.oldest_active,
+                "Oldest snapshot" => &mut stats.oldest_snapshot,
+                "Next transaction" => &mut stats.next_transaction,
+                "Sweep interval" => &mut stats.sweep_interval,
+                _ => continue,
+            };
+
+            *field = value.parse().ok();
+        }
+
+        stats.report = report;
+        stats
+    }
+
+    /// Gap between the next and the oldest interesting transaction. When it
+    /// grows beyond the sweep interval, a sweep is recommended
+    pub fn transaction_gap(&self) -> Option<u64> {
+        Some(
+            self.next_transaction?
+                .saturating_sub(self.oldest_transaction?),
+        )
+    }
+}
+
+/// Build the service parameter buffer to change the access mode of the database
+fn access_mode_spb(db_name: &str, read_only: bool) -> Vec<u8> {
+    let mut spb = vec![ibase::isc_action_svc_properties as u8];
+
+    spb_string(&mut spb, ibase::isc_spb_dbname, db_name);
+
+    let mode = if read_only {
+        ibase::isc_spb_prp_am_readonly
+    } else {
+        ibase::isc_spb_prp_am_readwrite
+    };
+    spb.push(ibase::isc_spb_prp_access_mode as u8);
+    spb.push(mode as u8);
+
+    spb
+}
+
+/// Identify the failures of the actions needing exclusive access caused by
+/// other attachments, reported by firebird as a lock timeout on the database.
+/// Only the message is changed, keeping the kind, code and status of the error
+fn err_db_in_use(mut e: FbError) -> FbError {
+    if matches!(e, FbError::MetadataChanged { .. }) || e.is_lock_conflict() {
+        if let FbError::Sql { msg, .. }
+        | FbError::Timeout { msg, .. }
+        | FbError::MetadataChanged { msg, .. } = &mut e
+        {
+            *msg = format!(
+                "The database is in use by other attachments, exclusive access is required: {}",
+                msg
+            );
+        }
+    }
+
+    e
+}
+
+/// Add a string item to the service parameter buffer
+fn spb_string(spb: &mut Vec<u8>, item: u32, value: &str) {
+    spb.push(item as u8);
+    spb.extend(&(value.len() as u16).to_le_bytes());
+    spb.extend(value.bytes());
+}
+
+/// Add an integer item to the service parameter buffer
+fn spb_int(spb: &mut Vec<u8>, item: u32, value: u32) {
+    spb.push(item as u8);
+    spb.extend(&value.to_le_bytes());
+}
+
+/// Parse a `isc_info_svc_line` response, returning `None`
+/// if the service has no more output
+fn parse_line(resp: &[u8]) -> Result<Option<String>, FbError> {
+    match resp {
+        [item, l1, l2, data @ ..] if *item == ibase::isc_info_svc_line as u8 => {
+            let len = u16::from_le_bytes([*l1, *l2]) as usize;
+
+            if len == 0 {
+                return Ok(None);
+            }
+
+            let line = data
+                .get(..len)
+                .ok_or_else(|| FbError::from("Invalid service output length"))?;
+
+            Ok(Some(String::from_utf8_lossy(line).into_owned()))
+        }
+        _ => Err(FbError::from("Invalid service output response")),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rsfbclient_core::StatusItem;
+
+    #[test]
+    fn backup_spb() {
+        let mut options = BackupOptions::new("db.fdb", "db.fbk");
+        options.verbose = true;
+
+        assert_eq!(
+            options.to_spb(),
+            b"\x01\x6a\x06\x00db.fdb\x05\x06\x00db.fbk\x6b".to_vec()
+        );
+    }
+
+    #[test]
+    fn restore_spb() {
+        let mut options = RestoreOptions::new("db.fbk", "db.fdb");
+        options.page_size = Some(8192);
+        options.overwrite = true;
+
+        assert_eq!(
+            options.to_spb(),
+            b"\x02\x05\x06\x00db.fbk\x6a\x06\x00db.fdb\x0a\x00\x20\x00\x00\x6c\x00\x10\x00\x00"
+                .to_vec()
+        );
+    }
+
+    #[test]
+    fn stats_spb() {
+        let options = StatsOptions {
+            data_pages: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            options.to_spb("db.fdb"),
+            b"\x0b\x6a\x06\x00db.fdb\x6c\x05\x00\x00\x00".to_vec()
+        );
+    }
+
+    #[test]
+    fn access_mode_spb_items() {
+        assert_eq!(
+            access_mode_spb("db.fdb", true),
+            b"\x08\x6a\x06\x00db.fdb\x0d\x27".to_vec()
+        );
+        assert_eq!(
+            access_mode_spb("db.fdb", false),
+            b"\x08\x6a\x06\x00db.fdb\x0d\x28".to_vec()
+        );
+    }
+
+    #[test]
+    fn stats_parse() {
+        let report = "Database header page information:
+\tFlags\t\t\t0
+\tPage size\t\t8192
+\tOldest transaction\t158
+\tOldest active\t\t159
+\tOldest snapshot\t\t159
+\tNext transaction\t161
+\tPage buffers\t\t0
+
+    Variable header data:
+\tSweep interval:\t\t20000
+\t*END*";
+
+        let stats = DatabaseStats::parse(report.to_string());
+
+        assert_eq!(stats.page_size, Some(8192));
+        assert_eq!(stats.page_buffers, Some(0));
+        assert_eq!(stats.oldest_transaction, Some(158));
+        assert_eq!(stats.oldest_active, Some(159));
+        assert_eq!(stats.oldest_snapshot, Some(159));
+        assert_eq!(stats.next_transaction, Some(161));
+        assert_eq!(stats.sweep_interval, Some(20000));
+        assert_eq!(stats.transaction_gap(), Some(3));
+        assert_eq!(stats.report, report);
+    }
+
+    #[test]
+    fn service_line() {
+        assert_eq!(
+            parse_line(b"\x3e\x05\x00gbak:\x01").unwrap(),
+            Some("gbak:".to_string())
+        );
+        assert_eq!(parse_line(b"\x3e\x00\x00\x01").unwrap(), None);
+        assert!(parse_line(b"\x02").is_err());
+    }
+
+    #[test]
+    fn db_in_use() {
+        let status = vec![StatusItem {
+            code: ibase::isc_lock_timeout,
+            args: vec![],
+        }];
+        let e = err_db_in_use(FbError::Sql {
+            msg: "lock time-out on wait transaction".to_string(),
+            code: -901,
+            status: status.clone(),
+        });
+        match e {
+            FbError::Sql {
+                msg,
+                code,
+                status: s,
+            } => {
+                assert!(msg.starts_with("The database is in use by other attachments"));
+                assert!(msg.ends_with("lock time-out on wait transaction"));
+                assert_eq!(-901, code);
+                assert_eq!(status, s);
+            }
+            e => panic!("Unexpected error: {:?}", e),
+        }
+
+        // Other errors are unchanged
+        let e = err_db_in_use(FbError::Sql {
+            msg: "I/O error".to_string(),
+            code: -902,
+            status: vec![],
+        });
+        assert!(matches!(e, FbError::Sql { msg, .. } if msg == "I/O error"));
+    }
+}
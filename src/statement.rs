@@ -5,16 +5,25 @@
 //!
 
 use crate::{
+    connection::{db_info, stmt_timeout_millis},
+    trace::{check_open_cursors, StmtTrace},
     transaction::{Transaction, TransactionData},
     Connection,
 };
 use rsfbclient_core::{
-    Column, FbError, FirebirdClient, FreeStmtOp, FromRow, IntoParams, NamedParams, StmtType,
+    ibase, Column, ColumnMeta, Dialect, FbError, FetchScroll, FirebirdClient,
+    FirebirdClientScrollOps, FreeStmtOp, FromRow, IntoParams, NamedParams, ParamMeta, ParamsType,
+    SqlType, StmtType,
 };
+use std::{time::Duration, vec};
 
 pub struct Statement<'c, 't, C: FirebirdClient> {
     pub(crate) data: StatementData<C>,
     pub(crate) tr: &'t mut Transaction<'c, C>,
+    /// Read the attachment counters on each execution, for the stats
+    pub(crate) collect_stats: bool,
+    /// Attachment counters at the start of the last execution
+    pub(crate) stats_start: Option<ReadCounters>,
 }
 
 impl<'c, 't, C> Statement<'c, 't, C>
@@ -29,7 +38,167 @@ where
     ) -> Result<Self, FbError> {
         let data = StatementData::prepare(tr.conn, &mut tr.data, sql, named_params)?;
 
-        Ok(Statement { data, tr })
+        Ok(Statement {
+            data,
+            tr,
+            collect_stats: false,
+            stats_start: None,
+        })
+    }
+
+    /// Prepare the statement that will be executed, parsing the sql with the dialect
+    /// instead of the one configured in the connection.
+    ///
+    /// See [`Connection::execute_with_dialect`] for the effects of the dialect
+    pub fn prepare_with_dialect(
+        tr: &'t mut Transaction<'c, C>,
+        sql: &str,
+        named_params: bool,
+        dialect: Dialect,
+    ) -> Result<Self, FbError> {
+        let data =
+            StatementData::prepare_with_dialect(tr.conn, &mut tr.data, sql, named_params, dialect)?;
+
+        Ok(Statement {
+            data,
+            tr,
+            collect_stats: false,
+            stats_start: None,
+        })
+    }
+
+    /// Description of the columns returned by the statement, available without fetching any row
+    pub fn columns(&mut self) -> Result<Vec<ColumnMeta>, FbError> {
+        self.data.columns(self.tr.conn)
+    }
+
+    /// Type of the statement, as identified by the server on the prepare
+    pub fn stmt_type(&self) -> StmtType {
+        self.data.stmt_type
+    }
+
+    /// The execution returns rows, to be read with [`query`][`Statement::query`],
+    /// otherwise only the count of affected rows, from [`execute`][`Statement::execute`].
+    ///
+    /// True for the selects, and for the procedures, `EXECUTE BLOCK` and the
+    /// statements with `RETURNING` that have outputs
+    pub fn produces_rows(&self) -> bool {
+        self.data.produces_rows()
+    }
+
+    /// Description of the parameters expected by the statement, in the order of the `?`
+    pub fn params_meta(&mut self) -> Result<Vec<ParamMeta>, FbError> {
+        self.data.params_meta(self.tr.conn)
+    }
+
+    /// Number of parameters expected by the statement, the count of `?` in the sql
+    /// prepared. Read from the server on the first call, and kept for the next ones
+    pub fn param_count(&mut self) -> Result<usize, FbError> {
+        self.data.param_count(self.tr.conn)
+    }
+
+    /// Sql text of the statement, as informed on the prepare
+    pub fn sql(&self) -> &str {
+        self.data.sql()
+    }
+
+    /// Sql text sent to the server, with the named parameters, if used, replaced by `?`
+    pub fn prepared_sql(&self) -> &str {
+        self.data.prepared_sql()
+    }
+
+    /// Name the cursor opened by the statement, so the rows fetched can be changed with
+    /// `UPDATE ... WHERE CURRENT OF name` or `DELETE ... WHERE CURRENT OF name`.
+    ///
+    /// Must be called before the `query`. The positioned statements must run in the same
+    /// transaction, while the cursor is open, changing the last row fetched. Use
+    /// `StatementFetch::transaction` to run them between the fetches. As they are bound to
+    /// the cursor when prepared, they must not come from the statement cache, so prepare
+    /// them with `Statement::prepare` or use `execute_immediate`. The select should have
+    /// a `FOR UPDATE` clause, so each row is fetched only when requested, and in the pure
+    /// rust client the fetch size must be 1, the default
+    ///
+    /// ```no_run
+    /// # use rsfbclient::{prelude::*, FbError, Statement};
+    /// # fn main() -> Result<(), FbError> {
+    /// # let mut conn = rsfbclient::builder_native().with_dyn_link().with_remote().connect()?;
+    /// conn.with_transaction(|tr| {
+    ///     let mut stmt = tr.prepare("select id, price from products for update", false)?;
+    ///     stmt.set_cursor_name("PRODUCTS_CUR")?;
+    ///
+    ///     let mut rows = stmt.query::<(i32, f64), _>(())?;
+    ///     while let Some((_id, price)) = rows.fetch()? {
+    ///         let mut update = Statement::prepare(
+    ///             rows.transaction(),
+    ///             "update products set price = ? where current of products_cur",
+    ///             false,
+    ///         )?;
+    ///         update.execute((price * 1.1,))?;
+    ///     }
+    ///
+    ///     Ok(())
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_cursor_name(&mut self, name: &str) -> Result<(), FbError> {
+        self.data.set_cursor_name(self.tr.conn, name)
+    }
+
+    /// Set the execution timeout of the statement, overriding the connection default.
+    /// When the timeout expires, the execution fails with `FbError::Timeout`.
+    /// Firebird counts it in milliseconds, so a shorter one is rounded up to 1 ms,
+    /// and a zero disables it. Sent to the server with each execution.
+    ///
+    /// Requires Firebird 4+, and the wire protocol 16 in the pure rust client
+    pub fn set_timeout(&mut self, timeout: Duration) -> Result<(), FbError> {
+        self.data.set_timeout(self.tr.conn, timeout)
+    }
+
+    /// Collect the statistics of the next executions, read with
+    /// [`stats`][`Statement::stats`]. Default: false
+    ///
+    /// Adds a round trip to each execution, to read the counters of the attachment
+    pub fn set_collect_stats(&mut self, collect: bool) {
+        self.collect_stats = collect;
+        self.stats_start = None;
+    }
+
+    /// Work done by the server in the last execution, including the fetches of its rows,
+    /// to find the statements reading too many rows or pages.
+    /// Requires [`set_collect_stats`][`Statement::set_collect_stats`] before the execution.
+    ///
+    /// The rows changed and selected are counted by the statement. The reads and fetches
+    /// are the increase of the attachment counters since the execution started, so they
+    /// include the other statements run in the connection meanwhile. The page counters are
+    /// kept for the whole database, including the work of the other attachments
+    pub fn stats(&mut self) -> Result<StatementStats, FbError> {
+        let start = self.stats_start.ok_or(
+            "No execution with the stats collection enabled, call `set_collect_stats` before it",
+        )?;
+        let now = ReadCounters::read(self.tr.conn)?;
+
+        let (selected, inserted, updated, deleted) = self.data.records(self.tr.conn)?;
+
+        Ok(StatementStats {
+            selected,
+            inserted,
+            updated,
+            deleted,
+            seq_reads: now.seq_reads.saturating_sub(start.seq_reads),
+            idx_reads: now.idx_reads.saturating_sub(start.idx_reads),
+            fetches: now.fetches.saturating_sub(start.fetches),
+            page_reads: now.page_reads.saturating_sub(start.page_reads),
+        })
+    }
+
+    /// Read the attachment counters before an execution, if collecting the stats
+    fn start_stats(&mut self) -> Result<(), FbError> {
+        if self.collect_stats {
+            self.stats_start = Some(ReadCounters::read(self.tr.conn)?);
+        }
+
+        Ok(())
     }
 
     /// Execute the current statement, returning a
@@ -40,19 +209,28 @@ where
     where
         T: IntoParams,
     {
-        self.data.execute(self.tr.conn, &mut self.tr.data, params)
+        self.start_stats()?;
+
+        let params = params.to_params();
+        self.data.execute(self.tr.conn, &mut self.tr.data, &params)
     }
 
     /// Execute the current statement
     /// and returns the lines founds
     ///
+    /// The procedures and `EXECUTE BLOCK`s without `SUSPEND` return
+    /// their output parameters as a single row
+    ///
     /// Use `()` for no parameters or a tuple of parameters
     pub fn query<'s, R, P>(&'s mut self, params: P) -> Result<StatementFetch<'c, 's, R, C>, FbError>
     where
         R: FromRow,
         P: IntoParams,
     {
-        self.data.query(self.tr.conn, &mut self.tr.data, params)?;
+        self.start_stats()?;
+
+        let params = params.to_params();
+        self.data.query(self.tr.conn, &mut self.tr.data, &params)?;
 
         Ok(StatementFetch {
             stmt: &mut self.data,
@@ -62,12 +240,48 @@ where
     }
 }
 
+impl<'c, 't, C> Statement<'c, 't, C>
+where
+    C: FirebirdClient + FirebirdClientScrollOps,
+{
+    /// Execute the current statement, opening a scrollable cursor
+    /// that can move backward and jump to any row of the result set.
+    ///
+    /// Firebird sets the scrollable flag when the cursor is opened, so only the cursors
+    /// opened by this method are scrollable, the `query` cursors can only move forward.
+    /// Requires Firebird 5+ and the pure rust client with the wire protocol 18, enabled
+    /// with `with_protocol_version(ProtocolVersion::V18)`. The native client returns an error
+    ///
+    /// Use `()` for no parameters or a tuple of parameters
+    pub fn query_scrollable<'s, R, P>(
+        &'s mut self,
+        params: P,
+    ) -> Result<ScrollableCursor<'c, 's, R, C>, FbError>
+    where
+        R: FromRow,
+        P: IntoParams,
+    {
+        self.start_stats()?;
+
+        let params = params.to_params();
+        self.data
+            .query_scrollable(self.tr.conn, &mut self.tr.data, &params)?;
+
+        Ok(ScrollableCursor {
+            stmt: &mut self.data,
+            tr: self.tr,
+            _marker: Default::default(),
+        })
+    }
+}
+
 impl<C> Drop for Statement<'_, '_, C>
 where
     C: FirebirdClient,
 {
     fn drop(&mut self) {
-        self.data.close(self.tr.conn).ok();
+        let res = self.data.close(self.tr.conn);
+        self.tr.conn.keep_cleanup_error(res);
     }
 }
 /// Cursor to fetch the results of a statement
@@ -90,6 +304,27 @@ where
             .fetch(self.tr.conn, &mut self.tr.data)
             .and_then(|row| row.map(FromRow::try_from).transpose())
     }
+
+    /// Fetch the values of the next row, without the column names
+    pub(crate) fn fetch_values(&mut self) -> Result<Option<Vec<SqlType>>, FbError> {
+        self.stmt.fetch_values(self.tr.conn, &mut self.tr.data)
+    }
+
+    /// Rows fetched so far by the server for the cursor (`isc_info_sql_records`),
+    /// cheap enough to feed a progress bar.
+    ///
+    /// The server counts the rows as the cursor delivers them, including the ones sent
+    /// ahead in the batches of the fetch size, so the count grows while fetching and
+    /// only reaches the total after the last row. Zero before the first fetch
+    pub fn rows_fetched(&mut self) -> Result<usize, FbError> {
+        self.stmt.rows_fetched(self.tr.conn)
+    }
+
+    /// Transaction of the cursor, to run other statements while it is open,
+    /// like the positioned updates of `Statement::set_cursor_name`
+    pub fn transaction(&mut self) -> &mut Transaction<'c, C> {
+        self.tr
+    }
 }
 
 impl<T, C> Iterator for StatementFetch<'_, '_, T, C>
@@ -109,7 +344,131 @@ where
     C: FirebirdClient,
 {
     fn drop(&mut self) {
-        self.stmt.close_cursor(self.tr.conn).ok();
+        let res = self.stmt.close_cursor(self.tr.conn);
+        self.tr.conn.keep_cleanup_error(res);
+    }
+}
+
+/// Scrollable cursor to fetch the results of a statement in any order.
+///
+/// Obtained with [`Statement::query_scrollable`]. Each method moves the cursor
+/// and returns the row in the new position, or `None` if it is out of the result set
+pub struct ScrollableCursor<'c, 's, R, C: FirebirdClient> {
+    pub(crate) stmt: &'s mut StatementData<C>,
+    /// Transaction needs to be alive for the fetch to work
+    pub(crate) tr: &'s mut Transaction<'c, C>,
+    /// Type to convert the rows
+    _marker: std::marker::PhantomData<R>,
+}
+
+impl<'c, 's, R, C> ScrollableCursor<'c, 's, R, C>
+where
+    R: FromRow,
+    C: FirebirdClient + FirebirdClientScrollOps,
+{
+    /// Move to the next row
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Result<Option<R>, FbError> {
+        self.fetch(FetchScroll::Next)
+    }
+
+    /// Move to the previous row
+    pub fn prior(&mut self) -> Result<Option<R>, FbError> {
+        self.fetch(FetchScroll::Prior)
+    }
+
+    /// Move to the first row
+    pub fn first(&mut self) -> Result<Option<R>, FbError> {
+        self.fetch(FetchScroll::First)
+    }
+
+    /// Move to the last row
+    pub fn last(&mut self) -> Result<Option<R>, FbError> {
+        self.fetch(FetchScroll::Last)
+    }
+
+    /// Move to the row in the position, starting from 1
+    pub fn absolute(&mut self, position: i32) -> Result<Option<R>, FbError> {
+        self.fetch(FetchScroll::Absolute(position))
+    }
+
+    /// Move by the offset from the current row. Negative values move backward
+    pub fn relative(&mut self, offset: i32) -> Result<Option<R>, FbError> {
+        self.fetch(FetchScroll::Relative(offset))
+    }
+
+    fn fetch(&mut self, op: FetchScroll) -> Result<Option<R>, FbError> {
+        self.stmt
+            .fetch_scroll(self.tr.conn, &mut self.tr.data, op)
+            .and_then(|row| row.map(FromRow::try_from).transpose())
+    }
+}
+
+impl<R, C> Drop for ScrollableCursor<'_, '_, R, C>
+where
+    C: FirebirdClient,
+{
+    fn drop(&mut self) {
+        let res = self.stmt.close_cursor(self.tr.conn);
+        self.tr.conn.keep_cleanup_error(res);
+    }
+}
+
+/// Work done by the server in an execution of a statement, from [`Statement::stats`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StatementStats {
+    /// Rows selected, returned by the queries or read to be changed
+    pub selected: u64,
+    /// Rows inserted
+    pub inserted: u64,
+    /// Rows updated
+    pub updated: u64,
+    /// Rows deleted
+    pub deleted: u64,
+    /// Rows read by full scans of the tables
+    pub seq_reads: u64,
+    /// Rows read using an index
+    pub idx_reads: u64,
+    /// Pages read from the cache
+    pub fetches: u64,
+    /// Pages read from the disk
+    pub page_reads: u64,
+}
+
+/// Counters of the rows and pages read, kept by the server since the attachment
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ReadCounters {
+    seq_reads: u64,
+    idx_reads: u64,
+    fetches: u64,
+    page_reads: u64,
+}
+
+impl ReadCounters {
+    /// Read the counters of the connection
+    fn read<C: FirebirdClient>(conn: &mut Connection<C>) -> Result<Self, FbError> {
+        let seq_reads = ibase::db_info_types_isc_info_read_seq_count as u8;
+        let idx_reads = ibase::db_info_types_isc_info_read_idx_count as u8;
+        let fetches = ibase::db_info_types_isc_info_fetches as u8;
+        let page_reads = ibase::db_info_types_isc_info_reads as u8;
+
+        let buffer = conn.db_info(
+            &[
+                seq_reads,
+                idx_reads,
+                fetches,
+                page_reads,
+                ibase::isc_info_end as u8,
+            ],
+            16 * 1024,
+        )?;
+
+        Ok(Self {
+            seq_reads: db_info::parse_table_counts(&buffer, seq_reads)?,
+            idx_reads: db_info::parse_table_counts(&buffer, idx_reads)?,
+            fetches: db_info::parse_int(&buffer, fetches)?,
+            page_reads: db_info::parse_int(&buffer, page_reads)?,
+        })
     }
 }
 
@@ -119,7 +478,20 @@ where
 pub struct StatementData<C: FirebirdClient> {
     pub(crate) handle: C::StmtHandle,
     pub(crate) stmt_type: StmtType,
+    /// The execution returns rows, from a cursor or from the procedure outputs
+    produces_rows: bool,
     named_params: NamedParams,
+    /// Sql text as informed, before the named parameters are replaced
+    raw_sql: String,
+    /// Trace of the last execution, open until all rows are fetched
+    trace: StmtTrace,
+    /// Rows returned directly by the execution of a procedure or `EXECUTE BLOCK`
+    /// without `SUSPEND`, fetched instead of the cursor, as none is opened
+    exec_rows: Option<vec::IntoIter<Vec<Column>>>,
+    /// A cursor was opened by the execution, counted in the connection
+    cursor_open: bool,
+    /// Number of parameters, read from the server when first requested
+    param_count: Option<usize>,
 }
 
 impl<C: FirebirdClient> StatementData<C>
@@ -132,6 +504,18 @@ where
         tr: &mut TransactionData<C>,
         raw_sql: &str,
         named_params: bool,
+    ) -> Result<Self, FbError> {
+        let dialect = conn.dialect;
+        Self::prepare_with_dialect(conn, tr, raw_sql, named_params, dialect)
+    }
+
+    /// Prepare the statement that will be executed, parsing the sql with the dialect
+    pub fn prepare_with_dialect(
+        conn: &mut Connection<C>,
+        tr: &mut TransactionData<C>,
+        raw_sql: &str,
+        named_params: bool,
+        dialect: Dialect,
     ) -> Result<Self, FbError> {
         let named_params = if named_params {
             NamedParams::parse(raw_sql)?
@@ -140,35 +524,62 @@ where
         };
         let sql = &named_params.sql;
 
-        let (stmt_type, handle) =
-            conn.cli
-                .prepare_statement(&mut conn.handle, &mut tr.handle, conn.dialect, sql)?;
+        let (stmt_type, mut handle) =
+            StmtTrace::start(&conn.trace, "prepare", sql, &[]).in_span(|| {
+                conn.cli
+                    .prepare_statement(&mut conn.handle, &mut tr.handle, dialect, sql)
+            })?;
+
+        let produces_rows = match stmt_type {
+            StmtType::Select | StmtType::SelectForUpd => true,
+            // Also used by the `INSERT`, `UPDATE`, `DELETE` and `MERGE` with `RETURNING`
+            StmtType::ExecProcedure => !conn.cli.columns(&mut handle)?.is_empty(),
+            _ => false,
+        };
+
+        // The default timeout of the connection goes with the statement,
+        // so it is not kept in the session
+        if let Some(timeout) = conn.stmt_timeout {
+            let timeout_res = conn
+                .cli
+                .set_statement_timeout(&mut handle, stmt_timeout_millis(timeout));
+
+            if let Err(e) = timeout_res {
+                conn.cli.free_statement(&mut handle, FreeStmtOp::Drop).ok();
+                return Err(e);
+            }
+        }
 
         Ok(Self {
             stmt_type,
+            produces_rows,
             handle,
             named_params,
+            raw_sql: raw_sql.to_string(),
+            trace: Default::default(),
+            exec_rows: None,
+            cursor_open: false,
+            param_count: None,
         })
     }
 
     /// Execute the current statement without returnig any row
     ///
     /// Use `()` for no parameters or a tuple of parameters
-    pub fn execute<T>(
+    pub fn execute(
         &mut self,
         conn: &mut Connection<C>,
         tr: &mut TransactionData<C>,
-        params: T,
-    ) -> Result<usize, FbError>
-    where
-        T: IntoParams,
-    {
-        let rows_count = conn.cli.execute(
-            &mut conn.handle,
-            &mut tr.handle,
-            &mut self.handle,
-            self.named_params.convert(params)?,
-        )?;
+        params: &ParamsType,
+    ) -> Result<usize, FbError> {
+        let params = self.named_params.convert_ref(params)?;
+
+        let mut trace = StmtTrace::start(&conn.trace, "execute", &self.named_params.sql, &params);
+        let rows_count = trace.in_span(|| {
+            conn.cli
+                .execute(&mut conn.handle, &mut tr.handle, &mut self.handle, &params)
+        })?;
+        trace.add_rows(rows_count);
 
         if self.stmt_type == StmtType::Select {
             // Close the cursor, as it will not be used
@@ -181,42 +592,67 @@ where
     /// Execute the current statement with input and returns a single row
     ///
     /// Use `()` for no parameters or a tuple of parameters
-    pub fn execute2<T>(
+    pub fn execute2(
         &mut self,
         conn: &mut Connection<C>,
         tr: &mut TransactionData<C>,
-        params: T,
-    ) -> Result<Vec<Column>, FbError>
-    where
-        T: IntoParams,
-    {
-        conn.cli.execute2(
-            &mut conn.handle,
-            &mut tr.handle,
-            &mut self.handle,
-            self.named_params.convert(params)?,
-        )
+        params: &ParamsType,
+    ) -> Result<Vec<Column>, FbError> {
+        let params = self.named_params.convert_ref(params)?;
+
+        let mut trace = StmtTrace::start(&conn.trace, "execute2", &self.named_params.sql, &params);
+        let row = trace.in_span(|| {
+            conn.cli
+                .execute2(&mut conn.handle, &mut tr.handle, &mut self.handle, &params)
+        })?;
+        trace.add_rows(1);
+
+        Ok(row)
     }
 
     /// Execute the current statement
     /// and returns the affected rows count
     ///
     /// Use `()` for no parameters or a tuple of parameters
-    pub fn query<'s, T>(
+    pub fn query<'s>(
         &'s mut self,
         conn: &'s mut Connection<C>,
         tr: &mut TransactionData<C>,
-        params: T,
-    ) -> Result<usize, FbError>
-    where
-        T: IntoParams,
-    {
-        conn.cli.execute(
-            &mut conn.handle,
-            &mut tr.handle,
-            &mut self.handle,
-            self.named_params.convert(params)?,
-        )
+        params: &ParamsType,
+    ) -> Result<usize, FbError> {
+        let params = self.named_params.convert_ref(params)?;
+
+        self.trace = StmtTrace::start(&conn.trace, "query", &self.named_params.sql, &params);
+        self.exec_rows = None;
+
+        if self.stmt_type == StmtType::ExecProcedure {
+            // The output values, if any, are returned by the execution itself
+            let rows = self.trace.in_span(|| {
+                if self.produces_rows {
+                    conn.cli
+                        .execute2(&mut conn.handle, &mut tr.handle, &mut self.handle, &params)
+                        .map(|row| vec![row])
+                } else {
+                    conn.cli
+                        .execute(&mut conn.handle, &mut tr.handle, &mut self.handle, &params)
+                        .map(|_| vec![])
+                }
+            })?;
+            self.exec_rows = Some(rows.into_iter());
+
+            return Ok(0);
+        }
+
+        let affected = self.trace.in_span(|| {
+            conn.cli
+                .execute(&mut conn.handle, &mut tr.handle, &mut self.handle, &params)
+        })?;
+
+        if self.produces_rows {
+            self.cursor_opened(conn);
+        }
+
+        Ok(affected)
     }
 
     /// Fetch for the next row, needs to be called after `query`
@@ -225,19 +661,222 @@ where
         conn: &mut Connection<C>,
         tr: &mut TransactionData<C>,
     ) -> Result<Option<Vec<Column>>, FbError> {
+        let row = match &mut self.exec_rows {
+            Some(rows) => Ok(rows.next()),
+            None => self.trace.in_span(|| {
+                conn.cli
+                    .fetch(&mut conn.handle, &mut tr.handle, &mut self.handle)
+            }),
+        };
+        self.trace_fetch(&row);
+
+        row
+    }
+
+    /// Fetch the values of the next row, like `fetch`, without the column names
+    pub fn fetch_values(
+        &mut self,
+        conn: &mut Connection<C>,
+        tr: &mut TransactionData<C>,
+    ) -> Result<Option<Vec<SqlType>>, FbError> {
+        let row = match &mut self.exec_rows {
+            Some(rows) => Ok(rows
+                .next()
+                .map(|cols| cols.into_iter().map(|col| col.value).collect())),
+            None => self.trace.in_span(|| {
+                conn.cli
+                    .fetch_values(&mut conn.handle, &mut tr.handle, &mut self.handle)
+            }),
+        };
+        self.trace_fetch(&row);
+
+        row
+    }
+
+    /// Count the row fetched, or finish the trace after the last one
+    fn trace_fetch<T>(&mut self, row: &Result<Option<T>, FbError>) {
+        match row {
+            Ok(Some(_)) => self.trace.add_rows(1),
+            Ok(None) => self.trace.finish(),
+            Err(_) => {}
+        }
+    }
+
+    /// Execute the current statement, opening a scrollable cursor
+    ///
+    /// Use `()` for no parameters or a tuple of parameters
+    pub fn query_scrollable(
+        &mut self,
+        conn: &mut Connection<C>,
+        tr: &mut TransactionData<C>,
+        params: &ParamsType,
+    ) -> Result<(), FbError>
+    where
+        C: FirebirdClientScrollOps,
+    {
+        let params = self.named_params.convert_ref(params)?;
+
+        self.trace = StmtTrace::start(
+            &conn.trace,
+            "query_scrollable",
+            &self.named_params.sql,
+            &params,
+        );
+        self.trace.in_span(|| {
+            conn.cli
+                .execute_scrollable(&mut conn.handle, &mut tr.handle, &mut self.handle, &params)
+        })?;
+        self.cursor_opened(conn);
+
+        Ok(())
+    }
+
+    /// Move the scrollable cursor and fetch the row, needs to be called after `query_scrollable`
+    pub fn fetch_scroll(
+        &mut self,
+        conn: &mut Connection<C>,
+        tr: &mut TransactionData<C>,
+        op: FetchScroll,
+    ) -> Result<Option<Vec<Column>>, FbError>
+    where
+        C: FirebirdClientScrollOps,
+    {
+        let row = self.trace.in_span(|| {
+            conn.cli
+                .fetch_scroll(&mut conn.handle, &mut tr.handle, &mut self.handle, op)
+        });
+
+        if let Ok(Some(_)) = &row {
+            self.trace.add_rows(1);
+        }
+
+        row
+    }
+
+    /// The execution returns rows
+    pub fn produces_rows(&self) -> bool {
+        self.produces_rows
+    }
+
+    /// Description of the columns returned by the statement
+    pub fn columns(&mut self, conn: &mut Connection<C>) -> Result<Vec<ColumnMeta>, FbError> {
+        conn.cli.columns(&mut self.handle)
+    }
+
+    /// Check the number of columns returned against the one expected by the row type
+    pub fn check_columns<R: FromRow>(&mut self, conn: &mut Connection<C>) -> Result<(), FbError> {
+        match R::COLUMNS {
+            Some(expected) => {
+                let found = self.columns(conn)?.len();
+                if found != expected {
+                    return Err(format!(
+                        "The query returns {} columns, expected {}",
+                        found, expected
+                    )
+                    .into());
+                }
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Description of the parameters expected by the statement
+    pub fn params_meta(&mut self, conn: &mut Connection<C>) -> Result<Vec<ParamMeta>, FbError> {
+        let params = conn.cli.params(&mut self.handle)?;
+        self.param_count = Some(params.len());
+
+        Ok(params)
+    }
+
+    /// Number of parameters expected by the statement
+    pub fn param_count(&mut self, conn: &mut Connection<C>) -> Result<usize, FbError> {
+        match self.param_count {
+            Some(count) => Ok(count),
+            None => Ok(self.params_meta(conn)?.len()),
+        }
+    }
+
+    /// Sql text as informed on the prepare
+    pub fn sql(&self) -> &str {
+        &self.raw_sql
+    }
+
+    /// Sql text prepared, with the named parameters replaced by `?`
+    pub fn prepared_sql(&self) -> &str {
+        &self.named_params.sql
+    }
+
+    /// Rows selected, inserted, updated and deleted by the last execution
+    pub fn records(&mut self, conn: &mut Connection<C>) -> Result<(u64, u64, u64, u64), FbError> {
+        let info = conn.cli.statement_info(
+            &mut self.handle,
+            &[ibase::isc_info_sql_records as u8, ibase::isc_info_end as u8],
+            128,
+        )?;
+
+        db_info::parse_records(&info)
+    }
+
+    /// Rows selected so far by the server for the open cursor, from `isc_info_sql_records`
+    pub fn rows_fetched(&mut self, conn: &mut Connection<C>) -> Result<usize, FbError> {
+        let (selected, _, _, _) = self.records(conn)?;
+
+        Ok(selected as usize)
+    }
+
+    /// Name the cursor of the statement
+    pub fn set_cursor_name(&mut self, conn: &mut Connection<C>, name: &str) -> Result<(), FbError> {
+        conn.cli.set_cursor_name(&mut self.handle, name)
+    }
+
+    /// Set the execution timeout, sent with the next executions
+    pub fn set_timeout(
+        &mut self,
+        conn: &mut Connection<C>,
+        timeout: Duration,
+    ) -> Result<(), FbError> {
         conn.cli
-            .fetch(&mut conn.handle, &mut tr.handle, &mut self.handle)
+            .set_statement_timeout(&mut self.handle, stmt_timeout_millis(timeout))
     }
 
     /// Closes the statement cursor, if it was open
     pub fn close_cursor(&mut self, conn: &mut Connection<C>) -> Result<(), FbError> {
+        self.trace.finish();
+        self.cursor_closed(conn);
+
+        if self.exec_rows.take().is_some() {
+            // No cursor was opened by the execution
+            return Ok(());
+        }
+
         conn.cli.free_statement(&mut self.handle, FreeStmtOp::Close)
     }
 
     /// Closes the statement
     pub fn close(&mut self, conn: &mut Connection<C>) -> Result<(), FbError> {
+        self.cursor_closed(conn);
+
         conn.cli.free_statement(&mut self.handle, FreeStmtOp::Drop)
     }
+
+    /// Count the cursor opened by the execution in the connection
+    fn cursor_opened(&mut self, conn: &mut Connection<C>) {
+        if !self.cursor_open {
+            self.cursor_open = true;
+            conn.open_cursors += 1;
+
+            check_open_cursors(&conn.trace, conn.open_cursors);
+        }
+    }
+
+    /// Remove the cursor closed from the count of the connection
+    fn cursor_closed(&mut self, conn: &mut Connection<C>) {
+        if self.cursor_open {
+            self.cursor_open = false;
+            conn.open_cursors -= 1;
+        }
+    }
 }
 
 #[cfg(test)]
@@ -246,8 +885,9 @@ static TABLE_COUNTER: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU3
 
 #[cfg(test)]
 mk_tests_default! {
-    use crate::{prelude::*, Connection, Row};
-    use rsfbclient_core::FirebirdClient;
+    use crate::{prelude::*, Connection, EngineVersion, FbError, Row, Statement, StmtType, SystemInfos};
+    use rsfbclient_core::{ibase, FirebirdClient};
+    use std::time::Duration;
 
     #[test]
     fn new_api_select() {
@@ -420,6 +1060,468 @@ mk_tests_default! {
     //     conn.close().expect("error on close the connection");
     // }
 
+    #[test]
+    fn statement_timeout() -> Result<(), FbError> {
+        use crate::connection::stmt_timeout_millis;
+        use rsfbclient_core::StatusItem;
+
+        assert_eq!(0, stmt_timeout_millis(Duration::ZERO));
+        assert_eq!(1, stmt_timeout_millis(Duration::from_micros(100)));
+        assert_eq!(1500, stmt_timeout_millis(Duration::from_millis(1500)));
+        assert_eq!(u32::MAX, stmt_timeout_millis(Duration::from_secs(u64::MAX)));
+
+        let status = |codes: &[u32]| {
+            codes.iter().map(|&code| StatusItem { code, args: vec![] }).collect()
+        };
+        let err = FbError::from_status(-901, String::new(), status(&[ibase::isc_cancelled, ibase::isc_req_stmt_timeout]));
+        assert!(matches!(err, FbError::Timeout { .. }));
+        let err = FbError::from_status(-901, String::new(), status(&[ibase::isc_cancelled, ibase::isc_random]));
+        assert!(matches!(err, FbError::Cancelled { .. }));
+
+        let mut conn = cbuilder().connect()?;
+
+        if conn.server_engine()? < EngineVersion::V4 {
+            return Ok(());
+        }
+
+        conn.with_transaction(|tr| {
+            for timeout in [Duration::from_millis(1), Duration::from_micros(100)] {
+                let mut stmt = tr.prepare(
+                    "select count(*) from rdb$types a, rdb$types b, rdb$types c",
+                    false,
+                )?;
+                stmt.set_timeout(timeout)?;
+
+                let res = stmt
+                    .query::<(i64,), _>(())
+                    .and_then(|mut rows| rows.fetch());
+
+                assert!(matches!(res, Err(FbError::Timeout { .. })));
+            }
+
+            Ok(())
+        })?;
+
+        // Sent with the statements, without changing the session timeout
+        let session_timeout = "select rdb$get_context('SYSTEM', 'STATEMENT_TIMEOUT') from rdb$database";
+        let (timeout,): (String,) = conn.query_first(session_timeout, ())?.unwrap();
+        assert_eq!("0", timeout);
+
+        let mut conn = cbuilder()
+            .with_statement_timeout(Duration::from_millis(1))
+            .connect()?;
+
+        let res: Result<Option<(i64,)>, _> = conn.query_first(
+            "select count(*) from rdb$types a, rdb$types b, rdb$types c",
+            (),
+        );
+        assert!(matches!(res, Err(FbError::Timeout { .. })));
+
+        let (timeout,): (String,) = conn.query_first(session_timeout, ())?.unwrap();
+        assert_eq!("0", timeout);
+
+        Ok(())
+    }
+
+    #[test]
+    fn columns_meta() -> Result<(), FbError> {
+        let (mut conn, table) = setup();
+
+        conn.with_transaction(|tr| {
+            let mut stmt = tr.prepare(
+                &format!("select id, name as product, quantity * 2 from {}", table),
+                false,
+            )?;
+
+            let cols = stmt.columns()?;
+            assert_eq!(3, cols.len());
+
+            assert_eq!("ID", cols[0].name);
+            assert_eq!("ID", cols[0].alias);
+            assert_eq!(table.to_uppercase(), cols[0].relation);
+            assert_eq!(ibase::SQL_LONG, cols[0].sql_type);
+            assert_eq!(4, cols[0].length);
+            assert_eq!(0, cols[0].scale);
+            assert!(cols[0].nullable);
+
+            assert_eq!("NAME", cols[1].name);
+            assert_eq!("PRODUCT", cols[1].alias);
+            assert_eq!(ibase::SQL_VARYING, cols[1].sql_type);
+
+            assert_eq!("", cols[2].relation);
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn params_meta() -> Result<(), FbError> {
+        let (mut conn, table) = setup();
+
+        conn.with_transaction(|tr| {
+            let mut stmt = tr.prepare(
+                &format!(
+                    "insert into {} (id, name, quantity) values (?, ?, cast(? as numeric(9, 2)))",
+                    table
+                ),
+                false,
+            )?;
+
+            let params = stmt.params_meta()?;
+            assert_eq!(3, params.len());
+
+            assert_eq!(ibase::SQL_LONG, params[0].sql_type);
+            assert_eq!(4, params[0].length);
+            assert_eq!(ibase::SQL_VARYING, params[1].sql_type);
+            assert_eq!(ibase::SQL_LONG, params[2].sql_type);
+            assert_eq!(-2, params[2].scale);
+            drop(stmt);
+
+            let mut stmt = tr.prepare(&format!("select id from {}", table), false)?;
+            assert!(stmt.params_meta()?.is_empty());
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn sql_and_param_count() -> Result<(), FbError> {
+        let (mut conn, table) = setup();
+
+        conn.with_transaction(|tr| {
+            let sql = format!("select id from {} where id = ? or name = ?", table);
+            let mut stmt = tr.prepare(&sql, false)?;
+            assert_eq!(sql, stmt.sql());
+            assert_eq!(2, stmt.param_count()?);
+            assert_eq!(2, stmt.param_count()?);
+            assert_eq!(sql, stmt.prepared_sql());
+            drop(stmt);
+
+            // The named parameters are replaced only in the prepared sql
+            let named = format!("select id from {} where id = :id or name = :name", table);
+            let mut stmt = tr.prepare(&named, true)?;
+            assert_eq!(named, stmt.sql());
+            assert_eq!(
+                format!("select id from {} where id = ? or name = ?", table),
+                stmt.prepared_sql()
+            );
+            assert_eq!(2, stmt.param_count()?);
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn reused_params() -> Result<(), FbError> {
+        let (mut conn, table) = setup();
+
+        conn.with_transaction(|tr| {
+            let mut stmt = tr.prepare(
+                &format!("insert into {} (id, name, quantity) values (?, ?, ?)", table),
+                false,
+            )?;
+
+            // Different lengths, types and nulls in the same parameters
+            stmt.execute((1, "a longer name", 10))?;
+            stmt.execute((2, "short", None::<i32>))?;
+            stmt.execute((3, None::<&str>, "30"))?;
+            stmt.execute((4, 44, 40.0))?;
+            stmt.execute((5, "", 50))?;
+            assert!(stmt.execute((6, "missing param")).is_err());
+            stmt.execute((7, "after the error", 70))?;
+            drop(stmt);
+
+            let rows: Vec<(i32, Option<String>, Option<i32>)> = tr.query(
+                &format!("select id, name, quantity from {} order by id", table),
+                (),
+            )?;
+            assert_eq!(
+                vec![
+                    (1, Some("a longer name".to_string()), Some(10)),
+                    (2, Some("short".to_string()), None),
+                    (3, None, Some(30)),
+                    (4, Some("44".to_string()), Some(40)),
+                    (5, Some("".to_string()), Some(50)),
+                    (7, Some("after the error".to_string()), Some(70)),
+                ],
+                rows
+            );
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    #[cfg(not(feature = "pure_rust"))]
+    fn describe_hint() -> Result<(), FbError> {
+        let mut conn = cbuilder().with_describe_hint(3).connect()?;
+
+        // Narrower and wider than the hint
+        let row: (i32,) = conn.query_first("select 1 from rdb$database", ())?.unwrap();
+        assert_eq!((1,), row);
+
+        let row: (i32, i32, i32, i32, i32) = conn
+            .query_first("select 1, 2, 3, 4, 5 from rdb$database", ())?
+            .unwrap();
+        assert_eq!((1, 2, 3, 4, 5), row);
+
+        Ok(())
+    }
+
+    #[test]
+    fn execute_block() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        // With `SUSPEND`, the rows are fetched from a cursor
+        let sql = "execute block (n int = ?, prefix varchar(10) = ?) returns (i int, name varchar(20)) as
+            begin
+                i = 1;
+                while (i <= n) do
+                begin
+                    name = prefix || i;
+                    suspend;
+                    i = i + 1;
+                end
+            end";
+
+        let rows: Vec<(i32, String)> = conn.query(sql, (3, "row"))?;
+        assert_eq!(
+            vec![
+                (1, "row1".to_string()),
+                (2, "row2".to_string()),
+                (3, "row3".to_string())
+            ],
+            rows
+        );
+        let rows: Vec<(i32, String)> = conn.query(sql, (0, "row"))?;
+        assert!(rows.is_empty());
+
+        // Without `SUSPEND`, the single row is returned by the execution
+        let sql = "execute block (a int = ?) returns (doubled int) as
+            begin
+                doubled = a * 2;
+            end";
+
+        let rows: Vec<(i32,)> = conn.query(sql, (21,))?;
+        assert_eq!(vec![(42,)], rows);
+        // Reusing the cached statement
+        let rows: Vec<(i32,)> = conn.query(sql, (5,))?;
+        assert_eq!(vec![(10,)], rows);
+
+        conn.with_transaction(|tr| {
+            let mut stmt = tr.prepare(sql, false)?;
+
+            let mut rows = stmt.query::<(i32,), _>((4,))?;
+            assert_eq!(Some((8,)), rows.fetch()?);
+            assert_eq!(None, rows.fetch()?);
+            drop(rows);
+
+            let mut rows = stmt.query::<(i32,), _>((6,))?;
+            assert_eq!(Some((12,)), rows.fetch()?);
+            drop(rows);
+            drop(stmt);
+
+            let row: (i32,) = tr.execute_returnable(sql, (1,))?;
+            assert_eq!((2,), row);
+
+            // Without outputs, no rows are returned
+            let rows: Vec<(i32,)> =
+                tr.query("execute block (a int = ?) as declare b int; begin b = a; end", (1,))?;
+            assert!(rows.is_empty());
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn positioned_update() -> Result<(), FbError> {
+        let (mut conn, table) = setup();
+
+        conn.with_transaction(|tr| {
+            for (id, quantity) in [(1, 10), (2, 20), (3, 30)] {
+                tr.execute(
+                    &format!("insert into {} (id, quantity) values (?, ?)", table),
+                    (id, quantity),
+                )?;
+            }
+
+            let mut stmt = tr.prepare(
+                &format!("select id, quantity from {} order by id for update", table),
+                false,
+            )?;
+            stmt.set_cursor_name("POSITIONED_CUR")?;
+
+            let mut rows = stmt.query::<(i32, i32), _>(())?;
+            while let Some((id, quantity)) = rows.fetch()? {
+                if id == 2 {
+                    rows.transaction().execute_immediate(&format!(
+                        "delete from {} where current of positioned_cur",
+                        table
+                    ))?;
+                    continue;
+                }
+
+                let mut update = Statement::prepare(
+                    rows.transaction(),
+                    &format!(
+                        "update {} set quantity = ? where current of positioned_cur",
+                        table
+                    ),
+                    false,
+                )?;
+                update.execute((quantity + 1,))?;
+            }
+            drop(rows);
+            drop(stmt);
+
+            let rows: Vec<(i32, i32)> =
+                tr.query(&format!("select id, quantity from {} order by id", table), ())?;
+            assert_eq!(vec![(1, 11), (3, 31)], rows);
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    #[cfg(all(feature = "pure_rust", not(feature = "native_client")))]
+    fn scrollable_cursor() -> Result<(), FbError> {
+        let mut conn = crate::builder_pure_rust()
+            .with_protocol_version(crate::ProtocolVersion::V18)
+            .connect()?;
+
+        if conn.server_engine()? < EngineVersion::V5 {
+            return Ok(());
+        }
+
+        let sql = "select r from (
+                select 1 r from rdb$database union all select 2 from rdb$database union all
+                select 3 from rdb$database union all select 4 from rdb$database union all
+                select 5 from rdb$database
+            ) order by r";
+
+        conn.with_transaction(|tr| {
+            let mut stmt = tr.prepare(sql, false)?;
+            let mut cursor = stmt.query_scrollable::<(i32,), _>(())?;
+
+            assert_eq!(Some((1,)), cursor.next()?);
+            assert_eq!(Some((2,)), cursor.next()?);
+            assert_eq!(Some((1,)), cursor.prior()?);
+            assert_eq!(None, cursor.prior()?);
+            assert_eq!(Some((5,)), cursor.last()?);
+            assert_eq!(None, cursor.next()?);
+            assert_eq!(Some((5,)), cursor.prior()?);
+            assert_eq!(Some((1,)), cursor.first()?);
+            assert_eq!(Some((4,)), cursor.absolute(4)?);
+            assert_eq!(Some((2,)), cursor.relative(-2)?);
+            assert_eq!(None, cursor.relative(10)?);
+            assert_eq!(Some((3,)), cursor.absolute(3)?);
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn scrollable_cursor_unsupported() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        // Fails in the native client, and before the wire protocol 18 in the pure rust one
+        conn.with_transaction(|tr| {
+            let mut stmt = tr.prepare("select 1 from rdb$database", false)?;
+            assert!(stmt.query_scrollable::<(i32,), _>(()).is_err());
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn statement_stats() -> Result<(), FbError> {
+        let (mut conn, table) = setup();
+
+        conn.with_transaction(|tr| {
+            let mut stmt = tr.prepare(&format!("insert into {} (id) values (?)", table), false)?;
+            assert!(stmt.stats().is_err());
+
+            stmt.set_collect_stats(true);
+            for id in 0..3 {
+                stmt.execute((id,))?;
+            }
+
+            let stats = stmt.stats()?;
+            assert_eq!(1, stats.inserted);
+            assert_eq!(0, stats.updated);
+            drop(stmt);
+
+            let mut stmt = tr.prepare(&format!("update {} set quantity = 1 where id > 0", table), false)?;
+            stmt.set_collect_stats(true);
+            stmt.execute(())?;
+
+            let stats = stmt.stats()?;
+            assert_eq!(2, stats.updated);
+            assert_eq!(3, stats.seq_reads);
+            assert!(stats.fetches > 0);
+            drop(stmt);
+
+            let mut stmt = tr.prepare(&format!("select id from {}", table), false)?;
+            stmt.set_collect_stats(true);
+            let rows = stmt.query::<(i32,), _>(())?.count();
+            assert_eq!(3, rows);
+
+            let stats = stmt.stats()?;
+            assert_eq!(3, stats.selected);
+            assert_eq!(3, stats.seq_reads);
+            assert_eq!(0, stats.idx_reads);
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn rows_fetched() -> Result<(), FbError> {
+        let (mut conn, table) = setup();
+
+        conn.with_transaction(|tr| {
+            for id in 0..3 {
+                tr.execute(&format!("insert into {} (id) values (?)", table), (id,))?;
+            }
+
+            let mut stmt = tr.prepare(&format!("select id from {} order by id desc", table), false)?;
+            let mut cursor = stmt.query::<(i32,), _>(())?;
+            assert_eq!(0, cursor.rows_fetched()?);
+
+            assert_eq!(Some((2,)), cursor.fetch()?);
+            while cursor.fetch()?.is_some() {}
+            assert_eq!(3, cursor.rows_fetched()?);
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn statement_type() -> Result<(), FbError> {
+        let (mut conn, table) = setup();
+
+        conn.with_transaction(|tr| {
+            let cases = [
+                (format!("select id from {}", table), StmtType::Select, true),
+                (format!("select id from {} for update", table), StmtType::SelectForUpd, true),
+                (format!("insert into {} (id) values (1)", table), StmtType::Insert, false),
+                (format!("insert into {} (id) values (1) returning id", table), StmtType::ExecProcedure, true),
+                (format!("update {} set quantity = 1", table), StmtType::Update, false),
+                (format!("delete from {}", table), StmtType::Delete, false),
+                ("execute block returns (a int) as begin a = 1; suspend; end".to_string(), StmtType::Select, true),
+                ("execute block as begin end".to_string(), StmtType::ExecProcedure, false),
+            ];
+
+            for (sql, stmt_type, produces_rows) in cases.iter() {
+                let stmt = tr.prepare(sql, false)?;
+                assert_eq!(*stmt_type, stmt.stmt_type(), "{}", sql);
+                assert_eq!(*produces_rows, stmt.produces_rows(), "{}", sql);
+            }
+
+            Ok(())
+        })
+    }
+
     fn setup() -> (Connection<impl FirebirdClient>, String) {
         let mut conn = cbuilder().connect()
             .expect("Error on connect in the test database");
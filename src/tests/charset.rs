@@ -5,7 +5,7 @@
 static TABLE_COUNTER: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
 
 mk_tests_default! {
-    use crate::{prelude::*, FbError};
+    use crate::{prelude::*, FbError, SystemInfos};
 
     #[test]
     fn params() -> Result<(), FbError> {
@@ -74,6 +74,50 @@ mk_tests_default! {
         Ok(())
     }
 
+    #[test]
+    fn connection_charset_none() -> Result<(), FbError> {
+        use crate::charset::NONE;
+
+        let mut conn = cbuilder().charset(NONE).connect()?;
+
+        // The text is not transliterated, so the bytes are the ones of the column charset
+        let (iso, utf, missing): (Vec<u8>, Vec<u8>, Option<Vec<u8>>) = conn
+            .query_first(
+                "select cast(x'70E36F' as varchar(10) character set iso8859_1),
+                    cast(cast(x'70E36F' as varchar(10) character set iso8859_1) as varchar(10) character set utf8),
+                    cast(null as varchar(10))
+                from rdb$database",
+                (),
+            )?
+            .unwrap();
+        assert_eq!(b"p\xE3o".to_vec(), iso);
+        assert_eq!("pão".as_bytes(), &utf[..]);
+        assert_eq!(None, missing);
+
+        // Read as string only when valid utf-8
+        let (abc, pao): (String, String) = conn
+            .query_first(
+                "select cast('abc' as varchar(10)),
+                    cast(cast(x'70E36F' as varchar(10) character set iso8859_1) as varchar(10) character set utf8)
+                from rdb$database",
+                (),
+            )?
+            .unwrap();
+        assert_eq!("abc", abc);
+        assert_eq!("pão", pao);
+
+        let res: Result<Option<(String,)>, FbError> = conn.query_first(
+            "select cast(x'70E36F' as varchar(10) character set iso8859_1) from rdb$database",
+            (),
+        );
+        assert!(res.is_err());
+
+        // The helpers reading system text still work
+        conn.server_engine()?;
+
+        Ok(())
+    }
+
     #[test]
     fn stmt_charset() -> Result<(), FbError> {
         use crate::charset::ISO_8859_1;
@@ -64,6 +64,25 @@ mk_tests_default! {
         Ok(())
     }
 
+    #[test]
+    #[cfg(all(feature = "linking", feature = "embedded_tests", not(feature = "dynamic_loading"), not(feature = "pure_rust")))]
+    fn local_conn() -> Result<(), FbError> {
+        let mut conn = builder_native()
+            .with_dyn_link()
+            .with_local()
+            .db_name("/tmp/embedded_tests.fdb")
+            .user("SYSDBA")
+            .pass("masterkey")
+            .connect()?;
+
+        let (protocol,): (Option<String>,) = conn
+            .query_first("select rdb$get_context('SYSTEM', 'NETWORK_PROTOCOL') from rdb$database", ())?
+            .unwrap();
+        assert_ne!(Some("TCPv4".to_string()), protocol);
+
+        Ok(())
+    }
+
     #[test]
     #[cfg(all(feature = "dynamic_loading", feature = "embedded_tests", not(feature = "linking"), not(feature = "pure_rust")))]
     fn string_conn4() -> Result<(), FbError> {
@@ -101,4 +120,334 @@ mk_tests_default! {
 
         Ok(())
     }
+
+    #[test]
+    #[cfg(all(feature = "pure_rust", not(feature = "native_client")))]
+    fn fetch_size() -> Result<(), FbError> {
+        use std::io::{Read, Write};
+        use std::net::TcpStream;
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+
+        // Counts the requests sent, each flushed once
+        struct Tunnel(TcpStream, Arc<AtomicUsize>);
+
+        impl Read for Tunnel {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                self.0.read(buf)
+            }
+        }
+
+        impl Write for Tunnel {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.write(buf)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                self.1.fetch_add(1, Ordering::SeqCst);
+                self.0.flush()
+            }
+        }
+
+        // 10k rows, the recursion is kept below the firebird depth limit
+        let sql = "with recursive n (i) as (
+                select 0 from rdb$database
+                union all
+                select i + 1 from n where i < 99
+            )
+            select a.i * 100 + b.i from n a cross join n b";
+
+        let mut round_trips = vec![];
+
+        for &fetch_size in &[1, 500] {
+            let requests = Arc::new(AtomicUsize::new(0));
+            let tunnel = Tunnel(TcpStream::connect(("localhost", 3050))?, requests.clone());
+
+            let mut conn = builder_pure_rust()
+                .with_fetch_size(fetch_size)
+                .from_stream(tunnel)
+                .connect()?;
+
+            let before = requests.load(Ordering::SeqCst);
+            let rows: Vec<(i64,)> = conn.query(sql, ())?;
+            round_trips.push(requests.load(Ordering::SeqCst) - before);
+
+            assert_eq!(10_000, rows.len());
+            assert_eq!((0..10_000).sum::<i64>(), rows.iter().map(|r| r.0).sum::<i64>());
+
+            // Rows still returned correctly after the statement is reused
+            let rows: Vec<(i64,)> = conn.query(sql, ())?;
+            assert_eq!(10_000, rows.len());
+        }
+
+        // One fetch request for each row, plus the prepare, execute and free
+        assert!(round_trips[0] >= 10_000, "{:?}", round_trips);
+        // One fetch request for each 500 rows
+        assert!(round_trips[1] >= 20, "{:?}", round_trips);
+        assert!(round_trips[1] <= 20 + 10, "{:?}", round_trips);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(all(feature = "pure_rust", not(feature = "native_client")))]
+    fn protocol_version() -> Result<(), FbError> {
+        let sql = "select mon$remote_version from mon$attachments where mon$attachment_id = current_connection";
+
+        let mut conn = builder_pure_rust()
+            .with_protocol_version(ProtocolVersion::V13)
+            .connect()?;
+
+        let (version,): (String,) = conn.query_first(sql, ())?.unwrap();
+        assert!(version.starts_with("P13"), "{}", version);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(all(feature = "pure_rust", not(feature = "native_client")))]
+    fn auto_reconnect() -> Result<(), FbError> {
+        let sql = "select current_connection from rdb$database";
+
+        let mut conn = builder_pure_rust().with_auto_reconnect(3).connect()?;
+        let (id,): (i64,) = conn.query_first(sql, ())?.unwrap();
+
+        // Kill the attachment from another connection
+        let mut other = builder_pure_rust().connect()?;
+        other.execute("delete from mon$attachments where mon$attachment_id = ?", (id,))?;
+
+        // The transaction in use was lost with the connection
+        let res: Result<Option<(i64,)>, _> = conn.query_first(sql, ());
+        assert!(matches!(res, Err(FbError::TransactionLost { .. })));
+
+        // Continues in a new attachment, with the statement prepared again
+        let (new_id,): (i64,) = conn.query_first(sql, ())?.unwrap();
+        assert_ne!(id, new_id);
+
+        // Explicit transactions must be rolled back after a reconnection
+        conn.begin_transaction()?;
+        other.execute("delete from mon$attachments where mon$attachment_id = ?", (new_id,))?;
+
+        let res: Result<Option<(i64,)>, _> = conn.query_first(sql, ());
+        assert!(matches!(res, Err(FbError::TransactionLost { .. })));
+        assert!(matches!(conn.commit(), Err(FbError::TransactionLost { .. })));
+
+        conn.rollback()?;
+        let res: Option<(i64,)> = conn.query_first(sql, ())?;
+        assert!(res.is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(all(feature = "pure_rust", not(feature = "native_client")))]
+    fn db_crypt_key() -> Result<(), FbError> {
+        // The key is only sent when requested, so it's ignored by the unencrypted databases
+        let mut conn = cbuilder()
+            .with_db_crypt_key("fbSampleKeyHolder", b"KeyRed").connect()?;
+
+        let (one,): (i32,) = conn.query_first("select 1 from rdb$database", ())?.unwrap();
+        assert_eq!(1, one);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(all(feature = "pure_rust", not(feature = "native_client")))]
+    fn provided_stream() -> Result<(), FbError> {
+        use std::io::{Read, Write};
+        use std::net::TcpStream;
+        use std::sync::{atomic::{AtomicUsize, Ordering}, Arc};
+
+        // Counts the bytes received, to check the stream is used
+        struct Tunnel(TcpStream, Arc<AtomicUsize>);
+
+        impl Read for Tunnel {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let len = self.0.read(buf)?;
+                self.1.fetch_add(len, Ordering::SeqCst);
+                Ok(len)
+            }
+        }
+
+        impl Write for Tunnel {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.write(buf)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                self.0.flush()
+            }
+        }
+
+        let received = Arc::new(AtomicUsize::new(0));
+        let tunnel = Tunnel(TcpStream::connect(("localhost", 3050))?, received.clone());
+
+        let mut builder = builder_pure_rust();
+        // Not used with the stream
+        builder.host("not.a.valid.host").from_stream(tunnel);
+
+        let mut conn = builder.connect()?;
+        let (one,): (i32,) = conn.query_first("select 1 from rdb$database", ())?.unwrap();
+        assert_eq!(1, one);
+        assert!(received.load(Ordering::SeqCst) > 0);
+        assert!(conn.cancel_handle().is_err());
+
+        // Only usable once
+        assert!(builder.connect().is_err());
+
+        conn.close()
+    }
+
+    #[test]
+    fn dpb_items() -> Result<(), FbError> {
+        use rsfbclient_core::ibase;
+
+        let mut conn = cbuilder()
+            .with_dpb_item(ibase::isc_dpb_sql_role_name as u8, b"RDB$ADMIN")?
+            .with_dpb_item(ibase::isc_dpb_num_buffers as u8, &100u32.to_le_bytes())?
+            .connect()?;
+
+        let (role,): (String,) = conn
+            .query_first("select current_role from rdb$database", ())?
+            .unwrap();
+        assert_eq!("RDB$ADMIN", role.trim());
+
+        assert!(cbuilder()
+            .with_dpb_item(ibase::isc_dpb_sql_role_name as u8, &[b'A'; 256])
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(not(feature = "embedded_tests"))]
+    fn client_info() -> Result<(), FbError> {
+        let mut conn = cbuilder().with_client_info("rsfbclient-tests")?.connect()?;
+
+        let (process, pid): (String, i32) = conn
+            .query_first(
+                "select mon$remote_process, mon$remote_pid from mon$attachments where mon$attachment_id = current_connection",
+                (),
+            )?
+            .unwrap();
+        assert_eq!("rsfbclient-tests", process.trim());
+        assert_eq!(std::process::id() as i32, pid);
+
+        assert!(cbuilder().with_client_info(&"A".repeat(256)).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn session_time_zone() -> Result<(), FbError> {
+        if cbuilder().connect()?.server_engine()? < EngineVersion::V4 {
+            return Ok(());
+        }
+
+        let mut conn = cbuilder()
+            .with_session_time_zone("America/Sao_Paulo")?
+            .connect()?;
+
+        let (tz,): (String,) = conn
+            .query_first("select rdb$get_context('SYSTEM', 'SESSION_TIMEZONE') from rdb$database", ())?
+            .unwrap();
+        assert_eq!("America/Sao_Paulo", tz);
+
+        // Same instant, with the offset of the session zone
+        let (same, offset): (bool, i32) = conn
+            .query_first(
+                "select cast(localtimestamp as timestamp with time zone) = current_timestamp,
+                    extract(timezone_hour from cast(timestamp '2020-06-01 12:00:00' as timestamp with time zone))
+                from rdb$database",
+                (),
+            )?
+            .unwrap();
+        assert!(same);
+        assert_eq!(-3, offset);
+
+        assert!(cbuilder().with_session_time_zone("Invalid/Zone")?.connect().is_err());
+        assert!(cbuilder().with_session_time_zone(&"A".repeat(256)).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_charset() -> Result<(), FbError> {
+        let typo = Charset {
+            on_firebird: "UTF-8",
+            on_rust: None,
+        };
+
+        let err = cbuilder()
+            .charset(typo)
+            .connect()
+            .err()
+            .expect("the charset should be rejected before connecting")
+            .to_string();
+        assert!(err.contains("Unknown firebird charset 'UTF-8'"), "{}", err);
+        assert!(err.contains("UTF8"), "{}", err);
+
+        charset::WIN_1252.validate()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn strict_close() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        conn.execute("DROP TABLE RSTRICT_CLOSE", ()).ok();
+        conn.execute("CREATE TABLE RSTRICT_CLOSE (id int)", ())?;
+
+        // Nothing pending
+        conn.execute("insert into rstrict_close (id) values (1)", ())?;
+        conn.close()?;
+
+        // The changes of the explicit transaction are lost
+        let mut conn = cbuilder().connect()?;
+        conn.begin_transaction()?;
+        conn.execute("insert into rstrict_close (id) values (2)", ())?;
+        assert!(matches!(conn.close(), Err(FbError::TransactionLost { .. })));
+
+        let mut conn = cbuilder().connect()?;
+        let (count,): (i32,) = conn
+            .query_first("select count(*) from rstrict_close", ())?
+            .unwrap();
+        assert_eq!(1, count);
+
+        // The iterator cleanups succeeded
+        {
+            let mut rows = conn.query_iter::<_, (i32,)>("select id from rstrict_close", ())?;
+            rows.next();
+        }
+        conn.close()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn broken_connection() -> Result<(), FbError> {
+        let sql = "select current_connection from rdb$database";
+
+        let mut conn = cbuilder().connect()?;
+        let (id,): (i64,) = conn.query_first(sql, ())?.unwrap();
+        assert!(!conn.is_broken());
+
+        // Other errors don't break the connection
+        assert!(conn.execute("select * from not_exists", ()).is_err());
+        assert!(!conn.is_broken());
+
+        // Kill the attachment from another connection
+        let mut other = cbuilder().connect()?;
+        other.execute("delete from mon$attachments where mon$attachment_id = ?", (id,))?;
+
+        let res: Result<Option<(i64,)>, _> = conn.query_first(sql, ());
+        assert!(res.is_err());
+        assert!(conn.is_broken());
+
+        Ok(())
+    }
 }
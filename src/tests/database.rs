@@ -139,4 +139,162 @@ mk_tests_default! {
 
         Ok(())
     }
+
+    #[test]
+    #[cfg(all(feature = "pure_rust", not(feature = "native_client")))]
+    fn pure_rust_overwrite() -> Result<(), FbError> {
+        let mut builder = builder_pure_rust();
+        builder
+            .db_name("test_create_db4.fdb")
+            .user("SYSDBA")
+            .page_size(8 * 1024)
+            .db_charset(charset::ISO_8859_1);
+
+        let conn = builder.create_database()?;
+        conn.close()?;
+
+        let exists = builder.create_database();
+        assert!(exists.is_err(), "Creating an existing database must fail");
+
+        let mut conn = builder.overwrite(true).create_database()?;
+
+        let (page_size, charset): (i32, String) = conn
+            .query_first(
+                "select mon$page_size, trim(rdb$character_set_name) from mon$database cross join rdb$database",
+                (),
+            )?
+            .unwrap();
+        assert_eq!(8 * 1024, page_size);
+        assert_eq!("ISO8859_1", charset);
+
+        conn.drop_database()?;
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(not(feature = "embedded_tests"))]
+    fn tuning_dpb_items() -> Result<(), FbError> {
+        let mut builder = cbuilder();
+        builder
+            .db_name("test_create_db_tuning.fdb")
+            .overwrite(true)
+            .with_num_buffers(500)
+            .with_forced_writes(false)
+            .with_no_garbage_collect();
+
+        // The only attachment with the new database, so the cache size is used
+        let mut conn = builder.create_database()?;
+
+        assert_eq!(500, conn.num_buffers()?);
+        assert!(!conn.forced_writes()?);
+        conn.close()?;
+
+        let mut conn = builder.with_forced_writes(true).connect()?;
+        assert!(conn.forced_writes()?);
+
+        conn.drop_database()?;
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(not(feature = "embedded_tests"))]
+    fn no_db_triggers() -> Result<(), FbError> {
+        let mut builder = cbuilder();
+        builder.db_name("test_create_db_no_triggers.fdb").overwrite(true);
+
+        let mut conn = builder.create_database()?;
+        conn.execute("create table conn_log (id int)", ())?;
+        conn.execute(
+            "create trigger log_connect on connect as begin insert into conn_log values (1); end",
+            (),
+        )?;
+        conn.close()?;
+
+        let conn = builder.connect()?;
+        conn.close()?;
+
+        let mut conn = builder.with_no_db_triggers().connect()?;
+
+        let (count,): (i32,) = conn
+            .query_first("select count(*) from conn_log", ())?
+            .unwrap();
+        assert_eq!(1, count);
+
+        conn.drop_database()?;
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(not(feature = "embedded_tests"))]
+    fn non_ascii_db_name() -> Result<(), FbError> {
+        let mut builder = cbuilder();
+        builder.db_name("test_create_db_ação_ü.fdb").overwrite(true);
+
+        let conn = builder.create_database()?;
+        conn.close()?;
+
+        let mut conn = builder.connect()?;
+
+        let (name,): (String,) = conn
+            .query_first("select mon$database_name from mon$database", ())?
+            .unwrap();
+        assert!(name.ends_with("test_create_db_ação_ü.fdb"), "{}", name);
+
+        conn.drop_database()?;
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(not(feature = "embedded_tests"))]
+    fn dialect_1() -> Result<(), FbError> {
+        use chrono::{NaiveDate, NaiveDateTime};
+
+        let mut builder = cbuilder();
+        builder
+            .db_name("test_create_db_dialect1.fdb")
+            .dialect(Dialect::D1)
+            .overwrite(true);
+
+        let mut conn = builder.create_database()?;
+
+        let (dialect,): (i16,) = conn
+            .query_first("select mon$sql_dialect from mon$database", ())?
+            .unwrap();
+        assert_eq!(1, dialect);
+
+        conn.execute(
+            "create table dialect1 (dt date, big numeric(15, 2), small numeric(9, 2), name varchar(20))",
+            (),
+        )?;
+
+        // The dialect 1 DATE has a time part
+        let dt = NaiveDate::from_ymd_opt(2021, 3, 4)
+            .unwrap()
+            .and_hms_opt(10, 20, 30)
+            .unwrap();
+
+        // Double quotes are string literals in dialect 1
+        conn.execute(
+            "insert into dialect1 (dt, big, small, name) values (?, ?, ?, \"dialect 1\")",
+            (dt, 12345678901.25, 1234.5),
+        )?;
+
+        let (rdt, big, big_f, small, name): (NaiveDateTime, String, f64, String, String) = conn
+            .query_first("select dt, big, big, small, name from dialect1", ())?
+            .unwrap();
+
+        assert_eq!(dt, rdt);
+        assert_eq!("12345678901.25", big);
+        assert_eq!(12345678901.25, big_f);
+        assert_eq!("1234.50", small);
+        assert_eq!("dialect 1", name);
+
+        conn.drop_database()?;
+
+        Ok(())
+    }
 }
@@ -104,9 +104,12 @@ macro_rules! mk_tests_default {
                     .clone()
             }
 
+            // With the statement timeouts
             #[cfg(feature = "pure_rust")]
             for pure_rust -> PureRustConnectionBuilder {
                 crate::builder_pure_rust()
+                    .with_protocol_version(crate::ProtocolVersion::V16)
+                    .clone()
             }
         }
     };
@@ -5,8 +5,8 @@
 //!
 
 mk_tests_default! {
-    use crate::{prelude::*, FbError, SqlType, EngineVersion, SystemInfos};
-    use chrono::{NaiveDate, NaiveTime};
+    use crate::{prelude::*, DurationMillis, DurationSecs, FbError, SqlType, EngineVersion, SystemInfos};
+    use chrono::{Duration, NaiveDate, NaiveTime};
     use rand::{distributions::Standard, Rng};
 
     #[test]
@@ -104,6 +104,24 @@ mk_tests_default! {
         Ok(())
     }
 
+    #[test]
+    fn struct_namedparams_raw_identifier() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        #[derive(Clone, IntoParams)]
+        struct UserType {
+            pub r#type: String
+        }
+
+        let res: Option<(String,)> = conn.query_first(
+            "select cast(:type as varchar(10)) from rdb$database",
+            UserType { r#type: "admin".to_string() },
+        )?;
+        assert_eq!(Some(("admin".to_string(),)), res);
+
+        Ok(())
+    }
+
     #[test]
     fn struct_namedparams() -> Result<(), FbError> {
         let mut conn = cbuilder().connect()?;
@@ -218,6 +236,31 @@ mk_tests_default! {
         Ok(())
     }
 
+    #[test]
+    fn huge_blob_binary() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        let data: Vec<u8> = rand::thread_rng()
+            .sample_iter::<u8, _>(Standard)
+            .take(1024 * 1024)
+            .collect();
+
+        conn.execute("DROP TABLE PHUGEBLOBBIN", ()).ok();
+        conn.execute("CREATE TABLE PHUGEBLOBBIN (id int, content blob sub_type 0)", ())?;
+
+        conn.execute("insert into phugeblobbin (id, content) values (1, ?)", (data.as_slice(),))?;
+        conn.execute("insert into phugeblobbin (id, content) values (2, ?)", (data.clone(),))?;
+
+        let rows: Vec<(Vec<u8>,)> = conn.query("select content from phugeblobbin order by id", ())?;
+        assert_eq!(2, rows.len());
+        for (content,) in rows {
+            assert_eq!(data.len(), content.len());
+            assert!(data == content);
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn big_blob_text() -> Result<(), FbError> {
         let mut conn = cbuilder().connect()?;
@@ -235,6 +278,28 @@ mk_tests_default! {
         Ok(())
     }
 
+    #[test]
+    fn blob_text_roundtrip() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        // Multi byte chars, in more than one segment
+        let content = "abc äbç 123 ❤ ".repeat(10_000);
+        let short = "short äbç".to_string();
+
+        conn.execute("DROP TABLE PBLOBTEXTRT", ()).ok();
+        conn.execute("CREATE TABLE PBLOBTEXTRT (id int, content blob sub_type text)", ())?;
+
+        conn.execute("insert into pblobtextrt (id, content) values (1, ?)", (&content,))?;
+        conn.execute("insert into pblobtextrt (id, content) values (2, ?)", (&short,))?;
+
+        let rows: Vec<(i32, String)> =
+            conn.query("select id, content from pblobtextrt order by id", ())?;
+
+        assert_eq!(vec![(1, content), (2, short)], rows);
+
+        Ok(())
+    }
+
     #[test]
     fn dates() -> Result<(), FbError> {
         let mut conn = cbuilder().connect()?;
@@ -278,6 +343,87 @@ mk_tests_default! {
         Ok(())
     }
 
+    #[test]
+    fn time_fractions() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        conn.execute("DROP TABLE PTIMES", ()).ok();
+        conn.execute("CREATE TABLE PTIMES (ref char(1), a time)", ())?;
+
+        let time = NaiveTime::from_hms_micro_opt(23, 59, 59, 999_900).unwrap();
+
+        conn.execute("insert into ptimes (ref, a) values ('a', ?)", (time,))?;
+        let val_exists: Option<(i16,)> = conn.query_first(
+            "select 1 from ptimes where ref = 'a' and a = '23:59:59.9999'",
+            (),
+        )?;
+        assert!(val_exists.is_some());
+
+        let (a,): (NaiveTime,) = conn
+            .query_first("select a from ptimes where ref = 'a'", ())?
+            .unwrap();
+        assert_eq!(time, a);
+
+        // Truncated to the 1/10000 second precision, and the leap second
+        // kept in the same second
+        conn.execute(
+            "insert into ptimes (ref, a) values ('b', ?)",
+            (NaiveTime::from_hms_nano_opt(23, 59, 59, 999_999_999).unwrap(),),
+        )?;
+        conn.execute(
+            "insert into ptimes (ref, a) values ('c', ?)",
+            (NaiveTime::from_hms_nano_opt(23, 59, 59, 1_500_000_000).unwrap(),),
+        )?;
+        let rows: Vec<(NaiveTime,)> =
+            conn.query("select a from ptimes where ref in ('b', 'c')", ())?;
+        assert_eq!(vec![(time,), (time,)], rows);
+
+        Ok(())
+    }
+
+    #[test]
+    fn durations() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        conn.execute("DROP TABLE PDURATIONS", ()).ok();
+        conn.execute(
+            "CREATE TABLE PDURATIONS (ref char(1), a bigint, b numeric(18, 3))",
+            (),
+        )?;
+
+        conn.execute(
+            "insert into pdurations (ref, a, b) values ('a', ?, ?)",
+            (
+                DurationMillis(Duration::milliseconds(1500)),
+                DurationSecs(Duration::milliseconds(-2250)),
+            ),
+        )?;
+        let val_exists: Option<(i16,)> = conn.query_first(
+            "select 1 from pdurations where ref = 'a' and a = 1500 and b = -2.25",
+            (),
+        )?;
+        assert!(val_exists.is_some());
+
+        let (a, b, c): (DurationSecs, DurationSecs, Option<DurationMillis>) = conn
+            .query_first("select a, b, cast(null as bigint) from pdurations where ref = 'a'", ())?
+            .unwrap();
+        assert_eq!(Duration::seconds(1500), a.0);
+        assert_eq!(Duration::milliseconds(-2250), b.0);
+        assert_eq!(None, c);
+
+        // Fractions of the unit rounded to the column scale
+        conn.execute(
+            "insert into pdurations (ref, a) values ('b', ?)",
+            (DurationSecs(Duration::milliseconds(2600)),),
+        )?;
+        let (a,): (DurationSecs,) = conn
+            .query_first("select a from pdurations where ref = 'b'", ())?
+            .unwrap();
+        assert_eq!(Duration::seconds(3), a.0);
+
+        Ok(())
+    }
+
     #[test]
     fn strings() -> Result<(), FbError> {
         let mut conn = cbuilder().connect()?;
@@ -311,6 +457,90 @@ mk_tests_default! {
         Ok(())
     }
 
+    #[test]
+    fn borrowed() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        let name = String::from("firebird");
+        let data = vec![1_u8, 2, 3];
+
+        // The same values bound in each execution, without giving up the ownership
+        for _ in 0..3 {
+            let (a, b, c, d): (String, String, Vec<u8>, Vec<u8>) = conn
+                .query_first(
+                    "select cast(? as varchar(10)), cast(? as varchar(10)), cast(? as blob), cast(? as blob) from rdb$database",
+                    (&name, name.as_str(), &data, data.as_slice()),
+                )?
+                .unwrap();
+
+            assert_eq!(name, a);
+            assert_eq!(name, b);
+            assert_eq!(data, c);
+            assert_eq!(data, d);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn custom_types() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        #[derive(Clone, Copy)]
+        struct UserId(i32);
+
+        impl IntoParam for UserId {
+            fn into_param(self) -> SqlType {
+                self.0.into_param()
+            }
+        }
+
+        #[derive(Clone, Copy)]
+        enum Status {
+            Active,
+            Blocked,
+        }
+
+        impl IntoParam for Status {
+            fn into_param(self) -> SqlType {
+                match self {
+                    Status::Active => "A",
+                    Status::Blocked => "B",
+                }
+                .into_param()
+            }
+        }
+
+        let sql = "select cast(? as int), cast(? as char(1)), cast(? as char(1)) from rdb$database";
+
+        let row: Option<(i32, String, Option<String>)> =
+            conn.query_first(sql, (UserId(10), Status::Active, None::<Status>))?;
+        assert_eq!(Some((10, "A".to_string(), None)), row);
+
+        let row: Option<(i32, String, Option<String>)> = conn.query_first(
+            sql,
+            vec![UserId(20).into(), Status::Blocked.into(), Some(Status::Active).into()],
+        )?;
+        assert_eq!(Some((20, "B".to_string(), Some("A".to_string()))), row);
+
+        #[derive(Clone, IntoParams)]
+        struct User {
+            id: UserId,
+            status: Status,
+        }
+
+        let row: Option<(i32, String)> = conn.query_first(
+            "select cast(:id as int), cast(:status as char(1)) from rdb$database",
+            User {
+                id: UserId(30),
+                status: Status::Blocked,
+            },
+        )?;
+        assert_eq!(Some((30, "B".to_string())), row);
+
+        Ok(())
+    }
+
     #[test]
     fn fixed_points() -> Result<(), FbError> {
         let mut conn = cbuilder().connect()?;
@@ -419,6 +649,37 @@ mk_tests_default! {
         Ok(())
     }
 
+    #[test]
+    fn untyped_null() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        conn.execute("DROP TABLE PUNTYPED_NULL", ()).ok();
+        conn.execute(
+            "CREATE TABLE PUNTYPED_NULL (id int, a smallint, b bigint, c numeric(10,2), d double precision, e date, f timestamp, g varchar(10), h char(5), i blob sub_type text)",
+            (),
+        )?;
+
+        conn.execute(
+            "insert into puntyped_null (id, a, b, c, d, e, f, g, h, i) values (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            (1, crate::null(), crate::null(), crate::null(), crate::null(), crate::null(), crate::null(), crate::null(), crate::null(), crate::null()),
+        )?;
+
+        let row: crate::Row = conn
+            .query_first("select a, b, c, d, e, f, g, h, i from puntyped_null where id = 1", ())?
+            .unwrap();
+        assert_eq!(9, row.cols.len());
+        assert!(row.cols.iter().all(|col| col.value.is_null()));
+
+        // Also in the expressions
+        let res: Option<(i32,)> = conn.query_first(
+            "select 1 from rdb$database where cast(? as integer) is null",
+            (crate::null(),),
+        )?;
+        assert_eq!(Some((1,)), res);
+
+        Ok(())
+    }
+
     #[test]
     fn lots_of_params() -> Result<(), FbError> {
         let mut conn = cbuilder().connect()?;
@@ -442,4 +703,30 @@ mk_tests_default! {
 
         Ok(())
     }
+
+    #[test]
+    fn dynamic_params() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        let ids: Vec<i32> = vec![1, 3, 5];
+
+        let params: Vec<SqlType> = ids.iter().map(|&id| id.into()).collect();
+        let sql = format!(
+            "select count(*) from rdb$database where 3 in ({})",
+            vec!["?"; params.len()].join(", ")
+        );
+
+        let count: i32 = conn.query_scalar(&sql, params.as_slice())?;
+        assert_eq!(1, count);
+
+        let count: i32 = conn.query_scalar(&sql, params)?;
+        assert_eq!(1, count);
+
+        let few: Vec<SqlType> = vec![1.into(), 3.into()];
+        let res: Result<i32, _> = conn.query_scalar(&sql, few.as_slice());
+        let err = res.expect_err("the parameter count should not match").to_string();
+        assert!(err.contains("has 3 parameters while providing 2"), "{}", err);
+
+        Ok(())
+    }
 }
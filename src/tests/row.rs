@@ -7,7 +7,7 @@
 mk_tests_default! {
     use crate::{prelude::*, FbError, Row, EngineVersion, SystemInfos};
     use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
-    use rsfbclient_core::ColumnToVal;
+    use rsfbclient_core::{Column, ColumnToVal, IntoParam, SqlType};
     use std::str;
     use rand::{distributions::Standard, Rng};
 
@@ -59,6 +59,41 @@ mk_tests_default! {
         let (val,): (i32,) = conn.execute_returnable("execute procedure get_value", ())?;
         assert_eq!(150, val);
 
+        // Executable procedure, with the output returned by the execution
+        conn.execute(
+            "create or alter procedure double_value(x int)
+                returns (y int)
+                as
+                begin
+                    y = x * 2;
+                end",
+            (),
+        )?;
+
+        let (val,): (i32,) = conn.execute_returnable("execute procedure double_value(?)", (21,))?;
+        assert_eq!(42, val);
+
+        let (val,): (i32,) = conn
+            .query_first("execute procedure double_value(?)", (5,))?
+            .unwrap();
+        assert_eq!(10, val);
+
+        // Without output parameters
+        conn.execute(
+            "create or alter procedure no_output(x int)
+                as
+                begin
+                    x = x * 2;
+                end",
+            (),
+        )?;
+
+        conn.execute_returnable::<_, ()>("execute procedure no_output(?)", (1,))?;
+        conn.execute("execute procedure no_output(?)", (1,))?;
+
+        let row: Option<(i32,)> = conn.query_first("execute procedure no_output(?)", (1,))?;
+        assert_eq!(None, row);
+
         Ok(())
     }
 
@@ -109,6 +144,27 @@ mk_tests_default! {
         Ok(())
     }
 
+    #[test]
+    fn insert_returning_row() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        conn.execute("DROP TABLE RINSERT_RETURNING_ROW", ()).ok();
+        conn.execute("CREATE TABLE RINSERT_RETURNING_ROW (id int, name varchar(10), note varchar(10))", ())?;
+
+        let row = conn.execute_returning_row("insert into rinsert_returning_row (id, name) values (10, 'abc') returning id, name, note as obs", ())?;
+
+        assert_eq!(vec!["ID", "NAME", "OBS"], row.column_names());
+        assert_eq!(10, row.get::<i32>("id")?);
+        assert_eq!(None, row.get::<Option<String>>("obs")?);
+
+        let map = row.into_map();
+        assert_eq!(3, map.len());
+        assert!(matches!(&map["NAME"], rsfbclient_core::SqlType::Text(name) if name == "abc"));
+        assert!(map["OBS"].is_null());
+
+        Ok(())
+    }
+
     #[test]
     #[allow(clippy::bool_assert_comparison)]
     fn boolean() -> Result<(), FbError> {
@@ -258,7 +314,7 @@ mk_tests_default! {
         assert_eq!(100.0, a);
         assert_eq!(100.0, b);
 
-        let (a, b): (f32, f32) = conn
+        let (a, b): (f64, f64) = conn
                 .query_first(
                     "select cast(2358.35321 as numeric(5, 5)), cast(2358.35321 as decimal(5, 5)) from rdb$database",
                     ()
@@ -267,6 +323,13 @@ mk_tests_default! {
         assert_eq!(2358.35321, a);
         assert_eq!(2358.35321, b);
 
+        // Not kept by the f32
+        let res: Result<Option<(f32,)>, _> = conn.query_first(
+            "select cast(2358.35321 as numeric(5, 5)) from rdb$database",
+            (),
+        );
+        assert!(res.is_err());
+
         let (a, b): (f64, f64) = conn
                 .query_first(
                     "select cast(2358.78353211234 as numeric(11, 11)), cast(2358.78353211234 as decimal(11, 11)) from rdb$database",
@@ -276,6 +339,109 @@ mk_tests_default! {
         assert_eq!(2358.78353211234, a);
         assert_eq!(2358.78353211234, b);
 
+        let (a, b): (String, String) = conn
+                .query_first(
+                    "select cast(922337203685477.5807 as numeric(18, 4)), cast(-0.05 as decimal(4, 2)) from rdb$database",
+                    ()
+                )?
+                .unwrap();
+        assert_eq!("922337203685477.5807", a);
+        assert_eq!("-0.05", b);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "decimal")]
+    fn decimals() -> Result<(), FbError> {
+        use rust_decimal::Decimal;
+        use std::str::FromStr;
+
+        let mut conn = cbuilder().connect()?;
+
+        let (a, b, c, d): (Decimal, Decimal, Decimal, Option<Decimal>) = conn
+            .query_first(
+                "select cast(922337203685477.5807 as numeric(18, 4)), cast(-922337203685477.5808 as numeric(18, 4)),
+                    cast(12.34 as numeric(9, 2)), cast(null as numeric(9, 2)) from rdb$database",
+                (),
+            )?
+            .unwrap();
+        assert_eq!(Decimal::from_str("922337203685477.5807").unwrap(), a);
+        assert_eq!(Decimal::from_str("-922337203685477.5808").unwrap(), b);
+        assert_eq!(Decimal::from_str("12.34").unwrap(), c);
+        assert_eq!(None, d);
+
+        conn.execute("DROP TABLE RDECIMALS", ()).ok();
+        conn.execute("CREATE TABLE RDECIMALS (val numeric(18, 4))", ())?;
+
+        let vals = ["922337203685477.5807", "-922337203685477.5808", "0.0001", "100", "-1.5"];
+        for val in vals.iter() {
+            conn.execute(
+                "insert into rdecimals (val) values (?)",
+                (Decimal::from_str(val).unwrap(),),
+            )?;
+        }
+
+        let res: Vec<(Decimal,)> = conn.query("select val from rdecimals", ())?;
+        for (val, (res,)) in vals.iter().zip(res) {
+            assert_eq!(Decimal::from_str(val).unwrap(), res);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "uuid")]
+    fn uuid() -> Result<(), FbError> {
+        use uuid::Uuid;
+
+        let mut conn = cbuilder().connect()?;
+
+        conn.execute("DROP TABLE RUUIDS", ()).ok();
+        conn.execute(
+            "CREATE TABLE RUUIDS (id char(16) character set octets not null, txt varchar(36))",
+            (),
+        )?;
+
+        let id = Uuid::parse_str("0b6e2f3c-8a1d-4c5e-9f70-a1b2c3d4e5f6").unwrap();
+        conn.execute(
+            "insert into ruuids (id, txt) values (?, ?)",
+            (id, id.to_string()),
+        )?;
+
+        let row: Row = conn
+            .query_first("select id, txt from ruuids where id = ?", (id,))?
+            .unwrap();
+        assert_eq!(id, row.get::<Uuid>(0)?);
+        assert_eq!(id, row.get::<Uuid>(1)?);
+
+        // The bytes must be kept in the same order
+        let raw: Vec<u8> = row.get(0)?;
+        assert_eq!(id.as_bytes().to_vec(), raw);
+
+        // Generated by the server, returned as a CHAR(16) CHARACTER SET OCTETS
+        let (generated,): (Uuid,) = conn.execute_returnable(
+            "insert into ruuids (id, txt) values (gen_uuid(), 'generated') returning id",
+            (),
+        )?;
+        assert!(!generated.is_nil());
+
+        let (stored, txt): (Uuid, String) = conn
+            .query_first("select id, txt from ruuids where id = ?", (generated,))?
+            .unwrap();
+        assert_eq!(generated, stored);
+        assert_eq!("generated", txt);
+
+        let (raw,): (Vec<u8>,) = conn
+            .query_first("select id from ruuids where txt = 'generated'", ())?
+            .unwrap();
+        assert_eq!(generated.as_bytes().to_vec(), raw);
+
+        let not_uuid: Result<(Uuid,), _> =
+            conn.query_first("select cast(x'0102' as char(2) character set octets) from rdb$database", ())
+                .map(|r| r.unwrap());
+        assert!(not_uuid.is_err());
+
         Ok(())
     }
 
@@ -321,6 +487,73 @@ mk_tests_default! {
         Ok(())
     }
 
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn float_precision() -> Result<(), FbError> {
+        use crate::{Column, SqlType};
+
+        let mut conn = cbuilder().connect()?;
+
+        // Subnormals
+        let (a, b): (f32, f64) = conn
+            .query_first(
+                "select cast(? as float), cast(? as double precision) from rdb$database",
+                (1.0e-40_f32, 4.9e-324),
+            )?
+            .unwrap();
+        assert_eq!(1.0e-40_f32, a);
+        assert_eq!(4.9e-324, b);
+
+        // Float widened to f64
+        let (a,): (f64,) = conn
+            .query_first("select cast(? as float) from rdb$database", (0.1_f32,))?
+            .unwrap();
+        assert_eq!(0.1_f32 as f64, a);
+
+        // Double narrowed to f32 only when exact
+        let (a,): (f32,) = conn
+            .query_first("select cast(0.5 as double precision) from rdb$database", ())?
+            .unwrap();
+        assert_eq!(0.5, a);
+
+        let res: Result<Option<(f32,)>, _> =
+            conn.query_first("select cast(0.1 as double precision) from rdb$database", ());
+        assert!(res.is_err());
+
+        // Boundaries
+        let to_f32 = |f: f64| Column::new("F".to_string(), SqlType::Floating(f)).to_val();
+
+        let max: f32 = to_f32(f32::MAX as f64)?;
+        assert_eq!(f32::MAX, max);
+        let min: f32 = to_f32(f32::MIN_POSITIVE as f64)?;
+        assert_eq!(f32::MIN_POSITIVE, min);
+        let inf: f32 = to_f32(f64::INFINITY)?;
+        assert_eq!(f32::INFINITY, inf);
+        let nan: f32 = to_f32(f64::NAN)?;
+        assert!(nan.is_nan());
+
+        assert!(ColumnToVal::<f32>::to_val(Column::new("F".to_string(), SqlType::Floating(f64::MAX))).is_err());
+        assert!(ColumnToVal::<f32>::to_val(Column::new("F".to_string(), SqlType::Floating(f64::MIN_POSITIVE))).is_err());
+
+        // Exact numerics narrowed only when the value is kept with the scale
+        let to_f32 = |value: SqlType| Column::new("N".to_string(), value).to_val();
+
+        let a: f32 = to_f32(SqlType::Numeric { value: 125, scale: -2 })?;
+        assert_eq!(1.25, a);
+        let a: f32 = to_f32(SqlType::Numeric { value: 10, scale: -2 })?;
+        assert_eq!(0.1, a);
+        let a: f32 = to_f32(SqlType::Numeric { value: 3, scale: 2 })?;
+        assert_eq!(300.0, a);
+        let a: f32 = to_f32(SqlType::Int128(1 << 24))?;
+        assert_eq!(16777216.0, a);
+
+        assert!(ColumnToVal::<f32>::to_val(Column::new("N".to_string(), SqlType::Numeric { value: 235835321, scale: -5 })).is_err());
+        assert!(ColumnToVal::<f32>::to_val(Column::new("N".to_string(), SqlType::Numeric { value: 16777217, scale: 0 })).is_err());
+        assert!(ColumnToVal::<f32>::to_val(Column::new("N".to_string(), SqlType::Int128((1 << 24) + 1))).is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn ints() -> Result<(), FbError> {
         let mut conn = cbuilder().connect()?;
@@ -368,6 +601,18 @@ mk_tests_default! {
         assert_eq!(i16::MIN, min);
         assert_eq!(i16::MAX, max);
 
+        // Out of the range of the type
+        let res: Result<Option<(i32,)>, _> =
+            conn.query_first("select cast(? as bigint) as big from rdb$database", (i64::MAX,));
+        let msg = res.unwrap_err().to_string();
+        assert!(msg.contains("9223372036854775807"), "{}", msg);
+        assert!(msg.contains("BIG"), "{}", msg);
+        assert!(msg.contains("i32 range"), "{}", msg);
+
+        let res: Result<Option<(i16,)>, _> =
+            conn.query_first("select cast(-32769 as int) from rdb$database", ());
+        assert!(res.is_err());
+
         Ok(())
     }
 
@@ -401,4 +646,559 @@ mk_tests_default! {
 
         Ok(())
     }
+
+    #[test]
+    fn column_by_name() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        let row: Row = conn
+            .query_first(
+                "select cast(10 as int) as id, cast('abc' as varchar(10)) as \"Name\", cast(null as int) as opt from rdb$database",
+                (),
+            )?
+            .expect("No row returned");
+
+        assert_eq!(10, row.get::<i32>("ID")?);
+        assert_eq!(10, row.get::<i32>("id")?);
+        assert_eq!("abc", row.get::<String>("NAME")?);
+        assert_eq!(None, row.get::<Option<i32>>("Opt")?);
+        assert_eq!(10, row.get::<i32>(0)?);
+        assert_eq!("abc", row.get_by_index::<String>(1)?);
+
+        assert!(row.get::<i32>("missing").is_err());
+        assert!(row.get::<i32>("opt").is_err());
+        assert!(row.get::<bool>("id").is_err());
+        assert!(row.get_by_index::<i32>(3).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn null_columns() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        conn.execute("DROP TABLE RNULLCOLS", ()).ok();
+        conn.execute("CREATE TABLE RNULLCOLS (num int, name varchar(10), ts timestamp)", ())?;
+        conn.execute("insert into rnullcols (num, name, ts) values (null, null, null)", ())?;
+        conn.execute(
+            "insert into rnullcols (num, name, ts) values (0, '', '1970-01-01 00:00:00')",
+            (),
+        )?;
+
+        let rows: Vec<Row> = conn.query("select num, name, ts from rnullcols order by num nulls first", ())?;
+
+        assert!(rows[0].is_null(0)?);
+        assert!(rows[0].is_null_named("name")?);
+        assert!(rows[0].is_null("TS")?);
+        assert_eq!(None, rows[0].get::<Option<i32>>(0)?);
+        assert_eq!(None, rows[0].get::<Option<String>>(1)?);
+        assert_eq!(None, rows[0].get::<Option<NaiveDateTime>>(2)?);
+
+        assert!(!rows[1].is_null(0)?);
+        assert!(!rows[1].is_null_named("name")?);
+        assert!(!rows[1].is_null("TS")?);
+        assert_eq!(Some(0), rows[1].get::<Option<i32>>(0)?);
+        assert_eq!(Some(String::new()), rows[1].get::<Option<String>>(1)?);
+
+        assert!(rows[0].is_null(3).is_err());
+        assert!(rows[0].is_null_named("missing").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn display_values() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        let row: Row = conn
+            .query_first(
+                "select cast(12.34 as numeric(9, 2)) as num, cast('2010-10-10 10:10:10.5' as timestamp) as ts, cast(null as int) as nothing, 'abc' as txt from rdb$database",
+                (),
+            )?
+            .unwrap();
+
+        let display: Vec<String> = row.cols.iter().map(|col| col.to_string()).collect();
+        assert_eq!(
+            vec!["NUM: 12.34", "TS: 2010-10-10T10:10:10.500", "NOTHING: NULL", "TXT: abc"],
+            display
+        );
+
+        let debug: Vec<String> = row.cols.iter().map(|col| format!("{:?}", col.value)).collect();
+        assert_eq!(
+            vec!["Numeric { value: 1234, scale: -2 }", "Timestamp(2010-10-10T10:10:10.500)", "Null", "Text(\"abc\")"],
+            debug
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn struct_from_row() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        conn.execute("DROP TABLE RFROMROW_USER", ()).ok();
+        conn.execute("CREATE TABLE RFROMROW_USER (id int, name varchar(50), email varchar(50))", ())?;
+        conn.execute("insert into rfromrow_user (id, name) values (1, 'Pedro')", ())?;
+
+        #[derive(FromRow)]
+        struct User {
+            name: String,
+            id: i32,
+            #[fb(rename = "EMAIL")]
+            mail: Option<String>
+        }
+
+        let users: Vec<User> = conn.query("select id, name, email from rfromrow_user", ())?;
+        assert_eq!(1, users.len());
+        assert_eq!(1, users[0].id);
+        assert_eq!("Pedro", users[0].name);
+        assert_eq!(None, users[0].mail);
+
+        #[derive(FromRow)]
+        struct UserTuple(i32, String);
+
+        let user: UserTuple = conn.query_first("select id, name from rfromrow_user", ())?
+            .unwrap();
+        assert_eq!(1, user.0);
+        assert_eq!("Pedro", user.1);
+
+        let missing: Result<Option<User>, _> = conn.query_first("select id, name from rfromrow_user", ());
+        assert!(missing.is_err());
+
+        #[derive(FromRow)]
+        struct UserType {
+            r#type: String
+        }
+
+        let user: UserType = conn.query_first("select 'admin' as \"TYPE\" from rdb$database", ())?
+            .unwrap();
+        assert_eq!("admin", user.r#type);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn query_json() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        let json = conn.query_json(
+            "select cast(1 as int) as id, cast('abc' as varchar(10)) as name, cast(null as int) as opt,
+                cast('2020-01-02 10:20:30.5' as timestamp) as ts, cast(1.5 as double precision) as num,
+                cast(-12.05 as numeric(9, 2)) as dec
+            from rdb$database",
+            (),
+        )?;
+
+        assert_eq!(
+            serde_json::json!([{
+                "ID": 1,
+                "NAME": "abc",
+                "OPT": null,
+                "TS": "2020-01-02T10:20:30.500",
+                "NUM": 1.5,
+                "DEC": "-12.05"
+            }]),
+            json
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn int128() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        if conn.server_engine()? < EngineVersion::V4 {
+            return Ok(());
+        }
+
+        let (a, b, c, d): (i128, i64, String, Option<i128>) = conn
+            .query_first(
+                "select cast(170141183460469231731687303715884105727 as int128), cast(10 as int128),
+                    cast(-12345678901234567890123456789.1234 as numeric(38, 4)), cast(null as int128) from rdb$database",
+                (),
+            )?
+            .unwrap();
+        assert_eq!(i128::MAX, a);
+        assert_eq!(10, b);
+        assert_eq!("-12345678901234567890123456789.1234", c);
+        assert_eq!(None, d);
+
+        let (min, max, small): (i128, i128, i128) = conn
+            .query_first(
+                "select cast(? as int128), cast(? as int128), cast(? as int128) from rdb$database",
+                (i128::MIN, i128::MAX, -5_i128),
+            )?
+            .unwrap();
+        assert_eq!(i128::MIN, min);
+        assert_eq!(i128::MAX, max);
+        assert_eq!(-5, small);
+
+        let big: Result<Option<(i64,)>, _> =
+            conn.query_first("select cast(? as int128) from rdb$database", (i128::MAX,));
+        assert!(big.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn timestamp_tz() -> Result<(), FbError> {
+        use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+
+        let mut conn = cbuilder().connect()?;
+
+        if conn.server_engine()? < EngineVersion::V4 {
+            return Ok(());
+        }
+
+        let cet = FixedOffset::east_opt(3600).unwrap();
+        let cest = FixedOffset::east_opt(2 * 3600).unwrap();
+
+        // Region time zones are resolved to the offset in effect, around the DST change
+        let (before, after, null): (DateTime<FixedOffset>, DateTime<FixedOffset>, Option<DateTime<FixedOffset>>) = conn
+            .query_first(
+                "select cast('2021-03-28 01:30:00 Europe/Berlin' as timestamp with time zone),
+                    cast('2021-03-28 03:30:00 Europe/Berlin' as timestamp with time zone),
+                    cast(null as timestamp with time zone) from rdb$database",
+                (),
+            )?
+            .unwrap();
+        assert_eq!(cet.with_ymd_and_hms(2021, 3, 28, 1, 30, 0).unwrap(), before);
+        assert_eq!(&cet, before.offset());
+        assert_eq!(cest.with_ymd_and_hms(2021, 3, 28, 3, 30, 0).unwrap(), after);
+        assert_eq!(&cest, after.offset());
+        assert_eq!(None, null);
+
+        conn.execute("DROP TABLE RTIMESTAMPTZ", ()).ok();
+        conn.execute("CREATE TABLE RTIMESTAMPTZ (id int, ts timestamp with time zone)", ())?;
+
+        // One second apart, across the DST change
+        let vals = [
+            cet.with_ymd_and_hms(2021, 3, 28, 1, 59, 59).unwrap(),
+            cest.with_ymd_and_hms(2021, 3, 28, 3, 0, 0).unwrap(),
+        ];
+        for (id, val) in vals.iter().enumerate() {
+            conn.execute("insert into rtimestamptz (id, ts) values (?, ?)", (id as i32, *val))?;
+        }
+
+        let res: Vec<(DateTime<FixedOffset>, DateTime<Utc>)> =
+            conn.query("select ts, ts from rtimestamptz order by id", ())?;
+        assert_eq!(2, res.len());
+        for (val, (res, res_utc)) in vals.iter().zip(res) {
+            assert_eq!(*val, res);
+            assert_eq!(val.offset(), res.offset());
+            assert_eq!(val.with_timezone(&Utc), res_utc);
+        }
+        assert_eq!(
+            Utc.with_ymd_and_hms(2021, 3, 28, 1, 0, 0).unwrap() - Utc.with_ymd_and_hms(2021, 3, 28, 0, 59, 59).unwrap(),
+            vals[1].with_timezone(&Utc) - vals[0].with_timezone(&Utc)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn query_opt_and_one() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        let row: Option<(i32, String)> =
+            conn.query_opt("select 1, 'a' from rdb$database", ())?;
+        assert_eq!(Some((1, "a".to_string())), row);
+
+        let row: Option<(i32,)> = conn.query_opt("select 1 from rdb$database where 1 = 0", ())?;
+        assert_eq!(None, row);
+
+        let res: Result<Option<(i32,)>, _> = conn.query_opt("select 1 from rdb$relations", ());
+        assert!(res.is_err());
+
+        let (id, name): (i32, String) = conn.query_one("select 1, 'a' from rdb$database", ())?;
+        assert_eq!((1, "a".to_string()), (id, name));
+
+        let res: Result<(i32,), _> = conn.query_one("select 1 from rdb$database where 1 = 0", ());
+        assert!(res.is_err());
+
+        let res: Result<(i32,), _> = conn.query_one("select 1 from rdb$relations", ());
+        assert!(res.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn query_scalar() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        let count: i64 = conn.query_scalar("select count(*) from rdb$database", ())?;
+        assert_eq!(1, count);
+
+        let name: Option<String> = conn.query_scalar("select null from rdb$database", ())?;
+        assert_eq!(None, name);
+
+        let count: i32 = conn.query_scalar(
+            "select count(*) from rdb$relations where rdb$relation_name = ?",
+            ("RDB$DATABASE",),
+        )?;
+        assert_eq!(1, count);
+
+        let res: Result<i32, _> = conn.query_scalar("select 1 from rdb$database where 1 = 0", ());
+        assert!(res.is_err());
+
+        let res: Result<i32, _> = conn.query_scalar("select 1 from rdb$relations", ());
+        assert!(res.is_err());
+
+        let res: Result<i32, _> = conn.query_scalar("select 1, 2 from rdb$database", ());
+        assert!(res.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn query_column() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        let names = conn
+            .query_column::<_, String>(
+                "select trim(rdb$relation_name) from rdb$relations where rdb$relation_name in (?, ?) order by 1",
+                ("RDB$DATABASE", "RDB$RELATIONS"),
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(vec!["RDB$DATABASE", "RDB$RELATIONS"], names);
+
+        let values = conn
+            .query_column::<_, Option<i32>>("select 1 from rdb$database union all select null from rdb$database", ())?
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(vec![Some(1), None], values);
+
+        assert!(conn.query_column::<_, i32>("select 1, 2 from rdb$database", ()).is_err());
+        // Checked after the prepare, even without rows
+        assert!(conn
+            .query_column::<_, i32>("select 1, 2 from rdb$database where 1 = 0", ())
+            .is_err());
+
+        // The connection is still usable
+        let one = conn
+            .query_column::<_, i32>("select 1 from rdb$database", ())?
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(vec![1], one);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(not(feature = "pure_rust"))]
+    fn array() -> Result<(), FbError> {
+        use crate::{Column, SqlType};
+
+        let mut conn = cbuilder().connect()?;
+
+        conn.execute("DROP TABLE RARRAY", ()).ok();
+        conn.execute(
+            "CREATE TABLE RARRAY (ID INT, INTS INT[3], NUMS NUMERIC(9, 2)[2], NAMES VARCHAR(10)[2], MATRIX DOUBLE PRECISION[2, 2])",
+            (),
+        )?;
+
+        // The arrays can't be written in sql, only null values are inserted
+        conn.execute("insert into rarray (id) values (1)", ())?;
+
+        let (ints, matrix): (Option<Vec<i32>>, Option<Vec<Vec<f64>>>) = conn
+            .query_first("select ints, matrix from rarray", ())?
+            .unwrap();
+        assert_eq!(None, ints);
+        assert_eq!(None, matrix);
+
+        let col = Column::new(
+            "INTS".to_string(),
+            SqlType::Array(vec![SqlType::Integer(1), SqlType::Integer(2), SqlType::Integer(3)]),
+        );
+        let ints: Vec<i32> = col.to_val()?;
+        assert_eq!(vec![1, 2, 3], ints);
+
+        let col = Column::new(
+            "NUMS".to_string(),
+            SqlType::Array(vec![
+                SqlType::Numeric { value: 125, scale: -2 },
+                SqlType::Numeric { value: -300, scale: -2 },
+            ]),
+        );
+        let nums: Vec<String> = col.to_val()?;
+        assert_eq!(vec!["1.25", "-3.00"], nums);
+
+        let col = Column::new(
+            "MATRIX".to_string(),
+            SqlType::Array(vec![
+                SqlType::Array(vec![SqlType::Floating(1.0), SqlType::Floating(2.0)]),
+                SqlType::Array(vec![SqlType::Floating(3.0), SqlType::Floating(4.0)]),
+            ]),
+        );
+        let matrix: Vec<Vec<f64>> = col.to_val()?;
+        assert_eq!(vec![vec![1.0, 2.0], vec![3.0, 4.0]], matrix);
+
+        Ok(())
+    }
+
+    /// Write the array of the row 1 with the fbclient api, as the arrays can't be written in sql
+    #[cfg(all(feature = "linking", not(feature = "pure_rust")))]
+    fn put_array<T: Copy>(
+        db: rsfbclient_core::ibase::isc_db_handle,
+        tr: rsfbclient_core::ibase::isc_tr_handle,
+        column: &str,
+        values: &mut [T],
+    ) -> Result<(), FbError> {
+        use rsfbclient_core::ibase::*;
+        use std::{ffi::{c_void, CString}, mem};
+
+        extern "C" {
+            fn isc_array_lookup_bounds(
+                status: *mut ISC_STATUS,
+                db: *mut isc_db_handle,
+                tr: *mut isc_tr_handle,
+                relation: *const ISC_SCHAR,
+                field: *const ISC_SCHAR,
+                desc: *mut ISC_ARRAY_DESC,
+            ) -> ISC_STATUS;
+
+            fn isc_array_put_slice(
+                status: *mut ISC_STATUS,
+                db: *mut isc_db_handle,
+                tr: *mut isc_tr_handle,
+                array_id: *mut ISC_QUAD,
+                desc: *const ISC_ARRAY_DESC,
+                slice: *mut c_void,
+                slice_len: *mut ISC_LONG,
+            ) -> ISC_STATUS;
+
+            fn isc_dsql_execute_immediate(
+                status: *mut ISC_STATUS,
+                db: *mut isc_db_handle,
+                tr: *mut isc_tr_handle,
+                sql_len: u16,
+                sql: *const ISC_SCHAR,
+                dialect: u16,
+                xsqlda: *const XSQLDA,
+            ) -> ISC_STATUS;
+        }
+
+        let (mut db, mut tr) = (db, tr);
+        let mut status: [ISC_STATUS; 20] = [0; 20];
+        let relation = CString::new("RARRAYVALUES").unwrap();
+        let field = CString::new(column).unwrap();
+        let sql = format!("update rarrayvalues set {} = ? where id = 1", column);
+        let mut array_id = ISC_QUAD { gds_quad_high: 0, gds_quad_low: 0 };
+        let mut slice_len = mem::size_of_val(values) as ISC_LONG;
+
+        unsafe {
+            let mut desc: ISC_ARRAY_DESC = mem::zeroed();
+
+            let mut xsqlda: XSQLDA = mem::zeroed();
+            xsqlda.version = SQLDA_VERSION1 as i16;
+            xsqlda.sqln = 1;
+            xsqlda.sqld = 1;
+            xsqlda.sqlvar[0].sqltype = SQL_ARRAY as i16;
+            xsqlda.sqlvar[0].sqllen = mem::size_of::<ISC_QUAD>() as i16;
+            xsqlda.sqlvar[0].sqldata = &mut array_id as *mut ISC_QUAD as *mut ISC_SCHAR;
+
+            if isc_array_lookup_bounds(&mut status[0], &mut db, &mut tr, relation.as_ptr(), field.as_ptr(), &mut desc) != 0
+                || isc_array_put_slice(&mut status[0], &mut db, &mut tr, &mut array_id, &desc, values.as_mut_ptr() as *mut c_void, &mut slice_len) != 0
+                || isc_dsql_execute_immediate(&mut status[0], &mut db, &mut tr, sql.len() as u16, sql.as_ptr() as *const ISC_SCHAR, SQL_DIALECT_CURRENT as u16, &xsqlda) != 0
+            {
+                return Err(format!("Error writing the array {}, gds code {}", column, status[1]).into());
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(all(feature = "linking", not(feature = "pure_rust")))]
+    fn array_values() -> Result<(), FbError> {
+        use crate::Transaction;
+
+        let mut conn = cbuilder().connect()?;
+
+        conn.execute("DROP TABLE RARRAYVALUES", ()).ok();
+        conn.execute(
+            "CREATE TABLE RARRAYVALUES (ID INT, INTS INT[3], NUMS NUMERIC(9, 2)[0:1], MATRIX DOUBLE PRECISION[2, 3])",
+            (),
+        )?;
+
+        let db = conn.handle();
+        let mut tr = Transaction::new(&mut conn)?;
+        tr.execute("insert into rarrayvalues (id) values (1)", ())?;
+
+        let tr_handle = tr.handle();
+        put_array(db, tr_handle, "INTS", &mut [1_i32, 2, 3])?;
+        // Scaled, with the lower bound 0
+        put_array(db, tr_handle, "NUMS", &mut [125_i32, -300])?;
+        // Multi-dimensional, the last dimension varying faster
+        put_array(db, tr_handle, "MATRIX", &mut [1.5_f64, 2.5, 3.5, 4.5, 5.5, 6.5])?;
+        tr.commit()?;
+
+        let (ints, nums, matrix): (Vec<i32>, Vec<String>, Vec<Vec<f64>>) = conn
+            .query_first("select ints, nums, matrix from rarrayvalues", ())?
+            .unwrap();
+        assert_eq!(vec![1, 2, 3], ints);
+        assert_eq!(vec!["1.25", "-3.00"], nums);
+        assert_eq!(vec![vec![1.5, 2.5, 3.5], vec![4.5, 5.5, 6.5]], matrix);
+
+        Ok(())
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Status {
+        Active = 1,
+        Blocked = 2,
+    }
+
+    impl IntoParam for Status {
+        fn into_param(self) -> SqlType {
+            (self as i64).into_param()
+        }
+    }
+
+    impl ColumnToVal<Status> for Column {
+        fn to_val(self) -> Result<Status, FbError> {
+            match ColumnToVal::<i64>::to_val(self)? {
+                1 => Ok(Status::Active),
+                2 => Ok(Status::Blocked),
+                v => Err(format!("Invalid status: {}", v).into()),
+            }
+        }
+    }
+
+    #[test]
+    fn custom_type() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        conn.execute("DROP TABLE RCUSTOM_TYPE", ()).ok();
+        conn.execute("DROP DOMAIN RSTATUS_DOMAIN", ()).ok();
+        conn.execute("CREATE DOMAIN RSTATUS_DOMAIN AS SMALLINT", ())?;
+        conn.execute("CREATE TABLE RCUSTOM_TYPE (id int, status RSTATUS_DOMAIN)", ())?;
+
+        conn.execute(
+            "insert into rcustom_type (id, status) values (?, ?)",
+            (1, Status::Active),
+        )?;
+        conn.execute(
+            "insert into rcustom_type (id, status) values (?, ?)",
+            (2, Some(Status::Blocked)),
+        )?;
+        conn.execute(
+            "insert into rcustom_type (id, status) values (?, ?)",
+            (3, None::<Status>),
+        )?;
+        conn.execute("insert into rcustom_type (id, status) values (4, 9)", ())?;
+
+        let rows: Vec<(i32, Option<Status>)> =
+            conn.query("select id, status from rcustom_type where id < 4 order by id", ())?;
+        assert_eq!(
+            vec![(1, Some(Status::Active)), (2, Some(Status::Blocked)), (3, None)],
+            rows
+        );
+
+        let res: Result<Option<(Status,)>, _> =
+            conn.query_first("select status from rcustom_type where id = 4", ());
+        assert!(res.is_err());
+
+        Ok(())
+    }
 }
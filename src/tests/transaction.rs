@@ -97,4 +97,208 @@ mk_tests_default! {
       rollback_result
   }
 
+  #[test]
+  fn transaction_id() -> Result<(), FbError> {
+      use crate::Queryable;
+
+      let mut conn = cbuilder().connect()?;
+
+      let mut transaction = Transaction::new(&mut conn)?;
+      let id = transaction.transaction_id()?;
+      let (current,): (i64,) = transaction
+          .query_first("select current_transaction from rdb$database", ())?
+          .unwrap();
+      assert_eq!(current as u64, id);
+
+      // A new transaction id is used after the commit retaining
+      transaction.commit_retaining()?;
+      assert!(transaction.transaction_id()? > id);
+
+      transaction.rollback()
+  }
+
+  #[test]
+  fn detached_transactions() -> Result<(), FbError> {
+      use crate::{Execute, Queryable, TrIsolationLevel};
+      const TABLE_NAME: &str = "RSFBCLIENT_TEST_TRANS_DETACHED";
+
+      let mut conn = cbuilder().connect()?;
+      setup(&mut conn, TABLE_NAME)?;
+
+      let count_sql = format!("select count(*) from {}", TABLE_NAME);
+      let count = |tr: &mut Transaction<_>| -> Result<i32, FbError> {
+          Ok(tr.query_first::<_, (i32,)>(&count_sql, ())?.unwrap().0)
+      };
+
+      // Both active at the same time in the same connection
+      let mut snapshot = conn.detached_transaction(TrIsolationLevel::Concurrency.into())?;
+      let mut writes = conn.detached_transaction(TrIsolationLevel::ReadCommited.into())?;
+
+      assert_eq!(0, snapshot.with_connection(&mut conn, count)?);
+      writes.with_connection(&mut conn, |tr| tr.execute_immediate(format!(insert_stmt_fmtstring!(), TABLE_NAME).as_str()))?;
+      writes.commit(&mut conn)?;
+
+      // The snapshot doesn't see the rows committed after it started
+      assert_eq!(0, snapshot.with_connection(&mut conn, count)?);
+      assert_eq!(1, conn.with_transaction(count)?);
+
+      // Only usable with its own connection
+      let mut other = cbuilder().connect()?;
+      assert!(snapshot.with_connection(&mut other, count).is_err());
+      assert_eq!(0, snapshot.with_connection(&mut conn, count)?);
+      snapshot.rollback(&mut conn)?;
+
+      // The default transaction is independent
+      conn.begin_transaction()?;
+      let detached = conn.detached_transaction(TrIsolationLevel::ReadCommited.into())?;
+      conn.execute(&format!("delete from {}", TABLE_NAME), ())?;
+      detached.rollback(&mut conn)?;
+      conn.commit()?;
+
+      other.close()?;
+
+      // Rolled back when dropped, not blocking the close
+      let mut dropped = conn.detached_transaction(TrIsolationLevel::ReadCommited.into())?;
+      dropped.with_connection(&mut conn, |tr| tr.execute_immediate(format!("delete from {}", TABLE_NAME).as_str()))?;
+      drop(dropped);
+      conn.close()?;
+
+      let mut conn = cbuilder().connect()?;
+      assert_eq!(1, conn.with_transaction(count)?);
+
+      teardown(conn, TABLE_NAME)
+  }
+
+  #[test]
+  #[cfg(not(feature = "pure_rust"))]
+  fn native_handles() -> Result<(), FbError> {
+      let mut conn = cbuilder().connect()?;
+      assert_ne!(0, conn.handle());
+
+      let mut transaction = Transaction::new(&mut conn)?;
+      let handle = transaction.handle();
+      assert_ne!(0, handle);
+
+      // Kept by the retaining operations
+      transaction.commit_retaining()?;
+      assert_eq!(handle, transaction.handle());
+
+      transaction.rollback()
+  }
+
+  #[test]
+  #[cfg(not(feature = "pure_rust"))]
+  fn multi_db_transaction() -> Result<(), FbError> {
+      use crate::{prelude::*, MultiDbTransaction};
+
+      let mut conn1 = cbuilder().connect()?;
+      let mut conn2 = cbuilder().connect()?;
+
+      conn1.execute("DROP TABLE RMULTI_DB_TR", ()).ok();
+      conn1.execute("CREATE TABLE RMULTI_DB_TR (id int, db int)", ())?;
+
+      let mut tr = MultiDbTransaction::new(vec![&mut conn1, &mut conn2])?;
+      tr.with_connection(0, |tr| tr.execute("insert into rmulti_db_tr (id, db) values (1, 1)", ()))?;
+      tr.with_connection(1, |tr| tr.execute("insert into rmulti_db_tr (id, db) values (1, 2)", ()))?;
+      assert!(tr.with_connection(2, |tr| tr.execute("select 1 from rdb$database", ())).is_err());
+      tr.prepare("rsfbclient multi db test")?;
+      tr.commit()?;
+
+      let mut tr = MultiDbTransaction::new(vec![&mut conn1, &mut conn2])?;
+      tr.with_connection(0, |tr| tr.execute("insert into rmulti_db_tr (id, db) values (2, 1)", ()))?;
+      tr.with_connection(1, |tr| tr.execute("insert into rmulti_db_tr (id, db) values (2, 2)", ()))?;
+      tr.rollback()?;
+
+      let rows: Vec<(i32, i32)> = conn1.query("select id, db from rmulti_db_tr order by id, db", ())?;
+      assert_eq!(vec![(1, 1), (1, 2)], rows);
+
+      Ok(())
+  }
+
+  #[test]
+  fn lock_conflict() -> Result<(), FbError> {
+      use crate::{Execute, Queryable, TrLockResolution};
+      use std::time::Duration;
+      const TABLE_NAME: &str = "RSFBCLIENT_TEST_TRANS_LOCK";
+
+      let mut conn = cbuilder().connect()?;
+      setup(&mut conn, TABLE_NAME)?;
+      conn.execute(&format!(insert_stmt_fmtstring!(), TABLE_NAME), ())?;
+
+      let lock_sql = format!("select id from {} where id = 543210 with lock", TABLE_NAME);
+
+      // Locks the row until the transaction ends
+      conn.begin_transaction()?;
+      let _: Vec<(i32,)> = conn.query(&lock_sql, ())?;
+
+      let mut no_wait = cbuilder().with_lock_resolution(TrLockResolution::NoWait).connect()?;
+      let err = no_wait.query::<_, (i32,)>(&lock_sql, ()).unwrap_err();
+      assert!(err.is_lock_conflict(), "{}", err);
+
+      let mut timeout = cbuilder()
+          .with_lock_resolution(TrLockResolution::WaitTimeout(Duration::from_secs(1)))
+          .connect()?;
+      let err = timeout.execute(&format!("update {} set description = 'x'", TABLE_NAME), ()).unwrap_err();
+      assert!(err.is_lock_conflict(), "{}", err);
+
+      // Other errors are not lock conflicts
+      assert!(!no_wait.execute("select 1 from not_exists_table", ()).unwrap_err().is_lock_conflict());
+
+      no_wait.close()?;
+      timeout.close()?;
+      conn.commit()?;
+      teardown(conn, TABLE_NAME)
+  }
+
+  #[test]
+  fn default_transaction_config() -> Result<(), FbError> {
+      use crate::{Execute, Queryable, TrDataAccessMode, TrIsolationLevel, TransactionConfiguration};
+      const TABLE_NAME: &str = "RSFBCLIENT_TEST_TRANS_DEFAULT";
+
+      let mut conn = cbuilder().connect()?;
+      setup(&mut conn, TABLE_NAME)?;
+      conn.close()?;
+
+      let mut conn = cbuilder()
+          .with_default_transaction(TransactionConfiguration {
+              isolation: TrIsolationLevel::Concurrency,
+              data_access: TrDataAccessMode::ReadOnly,
+          })
+          .connect()?;
+
+      let count_sql = format!("select count(*) from {}", TABLE_NAME);
+      let (count,): (i32,) = conn.query_first(&count_sql, ())?.unwrap();
+      assert_eq!(0, count);
+
+      // The implicit transactions are read only
+      let insert_sql = format!(insert_stmt_fmtstring!(), TABLE_NAME);
+      assert!(conn.execute(&insert_sql, ()).is_err());
+      assert!(conn.with_transaction(|tr| tr.execute(&insert_sql, ())).is_err());
+
+      // The explicit transactions use their own parameters
+      let mut detached = conn.detached_transaction(TrIsolationLevel::ReadCommited.into())?;
+      detached.with_connection(&mut conn, |tr| tr.execute(&insert_sql, ()))?;
+      detached.commit(&mut conn)?;
+
+      let (count,): (i32,) = conn.query_first(&count_sql, ())?.unwrap();
+      assert_eq!(1, count);
+
+      let mut detached = conn.detached_transaction(TransactionConfiguration {
+          isolation: TrIsolationLevel::ReadCommited,
+          data_access: TrDataAccessMode::ReadOnly,
+      })?;
+      assert!(detached.with_connection(&mut conn, |tr| tr.execute(&insert_sql, ())).is_err());
+      detached.rollback(&mut conn)?;
+
+      let mut tr = Transaction::new_with_config(&mut conn, TrIsolationLevel::ReadCommited.into())?;
+      tr.execute(&format!("delete from {}", TABLE_NAME), ())?;
+      tr.commit()?;
+
+      let (count,): (i32,) = conn.query_first(&count_sql, ())?.unwrap();
+      assert_eq!(0, count);
+
+      conn.close()?;
+      teardown(cbuilder().connect()?, TABLE_NAME)
+  }
+
 }
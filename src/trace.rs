@@ -0,0 +1,404 @@
+//!
+//! Rust Firebird Client
+//!
+//! Tracing of the statements executions, and their metrics
+//!
+
+use rsfbclient_core::{FbError, SqlType};
+
+/// Data recorded in the statements spans
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TraceConfig {
+    /// Record the sql text
+    pub sql: bool,
+
+    /// Record the parameter values
+    pub params: bool,
+
+    /// Warn when the connection has more cursors open
+    pub open_cursors_warning: Option<usize>,
+}
+
+impl Default for TraceConfig {
+    fn default() -> Self {
+        Self {
+            sql: true,
+            params: true,
+            open_cursors_warning: None,
+        }
+    }
+}
+
+/// Warn if the cursors open in the connection passed the configured threshold,
+/// usually due to iterators or statements kept alive without being dropped.
+///
+/// Does nothing without the `tracing` feature
+#[allow(unused_variables)]
+pub(crate) fn check_open_cursors(conf: &TraceConfig, open_cursors: usize) {
+    #[cfg(feature = "tracing")]
+    if let Some(threshold) = conf.open_cursors_warning {
+        if open_cursors > threshold {
+            tracing::warn!(
+                open_cursors,
+                threshold,
+                "Too many cursors open in the connection, an iterator may be leaking"
+            );
+        }
+    }
+}
+
+/// Span of a statement operation, recording the rows and the elapsed time.
+///
+/// Does nothing without the `tracing` and `metrics` features
+#[derive(Default)]
+pub(crate) struct StmtTrace {
+    #[cfg(any(feature = "tracing", feature = "metrics"))]
+    active: Option<ActiveTrace>,
+}
+
+#[cfg(any(feature = "tracing", feature = "metrics"))]
+struct ActiveTrace {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    #[cfg(feature = "metrics")]
+    op: &'static str,
+    start: std::time::Instant,
+    rows: usize,
+}
+
+#[cfg(any(feature = "tracing", feature = "metrics"))]
+impl StmtTrace {
+    /// Open the span of a statement operation
+    #[allow(unused_variables)]
+    pub fn start(conf: &TraceConfig, op: &'static str, sql: &str, params: &[SqlType]) -> Self {
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!(
+            "statement",
+            op,
+            sql = tracing::field::Empty,
+            params = tracing::field::Empty,
+            rows = tracing::field::Empty,
+            elapsed_ms = tracing::field::Empty,
+        );
+
+        #[cfg(feature = "tracing")]
+        {
+            if conf.sql {
+                span.record("sql", sql);
+            }
+            if conf.params && !params.is_empty() {
+                span.record("params", tracing::field::debug(params));
+            }
+        }
+
+        Self {
+            active: Some(ActiveTrace {
+                #[cfg(feature = "tracing")]
+                span,
+                #[cfg(feature = "metrics")]
+                op,
+                start: std::time::Instant::now(),
+                rows: 0,
+            }),
+        }
+    }
+
+    /// Run the operation inside of the span, emitting an event on errors
+    pub fn in_span<T, F>(&mut self, op: F) -> Result<T, FbError>
+    where
+        F: FnOnce() -> Result<T, FbError>,
+    {
+        let active = match &self.active {
+            Some(active) => active,
+            None => return op(),
+        };
+
+        #[cfg(feature = "tracing")]
+        let _enter = active.span.enter();
+
+        let res = op();
+
+        // With the gds code of the first error in the status vector
+        #[cfg(feature = "tracing")]
+        if let Err(e) = &res {
+            match e.status_vector().and_then(|status| status.first()) {
+                Some(item) => tracing::error!(code = item.code, error = %e, "statement failed"),
+                None => tracing::error!(error = %e, "statement failed"),
+            }
+        }
+
+        #[cfg(feature = "metrics")]
+        if let Err(e) = &res {
+            metrics::counter!("fb_errors_total", "op" => active.op, "class" => error_class(e))
+                .increment(1);
+        }
+
+        res
+    }
+
+    /// Count the affected or fetched rows
+    pub fn add_rows(&mut self, rows: usize) {
+        if let Some(active) = &mut self.active {
+            active.rows += rows;
+        }
+    }
+
+    /// Record the rows and the elapsed time, closing the span
+    pub fn finish(&mut self) {
+        if let Some(active) = self.active.take() {
+            let elapsed = active.start.elapsed();
+
+            #[cfg(feature = "tracing")]
+            {
+                let elapsed_ms = elapsed.as_millis() as u64;
+
+                active.span.record("rows", active.rows);
+                active.span.record("elapsed_ms", elapsed_ms);
+
+                let _enter = active.span.enter();
+                tracing::debug!(rows = active.rows, elapsed_ms, "statement finished");
+            }
+
+            #[cfg(feature = "metrics")]
+            {
+                // The prepares are only in the histogram
+                if active.op != "prepare" {
+                    metrics::counter!("fb_queries_total", "op" => active.op).increment(1);
+                }
+                metrics::histogram!("fb_query_duration_seconds", "op" => active.op)
+                    .record(elapsed.as_secs_f64());
+            }
+        }
+    }
+}
+
+/// Label of the error in the metrics: the class of the gds codes in the status vector,
+/// or the kind of the errors without one. Kept to a fixed set, to bound the labels cardinality
+#[cfg(feature = "metrics")]
+fn error_class(e: &FbError) -> &'static str {
+    use rsfbclient_core::ibase;
+
+    match e {
+        FbError::Sql { status, .. } => status
+            .iter()
+            .find_map(|item| match item.code {
+                ibase::isc_unique_key_violation
+                | ibase::isc_no_dup
+                | ibase::isc_foreign_key
+                | ibase::isc_foreign_key_target_doesnt_exist
+                | ibase::isc_foreign_key_references_present
+                | ibase::isc_check_constraint
+                | ibase::isc_not_valid
+                | ibase::isc_not_valid_for
+                | ibase::isc_not_valid_for_var => Some("constraint"),
+                ibase::isc_deadlock | ibase::isc_lock_conflict | ibase::isc_update_conflict => {
+                    Some("conflict")
+                }
+                ibase::isc_no_priv => Some("permission"),
+                ibase::isc_arith_except
+                | ibase::isc_string_truncation
+                | ibase::isc_numeric_out_of_range => Some("data"),
+                _ => None,
+            })
+            .unwrap_or("sql"),
+        FbError::Timeout { .. } => "timeout",
+        FbError::Cancelled { .. } => "cancelled",
+        FbError::ConnectionLost { .. } => "connection_lost",
+        FbError::TransactionLost { .. } => "transaction_lost",
+        FbError::MetadataChanged { .. } => "metadata_changed",
+        FbError::Io(_) => "io",
+        FbError::Other(_) => "other",
+    }
+}
+
+#[cfg(any(feature = "tracing", feature = "metrics"))]
+impl Drop for StmtTrace {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+#[cfg(not(any(feature = "tracing", feature = "metrics")))]
+impl StmtTrace {
+    #[inline(always)]
+    pub fn start(_conf: &TraceConfig, _op: &'static str, _sql: &str, _params: &[SqlType]) -> Self {
+        Self {}
+    }
+
+    #[inline(always)]
+    pub fn in_span<T, F>(&mut self, op: F) -> Result<T, FbError>
+    where
+        F: FnOnce() -> Result<T, FbError>,
+    {
+        op()
+    }
+
+    #[inline(always)]
+    pub fn add_rows(&mut self, _rows: usize) {}
+
+    #[inline(always)]
+    pub fn finish(&mut self) {}
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod test {
+    use super::*;
+    use metrics::{
+        Counter, CounterFn, Gauge, Histogram, HistogramFn, Key, KeyName, Metadata, Recorder,
+        SharedString, Unit,
+    };
+    use rsfbclient_core::{ibase, StatusItem};
+    use std::sync::{Arc, Mutex};
+
+    /// Recorder keeping the updates, as `name{label=value,..}` and the value
+    #[derive(Default)]
+    struct TestRecorder {
+        updates: Arc<Mutex<Vec<(String, f64)>>>,
+    }
+
+    struct TestHandle {
+        key: String,
+        updates: Arc<Mutex<Vec<(String, f64)>>>,
+    }
+
+    impl CounterFn for TestHandle {
+        fn increment(&self, value: u64) {
+            self.updates
+                .lock()
+                .unwrap()
+                .push((self.key.clone(), value as f64));
+        }
+
+        fn absolute(&self, value: u64) {
+            self.increment(value);
+        }
+    }
+
+    impl HistogramFn for TestHandle {
+        fn record(&self, value: f64) {
+            self.updates.lock().unwrap().push((self.key.clone(), value));
+        }
+    }
+
+    impl TestRecorder {
+        fn handle(&self, key: &Key) -> Arc<TestHandle> {
+            let labels: Vec<String> = key
+                .labels()
+                .map(|l| format!("{}={}", l.key(), l.value()))
+                .collect();
+
+            Arc::new(TestHandle {
+                key: format!("{}{{{}}}", key.name(), labels.join(",")),
+                updates: self.updates.clone(),
+            })
+        }
+
+        fn keys(&self) -> Vec<String> {
+            let updates = self.updates.lock().unwrap();
+            updates.iter().map(|(key, _)| key.clone()).collect()
+        }
+    }
+
+    impl Recorder for TestRecorder {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+            Counter::from_arc(self.handle(key))
+        }
+
+        fn register_gauge(&self, _: &Key, _: &Metadata<'_>) -> Gauge {
+            Gauge::noop()
+        }
+
+        fn register_histogram(&self, key: &Key, _: &Metadata<'_>) -> Histogram {
+            Histogram::from_arc(self.handle(key))
+        }
+    }
+
+    fn sql_error(codes: &[u32]) -> FbError {
+        FbError::Sql {
+            msg: "error".to_string(),
+            code: -1,
+            status: codes
+                .iter()
+                .map(|&code| StatusItem { code, args: vec![] })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn error_classes() {
+        assert_eq!(
+            error_class(&sql_error(&[ibase::isc_unique_key_violation])),
+            "constraint"
+        );
+        assert_eq!(
+            error_class(&sql_error(&[
+                ibase::isc_update_conflict,
+                ibase::isc_deadlock
+            ])),
+            "conflict"
+        );
+        assert_eq!(error_class(&sql_error(&[ibase::isc_no_priv])), "permission");
+        // Classified by the specific error after the generic one
+        assert_eq!(
+            error_class(&sql_error(&[
+                ibase::isc_arith_except,
+                ibase::isc_string_truncation
+            ])),
+            "data"
+        );
+        assert_eq!(
+            error_class(&sql_error(&[ibase::isc_dsql_error, ibase::isc_random])),
+            "sql"
+        );
+        assert_eq!(error_class(&sql_error(&[])), "sql");
+        assert_eq!(
+            error_class(&FbError::Timeout {
+                msg: "timeout".to_string(),
+                status: vec![]
+            }),
+            "timeout"
+        );
+        assert_eq!(error_class(&FbError::Other("other".to_string())), "other");
+    }
+
+    #[test]
+    fn operations_metrics() {
+        let recorder = TestRecorder::default();
+        let conf = TraceConfig::default();
+
+        metrics::with_local_recorder(&recorder, || {
+            let mut trace = StmtTrace::start(&conf, "prepare", "select 1", &[]);
+            trace.in_span(|| Ok(())).unwrap();
+            trace.finish();
+
+            let mut trace = StmtTrace::start(&conf, "execute", "delete", &[]);
+            trace
+                .in_span(|| Err::<(), _>(sql_error(&[ibase::isc_lock_conflict])))
+                .unwrap_err();
+            drop(trace);
+
+            let mut trace = StmtTrace::start(&conf, "query", "select 1", &[]);
+            trace.in_span(|| Ok(())).unwrap();
+            trace.add_rows(3);
+            trace.finish();
+            // Recorded only once
+            trace.finish();
+        });
+
+        assert_eq!(
+            recorder.keys(),
+            vec![
+                "fb_query_duration_seconds{op=prepare}",
+                "fb_errors_total{op=execute,class=conflict}",
+                "fb_queries_total{op=execute}",
+                "fb_query_duration_seconds{op=execute}",
+                "fb_queries_total{op=query}",
+                "fb_query_duration_seconds{op=query}",
+            ]
+        );
+    }
+}
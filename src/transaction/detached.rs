@@ -0,0 +1,123 @@
+//!
+//! Rust Firebird Client
+//!
+//! Transactions not borrowing the connection
+//!
+
+use rsfbclient_core::{FbError, FirebirdClient, TransactionConfiguration};
+use std::sync::{Arc, Mutex};
+
+use super::{Transaction, TransactionData};
+use crate::Connection;
+
+/// Transaction that doesn't keep the connection borrowed, so many of them can be active
+/// at the same time in the same connection, like a long running snapshot for the reads
+/// alongside short transactions for the writes.
+///
+/// A [`Transaction`] holds the `&mut Connection` until it is finished, so only one
+/// of them can exist at a time. This type only keeps the transaction handle, and the
+/// connection is lent on each use with
+/// [`with_connection`][`DetachedTransaction::with_connection`], for the duration of
+/// the closure. The operations of the transactions still run one at a time, as the
+/// connection is used by one of them at a time.
+///
+/// The transaction can only be used with the connection that started it. It must be
+/// finished with [`commit`][`DetachedTransaction::commit`] or
+/// [`rollback`][`DetachedTransaction::rollback`], as it can't be rolled back when dropped
+/// without the connection. If dropped, it is kept active until the connection starts
+/// another detached transaction or is closed, when it is rolled back.
+pub struct DetachedTransaction<C>
+where
+    C: FirebirdClient,
+{
+    /// Transaction handle, taken when the transaction is finished
+    data: Option<TransactionData<C>>,
+
+    /// Id of the connection that started the transaction
+    conn_id: u64,
+
+    /// Where the handle is sent when dropped, for the connection to roll it back
+    dropped_trs: Arc<Mutex<Vec<TransactionData<C>>>>,
+}
+
+impl<C> DetachedTransaction<C>
+where
+    C: FirebirdClient,
+{
+    /// Start a new transaction in the connection, with the isolation level and data
+    /// access mode. The default transaction configuration of the connection is not used
+    pub fn new(conn: &mut Connection<C>, conf: TransactionConfiguration) -> Result<Self, FbError> {
+        conn.rollback_dropped_trs()?;
+
+        let data = TransactionData::begin(conn, conf)?;
+
+        Ok(Self {
+            data: Some(data),
+            conn_id: conn.id,
+            dropped_trs: conn.dropped_trs.clone(),
+        })
+    }
+
+    /// Run a closure with the transaction, lending it the connection
+    pub fn with_connection<T, F>(
+        &mut self,
+        conn: &mut Connection<C>,
+        closure: F,
+    ) -> Result<T, FbError>
+    where
+        F: FnOnce(&mut Transaction<C>) -> Result<T, FbError>,
+    {
+        let data = self.take_data(conn)?;
+        let mut tr = data.into_transaction(conn);
+
+        let res = closure(&mut tr);
+
+        // The handle is kept in this struct, so the transaction must not be rolled back
+        self.data = Some(TransactionData::from_transaction(tr));
+
+        res
+    }
+
+    /// Commit the transaction changes
+    pub fn commit(mut self, conn: &mut Connection<C>) -> Result<(), FbError> {
+        let mut data = self.take_data(conn)?;
+
+        let result = data.commit(conn);
+        if result.is_err() {
+            data.rollback(conn).ok();
+        }
+
+        result
+    }
+
+    /// Rollback the transaction changes
+    pub fn rollback(mut self, conn: &mut Connection<C>) -> Result<(), FbError> {
+        self.take_data(conn)?.rollback(conn)
+    }
+
+    /// Take the handle, checking the transaction was started in the connection
+    fn take_data(&mut self, conn: &Connection<C>) -> Result<TransactionData<C>, FbError> {
+        if conn.id != self.conn_id {
+            return Err("The transaction was started in another connection".into());
+        }
+
+        self.data
+            .take()
+            .ok_or_else(|| "The transaction was already finished".into())
+    }
+}
+
+impl<C> Drop for DetachedTransaction<C>
+where
+    C: FirebirdClient,
+{
+    fn drop(&mut self) {
+        if let Some(data) = self.data.take() {
+            let mut dropped = match self.dropped_trs.lock() {
+                Ok(dropped) => dropped,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+            dropped.push(data);
+        }
+    }
+}
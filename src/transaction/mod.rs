@@ -4,19 +4,28 @@
 //! Transaction functions
 //!
 
-use rsfbclient_core::{FbError, FirebirdClient, FromRow, IntoParams, TrIsolationLevel, TrOp};
+use rsfbclient_core::{
+    ibase, FbError, FirebirdClient, FromRow, IntoParams, TrOp, TransactionConfiguration,
+};
 use std::marker;
 use std::mem;
 
 use super::{connection::Connection, statement::Statement};
 use crate::{
-    connection::stmt_cache::StmtCache, connection::stmt_cache::StmtCacheData,
+    connection::db_info, connection::stmt_cache::StmtCache, connection::stmt_cache::StmtCacheData,
     statement::StatementData, Execute, Queryable,
 };
 
+mod detached;
+mod multi_db;
 mod simple;
+pub use detached::DetachedTransaction;
+pub use multi_db::MultiDbTransaction;
 pub use simple::SimpleTransaction;
 
+/// Transaction in a connection, keeping the connection borrowed until it is
+/// finished. To have more than one transaction active at the same time in the
+/// same connection, use a [`DetachedTransaction`]
 pub struct Transaction<'c, C>
 where
     C: FirebirdClient,
@@ -26,13 +35,24 @@ where
 }
 
 impl<'c, C: FirebirdClient> Transaction<'c, C> {
-    /// Start a new transaction
+    /// Start a new transaction, with the default transaction configuration of the connection
     pub fn new(conn: &'c mut Connection<C>) -> Result<Self, FbError> {
         let data = TransactionData::new(conn)?;
 
         Ok(Transaction { data, conn })
     }
 
+    /// Start a new transaction with the isolation level and data access mode,
+    /// instead of the default transaction configuration of the connection
+    pub fn new_with_config(
+        conn: &'c mut Connection<C>,
+        conf: TransactionConfiguration,
+    ) -> Result<Self, FbError> {
+        let data = TransactionData::begin(conn, conf)?;
+
+        Ok(Transaction { data, conn })
+    }
+
     /// Commit the current transaction changes
     pub fn commit(mut self) -> Result<(), FbError> {
         let result = self.data.commit(self.conn);
@@ -68,6 +88,38 @@ impl<'c, C: FirebirdClient> Transaction<'c, C> {
         self.data.execute_immediate(self.conn, sql)
     }
 
+    /// Handle of the transaction in the client, to use the client api directly,
+    /// like creating and reading blobs with `isc_create_blob2` and `isc_open_blob2`.
+    ///
+    /// For the native client this is the `isc_tr_handle`, and the database handle is
+    /// obtained with [`Connection::handle`]. The pure rust client handles can't be used
+    /// outside of this crate.
+    ///
+    /// The handle is valid until the transaction is committed or rolled back, and is kept
+    /// by `commit_retaining` and `rollback_retaining`. The blobs created with it belong to
+    /// this transaction: their ids can only be used in the statements of this transaction,
+    /// and they are discarded if it is rolled back. The transaction must not be committed,
+    /// rolled back or otherwise modified directly with the handle, only with the methods of
+    /// this type
+    pub fn handle(&self) -> C::TrHandle
+    where
+        C::TrHandle: Copy,
+    {
+        self.data.handle
+    }
+
+    /// Id of the transaction in the database, the same returned by `CURRENT_TRANSACTION`
+    pub fn transaction_id(&mut self) -> Result<u64, FbError> {
+        let item = ibase::isc_info_tra_id as u8;
+        let buffer = self.conn.cli.transaction_info(
+            &mut self.data.handle,
+            &[item, ibase::isc_info_end as u8],
+            32,
+        )?;
+
+        db_info::parse_int(&buffer, item)
+    }
+
     /// Prepare a new statement for execute
     pub fn prepare<'t>(
         &'t mut self,
@@ -104,15 +156,17 @@ where
 {
     fn drop(&mut self) {
         // Close the cursor
-        self.stmt_cache_data
+        let res = self
+            .stmt_cache_data
             .as_mut()
             .unwrap()
             .stmt
-            .close_cursor(self.tr.conn)
-            .ok();
+            .close_cursor(self.tr.conn);
+        self.tr.conn.keep_cleanup_error(res);
 
         // Send the statement back to the cache
-        StmtCache::insert_and_close(self.tr.conn, self.stmt_cache_data.take().unwrap()).ok();
+        let res = StmtCache::insert_and_close(self.tr.conn, self.stmt_cache_data.take().unwrap());
+        self.tr.conn.keep_cleanup_error(res);
     }
 }
 
@@ -147,12 +201,13 @@ impl<'c, C: FirebirdClient> Queryable for Transaction<'c, C> {
         let params = params.to_params();
 
         // Get a statement from the cache
-        let mut stmt_cache_data = StmtCache::get_or_prepare(self, sql, params.named())?;
+        let (stmt_cache_data, res) =
+            StmtCache::run(self, sql, params, |stmt, conn, tr, params| {
+                stmt.check_columns::<R>(conn)?;
+                stmt.query(conn, tr, params)
+            })?;
 
-        match stmt_cache_data
-            .stmt
-            .query(self.conn, &mut self.data, params)
-        {
+        match res {
             Ok(_) => {
                 let iter = StmtIter {
                     stmt_cache_data: Some(stmt_cache_data),
@@ -166,7 +221,7 @@ impl<'c, C: FirebirdClient> Queryable for Transaction<'c, C> {
                 // Return the statement to the cache
                 StmtCache::insert_and_close(self.conn, stmt_cache_data)?;
 
-                Err(e)
+                self.conn.check_error(Err(e))
             }
         }
     }
@@ -179,18 +234,17 @@ impl<C: FirebirdClient> Execute for Transaction<'_, C> {
     {
         let params = params.to_params();
 
-        // Get a statement from the cache
-        let mut stmt_cache_data = StmtCache::get_or_prepare(self, sql, params.named())?;
-
-        // Do not return now in case of error, because we need to return the statement to the cache
-        let res = stmt_cache_data
-            .stmt
-            .execute(self.conn, &mut self.data, params);
+        // Get a statement from the cache. Do not return now in case of error,
+        // because we need to return the statement to the cache
+        let (stmt_cache_data, res) =
+            StmtCache::run(self, sql, params, |stmt, conn, tr, params| {
+                stmt.execute(conn, tr, params)
+            })?;
 
         // Return the statement to the cache
         StmtCache::insert_and_close(self.conn, stmt_cache_data)?;
 
-        res
+        self.conn.check_error(res)
     }
 
     fn execute_returnable<P, R>(&mut self, sql: &str, params: P) -> Result<R, FbError>
@@ -200,18 +254,17 @@ impl<C: FirebirdClient> Execute for Transaction<'_, C> {
     {
         let params = params.to_params();
 
-        // Get a statement from the cache
-        let mut stmt_cache_data = StmtCache::get_or_prepare(self, sql, params.named())?;
-
-        // Do not return now in case of error, because we need to return the statement to the cache
-        let res = stmt_cache_data
-            .stmt
-            .execute2(self.conn, &mut self.data, params);
+        // Get a statement from the cache. Do not return now in case of error,
+        // because we need to return the statement to the cache
+        let (stmt_cache_data, res) =
+            StmtCache::run(self, sql, params, |stmt, conn, tr, params| {
+                stmt.execute2(conn, tr, params)
+            })?;
 
         // Return the statement to the cache
         StmtCache::insert_and_close(self.conn, stmt_cache_data)?;
 
-        FromRow::try_from(res?)
+        FromRow::try_from(self.conn.check_error(res)?)
     }
 }
 
@@ -227,11 +280,18 @@ impl<C: FirebirdClient> TransactionData<C>
 where
     C::TrHandle: Send,
 {
-    /// Start a new transaction
+    /// Start a new transaction, with the default transaction configuration of the connection
     fn new(conn: &mut Connection<C>) -> Result<Self, FbError> {
+        let conf = conn.default_tr_config;
+
+        Self::begin(conn, conf)
+    }
+
+    /// Start a new transaction with the parameters
+    fn begin(conn: &mut Connection<C>, conf: TransactionConfiguration) -> Result<Self, FbError> {
         let handle = conn
             .cli
-            .begin_transaction(&mut conn.handle, TrIsolationLevel::ReadCommited)?;
+            .begin_transaction_with_config(&mut conn.handle, conf)?;
 
         Ok(Self { handle })
     }
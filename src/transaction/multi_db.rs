@@ -0,0 +1,163 @@
+//!
+//! Rust Firebird Client
+//!
+//! Transactions spanning more than one database
+//!
+
+use rsfbclient_core::{
+    FbError, FirebirdClient, FirebirdClientDbOps, FirebirdClientMultiDbOps, TrIsolationLevel,
+};
+
+use super::{Transaction, TransactionData};
+use crate::Connection;
+
+/// Transaction spanning more than one database, committed atomically with the two-phase commit.
+/// Only supported by the native client.
+///
+/// The statements are executed in each database with
+/// [`with_connection`][`MultiDbTransaction::with_connection`], using the position of the
+/// connection in the list passed to [`new`][`MultiDbTransaction::new`].
+///
+/// On [`commit`][`MultiDbTransaction::commit`], the transaction is first prepared in all the
+/// databases, and only committed after all of them succeed. If the preparation fails, the
+/// transaction is rolled back in all the databases. If the commit fails after the preparation,
+/// like when the connection with one of the servers is lost, the transaction can be left
+/// *in limbo* in some databases, keeping its changes and locks pending until it is resolved
+/// with `gfix -list` and `gfix -commit <id>` / `gfix -rollback <id>`, or `gfix -two_phase`
+/// to commit all of them. The description passed to [`prepare`][`MultiDbTransaction::prepare`]
+/// is stored in the databases to help identifying the transaction.
+pub struct MultiDbTransaction<'c, C>
+where
+    C: FirebirdClient + FirebirdClientMultiDbOps,
+{
+    /// Transaction handle, taken when the transaction is finished
+    data: Option<TransactionData<C>>,
+
+    /// Connections with the databases. The first one is used to prepare,
+    /// commit and rollback the transaction
+    conns: Vec<&'c mut Connection<C>>,
+
+    /// Already prepared with the first phase of the two-phase commit
+    prepared: bool,
+}
+
+impl<'c, C> MultiDbTransaction<'c, C>
+where
+    C: FirebirdClient + FirebirdClientMultiDbOps,
+    C::TrHandle: Copy,
+    <C as FirebirdClientDbOps>::DbHandle: Copy,
+{
+    /// Start a new transaction in the databases of all connections
+    pub fn new(mut conns: Vec<&'c mut Connection<C>>) -> Result<Self, FbError> {
+        let mut db_handles: Vec<_> = conns.iter().map(|conn| conn.handle).collect();
+
+        let first = conns
+            .first_mut()
+            .ok_or("The transaction needs at least one connection")?;
+
+        let handle = first
+            .cli
+            .begin_multi_transaction(&mut db_handles, TrIsolationLevel::ReadCommited)?;
+
+        Ok(Self {
+            data: Some(TransactionData { handle }),
+            conns,
+            prepared: false,
+        })
+    }
+
+    /// Run a closure with the transaction in the database of a connection,
+    /// by its position in the list passed to `new`
+    pub fn with_connection<T, F>(&mut self, conn_index: usize, closure: F) -> Result<T, FbError>
+    where
+        F: FnOnce(&mut Transaction<C>) -> Result<T, FbError>,
+    {
+        if self.prepared {
+            return Err("The transaction was already prepared".into());
+        }
+
+        let handle = self.handle()?;
+        let conn = self
+            .conns
+            .get_mut(conn_index)
+            .ok_or_else(|| FbError::from(format!("Invalid connection index: {}", conn_index)))?;
+
+        let mut tr = TransactionData { handle }.into_transaction(conn);
+
+        let res = closure(&mut tr);
+
+        // The handle is kept in this struct, so the transaction must not be rolled back
+        TransactionData::from_transaction(tr);
+
+        res
+    }
+
+    /// First phase of the two-phase commit, preparing the transaction to be committed
+    /// in all databases. The description is stored in the databases, to identify the
+    /// transaction if it is left in limbo.
+    ///
+    /// Called automatically by `commit` if not called before
+    pub fn prepare(&mut self, description: &str) -> Result<(), FbError> {
+        if self.prepared {
+            return Ok(());
+        }
+
+        let data = self
+            .data
+            .as_mut()
+            .ok_or("The transaction was already finished")?;
+
+        self.conns[0]
+            .cli
+            .prepare_transaction(&mut data.handle, description.as_bytes())?;
+
+        self.prepared = true;
+
+        Ok(())
+    }
+
+    /// Commit the changes in all databases, preparing the transaction first if needed.
+    ///
+    /// If the preparation fails, the transaction is rolled back. If the commit fails
+    /// after the preparation, the transaction is not rolled back, as it may be already
+    /// committed in some databases
+    pub fn commit(mut self) -> Result<(), FbError> {
+        if let Err(e) = self.prepare("") {
+            self.rollback().ok();
+            return Err(e);
+        }
+
+        if let Some(mut data) = self.data.take() {
+            data.commit(self.conns[0])?;
+        }
+
+        Ok(())
+    }
+
+    /// Rollback the changes in all databases
+    pub fn rollback(mut self) -> Result<(), FbError> {
+        if let Some(mut data) = self.data.take() {
+            data.rollback(self.conns[0])?;
+        }
+
+        Ok(())
+    }
+
+    fn handle(&self) -> Result<C::TrHandle, FbError> {
+        self.data
+            .as_ref()
+            .map(|data| data.handle)
+            .ok_or_else(|| "The transaction was already finished".into())
+    }
+}
+
+impl<'c, C> Drop for MultiDbTransaction<'c, C>
+where
+    C: FirebirdClient + FirebirdClientMultiDbOps,
+{
+    fn drop(&mut self) {
+        if let Some(mut data) = self.data.take() {
+            data.rollback(self.conns[0]).ok();
+        }
+    }
+}
@@ -168,6 +168,18 @@ impl<'c> SimpleTransaction<'c> {
         }
     }
 
+    /// Id of the transaction in the database, the same returned by `CURRENT_TRANSACTION`
+    pub fn transaction_id(&mut self) -> Result<u64, FbError> {
+        match &mut self.inner {
+            #[cfg(feature = "linking")]
+            TypeTransactionContainer::NativeDynLink(tr) => tr.transaction_id(),
+            #[cfg(feature = "dynamic_loading")]
+            TypeTransactionContainer::NativeDynLoad(tr) => tr.transaction_id(),
+            #[cfg(feature = "pure_rust")]
+            TypeTransactionContainer::PureRust(tr) => tr.transaction_id(),
+        }
+    }
+
     // TODO: add the prepare() method
 }
 
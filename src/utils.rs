@@ -3,6 +3,7 @@
 //!
 
 use crate::{FbError, Queryable};
+use rsfbclient_core::sql_words;
 
 /// Infos about the server, database, engine...
 pub trait SystemInfos {
@@ -11,6 +12,12 @@ pub trait SystemInfos {
 
     /// Return the current server version
     fn server_engine(&mut self) -> Result<EngineVersion, FbError>;
+
+    /// Limit the rows returned by the select to a page, with the syntax supported by the
+    /// server version. See [`paginate`]
+    fn paginate(&mut self, sql: &str, limit: u64, offset: u64) -> Result<String, FbError> {
+        paginate(sql, limit, offset, self.server_engine()?)
+    }
 }
 
 #[derive(PartialOrd, PartialEq, Eq, Debug, Copy, Clone)]
@@ -20,6 +27,87 @@ pub enum EngineVersion {
     V2 = 2,
     V3 = 3,
     V4 = 4,
+    V5 = 5,
+}
+
+/// Limit the rows returned by the select to `limit` rows, skipping the first `offset` ones,
+/// as firebird doesn't support the `LIMIT` clause. Appends `OFFSET m ROWS FETCH NEXT n ROWS ONLY`
+/// on Firebird 3+, appends `ROWS m + 1 TO m + n` on Firebird 2, and adds `FIRST n SKIP m`
+/// after the `SELECT` on older versions.
+///
+/// The select must not have a `FIRST`, `SKIP`, `ROWS` or `OFFSET` clause already, and
+/// must have an `ORDER BY` for the pages to be stable. The appended clauses start in a new
+/// line, so a comment at the end of the sql doesn't hide them.
+///
+/// Fails if the sql is not a select, if it has a `FOR UPDATE` or `WITH LOCK` clause,
+/// that must come after the pagination, or if the limit is zero
+pub fn paginate(
+    sql: &str,
+    limit: u64,
+    offset: u64,
+    engine: EngineVersion,
+) -> Result<String, FbError> {
+    let sql = sql
+        .trim()
+        .trim_end_matches(|c: char| c == ';' || c.is_whitespace());
+
+    let starts_with = |keyword: &str| {
+        sql.get(..keyword.len())
+            .map(|s| s.eq_ignore_ascii_case(keyword))
+            .unwrap_or(false)
+            && sql[keyword.len()..]
+                .chars()
+                .next()
+                .map(|c| c.is_whitespace())
+                .unwrap_or(false)
+    };
+
+    if !(starts_with("select") || (starts_with("with") && engine >= EngineVersion::V2)) {
+        return Err("Only the select statements can be paginated".into());
+    }
+
+    if has_lock_clause(sql) {
+        return Err(
+            "The selects with a FOR UPDATE or WITH LOCK clause can't be paginated, as it must come after the pagination"
+                .into(),
+        );
+    }
+
+    if limit == 0 {
+        return Err("The page limit must be greater than zero".into());
+    }
+
+    Ok(match engine {
+        EngineVersion::V1 => format!(
+            "{} FIRST {} SKIP {}{}",
+            &sql[.."select".len()],
+            limit,
+            offset,
+            &sql["select".len()..]
+        ),
+        EngineVersion::V2 => {
+            let last = offset
+                .checked_add(limit)
+                .ok_or_else(|| FbError::from("The page end overflows"))?;
+
+            format!("{}\nROWS {} TO {}", sql, offset + 1, last)
+        }
+        _ => format!(
+            "{}\nOFFSET {} ROWS FETCH NEXT {} ROWS ONLY",
+            sql, offset, limit
+        ),
+    })
+}
+
+/// The select has a `FOR UPDATE` or `WITH LOCK` clause, outside of the
+/// string literals and comments
+fn has_lock_clause(sql: &str) -> bool {
+    sql_words(sql).windows(2).any(|pair| {
+        matches!(
+            (pair[0].as_str(), pair[1].as_str()),
+            ("FOR", "UPDATE") | ("WITH", "LOCK")
+        )
+    })
 }
 
 impl<T> SystemInfos for T
@@ -45,6 +133,7 @@ where
 
         if let Some((version,)) = row {
             return match &version.get(0..2) {
+                Some("5.") => Ok(EngineVersion::V5),
                 Some("4.") => Ok(EngineVersion::V4),
                 Some("3.") => Ok(EngineVersion::V3),
                 Some("2.") => Ok(EngineVersion::V2),
@@ -57,6 +146,101 @@ where
     }
 }
 
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn paginate_syntax() -> Result<(), FbError> {
+        let sql = "select id from job order by id;";
+
+        assert_eq!(
+            "select id from job order by id\nOFFSET 20 ROWS FETCH NEXT 10 ROWS ONLY",
+            paginate(sql, 10, 20, EngineVersion::V4)?
+        );
+        assert_eq!(
+            "select id from job order by id\nROWS 21 TO 30",
+            paginate(sql, 10, 20, EngineVersion::V2)?
+        );
+        assert_eq!(
+            "select id from job order by id -- last\nOFFSET 0 ROWS FETCH NEXT 10 ROWS ONLY",
+            paginate(
+                "select id from job order by id -- last",
+                10,
+                0,
+                EngineVersion::V4
+            )?
+        );
+        assert_eq!(
+            "SELECT FIRST 10 SKIP 20\n id from job order by id",
+            paginate(
+                "SELECT\n id from job order by id",
+                10,
+                20,
+                EngineVersion::V1
+            )?
+        );
+        assert_eq!(
+            "with t as (select 1 a from rdb$database) select a from t\nROWS 1 TO 5",
+            paginate(
+                "  with t as (select 1 a from rdb$database) select a from t",
+                5,
+                0,
+                EngineVersion::V2
+            )?
+        );
+
+        assert!(paginate("delete from job", 10, 0, EngineVersion::V4).is_err());
+        assert!(paginate("selected", 10, 0, EngineVersion::V4).is_err());
+        assert!(paginate(
+            "with t as (select 1 a from rdb$database) select a from t",
+            10,
+            0,
+            EngineVersion::V1
+        )
+        .is_err());
+        assert!(paginate(sql, 0, 0, EngineVersion::V4).is_err());
+        assert!(paginate(
+            "select id from job order by id for update",
+            10,
+            0,
+            EngineVersion::V4
+        )
+        .is_err());
+        assert!(paginate(
+            "select id from job order by id\nwith  lock",
+            10,
+            0,
+            EngineVersion::V2
+        )
+        .is_err());
+        assert!(paginate(
+            "select id from job order by id for update of id with lock",
+            10,
+            0,
+            EngineVersion::V1
+        )
+        .is_err());
+        assert!(paginate(sql, u64::MAX, 1, EngineVersion::V2).is_err());
+        assert!(paginate(
+            "select 'for update' from job -- with lock",
+            10,
+            0,
+            EngineVersion::V4
+        )
+        .is_ok());
+        assert!(paginate(
+            "select id from job /* for\nupdate */ order by id",
+            10,
+            0,
+            EngineVersion::V2
+        )
+        .is_ok());
+
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mk_tests_default! {
     use crate::*;
@@ -69,7 +253,40 @@ mk_tests_default! {
         let version = conn.server_engine()?;
 
         // Our current CI versions..
-        assert!([EngineVersion::V2, EngineVersion::V3, EngineVersion::V4].contains(&version));
+        assert!([EngineVersion::V2, EngineVersion::V3, EngineVersion::V4, EngineVersion::V5].contains(&version));
+
+        Ok(())
+    }
+
+    #[test]
+    fn paginate() -> Result<(), FbError> {
+        let mut conn = cbuilder().connect()?;
+
+        let sql = conn.paginate(
+            "select rdb$relation_id from rdb$relations order by rdb$relation_id",
+            3,
+            2,
+        )?;
+        let page: Vec<(i16,)> = conn.query(&sql, ())?;
+
+        let all: Vec<(i16,)> = conn.query(
+            "select rdb$relation_id from rdb$relations order by rdb$relation_id",
+            (),
+        )?;
+        assert_eq!(&all[2..5], &page[..]);
+
+        // Not hidden by a comment at the end
+        let sql = conn.paginate(
+            "select rdb$relation_id from rdb$relations order by rdb$relation_id -- all",
+            3,
+            2,
+        )?;
+        let page: Vec<(i16,)> = conn.query(&sql, ())?;
+        assert_eq!(&all[2..5], &page[..]);
+
+        assert!(conn
+            .paginate("select rdb$relation_id from rdb$relations with lock", 3, 2)
+            .is_err());
 
         Ok(())
     }